@@ -0,0 +1,111 @@
+#[macro_use]
+extern crate criterion;
+extern crate octoon;
+
+use criterion::{Criterion, black_box};
+use octoon::math::*;
+use octoon::animation::*;
+
+const VMD_PATH:&str = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/vmd/M.vmd");
+
+// Poses sampled per iteration, half a frame apart so both ways blend.
+const SAMPLES:usize = 100;
+
+fn sample_keyframes(motion:&Motion, time:f32, translates:&mut [float3], rotations:&mut [Quaternion])
+{
+	for (clip, translate) in motion.bones.clips.iter().zip(translates.iter_mut())
+	{
+		let channel = |name:&str| clip.curves.get(name).map(|curve| curve.evaluate(time)).unwrap_or(0.0);
+		*translate = float3::new(channel("Position.X"), channel("Position.Y"), channel("Position.Z"));
+	}
+
+	for (clip, rotation) in motion.rotations.clips.iter().zip(rotations.iter_mut())
+	{
+		if let Some(curve) = clip.curves.get("Rotation")
+		{
+			*rotation = curve.evaluate(time).normalize();
+		}
+	}
+}
+
+fn sampling(c:&mut Criterion)
+{
+	let motion = VMDLoader::open(VMD_PATH).unwrap();
+	let baked = BakedMotion::new(&motion, 1.0);
+	let end = baked.len() as f32;
+
+	c.bench_function("keyframes", move |b|
+	{
+		let mut translates = vec![float3::zero(); motion.bones.clips.len()];
+		let mut rotations = vec![Quaternion::default(); motion.rotations.clips.len()];
+
+		b.iter(||
+		{
+			for i in 0..SAMPLES
+			{
+				sample_keyframes(&motion, black_box(end * i as f32 / SAMPLES as f32 + 0.5), &mut translates, &mut rotations);
+			}
+		})
+	});
+
+	c.bench_function("baked", move |b|
+	{
+		let mut translates = vec![float3::zero(); baked.names.len()];
+		let mut rotations = vec![Quaternion::default(); baked.names.len()];
+
+		b.iter(||
+		{
+			for i in 0..SAMPLES
+			{
+				baked.sample(black_box(baked.start + end * i as f32 / SAMPLES as f32 + 0.5), &mut translates, &mut rotations);
+			}
+		})
+	});
+}
+
+// Keys every frame for about ten minutes, the way a baked or captured motion comes in.
+const DENSE_KEYS:usize = 20000;
+
+fn dense_curve<F:Fn(f32) -> f32>(f:F) -> AnimationCurve
+{
+	let mut curve = AnimationCurve::new();
+	for i in 0..DENSE_KEYS
+	{
+		curve.add_keyframe(i as f32, f(i as f32), None);
+	}
+	curve
+}
+
+fn simplify(c:&mut Criterion)
+{
+	c.bench_function("simplify", |b|
+	{
+		b.iter_with_setup(|| VMDLoader::open(VMD_PATH).unwrap(), |mut motion|
+		{
+			motion.simplify(1e-3, 1e-3);
+			motion
+		})
+	});
+
+	// A slow swing reduces to long segments, a ripple to short ones.
+	c.bench_function("simplify dense", |b|
+	{
+		b.iter_with_setup(|| dense_curve(|time| (time * 0.002).sin() * 10.0), |mut curve|
+		{
+			curve.simplify(1e-2, true);
+			curve
+		})
+	});
+
+	c.bench_function("simplify dense ripple", |b|
+	{
+		b.iter_with_setup(|| dense_curve(|time| (time * 0.05).sin() * 10.0 + (time * 0.31).sin() * 0.5), |mut curve|
+		{
+			curve.simplify(1e-2, true);
+			curve
+		})
+	});
+}
+
+criterion_group!(benches, sampling, simplify);
+criterion_main!(benches);
@@ -16,8 +16,10 @@ fn main()
     println!("{}", octoon::scene::to_string(&scene).unwrap());
 
 	let mut window = Window::new("PMM Loader");
-	window.update(move |canvas, _|
+	// MikuMikuDance keys its motions at 30 frames a second.
+	window.update(move |canvas, time|
 	{
+		scene.update_pose(time * 30.0);
 		scene.update(canvas);
 		canvas.render(&scene);
 	});
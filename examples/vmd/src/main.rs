@@ -3,6 +3,6 @@ extern crate octoon;
 fn main()
 {
     let mut motion = octoon::animation::open("./M.vmd").unwrap();
-    motion.clips[0].add_event(|name, value| println!("{:?}:{:?}", name, value));
-    motion.evaluate(7.5);
+    motion.bones.clips[0].add_event(|name, value| println!("{:?}:{:?}", name, value));
+    motion.bones.evaluate(7.5);
 }
\ No newline at end of file
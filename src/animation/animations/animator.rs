@@ -1,5 +1,5 @@
 use super::super::animations::{AnimationClip};
-use super::super::core::{Animation};
+use super::super::core::{Animation, Identity, Timestamp, merge_ranges};
 
 #[derive(Debug)]
 pub struct Animator<Elem = f32, Time = f32> 
@@ -43,9 +43,18 @@ impl<Elem, Time> Animator<Elem, Time>
 	}
 }
 
-impl<T> Animator<T> where T:Animation + Copy
+impl<Elem, Time> Animator<Elem, Time> where Time:Timestamp
 {
-	pub fn evaluate(&self, time:f32)
+	/// The time of the earliest and the latest key of all clips, none when there are no keys.
+	pub fn range(&self) -> Option<(Time, Time)>
+	{
+		merge_ranges(self.clips.iter().filter_map(|clip| clip.range()))
+	}
+}
+
+impl<T, Time> Animator<T, Time> where T:Animation + Copy + Identity, Time:Timestamp
+{
+	pub fn evaluate(&self, time:Time)
 	{
 		for clip in self.clips.iter()
 		{
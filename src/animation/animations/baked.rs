@@ -0,0 +1,234 @@
+use std::f32::consts::SQRT_2;
+
+use crate::math::*;
+use super::super::core::Evaluate;
+use super::Motion;
+
+const QUANTIZE:f32 = 32766.0;
+
+/// A unit quaternion in 48 bits, the three smallest components take 15 bits each
+/// and the index of the largest one is kept in their top bits.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct QuantizedQuat(pub [u16;3]);
+
+impl QuantizedQuat
+{
+	pub fn new(q:Quaternion) -> Self
+	{
+		let q = q.normalize();
+		let c = [q.x, q.y, q.z, q.w];
+
+		let mut largest = 0;
+		for i in 1..4
+		{
+			if c[i].abs() > c[largest].abs()
+			{
+				largest = i;
+			}
+		}
+
+		// q and -q are the same rotation, the largest component is kept positive and left out.
+		let sign = if c[largest] < 0.0 { -1.0 } else { 1.0 };
+		let mut data = [0u16;3];
+
+		for (k, i) in (0..4).filter(|&i| i != largest).enumerate()
+		{
+			let v = ((c[i] * sign * SQRT_2 * 0.5 + 0.5) * QUANTIZE).round().max(0.0).min(QUANTIZE) as u16;
+			data[k] = v | (((largest >> k) as u16 & 1) << 15);
+		}
+
+		QuantizedQuat(data)
+	}
+
+	pub fn to_quat(&self) -> Quaternion
+	{
+		let largest = ((self.0[0] >> 15) | ((self.0[1] >> 15) << 1)) as usize;
+
+		let mut c = [0.0;4];
+		let mut sum = 0.0;
+
+		for (k, i) in (0..4).filter(|&i| i != largest).enumerate()
+		{
+			let v = ((self.0[k] & 0x7FFF) as f32 / QUANTIZE - 0.5) * SQRT_2;
+			c[i] = v;
+			sum += v * v;
+		}
+
+		c[largest] = (1.0 - sum).max(0.0).sqrt();
+		Quaternion::new(c[0], c[1], c[2], c[3])
+	}
+}
+
+impl From<Quaternion> for QuantizedQuat
+{
+	fn from(q:Quaternion) -> Self
+	{
+		QuantizedQuat::new(q)
+	}
+}
+
+impl From<QuantizedQuat> for Quaternion
+{
+	fn from(q:QuantizedQuat) -> Self
+	{
+		q.to_quat()
+	}
+}
+
+/// The bones and morph weights of a motion sampled at a fixed rate for playback, cameras and lights aren't baked.
+/// The samples of every bone at a frame are stored next to each other, a pose reads two short runs of memory and searches no keys.
+#[derive(Debug, Clone)]
+pub struct BakedMotion
+{
+	pub names:Vec<String>,
+	pub morph_names:Vec<String>,
+	pub start:f32,
+	pub rate:f32,
+	frames:usize,
+	translates:Vec<float3>,
+	rotations:Vec<QuantizedQuat>,
+	weights:Vec<f32>,
+}
+
+impl BakedMotion
+{
+	/// Samples the motion rate times per unit of its timeline, VMD motions count frames so 1.0 keeps all of them.
+	pub fn new(motion:&Motion, rate:f32) -> Self
+	{
+		let mut names:Vec<String> = motion.bones.clips.iter().map(|clip| clip.name.clone()).collect();
+		for clip in motion.rotations.clips.iter()
+		{
+			if !names.contains(&clip.name) { names.push(clip.name.clone()); }
+		}
+
+		let mut morph_names:Vec<String> = Vec::new();
+		for clip in motion.morphs.clips.iter()
+		{
+			if !morph_names.contains(&clip.name) { morph_names.push(clip.name.clone()); }
+		}
+
+		let times = motion.bones.clips.iter().chain(motion.morphs.clips.iter()).flat_map(|clip| clip.curves.values().flat_map(|curve| curve.frames.iter().map(|key| key.time)))
+			.chain(motion.rotations.clips.iter().flat_map(|clip| clip.curves.values().flat_map(|curve| curve.frames.iter().map(|key| key.time))))
+			.filter(|time| !time.is_nan());
+
+		let (start, end) = times.fold((std::f32::INFINITY, std::f32::NEG_INFINITY), |(start, end), time| (start.min(time), end.max(time)));
+		let frames = if start <= end && rate > 0.0 { ((end - start) * rate).ceil() as usize + 1 } else { 0 };
+
+		let mut translates = Vec::with_capacity(frames * names.len());
+		let mut rotations = Vec::with_capacity(frames * names.len());
+		let mut weights = Vec::with_capacity(frames * morph_names.len());
+
+		let bones:Vec<_> = names.iter().map(|name| motion.bones.clips.iter().find(|clip| clip.name == *name)).collect();
+		let rotation_curves:Vec<_> = names.iter()
+			.map(|name| motion.rotations.clips.iter().find(|clip| clip.name == *name).and_then(|clip| clip.curves.get("Rotation")).filter(|curve| curve.frames.len() > 0))
+			.collect();
+		let weight_curves:Vec<_> = morph_names.iter()
+			.map(|name| motion.morphs.clips.iter().find(|clip| clip.name == *name).and_then(|clip| clip.curves.get("Weight")).filter(|curve| curve.frames.len() > 0))
+			.collect();
+
+		for frame in 0..frames
+		{
+			let time = (start + frame as f32 / rate).min(end);
+
+			for (clip, rotation) in bones.iter().zip(rotation_curves.iter())
+			{
+				let channel = |name:&str| clip.and_then(|clip| clip.curves.get(name)).filter(|curve| curve.frames.len() > 0).map(|curve| curve.evaluate(time)).unwrap_or(0.0);
+				translates.push(float3::new(channel("Position.X"), channel("Position.Y"), channel("Position.Z")));
+				rotations.push(QuantizedQuat::new(rotation.map(|curve| curve.evaluate(time)).unwrap_or(Quaternion::new(0.0, 0.0, 0.0, 1.0))));
+			}
+
+			weights.extend(weight_curves.iter().map(|curve| curve.map(|curve| curve.evaluate(time)).unwrap_or(0.0)));
+		}
+
+		Self
+		{
+			names:names,
+			morph_names:morph_names,
+			start:if frames > 0 { start } else { 0.0 },
+			rate:rate,
+			frames:frames,
+			translates:translates,
+			rotations:rotations,
+			weights:weights,
+		}
+	}
+
+	/// The number of frames sampled.
+	pub fn len(&self) -> usize
+	{
+		self.frames
+	}
+
+	pub fn find(&self, name:&str) -> Option<usize>
+	{
+		self.names.iter().position(|other| other == name)
+	}
+
+	pub fn find_morph(&self, name:&str) -> Option<usize>
+	{
+		self.morph_names.iter().position(|other| other == name)
+	}
+
+	/// Bytes taken by the samples.
+	pub fn size(&self) -> usize
+	{
+		self.translates.len() * std::mem::size_of::<float3>() + self.rotations.len() * std::mem::size_of::<QuantizedQuat>() + self.weights.len() * std::mem::size_of::<f32>()
+	}
+
+	/// Samples every bone, in the order of `names`, blending the two nearest frames. Times outside
+	/// the motion are clamped to it.
+	pub fn sample(&self, time:f32, translates:&mut [float3], rotations:&mut [Quaternion])
+	{
+		let bones = self.names.len();
+
+		if self.frames == 0
+		{
+			translates.iter_mut().for_each(|translate| *translate = float3::zero());
+			rotations.iter_mut().for_each(|rotation| *rotation = Quaternion::new(0.0, 0.0, 0.0, 1.0));
+			return;
+		}
+
+		let (frame, next, t) = self.position(time);
+
+		let (a, b) = (&self.translates[frame * bones..][..bones], &self.translates[next * bones..][..bones]);
+		for (translate, (a, b)) in translates.iter_mut().zip(a.iter().zip(b.iter()))
+		{
+			*translate = *a + (*b - *a) * t;
+		}
+
+		let (a, b) = (&self.rotations[frame * bones..][..bones], &self.rotations[next * bones..][..bones]);
+		for (rotation, (a, b)) in rotations.iter_mut().zip(a.iter().zip(b.iter()))
+		{
+			*rotation = if t > 0.0 { a.to_quat().nlerp(b.to_quat(), t) } else { a.to_quat() };
+		}
+	}
+
+	/// Samples the weight of every morph, in the order of `morph_names`, like `sample` does the bones.
+	pub fn sample_morphs(&self, time:f32, weights:&mut [f32])
+	{
+		if self.frames == 0
+		{
+			weights.iter_mut().for_each(|weight| *weight = 0.0);
+			return;
+		}
+
+		let morphs = self.morph_names.len();
+		let (frame, next, t) = self.position(time);
+
+		let (a, b) = (&self.weights[frame * morphs..][..morphs], &self.weights[next * morphs..][..morphs]);
+		for (weight, (a, b)) in weights.iter_mut().zip(a.iter().zip(b.iter()))
+		{
+			*weight = a + (b - a) * t;
+		}
+	}
+
+	// The frames around the time and how far it is between them, a NaN time lands on the first frame.
+	fn position(&self, time:f32) -> (usize, usize, f32)
+	{
+		let position = ((time - self.start) * self.rate).max(0.0).min((self.frames - 1) as f32);
+
+		let frame = position.floor() as usize;
+		let next = (frame + 1).min(self.frames - 1);
+		(frame, next, position - frame as f32)
+	}
+}
\ No newline at end of file
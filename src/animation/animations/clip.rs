@@ -3,7 +3,7 @@ use std::ops::Fn;
 use std::fmt::Debug;
 
 use super::super::animations::{AnimationCurve};
-use super::super::core::{Animation, Evaluate};
+use super::super::core::{Animation, Identity, Evaluate, Timestamp, merge_ranges};
 
 pub struct AnimationClip<Elem = f32, Time = f32> 
 {
@@ -56,9 +56,18 @@ impl<Elem, Time> AnimationClip<Elem, Time>
 	}
 }
 
-impl<T> AnimationClip<T> where T:Animation + Copy
+impl<Elem, Time> AnimationClip<Elem, Time> where Time:Timestamp
 {
-	pub fn evaluate(&self, time:f32)
+	/// The time of the earliest and the latest key of all curves, none when every curve is empty.
+	pub fn range(&self) -> Option<(Time, Time)>
+	{
+		merge_ranges(self.curves.values().filter_map(|curve| curve.range()))
+	}
+}
+
+impl<T, Time> AnimationClip<T, Time> where T:Animation + Copy + Identity, Time:Timestamp
+{
+	pub fn evaluate(&self, time:Time)
 	{
 		for (key, curve) in self.curves.iter()
 		{
@@ -1,14 +1,31 @@
+use std::cmp::Ordering;
+
 use super::super::keyframes::Keyframe;
-use super::super::core::{Interpolator, Animation, Evaluate};
-use super::super::interpolators::{LinearInterpolator};
+use super::super::core::{Interpolator, Animation, Identity, Evaluate, Timestamp, Frame};
+use super::super::interpolators::{LinearInterpolator, CatmullRomInterpolator, PathInterpolator};
 
 #[derive(Debug)]
-pub struct AnimationCurve<Elem = f32, Time = f32> 
+pub struct AnimationCurve<Elem = f32, Time = f32>
 {
 	pub frames:Vec<Keyframe<Elem, Time>>,
 	pub interpolator:Box<Interpolator + 'static>,
 }
 
+// Keys are ordered by time, keys without a time (NaN) go after all the others.
+fn order<Time:PartialOrd>(a:&Time, b:&Time) -> Ordering
+{
+	match a.partial_cmp(b)
+	{
+		Some(ordering) => ordering,
+		None => match (a.partial_cmp(a).is_none(), b.partial_cmp(b).is_none())
+		{
+			(true, false) => Ordering::Greater,
+			(false, true) => Ordering::Less,
+			_ => Ordering::Equal,
+		}
+	}
+}
+
 impl<Elem, Time> AnimationCurve<Elem, Time>
 {
 	pub fn new() -> Self
@@ -20,8 +37,24 @@ impl<Elem, Time> AnimationCurve<Elem, Time>
 		}
 	}
 
-	pub fn new_with(frames:Vec<Keyframe<Elem, Time>>, interpolator:Option<Box<Interpolator + 'static>>) -> Self
+	pub fn len(&self) -> usize
+	{
+		self.frames.len()
+	}
+
+	pub fn is_empty(&self) -> bool
+	{
+		self.frames.is_empty()
+	}
+}
+
+impl<Elem, Time> AnimationCurve<Elem, Time> where Time:PartialOrd
+{
+	/// The frames are sorted by time, frames sharing a time keep their order.
+	pub fn new_with(mut frames:Vec<Keyframe<Elem, Time>>, interpolator:Option<Box<Interpolator + 'static>>) -> Self
 	{
+		frames.sort_by(|a, b| order(&a.time, &b.time));
+
 		Self
 		{
 			frames:frames,
@@ -29,61 +62,304 @@ impl<Elem, Time> AnimationCurve<Elem, Time>
 		}
 	}
 
+	/// Inserts the key in time order, after any key with the same time.
 	pub fn add(&mut self, key:Keyframe<Elem, Time>)
 	{
-		self.frames.push(key);
+		let index = self.frames.partition_point(|frame| order(&frame.time, &key.time) != Ordering::Greater);
+		self.frames.insert(index, key);
 	}
 
 	pub fn add_keyframe(&mut self, time:Time, value:Elem, interpolator:Option<Box<Interpolator + 'static>>)
 	{
-		self.frames.push(Keyframe::new(time, value, interpolator));
+		self.add(Keyframe::new(time, value, interpolator));
+	}
+
+	/// Moves the keys to another time type, e.g. the frame numbers of a VMD curve to `Frame::mmd` or to seconds.
+	pub fn map_time<T, F>(self, f:F) -> AnimationCurve<Elem, T> where T:PartialOrd, F:Fn(Time) -> T
+	{
+		let frames = self.frames.into_iter().map(|key| Keyframe::new(f(key.time), key.value, key.interpolator)).collect();
+		AnimationCurve::new_with(frames, Some(self.interpolator))
+	}
+}
+
+impl<Elem, Time> AnimationCurve<Elem, Time> where Time:Timestamp
+{
+	/// The time of the first and the last key, keys without a time aside. None when there are no keys.
+	pub fn range(&self) -> Option<(Time, Time)>
+	{
+		let first = self.frames.first().map(|key| key.time).filter(|time| time.is_valid())?;
+		let last = self.frames.iter().rev().map(|key| key.time).find(|time| time.is_valid())?;
+		Some((first, last))
+	}
+}
+
+impl<Time> AnimationCurve<f32, Time> where Time:Timestamp
+{
+	/// Replaces the interpolator of every key with Catmull-Rom tangents computed from its neighbours.
+	pub fn set_catmull_rom(&mut self)
+	{
+		let start = match self.frames.first() { Some(key) => key.time, None => return };
+		let keys:Vec<(f32, f32)> = self.frames.iter().map(|key| (key.time.since(start) as f32, key.value)).collect();
+
+		for (i, key) in self.frames.iter_mut().enumerate()
+		{
+			if let Some(&end) = keys.get(i + 1)
+			{
+				let prev = if i > 0 { keys.get(i - 1).cloned() } else { None };
+				let next = keys.get(i + 2).cloned();
+				key.interpolator = Some(Box::new(CatmullRomInterpolator::new(prev, keys[i], end, next)));
+			}
+		}
 	}
 }
 
-impl<T> Evaluate<T> for AnimationCurve<T> where T:Animation + Copy
+impl<Elem> AnimationCurve<Elem, Frame> where Elem:Animation + Copy + Identity
 {
-	fn evaluate(&self, time:f32) -> T
+	/// Samples the curve at every frame of another rate, from its first key to its last.
+	pub fn resample(&self, rate:u32) -> Self
 	{
-		let index = self.frames.binary_search_by(
-			|key|
+		let mut curve = AnimationCurve::new();
+
+		if let (Some(first), Some(last)) = (self.frames.first(), self.frames.last())
+		{
+			let start = first.time.to_rate(rate);
+			let end = last.time.to_rate(rate);
+
+			for number in start.number..=end.number
 			{
-				key.time.partial_cmp(&time).unwrap()
+				let frame = Frame::new(number, rate);
+				curve.add_keyframe(frame, self.evaluate(frame), None);
 			}
-		);
+		}
+
+		curve
+	}
+}
+
+impl<Elem, Time> AnimationCurve<Elem, Time> where Elem:Animation + Copy + Identity, Time:Timestamp
+{
+	/// Removes the keys the curve plays back without, within the tolerance measured by distance.
+	/// Segments losing keys between their ends become linear.
+	pub fn simplify_by<F>(&mut self, tolerance:f32, distance:F) where F:Fn(&Elem, &Elem) -> f32
+	{
+		self.reduce(tolerance, &distance, |_, _| None);
+	}
 
-		let x;
-		match index
+	// Stretches each segment over as many keys as fit within the tolerance of the removed keys and the middle
+	// of every segment they made, fit may offer an easing for a segment a line can't follow. The length is found
+	// by doubling it until a segment fails and bisecting back, so a segment over n keys is checked log n times
+	// rather than n. Keys sharing a time hold a jump, they stay.
+	fn reduce<F, G>(&mut self, tolerance:f32, distance:&F, fit:G) where F:Fn(&Elem, &Elem) -> f32, G:Fn(&[Keyframe<Elem, Time>], &[(f32, Elem)]) -> Option<PathInterpolator>
+	{
+		let count = self.frames.iter().take_while(|key| key.time.is_valid()).count();
+		if count < 3
 		{
-			Ok(value) => { x = value }
-			Err(value) => { x = value }
+			return;
 		}
 
-		if x < self.frames.len() - 1
+		let keys:Vec<(Time, Elem)> = self.frames[..count].iter().map(|key| (key.time, self.evaluate(key.time))).collect();
+		let middles:Vec<Option<(Time, Elem)>> = (0..count).map(|i| self.middle(i).map(|middle| (middle, self.evaluate(middle)))).collect();
+
+		let error = |start:usize, end:usize, samples:&[(Time, Elem)], interpolator:&Interpolator| -> f32
 		{
-			let anim0 = &self.frames[x];
-			let anim1 = &self.frames[x + 1];
-			let t = (time - anim0.time) / (anim1.time - anim0.time);
+			let (a, b) = (&self.frames[start], &self.frames[end]);
+			samples.iter()
+				.map(|&(time, value)| distance(&a.value.interpolate(&b.value, interpolator, time.fraction(a.time, b.time)), &value))
+				.fold(0.0, |max, error| if error > max || error.is_nan() { error } else { max })
+		};
 
-			if anim0.interpolator.is_some()
+		// The easing a segment plays back with, none when it can't.
+		let check = |start:usize, end:usize| -> Option<Option<PathInterpolator>>
+		{
+			let mut samples = Vec::with_capacity((end - start) * 2);
+			for i in start..end
 			{
-				let interpolator = anim0.interpolator.as_ref().unwrap();
-				anim0.value.animation(&anim1.value, interpolator.interpolator(t))
+				if i > start
+				{
+					samples.push(keys[i]);
+				}
+
+				if let Some(middle) = middles[i]
+				{
+					samples.push(middle);
+				}
 			}
-			else
+
+			if error(start, end, &samples, &*self.interpolator) <= tolerance
 			{
-				anim0.value.animation(&anim1.value, self.interpolator.interpolator(t))
+				return Some(None);
 			}
-		}
-		else
+
+			let normalized:Vec<(f32, Elem)> = samples.iter().map(|&(time, value)| (time.fraction(self.frames[start].time, self.frames[end].time), value)).collect();
+			match fit(&self.frames[start..=end], &normalized)
+			{
+				Some(fitted) if error(start, end, &samples, &fitted) <= tolerance => Some(Some(fitted)),
+				_ => None,
+			}
+		};
+
+		let mut segments:Vec<(usize, usize, Option<PathInterpolator>)> = Vec::new();
+		let mut start = 0;
+
+		while start + 1 < count
 		{
-			if x >= self.frames.len() - 1
+			let limit = (start..count - 1).find(|&i| !(self.frames[i + 1].time > self.frames[i].time)).unwrap_or(count - 1).max(start + 1);
+
+			let mut end = start + 1;
+			let mut path = None;
+			let mut failed = limit + 1;
+
+			while end < limit
 			{
-				self.frames[self.frames.len() - 1].value
+				let candidate = (start + (end - start) * 2).min(limit);
+				match check(start, candidate)
+				{
+					Some(fitted) => { end = candidate; path = fitted; },
+					None => { failed = candidate; break; },
+				}
 			}
-			else
+
+			while failed - end > 1
 			{
-				self.frames[0].value
+				let candidate = (end + failed) / 2;
+				match check(start, candidate)
+				{
+					Some(fitted) => { end = candidate; path = fitted; },
+					None => failed = candidate,
+				}
 			}
+
+			segments.push((start, end, path));
+			start = end;
+		}
+
+		let frames = std::mem::replace(&mut self.frames, Vec::new());
+		let mut segments = segments.into_iter().peekable();
+
+		for (i, mut key) in frames.into_iter().enumerate()
+		{
+			match segments.peek()
+			{
+				Some(&(start, end, path)) if start == i =>
+				{
+					if end > start + 1
+					{
+						key.interpolator = path.map(|path| Box::new(path) as Box<Interpolator + 'static>);
+					}
+
+					segments.next();
+					self.frames.push(key);
+				},
+				_ if i + 1 >= count => self.frames.push(key),
+				_ => {},
+			}
+		}
+	}
+
+	// The time halfway through a segment, unless it has no length.
+	fn middle(&self, i:usize) -> Option<Time>
+	{
+		let (a, b) = (&self.frames[i], self.frames.get(i + 1)?);
+		let middle = a.time.between(b.time, 0.5);
+
+		if middle > a.time && middle < b.time { Some(middle) } else { None }
+	}
+}
+
+impl<Time> AnimationCurve<f32, Time> where Time:Timestamp
+{
+	/// Removes the keys the curve plays back without within the tolerance, segments a line
+	/// can't follow get a fitted bezier when asked to.
+	pub fn simplify(&mut self, tolerance:f32, bezier:bool)
+	{
+		let distance = |a:&f32, b:&f32| (a - b).abs();
+
+		if bezier
+		{
+			self.reduce(tolerance, &distance, fit_path);
+		}
+		else
+		{
+			self.reduce(tolerance, &distance, |_, _| None);
+		}
+	}
+}
+
+// With control points a third and two thirds of the way in time the bezier is a cubic in time
+// and its heights fit the samples by least squares. Kept within the unit square like the beziers of VMD files.
+fn fit_path<Time>(keys:&[Keyframe<f32, Time>], samples:&[(f32, f32)]) -> Option<PathInterpolator>
+{
+	let start = keys.first()?.value;
+	let delta = keys.last()?.value - start;
+
+	if delta.abs() < 1e-6
+	{
+		return None;
+	}
+
+	let (mut aa, mut ab, mut bb, mut ar, mut br) = (0.0, 0.0, 0.0, 0.0, 0.0);
+
+	for &(x, value) in samples
+	{
+		let a = 3.0 * (1.0 - x) * (1.0 - x) * x;
+		let b = 3.0 * (1.0 - x) * x * x;
+		let r = (value - start) / delta - x * x * x;
+
+		aa += a * a;
+		ab += a * b;
+		bb += b * b;
+		ar += a * r;
+		br += b * r;
+	}
+
+	let det = aa * bb - ab * ab;
+	if det.abs() < 1e-12
+	{
+		return None;
+	}
+
+	let ya = (ar * bb - br * ab) / det;
+	let yb = (br * aa - ar * ab) / det;
+
+	Some(PathInterpolator::new(1.0 / 3.0, 2.0 / 3.0, ya.max(0.0).min(1.0), yb.max(0.0).min(1.0)))
+}
+
+impl<T, Time> Evaluate<T, Time> for AnimationCurve<T, Time> where T:Animation + Copy + Identity, Time:Timestamp
+{
+	/// Times before the first key or after the last are clamped to them, a NaN time gives the first key
+	/// and an empty curve gives the identity, like no rotation for quaternions.
+	fn evaluate(&self, time:Time) -> T
+	{
+		if self.frames.is_empty()
+		{
+			return T::identity();
+		}
+
+		if !time.is_valid()
+		{
+			return self.frames[0].value;
+		}
+
+		// Keys without a time are sorted last and never reached here.
+		let next = self.frames.partition_point(|key| key.time <= time);
+		if next == 0
+		{
+			return self.frames[0].value;
+		}
+
+		let anim0 = &self.frames[next - 1];
+		let anim1 = match self.frames.get(next)
+		{
+			Some(key) if key.time > time => key,
+			_ => return anim0.value,
+		};
+
+		let t = time.fraction(anim0.time, anim1.time);
+
+		match anim0.interpolator
+		{
+			Some(ref interpolator) => anim0.value.interpolate(&anim1.value, &**interpolator, t),
+			None => anim0.value.interpolate(&anim1.value, &*self.interpolator, t),
 		}
 	}
-}
\ No newline at end of file
+}
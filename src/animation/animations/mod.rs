@@ -1,7 +1,13 @@
 pub mod curve;
 pub mod clip;
 pub mod animator;
+pub mod motion;
+pub mod baked;
+pub mod player;
 
 pub use curve::*;
 pub use clip::*;
-pub use animator::*;
\ No newline at end of file
+pub use animator::*;
+pub use motion::*;
+pub use baked::*;
+pub use player::*;
\ No newline at end of file
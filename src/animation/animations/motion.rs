@@ -0,0 +1,78 @@
+use crate::math::{Quat, Quaternion};
+use super::super::animations::{Animator};
+use super::super::core::{Timestamp, merge_ranges};
+
+#[derive(Debug)]
+pub struct Motion<Elem = f32, Time = f32>
+{
+	pub name:String,
+	pub bones:Animator<Elem, Time>,
+	pub rotations:Animator<Quat<Elem>, Time>,
+	pub morphs:Animator<Elem, Time>,
+	pub cameras:Animator<Elem, Time>,
+	pub lights:Animator<Elem, Time>,
+	pub self_shadows:Animator<Elem, Time>,
+}
+
+impl<Elem, Time> Motion<Elem, Time>
+{
+	pub fn new() -> Self
+	{
+		Self
+		{
+			name:String::new(),
+			bones:Animator::new(),
+			rotations:Animator::new(),
+			morphs:Animator::new(),
+			cameras:Animator::new(),
+			lights:Animator::new(),
+			self_shadows:Animator::new(),
+		}
+	}
+
+	pub fn len(&self) -> usize
+	{
+		self.bones.len() + self.rotations.len() + self.morphs.len() + self.cameras.len() + self.lights.len() + self.self_shadows.len()
+	}
+}
+
+impl<Elem, Time> Motion<Elem, Time> where Time:Timestamp
+{
+	/// The time of the earliest and the latest key of all tracks, none when there are no keys.
+	pub fn range(&self) -> Option<(Time, Time)>
+	{
+		let tracks = [&self.bones, &self.morphs, &self.cameras, &self.lights, &self.self_shadows];
+		merge_ranges(tracks.iter().filter_map(|track| track.range()).chain(self.rotations.range()))
+	}
+}
+
+// The angle between two rotations, from the chord between them as acos is too coarse near 1.0 in f32.
+fn rotation_angle(a:&Quaternion, b:&Quaternion) -> f32
+{
+	let b = if a.dot(*b) < 0.0 { -*b } else { *b };
+	((*a - b).length() * 0.5).min(1.0).asin() * 4.0
+}
+
+impl<Time> Motion<f32, Time> where Time:Timestamp
+{
+	/// Removes the keys of bones and morphs an imported motion plays back without, positions and
+	/// weights within the tolerance using fitted beziers, rotations within the angle in radians.
+	pub fn simplify(&mut self, tolerance:f32, angle:f32)
+	{
+		for clip in self.bones.clips.iter_mut().chain(self.morphs.clips.iter_mut())
+		{
+			for curve in clip.curves.values_mut()
+			{
+				curve.simplify(tolerance, true);
+			}
+		}
+
+		for clip in self.rotations.clips.iter_mut()
+		{
+			for curve in clip.curves.values_mut()
+			{
+				curve.simplify_by(angle, rotation_angle);
+			}
+		}
+	}
+}
\ No newline at end of file
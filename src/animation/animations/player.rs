@@ -0,0 +1,420 @@
+use std::rc::Rc;
+use std::collections::HashMap;
+
+use crate::math::Quaternion;
+use super::super::animations::{Animator, Motion};
+use super::super::core::{Animation, Additive, Identity, Evaluate, Timestamp};
+
+/// What an animation shows before its first key and after its last.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum WrapMode
+{
+	Clamp,
+	Loop,
+	PingPong,
+}
+
+impl WrapMode
+{
+	/// Maps a time from the start of an animation into the animation.
+	pub fn wrap(self, time:f64, length:f64) -> f64
+	{
+		if length <= 0.0
+		{
+			return 0.0;
+		}
+
+		match self
+		{
+			WrapMode::Clamp => time.max(0.0).min(length),
+			WrapMode::Loop => time.rem_euclid(length),
+			WrapMode::PingPong =>
+			{
+				let time = time.rem_euclid(length * 2.0);
+				if time > length { length * 2.0 - time } else { time }
+			}
+		}
+	}
+}
+
+/// How a layer is combined with the layers below it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LayerBlend
+{
+	/// Blends towards the animation by the weight of the layer, from the identity where no layer below has the curve.
+	Override,
+	/// Adds the change of the animation from its first key, scaled by the weight of the layer.
+	Additive,
+}
+
+/// The value of every curve of an animator at one time, by clip and curve name.
+pub type Pose<Elem = f32> = HashMap<String, HashMap<String, Elem>>;
+
+/// The poses of the tracks of a motion at one time.
+#[derive(Debug, Clone, Default)]
+pub struct MotionPose
+{
+	pub bones:Pose,
+	pub rotations:Pose<Quaternion>,
+	pub morphs:Pose,
+	pub cameras:Pose,
+	pub lights:Pose,
+	pub self_shadows:Pose,
+}
+
+/// Poses the layers of a player are blended into.
+pub trait Blend : Default
+{
+	/// Blends a layer over this pose. `previous` and `current` are the poses of the animations the layer fades
+	/// between, each beside the pose at the start of its animation which additive layers measure from.
+	fn blend(&mut self, blend:LayerBlend, weight:f32, fade:f32, previous:Option<(&Self, &Self)>, current:Option<(&Self, &Self)>);
+}
+
+impl<Elem> Blend for Pose<Elem> where Elem:Animation + Additive + Copy + Identity
+{
+	fn blend(&mut self, blend:LayerBlend, weight:f32, fade:f32, previous:Option<(&Self, &Self)>, current:Option<(&Self, &Self)>)
+	{
+		let mut keys:Vec<(&String, &String)> = current.iter().chain(previous.iter())
+			.flat_map(|(pose, _)| pose.iter().flat_map(|(clip, curves)| curves.keys().map(move |curve| (clip, curve))))
+			.collect();
+
+		keys.sort();
+		keys.dedup();
+
+		let get = |pose:Option<(&Self, &Self)>, clip:&str, curve:&str| pose.and_then(|(values, start)|
+		{
+			let value = *values.get(clip)?.get(curve)?;
+			Some((value, start.get(clip).and_then(|curves| curves.get(curve)).cloned().unwrap_or(value)))
+		});
+
+		for (clip, curve) in keys
+		{
+			let base = self.get(clip).and_then(|curves| curves.get(curve)).cloned();
+
+			let value = match (blend, get(previous, clip, curve), get(current, clip, curve))
+			{
+				(_, None, None) => continue,
+				// Curves no layer below reaches, or only one of the crossfaded animations has, blend from the identity.
+				(LayerBlend::Override, Some((from, _)), Some((to, _))) => base.unwrap_or_else(Elem::identity).animation(&from.animation(&to, fade), weight),
+				(LayerBlend::Override, Some((value, _)), None) => base.unwrap_or_else(Elem::identity).animation(&value, weight * (1.0 - fade)),
+				(LayerBlend::Override, None, Some((value, _))) => base.unwrap_or_else(Elem::identity).animation(&value, weight * fade),
+				(LayerBlend::Additive, previous, current) =>
+				{
+					// Curves no layer below reaches start from the start of the animation.
+					let mut value = match base.or(current.or(previous).map(|(_, start)| start)) { Some(value) => value, None => continue };
+					if let Some((to, from)) = previous { value = value.additive(&from, &to, weight * (1.0 - fade)); }
+					if let Some((to, from)) = current { value = value.additive(&from, &to, weight * fade); }
+					value
+				},
+			};
+
+			self.entry(clip.clone()).or_insert_with(HashMap::new).insert(curve.clone(), value);
+		}
+	}
+}
+
+impl Blend for MotionPose
+{
+	fn blend(&mut self, blend:LayerBlend, weight:f32, fade:f32, previous:Option<(&Self, &Self)>, current:Option<(&Self, &Self)>)
+	{
+		self.bones.blend(blend, weight, fade, previous.map(|(pose, start)| (&pose.bones, &start.bones)), current.map(|(pose, start)| (&pose.bones, &start.bones)));
+		self.rotations.blend(blend, weight, fade, previous.map(|(pose, start)| (&pose.rotations, &start.rotations)), current.map(|(pose, start)| (&pose.rotations, &start.rotations)));
+		self.morphs.blend(blend, weight, fade, previous.map(|(pose, start)| (&pose.morphs, &start.morphs)), current.map(|(pose, start)| (&pose.morphs, &start.morphs)));
+		self.cameras.blend(blend, weight, fade, previous.map(|(pose, start)| (&pose.cameras, &start.cameras)), current.map(|(pose, start)| (&pose.cameras, &start.cameras)));
+		self.lights.blend(blend, weight, fade, previous.map(|(pose, start)| (&pose.lights, &start.lights)), current.map(|(pose, start)| (&pose.lights, &start.lights)));
+		self.self_shadows.blend(blend, weight, fade, previous.map(|(pose, start)| (&pose.self_shadows, &start.self_shadows)), current.map(|(pose, start)| (&pose.self_shadows, &start.self_shadows)));
+	}
+}
+
+/// What a layer plays, a whole `Animator` or `Motion` with all of its clips.
+pub trait Playable
+{
+	type Time:Timestamp;
+	type Pose:Blend;
+
+	/// The time of the earliest and the latest key, none when there are no keys.
+	fn range(&self) -> Option<(Self::Time, Self::Time)>;
+
+	/// The value of every curve with keys at a time.
+	fn sample(&self, time:Self::Time) -> Self::Pose;
+}
+
+impl<Elem, Time> Playable for Animator<Elem, Time> where Elem:Animation + Additive + Copy + Identity, Time:Timestamp
+{
+	type Time = Time;
+	type Pose = Pose<Elem>;
+
+	fn range(&self) -> Option<(Time, Time)>
+	{
+		Animator::range(self)
+	}
+
+	fn sample(&self, time:Time) -> Pose<Elem>
+	{
+		let mut pose = HashMap::new();
+
+		for clip in self.clips.iter()
+		{
+			for (name, curve) in clip.curves.iter().filter(|(_, curve)| !curve.is_empty())
+			{
+				pose.entry(clip.name.clone()).or_insert_with(HashMap::new).insert(name.clone(), curve.evaluate(time));
+			}
+		}
+
+		pose
+	}
+}
+
+impl<Time> Playable for Motion<f32, Time> where Time:Timestamp
+{
+	type Time = Time;
+	type Pose = MotionPose;
+
+	fn range(&self) -> Option<(Time, Time)>
+	{
+		Motion::range(self)
+	}
+
+	fn sample(&self, time:Time) -> MotionPose
+	{
+		MotionPose
+		{
+			bones:self.bones.sample(time),
+			rotations:self.rotations.sample(time),
+			morphs:self.morphs.sample(time),
+			cameras:self.cameras.sample(time),
+			lights:self.lights.sample(time),
+			self_shadows:self.self_shadows.sample(time),
+		}
+	}
+}
+
+/// An animation being played, the time is counted from its first key in the unit of `Timestamp::since`.
+#[derive(Debug)]
+pub struct AnimationState<P>
+{
+	pub animation:Rc<P>,
+	pub time:f64,
+	pub speed:f64,
+	pub pre_wrap:WrapMode,
+	pub post_wrap:WrapMode,
+}
+
+impl<P> AnimationState<P> where P:Playable
+{
+	/// The animation can be shared, like the motion of a scene node.
+	pub fn new(animation:Rc<P>) -> Self
+	{
+		Self
+		{
+			animation:animation,
+			time:0.0,
+			speed:1.0,
+			pre_wrap:WrapMode::Clamp,
+			post_wrap:WrapMode::Clamp,
+		}
+	}
+
+	pub fn looping(animation:Rc<P>) -> Self
+	{
+		Self
+		{
+			pre_wrap:WrapMode::Loop,
+			post_wrap:WrapMode::Loop,
+			..AnimationState::new(animation)
+		}
+	}
+
+	/// The time on the timeline of the animation this state shows, none when the animation has no keys.
+	pub fn sample_time(&self) -> Option<P::Time>
+	{
+		let (start, end) = self.animation.range()?;
+		let length = end.since(start);
+
+		let time = match self.time
+		{
+			time if time < 0.0 => self.pre_wrap.wrap(time, length),
+			time if time > length => self.post_wrap.wrap(time, length),
+			time => time,
+		};
+
+		if length > 0.0 { Some(start.between(end, time / length)) } else { Some(start) }
+	}
+
+	/// The pose this state shows beside the pose at the start of the animation.
+	pub fn sample(&self) -> Option<(P::Pose, P::Pose)>
+	{
+		let (start, _) = self.animation.range()?;
+		let time = self.sample_time()?;
+		Some((self.animation.sample(time), self.animation.sample(start)))
+	}
+}
+
+/// Plays one animation at a time and crossfades to the next.
+#[derive(Debug)]
+pub struct AnimationLayer<P>
+{
+	pub weight:f32,
+	pub blend:LayerBlend,
+	current:Option<AnimationState<P>>,
+	previous:Option<AnimationState<P>>,
+	fade:f64,
+	fading:f64,
+}
+
+impl<P> AnimationLayer<P>
+{
+	pub fn new() -> Self
+	{
+		Self
+		{
+			weight:1.0,
+			blend:LayerBlend::Override,
+			current:None,
+			previous:None,
+			fade:0.0,
+			fading:0.0,
+		}
+	}
+
+	pub fn additive() -> Self
+	{
+		Self
+		{
+			blend:LayerBlend::Additive,
+			..AnimationLayer::new()
+		}
+	}
+
+	#[inline(always)]
+	pub fn state(&self) -> Option<&AnimationState<P>>
+	{
+		self.current.as_ref()
+	}
+
+	#[inline(always)]
+	pub fn state_mut(&mut self) -> Option<&mut AnimationState<P>>
+	{
+		self.current.as_mut()
+	}
+
+	/// Switches to the state at once, dropping any crossfade.
+	pub fn play(&mut self, state:AnimationState<P>)
+	{
+		self.current = Some(state);
+		self.previous = None;
+		self.fade = 0.0;
+		self.fading = 0.0;
+	}
+
+	/// Fades from the playing state to this one over the duration, both keep playing meanwhile.
+	pub fn crossfade(&mut self, state:AnimationState<P>, duration:f64)
+	{
+		if duration <= 0.0 || self.current.is_none()
+		{
+			return self.play(state);
+		}
+
+		self.previous = self.current.take();
+		self.current = Some(state);
+		self.fade = duration;
+		self.fading = 0.0;
+	}
+
+	pub fn stop(&mut self)
+	{
+		self.current = None;
+		self.previous = None;
+	}
+
+	/// How far the crossfade is, 1.0 when there is none.
+	pub fn fade_weight(&self) -> f32
+	{
+		if self.previous.is_some() { (self.fading / self.fade) as f32 } else { 1.0 }
+	}
+
+	/// Moves the states by the delta times their speed, crossfades move by the delta alone.
+	pub fn advance(&mut self, delta:f64)
+	{
+		for state in self.current.iter_mut().chain(self.previous.iter_mut())
+		{
+			state.time += delta * state.speed;
+		}
+
+		if self.previous.is_some()
+		{
+			self.fading += delta;
+
+			if self.fading >= self.fade
+			{
+				self.previous = None;
+			}
+		}
+	}
+}
+
+/// Layers of animators or motions, each with its own weight, wrap modes, speed and crossfade.
+/// Layers apply in order, so additive layers go above the layers they move.
+#[derive(Debug)]
+pub struct AnimationPlayer<P>
+{
+	pub layers:Vec<AnimationLayer<P>>,
+}
+
+impl<P> AnimationPlayer<P>
+{
+	pub fn new() -> Self
+	{
+		Self
+		{
+			layers:Vec::new(),
+		}
+	}
+
+	/// Adds a layer above the others and returns its index.
+	pub fn add_layer(&mut self, layer:AnimationLayer<P>) -> usize
+	{
+		self.layers.push(layer);
+		self.layers.len() - 1
+	}
+
+	#[inline(always)]
+	pub fn layer(&self, index:usize) -> Option<&AnimationLayer<P>>
+	{
+		self.layers.get(index)
+	}
+
+	#[inline(always)]
+	pub fn layer_mut(&mut self, index:usize) -> Option<&mut AnimationLayer<P>>
+	{
+		self.layers.get_mut(index)
+	}
+
+	/// Moves every layer by the delta, in the unit of `Timestamp::since`.
+	pub fn advance(&mut self, delta:f64)
+	{
+		for layer in self.layers.iter_mut()
+		{
+			layer.advance(delta);
+		}
+	}
+}
+
+impl<P> AnimationPlayer<P> where P:Playable
+{
+	/// The blended pose of every curve the layers reach.
+	pub fn evaluate(&self) -> P::Pose
+	{
+		let mut pose = P::Pose::default();
+
+		for layer in self.layers.iter().filter(|layer| layer.weight > 0.0)
+		{
+			let previous = layer.previous.as_ref().and_then(|state| state.sample());
+			let current = layer.current.as_ref().and_then(|state| state.sample());
+
+			pose.blend(layer.blend, layer.weight, layer.fade_weight(), previous.as_ref().map(|(pose, start)| (pose, start)), current.as_ref().map(|(pose, start)| (pose, start)));
+		}
+
+		pose
+	}
+}
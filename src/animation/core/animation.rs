@@ -1,6 +1,15 @@
+use crate::math::{Vec3, Vec4, Quat, Dual, Quaternion, Zero, One};
+use super::interpolator::Interpolator;
+
 pub trait Animation<T = f32>
 {
 	fn animation(&self, rhs: &Self, t:T) -> Self; 
+
+	/// The value between this key and the next through the interpolator of the segment.
+	fn interpolate(&self, rhs:&Self, interpolator:&Interpolator<T>, t:T) -> Self where Self:Sized
+	{
+		self.animation(rhs, interpolator.interpolator(t))
+	}
 }
 
 impl Animation<f32> for f32
@@ -9,6 +18,11 @@ impl Animation<f32> for f32
 	{
 		self * (1.0 - t) + rhs * t
 	}
+
+	fn interpolate(&self, rhs:&Self, interpolator:&Interpolator<f32>, t:f32) -> Self
+	{
+		interpolator.value(*self, *rhs, t).unwrap_or_else(|| self.animation(rhs, interpolator.interpolator(t)))
+	}
 }
 
 impl Animation<f64> for f64
@@ -17,4 +31,66 @@ impl Animation<f64> for f64
 	{
 		self * (1.0 - t) + rhs * t
 	}
+}
+
+/// The value of a curve without keys, zero for vectors and no rotation for quaternions.
+pub trait Identity
+{
+	fn identity() -> Self;
+}
+
+impl Identity for f32
+{
+	fn identity() -> Self { 0.0 }
+}
+
+impl Identity for f64
+{
+	fn identity() -> Self { 0.0 }
+}
+
+impl<T> Identity for Vec3<T> where T:Zero
+{
+	fn identity() -> Self { Vec3 { x:T::zero(), y:T::zero(), z:T::zero() } }
+}
+
+impl<T> Identity for Vec4<T> where T:Zero
+{
+	fn identity() -> Self { Vec4 { x:T::zero(), y:T::zero(), z:T::zero(), w:T::zero() } }
+}
+
+impl<T> Identity for Quat<T> where T:Zero + One
+{
+	fn identity() -> Self { Quat { x:T::zero(), y:T::zero(), z:T::zero(), w:T::one() } }
+}
+
+impl<T> Identity for Dual<T> where T:Zero + One
+{
+	fn identity() -> Self { Dual { real:Quat::identity(), dual:Quat { x:T::zero(), y:T::zero(), z:T::zero(), w:T::zero() } } }
+}
+
+
+/// Values an additive layer moves, like a breathing loop played over a dance.
+pub trait Additive
+{
+	/// This value moved by the change from `from` to `to`, scaled by the weight.
+	fn additive(&self, from:&Self, to:&Self, weight:f32) -> Self;
+}
+
+impl Additive for f32
+{
+	fn additive(&self, from:&Self, to:&Self, weight:f32) -> Self
+	{
+		self + (to - from) * weight
+	}
+}
+
+impl Additive for Quaternion
+{
+	/// The rotation from `from` to `to` is applied after this one, slerped from identity by the weight.
+	fn additive(&self, from:&Self, to:&Self, weight:f32) -> Self
+	{
+		let delta = to.concat(from.inverse());
+		Quaternion::identity().animation(&delta, weight).concat(*self)
+	}
 }
\ No newline at end of file
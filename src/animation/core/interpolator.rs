@@ -3,4 +3,11 @@ use std::fmt::Debug;
 pub trait Interpolator<T = f32> : Debug
 {
 	fn interpolator(&self, t:T) -> T;
+
+	/// Control points (x1, y1, x2, y2) when the interpolator is a cubic bezier.
+	fn bezier(&self) -> Option<(T,T,T,T)> { None }
+
+	/// The value between the values of two keys when the interpolator holds tangents in value, which
+	/// an easing of the line between both keys loses when the keys are equal.
+	fn value(&self, _start:T, _end:T, _t:T) -> Option<T> { None }
 }
\ No newline at end of file
@@ -1,8 +1,8 @@
-use super::super::{Result, Animator};
+use super::super::{Result, Motion};
 
 pub trait Loader
 {
 	fn can_read(&self, buf:&[u8]) -> bool;
 
-	fn do_load(&self, buf:&[u8]) -> Result<Animator>;
+	fn do_load(&self, buf:&[u8]) -> Result<Motion>;
 }
\ No newline at end of file
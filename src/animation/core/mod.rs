@@ -3,9 +3,11 @@ pub mod interpolator;
 pub mod animation;
 pub mod evaluate;
 pub mod loader;
+pub mod time;
 
 pub use error::*;
 pub use interpolator::*;
 pub use animation::*;
 pub use evaluate::*;
-pub use loader::*;
\ No newline at end of file
+pub use loader::*;
+pub use time::*;
\ No newline at end of file
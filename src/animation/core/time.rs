@@ -0,0 +1,184 @@
+use std::cmp::Ordering;
+use std::fmt::Debug;
+
+/// MMD motions and projects count time in frames at this rate.
+pub const MMD_FRAME_RATE:u32 = 30;
+
+pub fn seconds_to_frames(seconds:f64) -> f64
+{
+	seconds * MMD_FRAME_RATE as f64
+}
+
+pub fn frames_to_seconds(frames:f64) -> f64
+{
+	frames / MMD_FRAME_RATE as f64
+}
+
+/// A point on the timeline of a curve, keys are sorted by it.
+pub trait Timestamp : Copy + PartialOrd + Debug
+{
+	/// The time from an earlier point to this one. Plain numbers give it in their own unit, e.g. frames
+	/// for VMD curves, while `Frame` gives seconds so frames at different rates can be compared.
+	fn since(self, earlier:Self) -> f64;
+
+	/// The point at a fraction of the way to end.
+	fn between(self, end:Self, fraction:f64) -> Self;
+
+	/// How far this point is from start to end, 0.0 at start and 1.0 at end.
+	fn fraction(self, start:Self, end:Self) -> f32
+	{
+		(self.since(start) / end.since(start)) as f32
+	}
+
+	/// Points which don't compare with themselves, like NaN, aren't on the timeline.
+	fn is_valid(self) -> bool
+	{
+		self.partial_cmp(&self).is_some()
+	}
+}
+
+/// The earliest start and the latest end of the ranges, none when there are none.
+pub fn merge_ranges<Time, I>(ranges:I) -> Option<(Time, Time)> where Time:Timestamp, I:IntoIterator<Item = (Time, Time)>
+{
+	ranges.into_iter().fold(None, |range, (start, end)| match range
+	{
+		None => Some((start, end)),
+		Some((first, last)) => Some((if start < first { start } else { first }, if end > last { end } else { last })),
+	})
+}
+
+impl Timestamp for f32
+{
+	fn since(self, earlier:Self) -> f64
+	{
+		self as f64 - earlier as f64
+	}
+
+	fn between(self, end:Self, fraction:f64) -> Self
+	{
+		(self as f64 + (end as f64 - self as f64) * fraction) as f32
+	}
+}
+
+impl Timestamp for f64
+{
+	fn since(self, earlier:Self) -> f64
+	{
+		self - earlier
+	}
+
+	fn between(self, end:Self, fraction:f64) -> Self
+	{
+		self + (end - self) * fraction
+	}
+}
+
+/// A frame number at a frame rate, frames at different rates compare by the time they show.
+#[derive(Debug, Copy, Clone)]
+pub struct Frame
+{
+	pub number:i64,
+	pub rate:u32,
+}
+
+impl Frame
+{
+	pub fn new(number:i64, rate:u32) -> Self
+	{
+		assert!(rate > 0, "a frame rate can't be zero");
+
+		Self
+		{
+			number:number,
+			rate:rate
+		}
+	}
+
+	pub fn mmd(number:i64) -> Self
+	{
+		Frame::new(number, MMD_FRAME_RATE)
+	}
+
+	/// The frame showing at that time, rounded to the nearest.
+	pub fn from_seconds(seconds:f64, rate:u32) -> Self
+	{
+		Frame::new((seconds * rate as f64).round() as i64, rate)
+	}
+
+	pub fn seconds(&self) -> f64
+	{
+		self.number as f64 / self.rate as f64
+	}
+
+	/// The nearest frame at another rate, exact when the rates are multiples of each other.
+	pub fn to_rate(&self, rate:u32) -> Self
+	{
+		let scaled = self.number as i128 * rate as i128;
+		let half = self.rate as i128 / 2;
+		let number = if scaled >= 0 { (scaled + half) / self.rate as i128 } else { (scaled - half) / self.rate as i128 };
+
+		Frame::new(number as i64, rate)
+	}
+}
+
+impl PartialEq for Frame
+{
+	fn eq(&self, other:&Self) -> bool
+	{
+		self.cmp(other) == Ordering::Equal
+	}
+}
+
+impl Eq for Frame {}
+
+impl PartialOrd for Frame
+{
+	fn partial_cmp(&self, other:&Self) -> Option<Ordering>
+	{
+		Some(self.cmp(other))
+	}
+}
+
+impl Ord for Frame
+{
+	fn cmp(&self, other:&Self) -> Ordering
+	{
+		(self.number as i128 * other.rate as i128).cmp(&(other.number as i128 * self.rate as i128))
+	}
+}
+
+impl Timestamp for Frame
+{
+	fn since(self, earlier:Self) -> f64
+	{
+		if self.rate == earlier.rate
+		{
+			(self.number - earlier.number) as f64 / self.rate as f64
+		}
+		else
+		{
+			let numerator = self.number as i128 * earlier.rate as i128 - earlier.number as i128 * self.rate as i128;
+			numerator as f64 / (self.rate as f64 * earlier.rate as f64)
+		}
+	}
+
+	/// The nearest frame at the rate of self.
+	fn between(self, end:Self, fraction:f64) -> Self
+	{
+		let end = end.to_rate(self.rate);
+		Frame::new(self.number + ((end.number - self.number) as f64 * fraction).round() as i64, self.rate)
+	}
+
+	fn fraction(self, start:Self, end:Self) -> f32
+	{
+		// Frames on the same rate divide exactly in frame counts.
+		if self.rate == start.rate && self.rate == end.rate
+		{
+			((self.number - start.number) as f64 / (end.number - start.number) as f64) as f32
+		}
+		else
+		{
+			(self.since(start) / end.since(start)) as f32
+		}
+	}
+}
\ No newline at end of file
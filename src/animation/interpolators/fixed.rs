@@ -1,6 +1,7 @@
 use std::fmt::Debug;
 use super::super::interpolator::*;
 
+/// Always gives the same fraction, `FixedInterpolator::new(0.0)` holds the value of a key until the next one.
 #[derive(Debug, Copy, Clone)]
 pub struct FixedInterpolator<T = f32>
 {
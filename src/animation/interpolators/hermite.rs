@@ -0,0 +1,99 @@
+use super::super::interpolator::*;
+
+/// Cubic Hermite easing, the tangents are slopes relative to the straight line between both keys,
+/// so 1.0 and 1.0 is linear and 0.0 and 0.0 eases in and out.
+#[derive(Debug, Copy, Clone)]
+pub struct HermiteInterpolator
+{
+	pub out_tangent:f32,
+	pub in_tangent:f32,
+}
+
+impl HermiteInterpolator
+{
+	pub fn new(out_tangent:f32, in_tangent:f32) -> Self
+	{
+		Self
+		{
+			out_tangent,
+			in_tangent
+		}
+	}
+
+	/// Builds the easing from tangents in value per time of a segment going from start to end,
+	/// both given as (time, value). A segment between equal values stays flat as an easing can't leave it,
+	/// `CatmullRomInterpolator` keeps its tangents in value for those.
+	pub fn from_slopes(start:(f32, f32), end:(f32, f32), out_slope:f32, in_slope:f32) -> Self
+	{
+		let delta = end.1 - start.1;
+		let duration = end.0 - start.0;
+
+		if delta == 0.0 || !(duration > 0.0)
+		{
+			return Self::new(0.0, 0.0);
+		}
+
+		Self::new(out_slope * duration / delta, in_slope * duration / delta)
+	}
+}
+
+impl Interpolator for HermiteInterpolator
+{
+	fn interpolator(&self, t:f32) -> f32
+	{
+		let t2 = t * t;
+		let t3 = t2 * t;
+
+		(t3 - 2.0 * t2 + t) * self.out_tangent + (-2.0 * t3 + 3.0 * t2) + (t3 - t2) * self.in_tangent
+	}
+}
+
+/// Hermite easing with the tangent of each key taken from its neighbours, the first and last keys of a curve
+/// have no neighbour on one side and use the segment itself. The tangents are also kept in value across the
+/// segment, so a segment between equal keys still curves towards its neighbours.
+#[derive(Debug, Copy, Clone)]
+pub struct CatmullRomInterpolator
+{
+	pub hermite:HermiteInterpolator,
+	pub out_tangent:f32,
+	pub in_tangent:f32,
+}
+
+impl CatmullRomInterpolator
+{
+	/// Keys are (time, value), prev and next are the keys around the segment going from start to end.
+	pub fn new(prev:Option<(f32, f32)>, start:(f32, f32), end:(f32, f32), next:Option<(f32, f32)>) -> Self
+	{
+		let out_slope = CatmullRomInterpolator::slope(prev.unwrap_or(start), end);
+		let in_slope = CatmullRomInterpolator::slope(start, next.unwrap_or(end));
+		let duration = if end.0 > start.0 { end.0 - start.0 } else { 0.0 };
+
+		Self
+		{
+			hermite:HermiteInterpolator::from_slopes(start, end, out_slope, in_slope),
+			out_tangent:out_slope * duration,
+			in_tangent:in_slope * duration,
+		}
+	}
+
+	fn slope(a:(f32, f32), b:(f32, f32)) -> f32
+	{
+		if b.0 > a.0 { (b.1 - a.1) / (b.0 - a.0) } else { 0.0 }
+	}
+}
+
+impl Interpolator for CatmullRomInterpolator
+{
+	fn interpolator(&self, t:f32) -> f32
+	{
+		self.hermite.interpolator(t)
+	}
+
+	fn value(&self, start:f32, end:f32, t:f32) -> Option<f32>
+	{
+		let t2 = t * t;
+		let t3 = t2 * t;
+
+		Some((2.0 * t3 - 3.0 * t2 + 1.0) * start + (t3 - 2.0 * t2 + t) * self.out_tangent + (-2.0 * t3 + 3.0 * t2) * end + (t3 - t2) * self.in_tangent)
+	}
+}
\ No newline at end of file
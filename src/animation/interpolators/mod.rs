@@ -1,7 +1,9 @@
 pub mod linear;
 pub mod fixed;
 pub mod path;
+pub mod hermite;
 
 pub use linear::*;
 pub use fixed::*;
-pub use path::*;
\ No newline at end of file
+pub use path::*;
+pub use hermite::*;
\ No newline at end of file
@@ -25,6 +25,11 @@ impl<T> PathInterpolator<T>
 
 impl Interpolator for PathInterpolator
 {
+	fn bezier(&self) -> Option<(f32,f32,f32,f32)>
+	{
+		Some((self.xa, self.ya, self.xb, self.yb))
+	}
+
 	fn interpolator(&self, t:f32) -> f32
 	{
 		let mut min = 0.0;
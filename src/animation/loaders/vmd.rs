@@ -1,18 +1,25 @@
-use std::io::{Cursor};
+use std::io::{Cursor, Write};
 use std::f32;
 use std::fs::File;
 use std::io::Read;
 use std::collections::HashMap;
-use byteorder::{LittleEndian, ReadBytesExt};
-use encoding::{Encoding, DecoderTrap};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use encoding::{Encoding, DecoderTrap, EncoderTrap};
 use encoding::all::WINDOWS_31J;
-use super::super::{Error, Result, Loader, Animator, AnimationClip, AnimationCurve};
+use crate::math::Quaternion;
+use crate::binary::read_count;
+use super::super::{Error, Result, Loader, Animator, AnimationClip, AnimationCurve, Evaluate, Interpolator, PathInterpolator, FixedInterpolator, Animation, Identity, Motion};
 
 pub struct VMDName{}
 pub struct VMDVector2{}
 pub struct VMDVector3{}
 pub struct VMDVector4{}
 
+pub const VMD_MAGIC:&'static str = "Vocaloid Motion Data 0002";
+
+// Default bezier control points of MikuMikuDance, a straight line.
+pub const VMD_BEZIER_LINEAR:[u8;4] = [20, 20, 107, 107];
+
 #[derive(Debug)]
 pub struct VMDHeader
 {
@@ -48,9 +55,9 @@ pub struct VMDCamera
 	pub length:f32,
 	pub position:(f32,f32,f32),
 	pub rotation:(f32,f32,f32),
-	pub bezier:[u8;24],
+	pub bezier:[u8;24], // X1, X2, Y1, Y2 of (x, y, z, rotation, distance, fov)
 	pub view_angle:u32,
-	pub perspective:u8,
+	pub perspective:u8, // 0 : on, 1 : off
 }
 
 #[derive(Debug)]
@@ -69,6 +76,21 @@ pub struct VMDSelfShadow
 	pub distance:f32, // 0.1 - (dist * 0.000001)
 }
 
+#[derive(Debug)]
+pub struct VMDIKState
+{
+	pub name:String, // Fixed: 20 length
+	pub enable:u8,
+}
+
+#[derive(Debug)]
+pub struct VMDProperty
+{
+	pub frame:u32,
+	pub visible:u8,
+	pub ik_states:Vec<VMDIKState>,
+}
+
 #[derive(Debug)]
 pub struct VMDFile
 {
@@ -78,6 +100,7 @@ pub struct VMDFile
 	pub cameras:Vec<VMDCamera>,
 	pub lights:Vec<VMDLight>,
 	pub self_shadows:Vec<VMDSelfShadow>,
+	pub properties:Vec<VMDProperty>,
 }
 
 impl VMDName
@@ -97,6 +120,24 @@ impl VMDName
 
 		Ok(WINDOWS_31J.decode(&bytes, DecoderTrap::Ignore).unwrap())
 	}
+
+	/// Encodes a name to Shift-JIS and truncates it to whole characters within `length` bytes.
+	pub fn save<W:Write>(writer:&mut W, name:&str, length:usize) -> Result<()>
+	{
+		let mut bytes = Vec::with_capacity(length);
+
+		for c in name.chars()
+		{
+			let mut buf = [0u8;4];
+			let encoded = WINDOWS_31J.encode(c.encode_utf8(&mut buf), EncoderTrap::Replace).unwrap();
+			if bytes.len() + encoded.len() > length { break; }
+			bytes.extend(encoded);
+		}
+
+		bytes.resize(length, 0);
+		writer.write_all(&bytes)?;
+		Ok(())
+	}
 }
 
 impl VMDVector2
@@ -118,6 +159,14 @@ impl VMDVector3
 		let z = reader.read_f32::<LittleEndian>()?;
 		Ok((x, y, z))
 	}
+
+	pub fn save<W:Write>(writer:&mut W, v:(f32,f32,f32)) -> Result<()>
+	{
+		writer.write_f32::<LittleEndian>(v.0)?;
+		writer.write_f32::<LittleEndian>(v.1)?;
+		writer.write_f32::<LittleEndian>(v.2)?;
+		Ok(())
+	}
 }
 
 impl VMDVector4
@@ -130,6 +179,15 @@ impl VMDVector4
 		let w = reader.read_f32::<LittleEndian>()?;
 		Ok((x, y, z, w))
 	}
+
+	pub fn save<W:Write>(writer:&mut W, v:(f32,f32,f32,f32)) -> Result<()>
+	{
+		writer.write_f32::<LittleEndian>(v.0)?;
+		writer.write_f32::<LittleEndian>(v.1)?;
+		writer.write_f32::<LittleEndian>(v.2)?;
+		writer.write_f32::<LittleEndian>(v.3)?;
+		Ok(())
+	}
 }
 
 impl VMDHeader
@@ -145,7 +203,7 @@ impl VMDHeader
 
 	pub fn valid(self) -> Result<Self>
 	{
-		if self.magic != "Vocaloid Motion Data 0002" { return Err(Error("Invalid header of magic.".to_string())); };
+		if self.magic != VMD_MAGIC { return Err(Error("Invalid header of magic.".to_string())); };
 		Ok(self)
 	}
 
@@ -157,6 +215,13 @@ impl VMDHeader
 
 		this.valid()
 	}
+
+	pub fn save<W:Write>(&self, writer:&mut W) -> Result<()>
+	{
+		VMDName::save(writer, &self.magic, 30)?;
+		VMDName::save(writer, &self.name, 20)?;
+		Ok(())
+	}
 }
 
 impl VMDMotion
@@ -198,8 +263,9 @@ impl VMDMotion
 
 	pub fn load_array(reader:&mut Cursor<&[u8]>) -> Result<Vec<Self>>
 	{
-		let num_motion = reader.read_u32::<LittleEndian>()?;
-		let mut motions = Vec::with_capacity(num_motion as usize);
+		let num_motion = reader.read_u32::<LittleEndian>()? as usize;
+		let num_motion = read_count(reader, num_motion, 111, "motions", "VMD")?;
+		let mut motions = Vec::with_capacity(num_motion);
 
 		for _ in 0..num_motion
 		{
@@ -208,6 +274,92 @@ impl VMDMotion
 
 		Ok(motions)
 	}
+
+	pub fn save<W:Write>(&self, writer:&mut W) -> Result<()>
+	{
+		VMDName::save(writer, &self.name, 15)?;
+		writer.write_u32::<LittleEndian>(self.frame)?;
+		VMDVector3::save(writer, self.position)?;
+		VMDVector4::save(writer, self.rotate)?;
+		writer.write_all(&self.bezier_x)?;
+		writer.write_all(&self.bezier_y)?;
+		writer.write_all(&self.bezier_z)?;
+		writer.write_all(&self.bezier_r)?;
+		Ok(())
+	}
+
+	pub fn save_array<W:Write>(writer:&mut W, motions:&[Self]) -> Result<()>
+	{
+		writer.write_u32::<LittleEndian>(motions.len() as u32)?;
+		for it in motions
+		{
+			it.save(writer)?;
+		}
+
+		Ok(())
+	}
+
+	/// Fills the four bezier tables with the same control points (x1, y1, x2, y2) for every channel.
+	pub fn set_bezier(&mut self, points:[u8;4])
+	{
+		for channel in 0..4
+		{
+			self.set_channel_bezier(channel, points);
+		}
+	}
+
+	/// Fills the control points of a single channel, see `bezier` for the channels.
+	pub fn set_channel_bezier(&mut self, channel:usize, points:[u8;4])
+	{
+		for table in [&mut self.bezier_x, &mut self.bezier_y, &mut self.bezier_z, &mut self.bezier_r].iter_mut()
+		{
+			for (i, point) in points.iter().enumerate()
+			{
+				table[i * 4 + channel] = *point;
+			}
+		}
+	}
+
+	/// Control points (x1, y1, x2, y2) of a channel, 0 : X, 1 : Y, 2 : Z, 3 : Rotation.
+	pub fn bezier(&self, channel:usize) -> [u8;4]
+	{
+		[self.bezier_x[channel], self.bezier_x[channel + 4], self.bezier_x[channel + 8], self.bezier_x[channel + 12]]
+	}
+
+	pub fn interpolator(&self, channel:usize) -> Option<Box<Interpolator + 'static>>
+	{
+		VMDMotion::path_interpolator(self.bezier(channel))
+	}
+
+	/// Returns None for a straight line, so the curve falls back to its linear interpolator.
+	pub fn path_interpolator(points:[u8;4]) -> Option<Box<Interpolator + 'static>>
+	{
+		let (x1, y1, x2, y2) = (points[0], points[1], points[2], points[3]);
+		if x1 == y1 && x2 == y2 { return None; }
+
+		Some(Box::new(PathInterpolator::new(x1 as f32 / 127.0, x2 as f32 / 127.0, y1 as f32 / 127.0, y2 as f32 / 127.0)))
+	}
+
+	/// The inverse of `path_interpolator`, interpolators without control points are written as a straight line.
+	pub fn bezier_points(interpolator:Option<&Interpolator>) -> [u8;4]
+	{
+		match interpolator.and_then(|interpolator| interpolator.bezier())
+		{
+			Some((x1, y1, x2, y2)) =>
+			{
+				let point = |v:f32| (v * 127.0).round().max(0.0).min(127.0) as u8;
+				[point(x1), point(y1), point(x2), point(y2)]
+			},
+			None => VMD_BEZIER_LINEAR
+		}
+	}
+
+	/// The bezier of a keyframe shapes the segment which ends at it, so it comes from the previous key of the curve.
+	pub fn segment_bezier<T>(curve:Option<&AnimationCurve<T>>, time:f32) -> [u8;4]
+	{
+		let previous = curve.and_then(|curve| curve.frames.iter().rev().find(|key| key.time < time));
+		VMDMotion::bezier_points(previous.and_then(|key| key.interpolator.as_ref().map(|interpolator| interpolator.as_ref())))
+	}
 }
 
 impl VMDMorph
@@ -239,8 +391,9 @@ impl VMDMorph
 
 	pub fn load_array(reader:&mut Cursor<&[u8]>) -> Result<Vec<Self>>
 	{
-		let num_morph = reader.read_u32::<LittleEndian>()?;
-		let mut morphs = Vec::with_capacity(num_morph as usize);
+		let num_morph = reader.read_u32::<LittleEndian>()? as usize;
+		let num_morph = read_count(reader, num_morph, 23, "morphs", "VMD")?;
+		let mut morphs = Vec::with_capacity(num_morph);
 
 		for _ in 0..num_morph
 		{
@@ -249,6 +402,25 @@ impl VMDMorph
 
 		Ok(morphs)
 	}
+
+	pub fn save<W:Write>(&self, writer:&mut W) -> Result<()>
+	{
+		VMDName::save(writer, &self.name, 15)?;
+		writer.write_u32::<LittleEndian>(self.frame)?;
+		writer.write_f32::<LittleEndian>(self.weight)?;
+		Ok(())
+	}
+
+	pub fn save_array<W:Write>(writer:&mut W, morphs:&[Self]) -> Result<()>
+	{
+		writer.write_u32::<LittleEndian>(morphs.len() as u32)?;
+		for it in morphs
+		{
+			it.save(writer)?;
+		}
+
+		Ok(())
+	}
 }
 
 impl VMDCamera
@@ -276,6 +448,7 @@ impl VMDCamera
 	{
 		let mut this = VMDCamera::new();
 		this.frame = reader.read_u32::<LittleEndian>()?;
+		this.length = reader.read_f32::<LittleEndian>()?;
 		this.position = VMDVector3::load(reader)?;
 		this.rotation = VMDVector3::load(reader)?;
 		for i in 0..this.bezier.len() { this.bezier[i] = reader.read_u8()?; }
@@ -287,8 +460,9 @@ impl VMDCamera
 
 	pub fn load_array(reader:&mut Cursor<&[u8]>) -> Result<Vec<Self>>
 	{
-		let num_camera = reader.read_u32::<LittleEndian>()?;
-		let mut cameras = Vec::with_capacity(num_camera as usize);
+		let num_camera = reader.read_u32::<LittleEndian>()? as usize;
+		let num_camera = read_count(reader, num_camera, 61, "cameras", "VMD")?;
+		let mut cameras = Vec::with_capacity(num_camera);
 
 		for _ in 0..num_camera
 		{
@@ -297,6 +471,41 @@ impl VMDCamera
 
 		Ok(cameras)
 	}
+
+	pub fn save<W:Write>(&self, writer:&mut W) -> Result<()>
+	{
+		writer.write_u32::<LittleEndian>(self.frame)?;
+		writer.write_f32::<LittleEndian>(self.length)?;
+		VMDVector3::save(writer, self.position)?;
+		VMDVector3::save(writer, self.rotation)?;
+		writer.write_all(&self.bezier)?;
+		writer.write_u32::<LittleEndian>(self.view_angle)?;
+		writer.write_u8(self.perspective)?;
+		Ok(())
+	}
+
+	pub fn save_array<W:Write>(writer:&mut W, cameras:&[Self]) -> Result<()>
+	{
+		writer.write_u32::<LittleEndian>(cameras.len() as u32)?;
+		for it in cameras
+		{
+			it.save(writer)?;
+		}
+
+		Ok(())
+	}
+
+	/// Control points (x1, y1, x2, y2) of a channel, 0 : X, 1 : Y, 2 : Z, 3 : Rotation, 4 : Distance, 5 : Fov.
+	pub fn bezier(&self, channel:usize) -> [u8;4]
+	{
+		let points = &self.bezier[channel * 4..channel * 4 + 4];
+		[points[0], points[2], points[1], points[3]]
+	}
+
+	pub fn interpolator(&self, channel:usize) -> Option<Box<Interpolator + 'static>>
+	{
+		VMDMotion::path_interpolator(self.bezier(channel))
+	}
 }
 
 impl VMDLight
@@ -328,8 +537,9 @@ impl VMDLight
 
 	pub fn load_array(reader:&mut Cursor<&[u8]>) -> Result<Vec<Self>>
 	{
-		let num_light = reader.read_u32::<LittleEndian>()?;
-		let mut lights = Vec::with_capacity(num_light as usize);
+		let num_light = reader.read_u32::<LittleEndian>()? as usize;
+		let num_light = read_count(reader, num_light, 28, "lights", "VMD")?;
+		let mut lights = Vec::with_capacity(num_light);
 
 		for _ in 0..num_light
 		{
@@ -338,6 +548,25 @@ impl VMDLight
 
 		Ok(lights)
 	}
+
+	pub fn save<W:Write>(&self, writer:&mut W) -> Result<()>
+	{
+		writer.write_u32::<LittleEndian>(self.frame)?;
+		VMDVector3::save(writer, self.rgb)?;
+		VMDVector3::save(writer, self.position)?;
+		Ok(())
+	}
+
+	pub fn save_array<W:Write>(writer:&mut W, lights:&[Self]) -> Result<()>
+	{
+		writer.write_u32::<LittleEndian>(lights.len() as u32)?;
+		for it in lights
+		{
+			it.save(writer)?;
+		}
+
+		Ok(())
+	}
 }
 
 impl VMDSelfShadow
@@ -369,8 +598,9 @@ impl VMDSelfShadow
 
 	pub fn load_array(reader:&mut Cursor<&[u8]>) -> Result<Vec<Self>>
 	{
-		let num_shadow = reader.read_u32::<LittleEndian>()?;
-		let mut shadows = Vec::with_capacity(num_shadow as usize);
+		let num_shadow = reader.read_u32::<LittleEndian>()? as usize;
+		let num_shadow = read_count(reader, num_shadow, 9, "self shadows", "VMD")?;
+		let mut shadows = Vec::with_capacity(num_shadow);
 
 		for _ in 0..num_shadow
 		{
@@ -379,6 +609,105 @@ impl VMDSelfShadow
 
 		Ok(shadows)
 	}
+
+	pub fn save<W:Write>(&self, writer:&mut W) -> Result<()>
+	{
+		writer.write_u32::<LittleEndian>(self.frame)?;
+		writer.write_u8(self.mode)?;
+		writer.write_f32::<LittleEndian>(self.distance)?;
+		Ok(())
+	}
+
+	pub fn save_array<W:Write>(writer:&mut W, shadows:&[Self]) -> Result<()>
+	{
+		writer.write_u32::<LittleEndian>(shadows.len() as u32)?;
+		for it in shadows
+		{
+			it.save(writer)?;
+		}
+
+		Ok(())
+	}
+}
+
+impl VMDProperty
+{
+	pub fn new() -> Self
+	{
+		Self
+		{
+			frame:0,
+			visible:1,
+			ik_states:Vec::new(),
+		}
+	}
+
+	pub fn valid(self) -> Result<Self>
+	{
+		Ok(self)
+	}
+
+	pub fn load(reader:&mut Cursor<&[u8]>) -> Result<Self>
+	{
+		let mut this = VMDProperty::new();
+		this.frame = reader.read_u32::<LittleEndian>()?;
+		this.visible = reader.read_u8()?;
+
+		for _ in 0..reader.read_u32::<LittleEndian>()?
+		{
+			let name = VMDName::load(reader, 20)?;
+			let enable = reader.read_u8()?;
+			this.ik_states.push(VMDIKState { name, enable });
+		}
+
+		this.valid()
+	}
+
+	pub fn load_array(reader:&mut Cursor<&[u8]>) -> Result<Vec<Self>>
+	{
+		// Files written before MikuMikuDance 7.40 end after the self shadow frames.
+		if reader.position() as usize >= reader.get_ref().len()
+		{
+			return Ok(Vec::new());
+		}
+
+		let num_property = reader.read_u32::<LittleEndian>()? as usize;
+		let num_property = read_count(reader, num_property, 9, "properties", "VMD")?;
+		let mut properties = Vec::with_capacity(num_property);
+
+		for _ in 0..num_property
+		{
+			properties.push(VMDProperty::load(reader)?);
+		}
+
+		Ok(properties)
+	}
+
+	pub fn save<W:Write>(&self, writer:&mut W) -> Result<()>
+	{
+		writer.write_u32::<LittleEndian>(self.frame)?;
+		writer.write_u8(self.visible)?;
+		writer.write_u32::<LittleEndian>(self.ik_states.len() as u32)?;
+
+		for it in self.ik_states.iter()
+		{
+			VMDName::save(writer, &it.name, 20)?;
+			writer.write_u8(it.enable)?;
+		}
+
+		Ok(())
+	}
+
+	pub fn save_array<W:Write>(writer:&mut W, properties:&[Self]) -> Result<()>
+	{
+		writer.write_u32::<LittleEndian>(properties.len() as u32)?;
+		for it in properties
+		{
+			it.save(writer)?;
+		}
+
+		Ok(())
+	}
 }
 
 impl VMDFile
@@ -393,6 +722,7 @@ impl VMDFile
 			cameras:Vec::new(),
 			lights:Vec::new(),
 			self_shadows:Vec::new(),
+			properties:Vec::new(),
 		}
 	}
 
@@ -408,87 +738,387 @@ impl VMDFile
 			cameras:VMDCamera::load_array(&mut reader)?,
 			lights:VMDLight::load_array(&mut reader)?,
 			self_shadows:VMDSelfShadow::load_array(&mut reader)?,
+			properties:VMDProperty::load_array(&mut reader)?,
 		})
 	}
 
-	fn collect_motions(&self) -> Animator
+	pub fn save(&self) -> Result<Vec<u8>>
 	{
-		let mut curves = HashMap::new();
-		for motion in self.motions.iter()
+		let mut writer = Vec::new();
+		self.header.save(&mut writer)?;
+		VMDMotion::save_array(&mut writer, &self.motions)?;
+		VMDMorph::save_array(&mut writer, &self.morphs)?;
+		VMDCamera::save_array(&mut writer, &self.cameras)?;
+		VMDLight::save_array(&mut writer, &self.lights)?;
+		VMDSelfShadow::save_array(&mut writer, &self.self_shadows)?;
+		VMDProperty::save_array(&mut writer, &self.properties)?;
+		Ok(writer)
+	}
+
+	/// Builds a file from clips laid out like `collect_motions` (bone clips) and `collect_morphs` (morph clips),
+	/// bone clips may also carry their rotation as "Rotation.X/Y/Z/W" curves.
+	pub fn from_animator(animator:&Animator) -> Self
+	{
+		let mut vmd = VMDFile::new();
+		vmd.header.magic = VMD_MAGIC.to_string();
+		vmd.header.name = animator.name.clone();
+		vmd.add_clips(animator, &Animator::new());
+		vmd
+	}
+
+	pub fn from_motion(motion:&Motion) -> Self
+	{
+		let mut vmd = VMDFile::new();
+		vmd.header.magic = VMD_MAGIC.to_string();
+		vmd.header.name = motion.name.clone();
+		vmd.add_clips(&motion.bones, &motion.rotations);
+		vmd.add_clips(&motion.morphs, &Animator::new());
+		vmd
+	}
+
+	fn add_clips(&mut self, animator:&Animator, rotations:&Animator<Quaternion>)
+	{
+		let mut names:Vec<&str> = animator.clips.iter().map(|clip| clip.name.as_str()).collect();
+		for clip in rotations.clips.iter()
+		{
+			if !names.contains(&clip.name.as_str()) { names.push(&clip.name); }
+		}
+
+		for name in names
 		{
-			if !curves.contains_key(&motion.name)
+			let clip = animator.clips.iter().find(|clip| clip.name == name);
+
+			if let Some(curve) = clip.and_then(|clip| clip.curves.get("Weight"))
 			{
-				curves.insert(motion.name.clone(), 
-					(
-						AnimationCurve::new(),
-						AnimationCurve::new(),
-						AnimationCurve::new(),
-						AnimationCurve::new(), 
-						AnimationCurve::new(),
-						AnimationCurve::new(),
-						AnimationCurve::new()
-					));
+				for time in frame_times(curve.frames.iter().map(|key| key.time))
+				{
+					let mut morph = VMDMorph::new();
+					morph.name = name.to_string();
+					morph.frame = time.round() as u32;
+					morph.weight = sample(Some(curve), time, 0.0);
+					self.morphs.push(morph);
+				}
+
+				continue;
 			}
 
-			if let Some(curve) = curves.get_mut(&motion.name)
+			let channels = ["Position.X", "Position.Y", "Position.Z", "Rotation.X", "Rotation.Y", "Rotation.Z", "Rotation.W"];
+			let curves:Vec<Option<&AnimationCurve>> = channels.iter().map(|channel| clip.and_then(|clip| clip.curves.get(*channel))).collect();
+			let rotation = rotations.clips.iter().find(|clip| clip.name == name).and_then(|clip| clip.curves.get("Rotation"));
+
+			let frames = frame_times(curves.iter()
+				.filter_map(|curve| *curve)
+				.flat_map(|curve| curve.frames.iter().map(|key| key.time))
+				.chain(rotation.iter().flat_map(|curve| curve.frames.iter().map(|key| key.time))));
+
+			for time in frames
 			{
-				curve.0.add_keyframe(motion.frame as f32, motion.position.0, None);
-				curve.1.add_keyframe(motion.frame as f32, motion.position.1, None);
-				curve.2.add_keyframe(motion.frame as f32, motion.position.2, None);
-				curve.3.add_keyframe(motion.frame as f32, motion.rotate.0, None);
-				curve.4.add_keyframe(motion.frame as f32, motion.rotate.1, None);
-				curve.5.add_keyframe(motion.frame as f32, motion.rotate.2, None);
-				curve.6.add_keyframe(motion.frame as f32, motion.rotate.3, None);
+				let mut motion = VMDMotion::new();
+				motion.name = name.to_string();
+				motion.frame = time.round() as u32;
+				motion.position = (sample(curves[0], time, 0.0), sample(curves[1], time, 0.0), sample(curves[2], time, 0.0));
+				motion.rotate = match rotation
+				{
+					Some(curve) => sample(Some(curve), time, Quaternion::new(0.0, 0.0, 0.0, 1.0)).to_tuple(),
+					None => (sample(curves[3], time, 0.0), sample(curves[4], time, 0.0), sample(curves[5], time, 0.0), sample(curves[6], time, 1.0)),
+				};
+				for channel in 0..3
+				{
+					motion.set_channel_bezier(channel, VMDMotion::segment_bezier(curves[channel], time));
+				}
+
+				motion.set_channel_bezier(3, match rotation
+				{
+					Some(curve) => VMDMotion::segment_bezier(Some(curve), time),
+					None => VMDMotion::segment_bezier(curves[3], time),
+				});
+
+				self.motions.push(motion);
 			}
 		}
+	}
+
+	/// Bone positions and rotations as "Position.X/Y/Z" and "Rotation.X/Y/Z/W" curves, kept for animators
+	/// built before `Motion`. The rotation components are interpolated one by one, which is lossy as the
+	/// rotation between keys leaves the unit sphere and doesn't take the shortest path, `collect` and
+	/// `collect_rotations` slerp them instead.
+	pub fn collect_motions(&self) -> Animator
+	{
+		self.collect_bone_channels(7)
+	}
+
+	/// Bone positions as "Position.X/Y/Z" curves, see `collect_rotations` for the rotations.
+	pub fn collect_positions(&self) -> Animator
+	{
+		self.collect_bone_channels(3)
+	}
+
+	fn collect_bone_channels(&self, count:usize) -> Animator
+	{
+		let names = ["Position.X", "Position.Y", "Position.Z", "Rotation.X", "Rotation.Y", "Rotation.Z", "Rotation.W"];
 
 		let mut animator = Animator::new();
-		for (key, curve) in curves
+		for (key, frames) in self.bone_frames()
 		{
+			let mut curves:Vec<AnimationCurve> = (0..count).map(|_| AnimationCurve::new()).collect();
+
+			for (i, motion) in frames.iter().enumerate()
+			{
+				let values = [motion.position.0, motion.position.1, motion.position.2, motion.rotate.0, motion.rotate.1, motion.rotate.2, motion.rotate.3];
+
+				// The bezier stored on a keyframe shapes the segment which ends at it.
+				let next = frames.get(i + 1);
+
+				for (channel, curve) in curves.iter_mut().enumerate()
+				{
+					curve.add_keyframe(motion.frame as f32, values[channel], next.and_then(|next| next.interpolator(channel.min(3))));
+				}
+			}
+
 			let mut clip = AnimationClip::new();
-			clip.set_name(&key);
-			clip.set_curve("Position.X", curve.0);
-			clip.set_curve("Position.Y", curve.1);
-			clip.set_curve("Position.Z", curve.2);
-			clip.set_curve("Rotation.X", curve.3);
-			clip.set_curve("Rotation.Y", curve.4);
-			clip.set_curve("Rotation.Z", curve.5);
-			clip.set_curve("Rotation.W", curve.6);
-			
+			clip.set_name(key);
+
+			for (name, curve) in names.iter().zip(curves.into_iter())
+			{
+				clip.set_curve(name, curve);
+			}
+
 			animator.add_clip(clip);
 		}
 
 		animator
 	}
 
-	fn collect_morphs(&self) -> Animator
+	/// One "Rotation" curve per bone, slerped along the shortest path.
+	pub fn collect_rotations(&self) -> Animator<Quaternion>
 	{
-		let mut curves = HashMap::new();
-		for morph in self.morphs.iter()
+		let mut animator = Animator::new();
+		for (key, frames) in self.bone_frames()
 		{
-			if !curves.contains_key(&morph.name)
+			let mut curve = AnimationCurve::new();
+
+			for (i, motion) in frames.iter().enumerate()
 			{
-				curves.insert(morph.name.clone(), AnimationCurve::new());
+				let rotate = Quaternion::new(motion.rotate.0, motion.rotate.1, motion.rotate.2, motion.rotate.3);
+				curve.add_keyframe(motion.frame as f32, rotate, frames.get(i + 1).and_then(|next| next.interpolator(3)));
 			}
 
-			if let Some(curve) = curves.get_mut(&morph.name)
+			let mut clip = AnimationClip::new();
+			clip.set_name(key);
+			clip.set_curve("Rotation", curve);
+
+			animator.add_clip(clip);
+		}
+
+		animator
+	}
+
+	fn bone_frames(&self) -> HashMap<&str, Vec<&VMDMotion>>
+	{
+		let mut motions:HashMap<&str, Vec<&VMDMotion>> = HashMap::new();
+		for motion in self.motions.iter()
+		{
+			motions.entry(&motion.name).or_insert(Vec::new()).push(motion);
+		}
+
+		for frames in motions.values_mut()
+		{
+			frames.sort_by_key(|motion| motion.frame);
+		}
+
+		motions
+	}
+
+	pub fn collect_morphs(&self) -> Animator
+	{
+		let mut morphs:HashMap<&str, Vec<&VMDMorph>> = HashMap::new();
+		for morph in self.morphs.iter()
+		{
+			morphs.entry(&morph.name).or_insert(Vec::new()).push(morph);
+		}
+
+		let mut animator = Animator::new();
+		for (key, mut frames) in morphs
+		{
+			frames.sort_by_key(|morph| morph.frame);
+
+			let mut curve = AnimationCurve::new();
+			for morph in frames
 			{
 				curve.add_keyframe(morph.frame as f32, morph.weight, None);
 			}
+
+			let mut clip = AnimationClip::new();
+			clip.set_name(key);
+			clip.set_curve("Weight", curve);
+
+			animator.add_clip(clip);
+		}
+
+		animator
+	}
+
+	/// A single "Camera" clip, the camera orbits Target at Distance (negative in front of it) with an euler Rotation in radians.
+	pub fn collect_cameras(&self) -> Animator
+	{
+		let mut frames:Vec<&VMDCamera> = self.cameras.iter().collect();
+		frames.sort_by_key(|camera| camera.frame);
+
+		let mut curves:Vec<AnimationCurve> = (0..9).map(|_| AnimationCurve::new()).collect();
+
+		for (i, camera) in frames.iter().enumerate()
+		{
+			let values = [
+				camera.length,
+				camera.position.0, camera.position.1, camera.position.2,
+				camera.rotation.0, camera.rotation.1, camera.rotation.2,
+				camera.view_angle as f32,
+				camera.perspective as f32
+			];
+
+			// Same as bones, the bezier stored on a keyframe shapes the segment which ends at it.
+			let next = frames.get(i + 1);
+			let channels = [4, 0, 1, 2, 3, 3, 3, 5];
+
+			for (channel, curve) in curves.iter_mut().enumerate()
+			{
+				let interpolator:Option<Box<Interpolator + 'static>> = match channels.get(channel)
+				{
+					Some(bezier) => next.and_then(|next| next.interpolator(*bezier)),
+					None => Some(Box::new(FixedInterpolator::new(0.0))),
+				};
+
+				curve.add_keyframe(camera.frame as f32, values[channel], interpolator);
+			}
 		}
 
 		let mut animator = Animator::new();
-		for (key, curve) in curves
+		if frames.len() > 0
 		{
+			let mut curves = curves.into_iter();
+
 			let mut clip = AnimationClip::new();
-			clip.set_name(&key);
-			clip.set_curve("Weight", curve);
+			clip.set_name("Camera");
+			clip.set_curve("Distance", curves.next().unwrap());
+			clip.set_curve("Target.X", curves.next().unwrap());
+			clip.set_curve("Target.Y", curves.next().unwrap());
+			clip.set_curve("Target.Z", curves.next().unwrap());
+			clip.set_curve("Rotation.X", curves.next().unwrap());
+			clip.set_curve("Rotation.Y", curves.next().unwrap());
+			clip.set_curve("Rotation.Z", curves.next().unwrap());
+			clip.set_curve("Fov", curves.next().unwrap());
+			clip.set_curve("Perspective", curves.next().unwrap());
+
+			animator.add_clip(clip);
+		}
+
+		animator
+	}
+
+	pub fn collect_lights(&self) -> Animator
+	{
+		let mut frames:Vec<&VMDLight> = self.lights.iter().collect();
+		frames.sort_by_key(|light| light.frame);
+
+		let mut curves:Vec<AnimationCurve> = (0..6).map(|_| AnimationCurve::new()).collect();
+
+		for light in frames.iter()
+		{
+			let values = [light.rgb.0, light.rgb.1, light.rgb.2, light.position.0, light.position.1, light.position.2];
+
+			for (channel, curve) in curves.iter_mut().enumerate()
+			{
+				curve.add_keyframe(light.frame as f32, values[channel], None);
+			}
+		}
+
+		let mut animator = Animator::new();
+		if frames.len() > 0
+		{
+			let mut curves = curves.into_iter();
+
+			let mut clip = AnimationClip::new();
+			clip.set_name("Light");
+			clip.set_curve("Color.R", curves.next().unwrap());
+			clip.set_curve("Color.G", curves.next().unwrap());
+			clip.set_curve("Color.B", curves.next().unwrap());
+			clip.set_curve("Direction.X", curves.next().unwrap());
+			clip.set_curve("Direction.Y", curves.next().unwrap());
+			clip.set_curve("Direction.Z", curves.next().unwrap());
+
+			animator.add_clip(clip);
+		}
+
+		animator
+	}
+
+	pub fn collect_self_shadows(&self) -> Animator
+	{
+		let mut frames:Vec<&VMDSelfShadow> = self.self_shadows.iter().collect();
+		frames.sort_by_key(|shadow| shadow.frame);
+
+		let mut mode = AnimationCurve::new();
+		let mut distance = AnimationCurve::new();
+
+		for shadow in frames.iter()
+		{
+			mode.add_keyframe(shadow.frame as f32, shadow.mode as f32, Some(Box::new(FixedInterpolator::new(0.0))));
+			distance.add_keyframe(shadow.frame as f32, shadow.distance, None);
+		}
+
+		let mut animator = Animator::new();
+		if frames.len() > 0
+		{
+			let mut clip = AnimationClip::new();
+			clip.set_name("SelfShadow");
+			clip.set_curve("Mode", mode);
+			clip.set_curve("Distance", distance);
 
 			animator.add_clip(clip);
 		}
 
 		animator
 	}
+
+	pub fn collect(&self) -> Motion
+	{
+		let mut motion = Motion::new();
+		motion.name = self.header.name.clone();
+		motion.bones = self.collect_positions();
+		motion.rotations = self.collect_rotations();
+		motion.morphs = self.collect_morphs();
+		motion.cameras = self.collect_cameras();
+		motion.lights = self.collect_lights();
+		motion.self_shadows = self.collect_self_shadows();
+		motion
+	}
+}
+
+// Keys are written at whole frames, keys before the first frame or without a frame number are skipped
+// and of the keys rounding to the same frame only the first one is kept.
+fn frame_times<I:Iterator<Item=f32>>(times:I) -> Vec<f32>
+{
+	let mut frames:Vec<f32> = times.filter(|time| time.is_finite() && *time >= 0.0).collect();
+	frames.sort_by(|a, b| a.total_cmp(b));
+	frames.dedup_by_key(|time| time.round() as u32);
+	frames
+}
+
+// Channels without their own key at this frame are sampled from the curve.
+fn sample<T:Animation + Copy + Identity>(curve:Option<&AnimationCurve<T>>, time:f32, default:T) -> T
+{
+	match curve
+	{
+		Some(curve) if curve.frames.len() > 0 =>
+		{
+			match curve.frames.iter().find(|key| key.time == time)
+			{
+				Some(key) => key.value,
+				None => curve.evaluate(time),
+			}
+		},
+		_ => default
+	}
 }
 
 #[derive(Debug)]
@@ -503,19 +1133,16 @@ impl VMDLoader
 		}
 	}
 
-	pub fn do_save(_buf:&[u8], _model:&mut VMDFile) -> Result<()>
+	pub fn do_save(&self, motion:&Motion) -> Result<Vec<u8>>
 	{
-		Err(Error("Not Implmention yet".to_string()))
+		VMDFile::from_motion(motion).save()
 	}
 
-	pub fn open<P: AsRef<std::path::Path>>(path:P) -> Result<Animator>
+	pub fn open<P: AsRef<std::path::Path>>(path:P) -> Result<Motion>
 	{
 		let mut buffer = Vec::new();
 		File::open(path)?.read_to_end(&mut buffer)?;
-		let vmd = VMDFile::load(&buffer)?;
-		let motions = vmd.collect_motions();
-
-		Ok(motions)
+		VMDFile::load(&buffer).map(|vmd| vmd.collect())
 	}
 }
 
@@ -526,11 +1153,9 @@ impl Loader for VMDLoader
 		VMDHeader::load(&mut Cursor::new(buf)).is_ok()
 	}
 
-	fn do_load(&self, buf:&[u8]) -> Result<Animator>
+	/// Loads every track of the file, with one slerped rotation curve per bone.
+	fn do_load(&self, buf:&[u8]) -> Result<Motion>
 	{
-		let vmd = VMDFile::load(buf)?;
-		let motions = vmd.collect_motions();
-
-		Ok(motions)
+		Ok(VMDFile::load(buf)?.collect())
 	}
 }
\ No newline at end of file
@@ -13,7 +13,7 @@ pub use self::loaders::*;
 use std::io::prelude::*;
 use std::fs::File;
 
-pub fn load_from_memory(buf:&[u8]) -> Result<Animator>
+pub fn load_from_memory(buf:&[u8]) -> Result<Motion>
 {
 	let loaders = vec![Box::new(VMDLoader::new())];
 
@@ -28,12 +28,12 @@ pub fn load_from_memory(buf:&[u8]) -> Result<Animator>
 	Err(Error("Not supported yet".to_string()))
 }
 
-pub fn load_from_buf<R:BufRead + Seek>(mut r:R) -> Result<Animator>
+pub fn load_from_buf<R:BufRead + Seek>(mut r:R) -> Result<Motion>
 {
 	load_from_memory(r.fill_buf()?)
 }
 
-pub fn open<P: AsRef<std::path::Path>>(path:P) -> Result<Animator>
+pub fn open<P: AsRef<std::path::Path>>(path:P) -> Result<Motion>
 {
 	let mut buffer = Vec::new();
 	File::open(path)?.read_to_end(&mut buffer)?;
@@ -0,0 +1,3 @@
+pub mod reader;
+
+pub use self::reader::*;
\ No newline at end of file
@@ -0,0 +1,14 @@
+use std::io::Cursor;
+
+/// Checks a count read from a file against the bytes left, before anything is allocated for it.
+/// The error names what was counted and the format, like "Invalid count of vertices in PMX".
+pub fn read_count(reader:&Cursor<&[u8]>, count:usize, size:usize, what:&str, format:&str) -> Result<usize, String>
+{
+	let remaining = reader.get_ref().len().saturating_sub(reader.position() as usize);
+	if count.saturating_mul(size) > remaining
+	{
+		return Err(format!("Invalid count of {} in {}", what, format));
+	}
+
+	Ok(count)
+}
@@ -20,6 +20,8 @@ pub mod models;
 pub mod scene;
 pub mod renderer;
 
+mod binary;
+
 pub use self::animation::*;
 pub use self::math::*;
 pub use self::models::*;
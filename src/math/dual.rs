@@ -6,6 +6,7 @@ use super::vec::{Vec, Math, Lerp, Slerp};
 use super::quat::Quat;
 use super::vec3::Vec3;
 use super::consts::{Zero, One};
+use super::super::animation::Animation;
 
 // http://wscg.zcu.cz/wscg2012/short/a29-full.pdf
 #[repr(C)]
@@ -174,10 +175,61 @@ impl<T> Dual<T> where T:Vec + Math
 		Self
 		{
 			real:rotation,
-			dual:Quat::new(t.x,t.y,t.z,T::zero()) * rotation * T::onehalf()
+			dual:Quat::new(t.x,t.y,t.z,T::zero()).concat(rotation) * T::onehalf()
 		}
 	}
 
+	/// Dual quaternion product, applying b first and then self.
+	pub fn product(&self, b:Self) -> Self
+	{
+		Self
+		{
+			real:self.real.concat(b.real),
+			dual:self.real.concat(b.dual) + self.dual.concat(b.real),
+		}
+	}
+
+	/// Raises a unit dual quaternion to the power t by scaling its screw angle and pitch.
+	pub fn pow(&self, t:T) -> Self
+	{
+		let axis = Vec3::new(self.real.x, self.real.y, self.real.z);
+		let sin_half = axis.length();
+
+		if sin_half.le(T::zero())
+		{
+			return Self
+			{
+				real:Quat::new(T::zero(), T::zero(), T::zero(), T::one()),
+				dual:self.dual * t,
+			};
+		}
+
+		let half = self.real.w.clamp(-T::one(), T::one()).acos();
+		let axis = axis / sin_half;
+		let pitch = -self.dual.w / sin_half;
+		let moment = (Vec3::new(self.dual.x, self.dual.y, self.dual.z) - axis * (pitch * self.real.w)) / sin_half;
+
+		let half = half * t;
+		let pitch = pitch * t;
+		let (s, c) = half.sincos();
+
+		let real = axis * s;
+		let dual = moment * s + axis * (pitch * c);
+
+		Self
+		{
+			real:Quat::new(real.x, real.y, real.z, c),
+			dual:Quat::new(dual.x, dual.y, dual.z, -pitch * s),
+		}
+	}
+
+	/// Screw linear interpolation, rotates and translates along a single screw motion at constant speed.
+	pub fn sclerp(&self, b:Self, t:T) -> Self
+	{
+		let b = if self.dot(b).lt(T::zero()) { b * -T::one() } else { b };
+		self.product(self.conjugate().product(b).pow(t))
+	}
+
 	pub fn dot(&self, b: Self) -> T 
 	{
 		return self.real.dot(b.real);
@@ -212,7 +264,7 @@ impl<T> Dual<T> where T:Vec + Math
 
 	pub fn translate(&self) -> Vec3<T>
 	{
-		let t = self.dual * T::two() * self.real.conj();
+		let t = self.dual.concat(self.real.conj()) * T::two();
 		Vec3::new(t.x, t.y, t.z)
 	}
 
@@ -264,6 +316,14 @@ impl<T> Slerp<T> for Dual<T> where T: Vec + Math
 	}
 }
 
+impl<T> Animation<T> for Dual<T> where T: Vec + Math
+{
+	fn animation(&self, rhs: &Self, t:T) -> Self
+	{
+		self.sclerp(*rhs, t)
+	}
+}
+
 impl<T> Zero for Dual<T> where T:Zero
 {
 	#[inline(always)]
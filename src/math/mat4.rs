@@ -386,6 +386,36 @@ impl<T> Mat4<T> where T:Vec + Math + PiByC180
 		)
 	}
 
+	/// Splits an affine transform into translate, rotation and scale so that
+	/// `translate * rotation * scale` rebuilds it, a mirrored basis flips the sign of scale.x.
+	pub fn decompose(&self) -> (Vec3<T>, Quat<T>, Vec3<T>)
+	{
+		let translate = Vec3::new(self.d1, self.d2, self.d3);
+
+		let x = Vec3::new(self.a1, self.a2, self.a3);
+		let y = Vec3::new(self.b1, self.b2, self.b3);
+		let z = Vec3::new(self.c1, self.c2, self.c3);
+
+		let mut scale = Vec3::new(x.length(), y.length(), z.length());
+		if x.cross(y).dot(z).lt(T::zero())
+		{
+			scale.x = -scale.x;
+		}
+
+		let unit = |v:Vec3<T>, s:T| if s.eq(T::zero()) { v } else { v / s };
+		let x = unit(x, scale.x);
+		let y = unit(y, scale.y);
+		let z = unit(z, scale.z);
+
+		let rotation = Mat4::new(
+			x.x, x.y, x.z, T::zero(),
+			y.x, y.y, y.z, T::zero(),
+			z.x, z.y, z.z, T::zero(),
+			T::zero(), T::zero(), T::zero(), T::one());
+
+		(translate, Quat::from(rotation).normalize(), scale)
+	}
+
 	pub fn det(&self) -> T
 	{
 		let m = self;
@@ -5,7 +5,9 @@ use std::ops::{AddAssign, SubAssign, MulAssign, DivAssign};
 use super::vec::{Vec, Math, Lerp, Slerp};
 use super::vec3::Vec3;
 use super::vec4::Vec4;
-use super::consts::{Zero, One};
+use super::mat4::Mat4;
+use super::consts::{Zero, One, Epsilon};
+use super::super::animation::Animation;
 
 #[repr(C)]
 #[derive(Debug, Copy, Clone, Default, Hash, Eq, PartialEq)]
@@ -17,6 +19,43 @@ pub struct Quat<T>
 	pub w:T,
 }
 
+/// Order in which euler angles are applied, `XYZ` rotates around X first and Z last.
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
+pub enum RotationOrder
+{
+	XYZ,
+	XZY,
+	YXZ,
+	YZX,
+	ZXY,
+	ZYX,
+}
+
+impl RotationOrder
+{
+	// Axes in application order, and whether they form an even permutation.
+	fn axes(&self) -> (usize, usize, usize, bool)
+	{
+		match *self
+		{
+			RotationOrder::XYZ => (0, 1, 2, true),
+			RotationOrder::XZY => (0, 2, 1, false),
+			RotationOrder::YXZ => (1, 0, 2, false),
+			RotationOrder::YZX => (1, 2, 0, true),
+			RotationOrder::ZXY => (2, 0, 1, true),
+			RotationOrder::ZYX => (2, 1, 0, false),
+		}
+	}
+}
+
+impl Default for RotationOrder
+{
+	fn default() -> Self
+	{
+		RotationOrder::XYZ
+	}
+}
+
 impl<T> Neg for Quat<T> where T:Neg<Output=T>
 {
 	type Output = Self;
@@ -341,6 +380,61 @@ impl<T> Quat<T> where T:Vec + Math
 		}
 	}
 
+	/// Composes the three axis rotations of an euler angle in the given order.
+	pub fn euler(euler:&Vec3<T>, order:RotationOrder) -> Self
+	{
+		let (i, j, k, _) = order.axes();
+		let angles = [euler.x, euler.y, euler.z];
+		let axis = |n:usize| match n
+		{
+			0 => Self::rotation_x(angles[0]),
+			1 => Self::rotation_y(angles[1]),
+			_ => Self::rotation_z(angles[2]),
+		};
+
+		axis(k).concat(axis(j)).concat(axis(i))
+	}
+
+	/// Euler angles that rebuild this rotation through `Quat::euler` with the same order.
+	/// At gimbal lock the last axis is set to zero and the first one takes the whole turn.
+	pub fn to_euler(&self, order:RotationOrder) -> Vec3<T> where T:Epsilon
+	{
+		let (i, j, k, even) = order.axes();
+		let m = self.to_matrix3();
+		let s = if even { T::one() } else { -T::one() };
+
+		let cos_j = (m[k][j] * m[k][j] + m[k][k] * m[k][k]).sqrt();
+
+		let mut angles = [T::zero(); 3];
+		angles[j] = (-s * m[k][i]).atan2(cos_j);
+
+		if cos_j.gt(T::epsilon())
+		{
+			angles[i] = (s * m[k][j]).atan2(m[k][k]);
+			angles[k] = (s * m[j][i]).atan2(m[i][i]);
+		}
+		else
+		{
+			angles[i] = (-s * m[j][k]).atan2(m[j][j]);
+			angles[k] = T::zero();
+		}
+
+		Vec3::new(angles[0], angles[1], angles[2])
+	}
+
+	// Rotation matrix of a unit quaternion, indexed as [row][column].
+	fn to_matrix3(&self) -> [[T; 3]; 3]
+	{
+		let (x, y, z, w) = (self.x, self.y, self.z, self.w);
+		let two = T::two();
+
+		[
+			[T::one() - two * (y * y + z * z), two * (x * y - w * z), two * (x * z + w * y)],
+			[two * (x * y + w * z), T::one() - two * (x * x + z * z), two * (y * z - w * x)],
+			[two * (x * z - w * y), two * (y * z + w * x), T::one() - two * (x * x + y * y)],
+		]
+	}
+
 	pub fn dot(&self, b: Self) -> T 
 	{
 		return self.x * b.x + self.y * b.y + self.z * b.z + self.w * b.w;
@@ -429,6 +523,27 @@ impl<T> Quat<T> where T:Vec + Math
 	{
 		self.conjugate()
 	}
+
+	/// Hamilton product self * b, rotating by b first and then by self. `Mul` is component-wise.
+	pub fn concat(&self, b: Self) -> Self
+	{
+		b.cross(*self)
+	}
+
+	/// Rotates a vector by a unit quaternion.
+	pub fn rotate(&self, v: Vec3<T>) -> Vec3<T>
+	{
+		let u = Vec3::new(self.x, self.y, self.z);
+		let t = u.cross(v) * T::two();
+		v + t * self.w + u.cross(t)
+	}
+
+	/// Normalized lerp along the shortest path, cheaper than slerp but not constant speed.
+	pub fn nlerp(&self, b: Self, t: T) -> Self
+	{
+		let b = if self.dot(b).lt(T::zero()) { -b } else { b };
+		(*self * (T::one() - t) + b * t).normalize()
+	}
 }
 
 impl<T> Math for Quat<T> where T:Copy + Math
@@ -535,6 +650,15 @@ impl<T> Math for Quat<T> where T:Copy + Math
 		Self { x: mx, y: my, z: mz, w:mw }
 	}
 
+	fn atan2(self, _rhs: Self) -> Self
+	{
+		let mx = self.x.atan2(_rhs.x);
+		let my = self.y.atan2(_rhs.y);
+		let mz = self.z.atan2(_rhs.z);
+		let mw = self.w.atan2(_rhs.w);
+		Self { x: mx, y: my, z: mz, w:mw }
+	}
+
 	fn exp(self) -> Self
 	{
 		let mx = self.x.exp();
@@ -668,13 +792,34 @@ impl<T> Slerp<T> for Quat<T> where T: Vec + Math
 {
 	fn slerp(self, other: Self, factor: T) -> Self 
 	{
-		let dot = self.dot(other);
+		let mut cos_theta = self.dot(other);
+		let mut other = other;
+
+		if cos_theta.lt(T::zero())
+		{
+			cos_theta = -cos_theta;
+			other = -other;
+		}
+
+		let theta = cos_theta.min(T::one()).acos();
+		let sin_theta = theta.sin();
+		if sin_theta.le(T::zero())
+		{
+			return self.nlerp(other, factor);
+		}
+
+		let s = ((T::one() - factor) * theta).sin() / sin_theta;
+		let t = (factor * theta).sin() / sin_theta;
 
-		let s = T::one() - factor;
-		let t = if dot.gt(T::zero()) { factor } else { -factor };
-		let q = self * s + other * t;
+		(self * s + other * t).normalize()
+	}
+}
 
-		q.normalize()
+impl<T> Animation<T> for Quat<T> where T: Vec + Math
+{
+	fn animation(&self, rhs: &Self, t:T) -> Self
+	{
+		self.slerp(*rhs, t)
 	}
 }
 
@@ -763,6 +908,37 @@ impl<T> From<(T,T,T,T)> for Quat<T> where T:Copy + Div<Output=T>
 	}
 }
 
+/// Rotation of the upper 3x3 part, which is expected to be orthonormal.
+impl<T> From<Mat4<T>> for Quat<T> where T:Vec + Math
+{
+	fn from(m:Mat4<T>) -> Self
+	{
+		let trace = m.a1 + m.b2 + m.c3;
+		let quarter = T::onehalf() * T::onehalf();
+
+		if trace.gt(T::zero())
+		{
+			let s = (trace + T::one()).sqrt() * T::two();
+			Self { w:quarter * s, x:(m.b3 - m.c2) / s, y:(m.c1 - m.a3) / s, z:(m.a2 - m.b1) / s }
+		}
+		else if m.a1.gt(m.b2) && m.a1.gt(m.c3)
+		{
+			let s = (T::one() + m.a1 - m.b2 - m.c3).sqrt() * T::two();
+			Self { w:(m.b3 - m.c2) / s, x:quarter * s, y:(m.b1 + m.a2) / s, z:(m.c1 + m.a3) / s }
+		}
+		else if m.b2.gt(m.c3)
+		{
+			let s = (T::one() + m.b2 - m.a1 - m.c3).sqrt() * T::two();
+			Self { w:(m.c1 - m.a3) / s, x:(m.b1 + m.a2) / s, y:quarter * s, z:(m.c2 + m.b3) / s }
+		}
+		else
+		{
+			let s = (T::one() + m.c3 - m.a1 - m.b2).sqrt() * T::two();
+			Self { w:(m.a2 - m.b1) / s, x:(m.c1 + m.a3) / s, y:(m.c2 + m.b3) / s, z:quarter * s }
+		}
+	}
+}
+
 impl<T> AsRef<Quat<T>> for Quat<T>
 {
 	fn as_ref(&self) -> &Quat<T>
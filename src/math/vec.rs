@@ -38,6 +38,7 @@ pub trait Math: Sized
 	fn acos(self) -> Self;
 	fn asin(self) -> Self;
 	fn atan(self) -> Self;
+	fn atan2(self, rhs: Self) -> Self;
 	fn exp(self) -> Self;
 	fn exp2(self) -> Self;
 	fn log(self, rhs: Self) -> Self;
@@ -66,6 +67,7 @@ impl Math for f32
 	#[inline(always)] fn acos(self) -> Self { f32::acos(self) }
 	#[inline(always)] fn asin(self) -> Self { f32::asin(self) }
 	#[inline(always)] fn atan(self) -> Self { f32::atan(self) }
+	#[inline(always)] fn atan2(self, x: f32) -> Self { f32::atan2(self, x) }
 	#[inline(always)] fn exp(self) -> Self { f32::exp(self) }
 	#[inline(always)] fn exp2(self) -> Self { f32::exp2(self) }
 	#[inline(always)] fn log(self, y:f32) -> Self { f32::log(self, y) }
@@ -94,6 +96,7 @@ impl Math for f64
 	#[inline(always)] fn acos(self) -> Self { f64::acos(self) }
 	#[inline(always)] fn asin(self) -> Self { f64::asin(self) }
 	#[inline(always)] fn atan(self) -> Self { f64::atan(self) }
+	#[inline(always)] fn atan2(self, x: f64) -> Self { f64::atan2(self, x) }
 	#[inline(always)] fn exp(self) -> Self { f64::exp(self) }
 	#[inline(always)] fn exp2(self) -> Self { f64::exp2(self) }
 	#[inline(always)] fn log(self, y:f64) -> Self { f64::log(self, y) }
@@ -405,6 +405,14 @@ impl<T> Math for Vec2<T> where T:Copy + Math
 		Self { x: mx, y: my }
 	}
 
+	#[inline]
+	fn atan2(self, _rhs: Self) -> Self
+	{
+		let mx = self.x.atan2(_rhs.x);
+		let my = self.y.atan2(_rhs.y);
+		Self { x: mx, y: my }
+	}
+
 	#[inline]
 	fn exp(self) -> Self
 	{
@@ -6,6 +6,7 @@ use super::vec::{Vec, Math, Lerp, Slerp};
 use super::vec2::Vec2;
 use super::vec4::Vec4;
 use super::consts::{Zero, One, UnitX, UnitY, UnitZ};
+use super::super::animation::Animation;
 
 #[repr(C)]
 #[derive(Debug, Copy, Clone, Default, Hash, Eq, PartialEq)]
@@ -610,6 +611,15 @@ impl<T> Math for Vec3<T> where T:Copy + Math
 		let mz = self.z.atan();
 		Self { x: mx, y: my, z: mz }
 	}
+
+	#[inline]
+	fn atan2(self, _rhs: Self) -> Self
+	{
+		let mx = self.x.atan2(_rhs.x);
+		let my = self.y.atan2(_rhs.y);
+		let mz = self.z.atan2(_rhs.z);
+		Self { x: mx, y: my, z: mz }
+	}
 	
 	#[inline]
 	fn exp(self) -> Self
@@ -738,6 +748,14 @@ impl<T> Lerp<T> for Vec3<T> where T: Copy + One + Mul<Output=T> + Add<Output=T>
 	}
 }
 
+impl<T> Animation<T> for Vec3<T> where T: Copy + One + Mul<Output=T> + Add<Output=T> + Sub<Output=T>
+{
+	fn animation(&self, rhs: &Self, t:T) -> Self
+	{
+		self.lerp(*rhs, t)
+	}
+}
+
 impl<T> Slerp<T> for Vec3<T> where T: Vec + Math
 {
 	fn slerp(self, other: Self, factor: T) -> Self 
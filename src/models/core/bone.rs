@@ -1,3 +1,20 @@
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BoneGrant
+{
+    pub parent:u16,
+    pub ratio:f32,
+    pub rotation:bool,
+    pub translation:bool,
+}
+
+/// Where a bone points to, drawn by editors and used to aim the bone.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum BoneTail
+{
+    Offset(f32,f32,f32), // relative to the bone position
+    Bone(u16),
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Bone
 {
@@ -5,6 +22,28 @@ pub struct Bone
     pub parent:Option<u16>,
     pub position:(f32,f32,f32),
     pub rotation:(f32,f32,f32),
+    pub tail:BoneTail,
+    pub order:i32, // bones are deformed by order, then by index
+    pub after_physics:bool,
+    pub grant:Option<BoneGrant>,
+    pub rotatable:bool,
+    pub movable:bool,
+    pub visible:bool,
+    pub operable:bool,
+}
+
+impl BoneGrant
+{
+    pub fn new() -> Self
+    {
+        Self
+        {
+            parent:0,
+            ratio:1.0,
+            rotation:false,
+            translation:false,
+        }
+    }
 }
 
 impl Bone
@@ -17,6 +56,14 @@ impl Bone
             parent:None,
             position:(0.0,0.0,0.0),
             rotation:(0.0,0.0,0.0),
+            tail:BoneTail::Offset(0.0,0.0,0.0),
+            order:0,
+            after_physics:false,
+            grant:None,
+            rotatable:true,
+            movable:true,
+            visible:true,
+            operable:true,
         }
     }
-}
\ No newline at end of file
+}
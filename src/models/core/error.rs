@@ -61,4 +61,12 @@ impl From<std::string::FromUtf16Error> for Error
 	}
 }
 
+impl From<String> for Error
+{
+	fn from(err: String) -> Error
+	{
+		Error(err)
+	}
+}
+
 pub type Result<T> = std::result::Result<T, Error>;
\ No newline at end of file
@@ -1,5 +1,22 @@
 use std::collections::HashMap;
 
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SphereMode
+{
+	None,
+	Multiply,
+	Additive,
+	SubTexture,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Toon
+{
+	None,
+	Shared(u8), // 0 ~ 9 : toon01.bmp ~ toon10.bmp
+	Texture(usize),
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Material
 {
@@ -15,6 +32,19 @@ pub struct Material
 	pub specular_texture: Option<usize>,
 	pub normal_texture: Option<usize>,
 	pub dissolve_texture: Option<usize>,
+	pub sphere_texture: Option<usize>,
+	pub sphere_mode: SphereMode,
+	pub toon: Toon,
+	pub edge_color: [f32; 4],
+	pub edge_size: f32,
+	pub double_sided: bool,
+	pub ground_shadow: bool,
+	pub cast_shadow: bool,
+	pub receive_shadow: bool,
+	pub edge: bool,
+	pub vertex_color: bool,
+	pub point_draw: bool,
+	pub line_draw: bool,
 	pub illumination_model: Option<u8>,
 	pub unknown_param: HashMap<String, String>,
 }
@@ -37,8 +67,29 @@ impl Material
 			specular_texture: None,
 			normal_texture: None,
 			dissolve_texture: None,
+			sphere_texture: None,
+			sphere_mode: SphereMode::None,
+			toon: Toon::None,
+			edge_color: [0.0, 0.0, 0.0, 1.0],
+			edge_size: 1.0,
+			double_sided: false,
+			ground_shadow: false,
+			cast_shadow: false,
+			receive_shadow: false,
+			edge: false,
+			vertex_color: false,
+			point_draw: false,
+			line_draw: false,
 			illumination_model: None,
 			unknown_param: HashMap::new(),
 		}
 	}
+}
+
+impl Toon
+{
+	pub fn shared_name(index:u8) -> String
+	{
+		format!("toon{:02}.bmp", index as u32 + 1)
+	}
 }
\ No newline at end of file
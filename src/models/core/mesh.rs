@@ -1,3 +1,5 @@
+use super::MorphTarget;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VertexWeight
 {
@@ -27,6 +29,7 @@ pub struct Mesh
 	pub texcoords:Vec<f32>,
 	pub indices:Vec<u32>,
 	pub weights:Vec<VertexWeight>,
+	pub morphs:Vec<MorphTarget>,
 }
 
 impl Mesh
@@ -42,6 +45,7 @@ impl Mesh
 			texcoords:Vec::new(),
 			indices:Vec::new(),
 			weights:Vec::new(),
+			morphs:Vec::new(),
 		}
 	}
 
@@ -55,6 +59,7 @@ impl Mesh
 			texcoords:Vec::with_capacity(vertex * 2),
 			indices:Vec::with_capacity(index),
 			weights:Vec::new(),
+			morphs:Vec::new(),
 			material_id:None,
 		}
 	}
@@ -3,7 +3,11 @@ pub mod error;
 pub mod material;
 pub mod mesh;
 pub mod solver;
+pub mod morph;
+pub mod physics;
 pub mod model;
+pub mod skeleton;
+pub mod retarget;
 pub mod loader;
 
 pub use self::bone::*;
@@ -11,5 +15,9 @@ pub use self::error::*;
 pub use self::material::*;
 pub use self::mesh::*;
 pub use self::solver::*;
+pub use self::morph::*;
+pub use self::physics::*;
 pub use self::model::*;
+pub use self::skeleton::*;
+pub use self::retarget::*;
 pub use self::loader::*;
\ No newline at end of file
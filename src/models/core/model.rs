@@ -1,4 +1,4 @@
-use super::{Mesh, Material, Bone, Solver};
+use super::{Mesh, Material, Bone, Solver, Morph, RigidBody, Joint, SoftBody};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Model
@@ -9,6 +9,10 @@ pub struct Model
 	pub textures: Vec<String>,
 	pub bones: Vec<Bone>,
 	pub iks: Vec<Solver>,
+	pub morphs: Vec<Morph>,
+	pub rigidbodies: Vec<RigidBody>,
+	pub joints: Vec<Joint>,
+	pub softbodies: Vec<SoftBody>,
 }
 
 impl Model
@@ -23,6 +27,10 @@ impl Model
 			textures: Vec::new(),
 			bones:Vec::new(),
 			iks:Vec::new(),
+			morphs:Vec::new(),
+			rigidbodies:Vec::new(),
+			joints:Vec::new(),
+			softbodies:Vec::new(),
 		}
 	}
 
@@ -55,4 +63,28 @@ impl Model
 		self.iks.push(ik);
 		self
 	}
+
+	pub fn add_morph(&mut self, morph:Morph) -> &mut Self
+	{
+		self.morphs.push(morph);
+		self
+	}
+
+	pub fn add_rigidbody(&mut self, rigidbody:RigidBody) -> &mut Self
+	{
+		self.rigidbodies.push(rigidbody);
+		self
+	}
+
+	pub fn add_joint(&mut self, joint:Joint) -> &mut Self
+	{
+		self.joints.push(joint);
+		self
+	}
+
+	pub fn add_softbody(&mut self, softbody:SoftBody) -> &mut Self
+	{
+		self.softbodies.push(softbody);
+		self
+	}
 }
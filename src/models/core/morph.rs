@@ -0,0 +1,249 @@
+use std::collections::HashMap;
+use super::Material;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VertexMorph
+{
+	pub index:u32,
+	pub offset:(f32,f32,f32),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UVMorph
+{
+	pub index:u32,
+	pub offset:(f32,f32,f32,f32),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BoneMorph
+{
+	pub bone:u16,
+	pub translate:(f32,f32,f32),
+	pub rotation:(f32,f32,f32,f32),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaterialMorph
+{
+	pub material:Option<usize>, // None : All materials
+	pub operation:u8, // 0 : Multiply, 1 : Additive
+	pub diffuse:(f32,f32,f32,f32),
+	pub specular:(f32,f32,f32),
+	pub shininess:f32,
+	pub ambient:(f32,f32,f32),
+	pub edge_color:(f32,f32,f32,f32),
+	pub edge_size:f32,
+	pub texture:(f32,f32,f32,f32),
+	pub sphere:(f32,f32,f32,f32),
+	pub toon:(f32,f32,f32,f32),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupMorph
+{
+	pub morph:u32,
+	pub weight:f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImpulseMorph
+{
+	pub rigidbody:u32,
+	pub local:bool,
+	pub velocity:(f32,f32,f32),
+	pub torque:(f32,f32,f32),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum MorphData
+{
+	Group(Vec<GroupMorph>),
+	Vertex(Vec<VertexMorph>),
+	Bone(Vec<BoneMorph>),
+	UV(u8, Vec<UVMorph>),
+	Material(Vec<MaterialMorph>),
+	Flip(Vec<GroupMorph>),
+	Impulse(Vec<ImpulseMorph>),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Morph
+{
+	pub name:String,
+	pub panel:u8, // 0 : System, 1 : Eyebrow, 2 : Eye, 3 : Lip, 4 : Other
+	pub data:MorphData,
+}
+
+impl Morph
+{
+	pub fn new() -> Self
+	{
+		Self
+		{
+			name:String::new(),
+			panel:0,
+			data:MorphData::Vertex(Vec::new()),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MorphTarget
+{
+	pub name:String,
+	pub morph:Option<usize>, // the morph of the model the target was flattened from, names may repeat
+	pub positions:Vec<VertexMorph>,
+	pub texcoords:Vec<UVMorph>,
+	pub materials:Vec<MaterialMorph>,
+}
+
+impl MaterialMorph
+{
+	/// Returns a morph which gives the same result at full weight as this one does at `weight`.
+	pub fn scale(&self, weight:f32) -> Self
+	{
+		let mut this = self.clone();
+		let s = |v:f32| if self.operation == 0 { 1.0 + (v - 1.0) * weight } else { v * weight };
+
+		this.diffuse = (s(self.diffuse.0), s(self.diffuse.1), s(self.diffuse.2), s(self.diffuse.3));
+		this.specular = (s(self.specular.0), s(self.specular.1), s(self.specular.2));
+		this.shininess = s(self.shininess);
+		this.ambient = (s(self.ambient.0), s(self.ambient.1), s(self.ambient.2));
+		this.edge_color = (s(self.edge_color.0), s(self.edge_color.1), s(self.edge_color.2), s(self.edge_color.3));
+		this.edge_size = s(self.edge_size);
+		this.texture = (s(self.texture.0), s(self.texture.1), s(self.texture.2), s(self.texture.3));
+		this.sphere = (s(self.sphere.0), s(self.sphere.1), s(self.sphere.2), s(self.sphere.3));
+		this.toon = (s(self.toon.0), s(self.toon.1), s(self.toon.2), s(self.toon.3));
+		this
+	}
+
+	pub fn apply(&self, material:&mut Material, weight:f32)
+	{
+		let morph = self.scale(weight);
+		let op = |a:&mut f32, b:f32| if morph.operation == 0 { *a *= b } else { *a += b };
+
+		op(&mut material.diffuse[0], morph.diffuse.0);
+		op(&mut material.diffuse[1], morph.diffuse.1);
+		op(&mut material.diffuse[2], morph.diffuse.2);
+		op(&mut material.dissolve, morph.diffuse.3);
+		op(&mut material.specular[0], morph.specular.0);
+		op(&mut material.specular[1], morph.specular.1);
+		op(&mut material.specular[2], morph.specular.2);
+		op(&mut material.shininess, morph.shininess);
+		op(&mut material.ambient[0], morph.ambient.0);
+		op(&mut material.ambient[1], morph.ambient.1);
+		op(&mut material.ambient[2], morph.ambient.2);
+		op(&mut material.edge_color[0], morph.edge_color.0);
+		op(&mut material.edge_color[1], morph.edge_color.1);
+		op(&mut material.edge_color[2], morph.edge_color.2);
+		op(&mut material.edge_color[3], morph.edge_color.3);
+		op(&mut material.edge_size, morph.edge_size);
+	}
+}
+
+impl MorphTarget
+{
+	pub fn new() -> Self
+	{
+		Self
+		{
+			name:String::new(),
+			morph:None,
+			positions:Vec::new(),
+			texcoords:Vec::new(),
+			materials:Vec::new(),
+		}
+	}
+
+	/// Resolves group morphs down to the vertex, uv and material offsets they drive.
+	pub fn flatten(morphs:&[Morph], index:usize) -> Self
+	{
+		let mut positions = HashMap::new();
+		let mut texcoords = HashMap::new();
+		let mut materials = Vec::new();
+
+		MorphTarget::collect(morphs, index, 1.0, 0, &mut positions, &mut texcoords, &mut materials);
+
+		let mut this = MorphTarget::new();
+		this.name = morphs[index].name.clone();
+		this.morph = Some(index);
+		this.positions = positions.into_iter().map(|(index, offset)| VertexMorph { index, offset }).collect();
+		this.texcoords = texcoords.into_iter().map(|(index, offset)| UVMorph { index, offset }).collect();
+		this.materials = materials;
+		this.positions.sort_by_key(|v| v.index);
+		this.texcoords.sort_by_key(|v| v.index);
+		this
+	}
+
+	/// Keeps the offsets which touch a mesh, rewriting vertex indices through the loader's index map.
+	pub fn remap(&self, map:&HashMap<u32,u32>, material_id:usize) -> Self
+	{
+		let mut this = MorphTarget::new();
+		this.name = self.name.clone();
+		this.morph = self.morph;
+		this.positions = self.positions.iter().filter_map(|v| map.get(&v.index).map(|&index| VertexMorph { index, offset:v.offset })).collect();
+		this.texcoords = self.texcoords.iter().filter_map(|v| map.get(&v.index).map(|&index| UVMorph { index, offset:v.offset })).collect();
+		this.materials = self.materials.iter().filter(|m| m.material.map_or(true, |id| id == material_id)).cloned().collect();
+		this.positions.sort_by_key(|v| v.index);
+		this.texcoords.sort_by_key(|v| v.index);
+		this
+	}
+
+	pub fn is_empty(&self) -> bool
+	{
+		self.positions.is_empty() && self.texcoords.is_empty() && self.materials.is_empty()
+	}
+
+	fn collect(morphs:&[Morph], index:usize, weight:f32, depth:u32, positions:&mut HashMap<u32,(f32,f32,f32)>, texcoords:&mut HashMap<u32,(f32,f32,f32,f32)>, materials:&mut Vec<MaterialMorph>)
+	{
+		// Group morphs are not allowed to nest in PMX, the limit only guards against broken files.
+		if depth > 4 { return; }
+
+		let morph = match morphs.get(index)
+		{
+			Some(morph) => morph,
+			None => { return; }
+		};
+
+		match morph.data
+		{
+			MorphData::Vertex(ref v) =>
+			{
+				for it in v
+				{
+					let p = positions.entry(it.index).or_insert((0.0,0.0,0.0));
+					p.0 += it.offset.0 * weight;
+					p.1 += it.offset.1 * weight;
+					p.2 += it.offset.2 * weight;
+				}
+			},
+			MorphData::UV(0, ref v) =>
+			{
+				for it in v
+				{
+					let p = texcoords.entry(it.index).or_insert((0.0,0.0,0.0,0.0));
+					p.0 += it.offset.0 * weight;
+					p.1 += it.offset.1 * weight;
+					p.2 += it.offset.2 * weight;
+					p.3 += it.offset.3 * weight;
+				}
+			},
+			MorphData::Material(ref v) =>
+			{
+				for it in v
+				{
+					materials.push(it.scale(weight));
+				}
+			},
+			MorphData::Group(ref v) =>
+			{
+				for it in v
+				{
+					MorphTarget::collect(morphs, it.morph as usize, weight * it.weight, depth + 1, positions, texcoords, materials);
+				}
+			},
+			_ => {}
+		}
+	}
+}
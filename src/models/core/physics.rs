@@ -0,0 +1,139 @@
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RigidBody
+{
+	pub name:String,
+	pub bone:Option<u16>,
+	pub group:u8,
+	pub group_mask:u16,
+	pub shape:u8, // 0 : Sphere, 1 : Box, 2 : Capsule
+	pub size:(f32,f32,f32),
+	pub position:(f32,f32,f32),
+	pub rotation:(f32,f32,f32),
+	pub mass:f32,
+	pub linear_damping:f32,
+	pub angular_damping:f32,
+	pub restitution:f32,
+	pub friction:f32,
+	pub physics_operation:u8, // 0 : Static, 1 : Dynamic, 2 : Dynamic with bone
+}
+
+impl RigidBody
+{
+	pub fn new() -> Self
+	{
+		Self
+		{
+			name:String::new(),
+			bone:None,
+			group:0,
+			group_mask:0,
+			shape:0,
+			size:(0.0,0.0,0.0),
+			position:(0.0,0.0,0.0),
+			rotation:(0.0,0.0,0.0),
+			mass:0.0,
+			linear_damping:0.0,
+			angular_damping:0.0,
+			restitution:0.0,
+			friction:0.0,
+			physics_operation:0,
+		}
+	}
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Joint
+{
+	pub name:String,
+	pub kind:u8, // 0 : Spring6DOF, 1 : 6DOF, 2 : P2P, 3 : ConeTwist, 4 : Slider, 5 : Hinge
+	pub rigidbody_a:u32,
+	pub rigidbody_b:u32,
+	pub position:(f32,f32,f32),
+	pub rotation:(f32,f32,f32),
+	pub translate_lower_limit:(f32,f32,f32),
+	pub translate_upper_limit:(f32,f32,f32),
+	pub rotation_lower_limit:(f32,f32,f32),
+	pub rotation_upper_limit:(f32,f32,f32),
+	pub translate_spring:(f32,f32,f32),
+	pub rotation_spring:(f32,f32,f32),
+}
+
+impl Joint
+{
+	pub fn new() -> Self
+	{
+		Self
+		{
+			name:String::new(),
+			kind:0,
+			rigidbody_a:0,
+			rigidbody_b:0,
+			position:(0.0,0.0,0.0),
+			rotation:(0.0,0.0,0.0),
+			translate_lower_limit:(0.0,0.0,0.0),
+			translate_upper_limit:(0.0,0.0,0.0),
+			rotation_lower_limit:(0.0,0.0,0.0),
+			rotation_upper_limit:(0.0,0.0,0.0),
+			translate_spring:(0.0,0.0,0.0),
+			rotation_spring:(0.0,0.0,0.0),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SoftBodyAnchor
+{
+	pub rigidbody:u32,
+	pub vertex:u32,
+	pub near_mode:bool,
+}
+
+/// Vertices of anchors and pins index the mesh drawn with the material of the soft body.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SoftBody
+{
+	pub name:String,
+	pub shape:u8, // 0 : TriMesh, 1 : Rope
+	pub material:Option<usize>,
+	pub group:u8,
+	pub group_mask:u16,
+	pub flag:u8, // 0x01 : B-Link, 0x02 : Cluster, 0x04 : Link crossing
+	pub blink_length:i32,
+	pub num_clusters:i32,
+	pub total_mass:f32,
+	pub collision_margin:f32,
+	pub aero_model:i32,
+	pub config:[f32;12],
+	pub cluster:[f32;6],
+	pub iteration:[i32;4],
+	pub material_coefficient:[f32;3],
+	pub anchors:Vec<SoftBodyAnchor>,
+	pub pin_vertices:Vec<u32>,
+}
+
+impl SoftBody
+{
+	pub fn new() -> Self
+	{
+		Self
+		{
+			name:String::new(),
+			shape:0,
+			material:None,
+			group:0,
+			group_mask:0,
+			flag:0,
+			blink_length:0,
+			num_clusters:0,
+			total_mass:0.0,
+			collision_margin:0.0,
+			aero_model:0,
+			config:[0.0;12],
+			cluster:[0.0;6],
+			iteration:[0;4],
+			material_coefficient:[0.0;3],
+			anchors:Vec::new(),
+			pin_vertices:Vec::new(),
+		}
+	}
+}
@@ -0,0 +1,428 @@
+use std::collections::HashMap;
+
+use crate::math::*;
+use crate::animation::{Animator, AnimationClip, Motion, Evaluate};
+use super::Bone;
+
+// Bones named the same way on both sides of the model, English names are the ones MMD writes into PMX files.
+const CENTER_ALIASES:&[(&str, &[&str])] = &[
+	("全ての親", &["mother", "master", "root"]),
+	("センター", &["center", "centre"]),
+	("グルーブ", &["groove"]),
+	("腰", &["waist"]),
+	("上半身", &["upper body"]),
+	("上半身2", &["upper body2"]),
+	("下半身", &["lower body"]),
+	("首", &["neck"]),
+	("頭", &["head"]),
+	("両目", &["eyes"]),
+];
+
+// Bones on each side, prefixed with 左 or 右 in Japanese and suffixed with _L or _R in English.
+const SIDE_ALIASES:&[(&str, &str)] = &[
+	("肩", "shoulder"),
+	("腕", "arm"),
+	("腕捩", "arm twist"),
+	("ひじ", "elbow"),
+	("手捩", "wrist twist"),
+	("手首", "wrist"),
+	("親指０", "thumb0"),
+	("親指１", "thumb1"),
+	("親指２", "thumb2"),
+	("人指１", "fore1"),
+	("人指２", "fore2"),
+	("人指３", "fore3"),
+	("中指１", "middle1"),
+	("中指２", "middle2"),
+	("中指３", "middle3"),
+	("薬指１", "third1"),
+	("薬指２", "third2"),
+	("薬指３", "third3"),
+	("小指１", "little1"),
+	("小指２", "little2"),
+	("小指３", "little3"),
+	("足", "leg"),
+	("ひざ", "knee"),
+	("足首", "ankle"),
+	("つま先", "toe"),
+	("足ＩＫ", "leg IK"),
+	("つま先ＩＫ", "toe IK"),
+	("目", "eye"),
+];
+
+// Full width letters and digits become ASCII, case and separators are ignored.
+fn normalize(name:&str) -> String
+{
+	name.chars()
+		.map(|c| match c as u32
+		{
+			0xFF01 ..= 0xFF5E => std::char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+			_ => c,
+		})
+		.filter(|c| !(c.is_whitespace() || *c == '_' || *c == '.' || *c == '-'))
+		.flat_map(|c| c.to_lowercase())
+		.collect()
+}
+
+/// Maps the bone names of a motion to the bones of a model, through names set explicitly
+/// and then through groups of aliases naming the same bone.
+#[derive(Debug, Clone)]
+pub struct BoneMap
+{
+	names:HashMap<String, String>,
+	aliases:Vec<Vec<String>>,
+}
+
+impl BoneMap
+{
+	pub fn new() -> Self
+	{
+		Self
+		{
+			names:HashMap::new(),
+			aliases:Vec::new(),
+		}
+	}
+
+	/// The standard MMD bones with their Japanese and English names.
+	pub fn mmd() -> Self
+	{
+		let mut map = BoneMap::new();
+
+		for &(name, aliases) in CENTER_ALIASES
+		{
+			let mut names = vec![name];
+			names.extend(aliases);
+			map.add_aliases(&names);
+		}
+
+		for &(side, suffix, word) in &[("左", "L", "left"), ("右", "R", "right")]
+		{
+			for &(name, alias) in SIDE_ALIASES
+			{
+				let japanese = format!("{}{}", side, name);
+				let english = format!("{}_{}", alias, suffix);
+				let long = format!("{} {}", word, alias);
+				map.add_aliases(&[&japanese, &english, &long]);
+			}
+		}
+
+		map
+	}
+
+	/// Maps a bone of the motion to a bone of the model, before any alias.
+	pub fn insert(&mut self, source:&str, target:&str) -> &mut Self
+	{
+		self.names.insert(source.to_string(), target.to_string());
+		self
+	}
+
+	/// Names which all refer to the same bone.
+	pub fn add_aliases(&mut self, names:&[&str]) -> &mut Self
+	{
+		self.aliases.push(names.iter().map(|name| normalize(name)).collect());
+		self
+	}
+
+	/// The bone named like the motion bone, by explicit name first, then by name and finally by alias.
+	pub fn find(&self, bones:&[Bone], name:&str) -> Option<usize>
+	{
+		if let Some(target) = self.names.get(name)
+		{
+			return bones.iter().position(|bone| bone.name == *target);
+		}
+
+		if let Some(index) = bones.iter().position(|bone| bone.name == name)
+		{
+			return Some(index);
+		}
+
+		let name = normalize(name);
+		let names:Vec<String> = bones.iter().map(|bone| normalize(&bone.name)).collect();
+
+		if let Some(index) = names.iter().position(|other| *other == name)
+		{
+			return Some(index);
+		}
+
+		let aliases = self.aliases.iter().find(|aliases| aliases.contains(&name))?;
+		names.iter().position(|other| aliases.contains(other))
+	}
+}
+
+/// Moves a motion made for one model onto another. Rotations are corrected for bones resting
+/// in other directions, like arms in an A-pose and a T-pose, and translations are scaled by the
+/// ratio of leg lengths. Without a source model both are assumed to rest alike.
+#[derive(Debug, Clone)]
+pub struct Retargeter
+{
+	pub map:BoneMap,
+	target:Vec<Bone>,
+	rests:Vec<Quaternion>,
+	scale:f32,
+}
+
+fn position(bone:&Bone) -> float3
+{
+	float3::new(bone.position.0, bone.position.1, bone.position.2)
+}
+
+fn parent(bones:&[Bone], bone:usize) -> Option<usize>
+{
+	bones[bone].parent.map(|parent| parent as usize).filter(|&parent| parent < bones.len() && parent != bone)
+}
+
+// Every key is moved as a whole vector, sampling the other channels at its time.
+fn retarget_channels<F>(clip:&mut AnimationClip, channels:&[&str], defaults:&[f32], map:F) where F:Fn(&[f32]) -> [f32; 4]
+{
+	let mut values:Vec<Vec<f32>> = Vec::new();
+	for (channel, name) in channels.iter().enumerate()
+	{
+		let keys:Vec<f32> = clip.curves.get(*name).map(|curve| curve.frames.iter().map(|key| key.time).collect()).unwrap_or(Vec::new());
+		values.push(keys.iter().map(|&time|
+		{
+			let sample:Vec<f32> = channels.iter().zip(defaults.iter())
+				.map(|(name, &default)| clip.curves.get(*name).filter(|curve| curve.frames.len() > 0).map(|curve| curve.evaluate(time)).unwrap_or(default))
+				.collect();
+			map(&sample)[channel]
+		}).collect());
+	}
+
+	for (name, values) in channels.iter().zip(values.into_iter())
+	{
+		if let Some(curve) = clip.curves.get_mut(*name)
+		{
+			for (key, value) in curve.frames.iter_mut().zip(values.into_iter())
+			{
+				key.value = value;
+			}
+		}
+	}
+}
+
+impl Retargeter
+{
+	pub fn new(map:BoneMap, target:&[Bone]) -> Self
+	{
+		Self
+		{
+			map:map,
+			target:target.to_vec(),
+			rests:vec![Quaternion::new(0.0, 0.0, 0.0, 1.0); target.len()],
+			scale:1.0,
+		}
+	}
+
+	/// Measures the model the motion was made for against the target.
+	pub fn with_source(mut self, source:&[Bone]) -> Self
+	{
+		let source_leg = self.leg_length(source);
+		let target_leg = self.leg_length(&self.target);
+
+		self.scale = match (source_leg, target_leg)
+		{
+			(Some(source), Some(target)) if source > 0.0 => target / source,
+			_ => 1.0,
+		};
+
+		// Which source bone each target bone follows.
+		let mut sources = vec![None; self.target.len()];
+		for (i, bone) in source.iter().enumerate()
+		{
+			if let Some(target) = self.map.find(&self.target, &bone.name)
+			{
+				if sources[target].is_none()
+				{
+					sources[target] = Some(i);
+				}
+			}
+		}
+
+		let mut rests:Vec<Option<Quaternion>> = vec![None; self.target.len()];
+		for bone in 0..self.target.len()
+		{
+			self.rest(source, &sources, &mut rests, bone, 0);
+		}
+
+		self.rests = rests.into_iter().map(|rest| rest.unwrap_or(Quaternion::new(0.0, 0.0, 0.0, 1.0))).collect();
+		self
+	}
+
+	pub fn scale(&self) -> f32
+	{
+		self.scale
+	}
+
+	/// The rotation turning a target bone from its rest direction to the one of its source bone.
+	pub fn rest_rotation(&self, bone:usize) -> Quaternion
+	{
+		self.rests[bone]
+	}
+
+	/// The bone of the target a motion bone drives.
+	pub fn find(&self, name:&str) -> Option<usize>
+	{
+		self.map.find(&self.target, name)
+	}
+
+	/// Retargets the "Position.X/Y/Z" curves made by `VMDFile::collect_positions` and the "Rotation.X/Y/Z/W"
+	/// curves `VMDFile::collect_motions` adds, clips of bones missing from the target are dropped and the
+	/// others take the name of their target bone.
+	pub fn retarget(&self, motions:Animator) -> Animator
+	{
+		let mut animator = Animator::new();
+		animator.name = motions.name;
+
+		for mut clip in motions.clips.into_iter()
+		{
+			let bone = match self.find(&clip.name)
+			{
+				Some(bone) => bone,
+				None => continue,
+			};
+
+			let parent = self.parent_rest(bone).inverse();
+			let rest = self.rests[bone];
+
+			retarget_channels(&mut clip, &["Position.X", "Position.Y", "Position.Z"], &[0.0, 0.0, 0.0], |v|
+			{
+				let v = parent.rotate(float3::new(v[0], v[1], v[2])) * self.scale;
+				[v.x, v.y, v.z, 0.0]
+			});
+
+			// The components are corrected like the rotations of `retarget_rotations`.
+			retarget_channels(&mut clip, &["Rotation.X", "Rotation.Y", "Rotation.Z", "Rotation.W"], &[0.0, 0.0, 0.0, 1.0], |v|
+			{
+				let q = parent.concat(Quaternion::new(v[0], v[1], v[2], v[3])).concat(rest).normalize();
+				[q.x, q.y, q.z, q.w]
+			});
+
+			clip.name = self.target[bone].name.clone();
+			animator.add_clip(clip);
+		}
+
+		animator
+	}
+
+	/// Retargets the "Rotation" curves made by `VMDFile::collect_rotations`.
+	pub fn retarget_rotations(&self, rotations:Animator<Quaternion>) -> Animator<Quaternion>
+	{
+		let mut animator = Animator::new();
+		animator.name = rotations.name;
+
+		for mut clip in rotations.clips.into_iter()
+		{
+			let bone = match self.find(&clip.name)
+			{
+				Some(bone) => bone,
+				None => continue,
+			};
+
+			let parent = self.parent_rest(bone).inverse();
+			let rest = self.rests[bone];
+
+			for curve in clip.curves.values_mut()
+			{
+				for key in curve.frames.iter_mut()
+				{
+					key.value = parent.concat(key.value).concat(rest).normalize();
+				}
+			}
+
+			clip.name = self.target[bone].name.clone();
+			animator.add_clip(clip);
+		}
+
+		animator
+	}
+
+	/// Retargets the bones of a motion, morphs, cameras and lights are kept as they are.
+	pub fn retarget_motion(&self, mut motion:Motion) -> Motion
+	{
+		motion.bones = self.retarget(std::mem::replace(&mut motion.bones, Animator::new()));
+		motion.rotations = self.retarget_rotations(std::mem::replace(&mut motion.rotations, Animator::new()));
+		motion
+	}
+
+	fn parent_rest(&self, bone:usize) -> Quaternion
+	{
+		match parent(&self.target, bone)
+		{
+			Some(parent) => self.rests[parent],
+			None => Quaternion::new(0.0, 0.0, 0.0, 1.0),
+		}
+	}
+
+	// A bone rests like its parent unless both models have a child to aim it at.
+	fn rest(&self, source:&[Bone], sources:&[Option<usize>], rests:&mut Vec<Option<Quaternion>>, bone:usize, depth:usize) -> Quaternion
+	{
+		if let Some(rest) = rests[bone]
+		{
+			return rest;
+		}
+
+		let inherited = match parent(&self.target, bone)
+		{
+			Some(parent) if depth < self.target.len() => self.rest(source, sources, rests, parent, depth + 1),
+			_ => Quaternion::new(0.0, 0.0, 0.0, 1.0),
+		};
+
+		let rest = sources[bone].and_then(|from| self.aim(source, from, bone)).unwrap_or(inherited);
+		rests[bone] = Some(rest);
+		rest
+	}
+
+	fn aim(&self, source:&[Bone], from:usize, bone:usize) -> Option<Quaternion>
+	{
+		for (i, child) in source.iter().enumerate()
+		{
+			if parent(source, i) != Some(from)
+			{
+				continue;
+			}
+
+			let target = match self.map.find(&self.target, &child.name)
+			{
+				Some(target) if target != bone => target,
+				_ => continue,
+			};
+
+			let a = position(&self.target[target]) - position(&self.target[bone]);
+			let b = position(child) - position(&source[from]);
+			if a.length2() < 1e-8 || b.length2() < 1e-8
+			{
+				continue;
+			}
+
+			let a = a.normalize();
+			let b = b.normalize();
+
+			// Bones pointing opposite ways have no single shortest turn, leave them be.
+			if a.dot(b) < -0.9999
+			{
+				return None;
+			}
+
+			return Some(Quaternion::direction(&a, &b).normalize());
+		}
+
+		None
+	}
+
+	fn leg_length(&self, bones:&[Bone]) -> Option<f32>
+	{
+		for side in &["左", "右"]
+		{
+			let leg = self.map.find(bones, &format!("{}足", side));
+			let knee = self.map.find(bones, &format!("{}ひざ", side));
+			let ankle = self.map.find(bones, &format!("{}足首", side));
+
+			if let (Some(leg), Some(knee), Some(ankle)) = (leg, knee, ankle)
+			{
+				return Some((position(&bones[knee]) - position(&bones[leg])).length() + (position(&bones[ankle]) - position(&bones[knee])).length());
+			}
+		}
+
+		None
+	}
+}
\ No newline at end of file
@@ -0,0 +1,300 @@
+use std::collections::HashMap;
+
+use crate::math::*;
+use crate::math::vec::{Math, Slerp};
+use crate::animation::{Motion, Evaluate};
+use super::{Model, Bone, Solver};
+
+#[derive(Debug, Copy, Clone)]
+pub struct BonePose
+{
+	pub translate:float3,
+	pub rotation:Quaternion,
+}
+
+#[derive(Debug, Clone)]
+pub struct Skeleton
+{
+	pub bones:Vec<Bone>,
+	pub iks:Vec<Solver>,
+	pub poses:Vec<BonePose>, // animated transforms relative to the rest pose
+	pub locals:Vec<BonePose>,
+	pub worlds:Vec<BonePose>,
+	inverse_binds:Vec<float4x4>,
+	order:Vec<usize>,
+	ik_rotations:Vec<Quaternion>,
+	names:HashMap<String, usize>,
+}
+
+impl BonePose
+{
+	pub fn new() -> Self
+	{
+		Self
+		{
+			translate:float3::zero(),
+			rotation:Quaternion::new(0.0, 0.0, 0.0, 1.0),
+		}
+	}
+
+	pub fn to_matrix(&self) -> float4x4
+	{
+		let mut m = float4x4::from(self.rotation);
+		m.d1 = self.translate.x;
+		m.d2 = self.translate.y;
+		m.d3 = self.translate.z;
+		m
+	}
+}
+
+impl Skeleton
+{
+	pub fn new(model:&Model) -> Self
+	{
+		let mut order:Vec<usize> = (0..model.bones.len()).collect();
+		order.sort_by_key(|&i| (model.bones[i].after_physics, model.bones[i].order, i));
+
+		Self
+		{
+			bones:model.bones.clone(),
+			iks:model.iks.clone(),
+			poses:vec![BonePose::new(); model.bones.len()],
+			locals:vec![BonePose::new(); model.bones.len()],
+			worlds:vec![BonePose::new(); model.bones.len()],
+			inverse_binds:model.bones.iter().map(|bone| float4x4::translate(-bone.position.0, -bone.position.1, -bone.position.2)).collect(),
+			order:order,
+			ik_rotations:vec![Quaternion::new(0.0, 0.0, 0.0, 1.0); model.bones.len()],
+			names:model.bones.iter().enumerate().map(|(i, bone)| (bone.name.clone(), i)).collect(),
+		}
+	}
+
+	pub fn len(&self) -> usize
+	{
+		self.bones.len()
+	}
+
+	pub fn find(&self, name:&str) -> Option<usize>
+	{
+		self.names.get(name).cloned()
+	}
+
+	pub fn reset(&mut self)
+	{
+		for pose in self.poses.iter_mut()
+		{
+			*pose = BonePose::new();
+		}
+	}
+
+	pub fn set_translate(&mut self, bone:usize, translate:float3)
+	{
+		self.poses[bone].translate = translate;
+	}
+
+	pub fn set_rotation(&mut self, bone:usize, rotation:Quaternion)
+	{
+		self.poses[bone].rotation = rotation;
+	}
+
+	/// Samples the bone clips of a motion by name, bones without a clip keep their pose.
+	pub fn sample(&mut self, motion:&Motion, time:f32)
+	{
+		for clip in motion.bones.clips.iter()
+		{
+			if let Some(bone) = self.find(&clip.name)
+			{
+				let channel = |name:&str| clip.curves.get(name).filter(|curve| curve.frames.len() > 0).map(|curve| curve.evaluate(time)).unwrap_or(0.0);
+				self.poses[bone].translate = float3::new(channel("Position.X"), channel("Position.Y"), channel("Position.Z"));
+			}
+		}
+
+		for clip in motion.rotations.clips.iter()
+		{
+			if let (Some(bone), Some(curve)) = (self.find(&clip.name), clip.curves.get("Rotation"))
+			{
+				if curve.frames.len() > 0
+				{
+					self.poses[bone].rotation = curve.evaluate(time).normalize();
+				}
+			}
+		}
+	}
+
+	/// Updates the world transforms in deform order, solving each IK once its bone is placed.
+	pub fn update(&mut self)
+	{
+		for rotation in self.ik_rotations.iter_mut()
+		{
+			*rotation = Quaternion::new(0.0, 0.0, 0.0, 1.0);
+		}
+
+		for n in 0..self.order.len()
+		{
+			let bone = self.order[n];
+			self.update_local(bone);
+			self.update_world(bone);
+
+			for i in 0..self.iks.len()
+			{
+				if self.iks[i].bone as usize == bone
+				{
+					self.solve(i);
+				}
+			}
+		}
+	}
+
+	pub fn position(&self, bone:usize) -> float3
+	{
+		self.worlds[bone].translate
+	}
+
+	pub fn world(&self, bone:usize) -> float4x4
+	{
+		self.worlds[bone].to_matrix()
+	}
+
+	/// Moves a vertex from model space into the space of each bone, PMX bones carry no rotation at rest.
+	pub fn inverse_bind_matrices(&self) -> &[float4x4]
+	{
+		&self.inverse_binds
+	}
+
+	/// Skinning matrices, each moves a vertex from the rest pose to the current pose of its bone.
+	pub fn palette(&self) -> Vec<float4x4>
+	{
+		self.worlds.iter().zip(self.inverse_binds.iter()).map(|(world, inverse_bind)| world.to_matrix() * *inverse_bind).collect()
+	}
+
+	fn update_local(&mut self, bone:usize)
+	{
+		let mut translate = self.poses[bone].translate;
+		let mut rotation = self.poses[bone].rotation;
+
+		if let Some(ref grant) = self.bones[bone].grant
+		{
+			let parent = grant.parent as usize;
+			if parent < self.bones.len() && parent != bone
+			{
+				if grant.rotation
+				{
+					let identity = Quaternion::new(0.0, 0.0, 0.0, 1.0);
+					rotation = rotation.concat(identity.slerp(self.locals[parent].rotation, grant.ratio));
+				}
+
+				if grant.translation
+				{
+					translate = translate + (self.locals[parent].translate - self.rest_offset(parent)) * grant.ratio;
+				}
+			}
+		}
+
+		self.locals[bone].translate = self.rest_offset(bone) + translate;
+		self.locals[bone].rotation = rotation.concat(self.ik_rotations[bone]).normalize();
+	}
+
+	fn update_world(&mut self, bone:usize)
+	{
+		let local = self.locals[bone];
+
+		self.worlds[bone] = match self.parent(bone)
+		{
+			Some(parent) =>
+			{
+				let parent = self.worlds[parent];
+				BonePose
+				{
+					translate:parent.translate + parent.rotation.rotate(local.translate),
+					rotation:parent.rotation.concat(local.rotation),
+				}
+			},
+			None => local
+		};
+	}
+
+	fn update_worlds(&mut self)
+	{
+		for n in 0..self.order.len()
+		{
+			let bone = self.order[n];
+			self.update_world(bone);
+		}
+	}
+
+	fn parent(&self, bone:usize) -> Option<usize>
+	{
+		self.bones[bone].parent.map(|parent| parent as usize).filter(|&parent| parent < self.bones.len())
+	}
+
+	fn rest_offset(&self, bone:usize) -> float3
+	{
+		let (x, y, z) = self.bones[bone].position;
+		match self.parent(bone)
+		{
+			Some(parent) =>
+			{
+				let (px, py, pz) = self.bones[parent].position;
+				float3::new(x - px, y - py, z - pz)
+			},
+			None => float3::new(x, y, z)
+		}
+	}
+
+	// Cyclic coordinate descent, every link turns the effector towards the IK bone in its own space.
+	fn solve(&mut self, index:usize)
+	{
+		let ik = self.iks[index].clone();
+		let goal = ik.bone as usize;
+		let effector = ik.target_bone as usize;
+
+		if goal >= self.bones.len() || effector >= self.bones.len()
+		{
+			return;
+		}
+
+		for _ in 0..ik.loop_count.max(1)
+		{
+			for link in ik.links.iter()
+			{
+				let joint = link.bone as usize;
+				if joint >= self.bones.len() { continue; }
+
+				let world = self.worlds[joint];
+				let inverse = world.rotation.conjugate();
+				let from = inverse.rotate(self.position(effector) - world.translate);
+				let to = inverse.rotate(self.position(goal) - world.translate);
+
+				if from.length2() < 1e-12 || to.length2() < 1e-12 { continue; }
+
+				let (from, to) = (from.normalize(), to.normalize());
+				let angle = from.dot(to).min(1.0).max(-1.0).acos();
+				if angle < 1e-5 { continue; }
+
+				let axis = from.cross(to);
+				if axis.length2() < 1e-12 { continue; }
+
+				let angle = if ik.limited_radian > 0.0 { angle.min(ik.limited_radian) } else { angle };
+				let delta = Quaternion::rotation(&axis.normalize(), angle);
+
+				let base = self.locals[joint].rotation.concat(self.ik_rotations[joint].conjugate());
+				let mut rotation = self.locals[joint].rotation.concat(delta);
+
+				if link.rotate_limited
+				{
+					let min = float3::new(link.minimum_radian.0, link.minimum_radian.1, link.minimum_radian.2);
+					let max = float3::new(link.maximum_radian.0, link.maximum_radian.1, link.maximum_radian.2);
+					rotation = Quaternion::euler(&rotation.to_euler(RotationOrder::ZYX).clamp(min, max), RotationOrder::ZYX);
+				}
+
+				self.ik_rotations[joint] = base.conjugate().concat(rotation).normalize();
+				self.update_local(joint);
+				self.update_worlds();
+			}
+
+			if (self.position(effector) - self.position(goal)).length2() < 1e-10
+			{
+				break;
+			}
+		}
+	}
+}
@@ -1,5 +1,7 @@
 pub mod pmx;
 pub mod pmd;
+pub mod x;
 
 pub use self::pmx::*;
-pub use self::pmd::*;
\ No newline at end of file
+pub use self::pmd::*;
+pub use self::x::*;
\ No newline at end of file
@@ -0,0 +1,1071 @@
+use std::io::{Cursor};
+use std::collections::HashMap;
+use std::f32;
+use byteorder::{LittleEndian, ReadBytesExt};
+use encoding::{Encoding, DecoderTrap};
+use encoding::all::WINDOWS_31J;
+use crate::binary::read_count;
+use super::super::{Loader, Model, Mesh, Material, SphereMode, Toon, VertexWeight, Bone, BoneTail, BoneGrant, BoneLink, Solver, Error, Result};
+use super::super::{Morph, MorphData, MorphTarget, VertexMorph, RigidBody, Joint};
+
+pub const PMD_VERSION_1_0: f32 = 1.0;
+pub const PMD_BONE_NONE: u16 = 0xFFFF;
+pub const PMD_TOON_NONE: u8 = 0xFF;
+pub const PMD_TOON_COUNT: usize = 10;
+
+// PmdBoneType
+pub const PMD_BONE_ROTATE:u8 = 0;
+pub const PMD_BONE_ROTATE_MOVE:u8 = 1;
+pub const PMD_BONE_IK:u8 = 2;
+pub const PMD_BONE_UNKNOWN:u8 = 3;
+pub const PMD_BONE_IK_LINK:u8 = 4;
+pub const PMD_BONE_ROTATE_EFFECT:u8 = 5;
+pub const PMD_BONE_IK_TARGET:u8 = 6;
+pub const PMD_BONE_INVISIBLE:u8 = 7;
+pub const PMD_BONE_TWIST:u8 = 8;
+pub const PMD_BONE_ROTATE_RATIO:u8 = 9;
+
+// PmdMorphType
+pub const PMD_MORPH_BASE:u8 = 0;
+pub const PMD_MORPH_EYEBROW:u8 = 1;
+pub const PMD_MORPH_EYE:u8 = 2;
+pub const PMD_MORPH_LIP:u8 = 3;
+pub const PMD_MORPH_OTHER:u8 = 4;
+
+pub struct PmdVector2();
+pub struct PmdVector3();
+pub struct PmdName();
+
+#[derive(Debug)]
+pub struct PmdHeader
+{
+	pub magic:[u8;3],   // Pmd
+	pub version:f32,    // 1.0
+	pub name:String,    // Fixed: 20 length
+	pub comment:String, // Fixed: 256 length
+}
+
+#[derive(Debug)]
+pub struct PmdVertex
+{
+	pub position:(f32,f32,f32),
+	pub normal:(f32,f32,f32),
+	pub coord:(f32,f32),
+	pub bone:(u16,u16),
+	pub weight:u8, // 0 ~ 100, weight of the first bone
+	pub edge:u8,   // 0 : Enable, 1 : Disable
+}
+
+#[derive(Debug)]
+pub struct PmdMaterial
+{
+	pub diffuse:(f32,f32,f32),
+	pub opacity:f32,
+	pub shininess:f32,
+	pub specular:(f32,f32,f32),
+	pub ambient:(f32,f32,f32),
+	pub toon_index:u8,
+	pub edge:u8,
+	pub face_count:u32,
+	pub texture_name:String, // Fixed: 20 length, "diffuse.bmp*sphere.sph"
+}
+
+#[derive(Debug)]
+pub struct PmdBone
+{
+	pub name:String, // Fixed: 20 length
+	pub parent:u16,
+	pub child:u16,
+	pub kind:u8, // PmdBoneType
+	pub ik_parent:u16,
+	pub position:(f32,f32,f32),
+}
+
+#[derive(Debug)]
+pub struct PmdIK
+{
+	pub bone_index:u16,
+	pub target_bone_index:u16,
+	pub chain_length:u8,
+	pub loop_count:u16,
+	pub limited_radian:f32,
+	pub child_bone_index:Vec<u16>,
+}
+
+#[derive(Debug)]
+pub struct PmdMorphVertex
+{
+	pub index:u32,
+	pub offset:(f32,f32,f32),
+}
+
+#[derive(Debug)]
+pub struct PmdMorph
+{
+	pub name:String, // Fixed: 20 length
+	pub kind:u8, // PmdMorphType
+	pub vertices:Vec<PmdMorphVertex>,
+}
+
+#[derive(Debug)]
+pub struct PmdBoneDisplay
+{
+	pub bone_index:u16,
+	pub frame_index:u8,
+}
+
+#[derive(Debug)]
+pub struct PmdDescription
+{
+	pub name:String,
+	pub comment:String,
+	pub bone_names:Vec<String>,
+	pub morph_names:Vec<String>,
+	pub frame_names:Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct PmdRigidbody
+{
+	pub name:String, // Fixed: 20 length
+	pub bone:u16,
+	pub group:u8,
+	pub group_mask:u16,
+	pub shape:u8, // 0 : Sphere, 1 : Box, 2 : Capsule
+	pub scale:(f32,f32,f32),
+	pub position:(f32,f32,f32),
+	pub rotation:(f32,f32,f32),
+	pub mass:f32,
+	pub movement_decay:f32,
+	pub rotation_decay:f32,
+	pub elasticity:f32,
+	pub friction:f32,
+	pub physics_operation:u8, // 0 : Static, 1 : Dynamic, 2 : Dynamic with bone
+}
+
+#[derive(Debug)]
+pub struct PmdJoint
+{
+	pub name:String, // Fixed: 20 length
+	pub rigidbody_a:u32,
+	pub rigidbody_b:u32,
+	pub position:(f32,f32,f32),
+	pub rotation:(f32,f32,f32),
+	pub movement_lower_limit:(f32,f32,f32),
+	pub movement_upper_limit:(f32,f32,f32),
+	pub rotation_lower_limit:(f32,f32,f32),
+	pub rotation_upper_limit:(f32,f32,f32),
+	pub movement_spring_constant:(f32,f32,f32),
+	pub rotation_spring_constant:(f32,f32,f32),
+}
+
+#[derive(Debug)]
+pub struct PMDFile
+{
+	pub header:PmdHeader,
+	pub vertices:Vec<PmdVertex>,
+	pub indices:Vec<u16>,
+	pub materials:Vec<PmdMaterial>,
+	pub bones:Vec<PmdBone>,
+	pub iks:Vec<PmdIK>,
+	pub morphs:Vec<PmdMorph>,
+	pub morph_display:Vec<u16>,
+	pub frame_names:Vec<String>,
+	pub bone_display:Vec<PmdBoneDisplay>,
+	pub description:Option<PmdDescription>,
+	pub toons:Vec<String>,
+	pub rigidbodies:Vec<PmdRigidbody>,
+	pub joints:Vec<PmdJoint>,
+}
+
+impl PmdVector2
+{
+	pub fn load(reader:&mut Cursor<&[u8]>) -> Result<(f32,f32)>
+	{
+		let x = reader.read_f32::<LittleEndian>()?;
+		let y = reader.read_f32::<LittleEndian>()?;
+		Ok((x, y))
+	}
+}
+
+impl PmdVector3
+{
+	pub fn load(reader:&mut Cursor<&[u8]>) -> Result<(f32,f32,f32)>
+	{
+		let x = reader.read_f32::<LittleEndian>()?;
+		let y = reader.read_f32::<LittleEndian>()?;
+		let z = reader.read_f32::<LittleEndian>()?;
+		Ok((x, y, z))
+	}
+}
+
+impl PmdName
+{
+	pub fn load(reader:&mut Cursor<&[u8]>, length:usize) -> Result<String>
+	{
+		let mut bytes = Vec::with_capacity(length);
+		let mut terminated = false;
+
+		for _ in 0..length
+		{
+			let value = reader.read_u8()?;
+			if value == 0 { terminated = true; }
+			if !terminated { bytes.push(value); }
+		}
+
+		Ok(WINDOWS_31J.decode(&bytes, DecoderTrap::Ignore).unwrap())
+	}
+}
+
+impl PmdHeader
+{
+	pub fn new() -> Self
+	{
+		Self
+		{
+			magic:[0;3],
+			version:PMD_VERSION_1_0,
+			name:String::new(),
+			comment:String::new(),
+		}
+	}
+
+	pub fn valid(self) -> Result<Self>
+	{
+		if self.magic[0] != 'p' as u8 && self.magic[0] != 'P' as u8 { return Err(Error("Invalid magic in PMD Header".to_string())); }
+		if self.magic[1] != 'm' as u8 && self.magic[1] != 'M' as u8 { return Err(Error("Invalid magic in PMD Header".to_string())); }
+		if self.magic[2] != 'd' as u8 && self.magic[2] != 'D' as u8 { return Err(Error("Invalid magic in PMD Header".to_string())); }
+		if self.version != PMD_VERSION_1_0 { return Err(Error("Invalid version in PMD Header".to_string())); }
+
+		Ok(self)
+	}
+
+	pub fn load(reader:&mut Cursor<&[u8]>) -> Result<Self>
+	{
+		let mut this = PmdHeader::new();
+		this.magic[0] = reader.read_u8()?;
+		this.magic[1] = reader.read_u8()?;
+		this.magic[2] = reader.read_u8()?;
+		this.version = reader.read_f32::<LittleEndian>()?;
+		this.name = PmdName::load(reader, 20)?;
+		this.comment = PmdName::load(reader, 256)?;
+		this.valid()
+	}
+}
+
+impl PmdVertex
+{
+	pub fn new() -> Self
+	{
+		Self
+		{
+			position:(0.0,0.0,0.0),
+			normal:(0.0,0.0,0.0),
+			coord:(0.0,0.0),
+			bone:(0,0),
+			weight:100,
+			edge:0,
+		}
+	}
+
+	pub fn valid(self) -> Result<Self>
+	{
+		if self.weight > 100 { return Err(Error("Invalid weight in PMD Vertex".to_string())); }
+		Ok(self)
+	}
+
+	pub fn load(reader:&mut Cursor<&[u8]>) -> Result<Self>
+	{
+		let mut this = PmdVertex::new();
+		this.position = PmdVector3::load(reader)?;
+		this.normal = PmdVector3::load(reader)?;
+		this.coord = PmdVector2::load(reader)?;
+		this.bone.0 = reader.read_u16::<LittleEndian>()?;
+		this.bone.1 = reader.read_u16::<LittleEndian>()?;
+		this.weight = reader.read_u8()?;
+		this.edge = reader.read_u8()?;
+
+		this.valid()
+	}
+
+	pub fn load_arrays(reader:&mut Cursor<&[u8]>) -> Result<Vec<Self>>
+	{
+		let count = reader.read_u32::<LittleEndian>()? as usize;
+		let count = read_count(reader, count, 38, "vertices", "PMD")?;
+
+		let mut vertices = Vec::with_capacity(count);
+		for _ in 0..count
+		{
+			vertices.push(PmdVertex::load(reader)?);
+		}
+
+		Ok(vertices)
+	}
+}
+
+impl PmdMaterial
+{
+	pub fn new() -> Self
+	{
+		Self
+		{
+			diffuse:(0.0,0.0,0.0),
+			opacity:1.0,
+			shininess:0.0,
+			specular:(0.0,0.0,0.0),
+			ambient:(0.0,0.0,0.0),
+			toon_index:PMD_TOON_NONE,
+			edge:0,
+			face_count:0,
+			texture_name:String::new(),
+		}
+	}
+
+	pub fn valid(self) -> Result<Self>
+	{
+		Ok(self)
+	}
+
+	pub fn load(reader:&mut Cursor<&[u8]>) -> Result<Self>
+	{
+		let mut this = PmdMaterial::new();
+		this.diffuse = PmdVector3::load(reader)?;
+		this.opacity = reader.read_f32::<LittleEndian>()?;
+		this.shininess = reader.read_f32::<LittleEndian>()?;
+		this.specular = PmdVector3::load(reader)?;
+		this.ambient = PmdVector3::load(reader)?;
+		this.toon_index = reader.read_u8()?;
+		this.edge = reader.read_u8()?;
+		this.face_count = reader.read_u32::<LittleEndian>()?;
+		this.texture_name = PmdName::load(reader, 20)?;
+
+		this.valid()
+	}
+
+	pub fn load_arrays(reader:&mut Cursor<&[u8]>) -> Result<Vec<Self>>
+	{
+		let count = reader.read_u32::<LittleEndian>()? as usize;
+		let count = read_count(reader, count, 70, "materials", "PMD")?;
+
+		let mut materials = Vec::with_capacity(count);
+		for _ in 0..count
+		{
+			materials.push(PmdMaterial::load(reader)?);
+		}
+
+		Ok(materials)
+	}
+
+	pub fn diffuse_texture(&self) -> Option<&str>
+	{
+		self.texture_name.split('*').find(|name| !name.is_empty() && !PmdMaterial::is_sphere(name))
+	}
+
+	pub fn sphere_texture(&self) -> Option<&str>
+	{
+		self.texture_name.split('*').find(|name| PmdMaterial::is_sphere(name))
+	}
+
+	fn is_sphere(name:&str) -> bool
+	{
+		let name = name.to_lowercase();
+		name.ends_with(".sph") || name.ends_with(".spa")
+	}
+}
+
+impl PmdBone
+{
+	pub fn new() -> Self
+	{
+		Self
+		{
+			name:String::new(),
+			parent:PMD_BONE_NONE,
+			child:PMD_BONE_NONE,
+			kind:PMD_BONE_ROTATE,
+			ik_parent:0,
+			position:(0.0,0.0,0.0),
+		}
+	}
+
+	pub fn valid(self) -> Result<Self>
+	{
+		Ok(self)
+	}
+
+	pub fn load(reader:&mut Cursor<&[u8]>) -> Result<Self>
+	{
+		let mut this = PmdBone::new();
+		this.name = PmdName::load(reader, 20)?;
+		this.parent = reader.read_u16::<LittleEndian>()?;
+		this.child = reader.read_u16::<LittleEndian>()?;
+		this.kind = reader.read_u8()?;
+		this.ik_parent = reader.read_u16::<LittleEndian>()?;
+		this.position = PmdVector3::load(reader)?;
+
+		this.valid()
+	}
+
+	pub fn load_arrays(reader:&mut Cursor<&[u8]>) -> Result<Vec<Self>>
+	{
+		let count = reader.read_u16::<LittleEndian>()? as usize;
+		let count = read_count(reader, count, 39, "bones", "PMD")?;
+
+		let mut bones = Vec::with_capacity(count);
+		for _ in 0..count
+		{
+			bones.push(PmdBone::load(reader)?);
+		}
+
+		Ok(bones)
+	}
+}
+
+impl PmdIK
+{
+	pub fn new() -> Self
+	{
+		Self
+		{
+			bone_index:0,
+			target_bone_index:0,
+			chain_length:0,
+			loop_count:0,
+			limited_radian:0.0,
+			child_bone_index:Vec::new(),
+		}
+	}
+
+	pub fn valid(self) -> Result<Self>
+	{
+		Ok(self)
+	}
+
+	pub fn load(reader:&mut Cursor<&[u8]>) -> Result<Self>
+	{
+		let mut this = PmdIK::new();
+		this.bone_index = reader.read_u16::<LittleEndian>()?;
+		this.target_bone_index = reader.read_u16::<LittleEndian>()?;
+		this.chain_length = reader.read_u8()?;
+		this.loop_count = reader.read_u16::<LittleEndian>()?;
+		this.limited_radian = reader.read_f32::<LittleEndian>()?;
+
+		for _ in 0..this.chain_length
+		{
+			this.child_bone_index.push(reader.read_u16::<LittleEndian>()?);
+		}
+
+		this.valid()
+	}
+
+	pub fn load_arrays(reader:&mut Cursor<&[u8]>) -> Result<Vec<Self>>
+	{
+		let count = reader.read_u16::<LittleEndian>()? as usize;
+		let count = read_count(reader, count, 11, "IK", "PMD")?;
+
+		let mut iks = Vec::with_capacity(count);
+		for _ in 0..count
+		{
+			iks.push(PmdIK::load(reader)?);
+		}
+
+		Ok(iks)
+	}
+}
+
+impl PmdMorph
+{
+	pub fn new() -> Self
+	{
+		Self
+		{
+			name:String::new(),
+			kind:PMD_MORPH_BASE,
+			vertices:Vec::new(),
+		}
+	}
+
+	pub fn valid(self) -> Result<Self>
+	{
+		if self.kind > PMD_MORPH_OTHER { return Err(Error("Invalid type in PMD Morph".to_string())); }
+		Ok(self)
+	}
+
+	pub fn load(reader:&mut Cursor<&[u8]>) -> Result<Self>
+	{
+		let mut this = PmdMorph::new();
+		this.name = PmdName::load(reader, 20)?;
+
+		let num_vertices = reader.read_u32::<LittleEndian>()?;
+		this.kind = reader.read_u8()?;
+		this.vertices = Vec::with_capacity(read_count(reader, num_vertices as usize, 16, "morph vertices", "PMD")?);
+
+		for _ in 0..num_vertices
+		{
+			let index = reader.read_u32::<LittleEndian>()?;
+			let offset = PmdVector3::load(reader)?;
+			this.vertices.push(PmdMorphVertex { index, offset });
+		}
+
+		this.valid()
+	}
+
+	pub fn load_arrays(reader:&mut Cursor<&[u8]>) -> Result<Vec<Self>>
+	{
+		let count = reader.read_u16::<LittleEndian>()? as usize;
+		let count = read_count(reader, count, 25, "morphs", "PMD")?;
+
+		let mut morphs = Vec::with_capacity(count);
+		for _ in 0..count
+		{
+			morphs.push(PmdMorph::load(reader)?);
+		}
+
+		Ok(morphs)
+	}
+}
+
+impl PmdDescription
+{
+	pub fn new() -> Self
+	{
+		Self
+		{
+			name:String::new(),
+			comment:String::new(),
+			bone_names:Vec::new(),
+			morph_names:Vec::new(),
+			frame_names:Vec::new(),
+		}
+	}
+
+	pub fn valid(self) -> Result<Self>
+	{
+		Ok(self)
+	}
+
+	pub fn load(reader:&mut Cursor<&[u8]>, num_bone:usize, num_morph:usize, num_frame:usize) -> Result<Self>
+	{
+		let mut this = PmdDescription::new();
+		this.name = PmdName::load(reader, 20)?;
+		this.comment = PmdName::load(reader, 256)?;
+
+		for _ in 0..num_bone
+		{
+			this.bone_names.push(PmdName::load(reader, 20)?);
+		}
+
+		// The base morph has no english name.
+		for _ in 1..num_morph
+		{
+			this.morph_names.push(PmdName::load(reader, 20)?);
+		}
+
+		for _ in 0..num_frame
+		{
+			this.frame_names.push(PmdName::load(reader, 50)?);
+		}
+
+		this.valid()
+	}
+}
+
+impl PmdRigidbody
+{
+	pub fn new() -> Self
+	{
+		Self
+		{
+			name:String::new(),
+			bone:0,
+			group:0,
+			group_mask:0,
+			shape:0,
+			scale:(0.0,0.0,0.0),
+			position:(0.0,0.0,0.0),
+			rotation:(0.0,0.0,0.0),
+			mass:0.0,
+			movement_decay:0.0,
+			rotation_decay:0.0,
+			elasticity:0.0,
+			friction:0.0,
+			physics_operation:0,
+		}
+	}
+
+	pub fn valid(self) -> Result<Self>
+	{
+		Ok(self)
+	}
+
+	pub fn load(reader:&mut Cursor<&[u8]>) -> Result<Self>
+	{
+		let mut this = PmdRigidbody::new();
+		this.name = PmdName::load(reader, 20)?;
+		this.bone = reader.read_u16::<LittleEndian>()?;
+		this.group = reader.read_u8()?;
+		this.group_mask = reader.read_u16::<LittleEndian>()?;
+		this.shape = reader.read_u8()?;
+		this.scale = PmdVector3::load(reader)?;
+		this.position = PmdVector3::load(reader)?;
+		this.rotation = PmdVector3::load(reader)?;
+		this.mass = reader.read_f32::<LittleEndian>()?;
+		this.movement_decay = reader.read_f32::<LittleEndian>()?;
+		this.rotation_decay = reader.read_f32::<LittleEndian>()?;
+		this.elasticity = reader.read_f32::<LittleEndian>()?;
+		this.friction = reader.read_f32::<LittleEndian>()?;
+		this.physics_operation = reader.read_u8()?;
+
+		this.valid()
+	}
+
+	pub fn load_arrays(reader:&mut Cursor<&[u8]>) -> Result<Vec<Self>>
+	{
+		let count = reader.read_u32::<LittleEndian>()? as usize;
+		let count = read_count(reader, count, 83, "rigidbodies", "PMD")?;
+
+		let mut rigidbodies = Vec::with_capacity(count);
+		for _ in 0..count
+		{
+			rigidbodies.push(PmdRigidbody::load(reader)?);
+		}
+
+		Ok(rigidbodies)
+	}
+}
+
+impl PmdJoint
+{
+	pub fn new() -> Self
+	{
+		Self
+		{
+			name:String::new(),
+			rigidbody_a:0,
+			rigidbody_b:0,
+			position:(0.0,0.0,0.0),
+			rotation:(0.0,0.0,0.0),
+			movement_lower_limit:(0.0,0.0,0.0),
+			movement_upper_limit:(0.0,0.0,0.0),
+			rotation_lower_limit:(0.0,0.0,0.0),
+			rotation_upper_limit:(0.0,0.0,0.0),
+			movement_spring_constant:(0.0,0.0,0.0),
+			rotation_spring_constant:(0.0,0.0,0.0),
+		}
+	}
+
+	pub fn valid(self) -> Result<Self>
+	{
+		Ok(self)
+	}
+
+	pub fn load(reader:&mut Cursor<&[u8]>) -> Result<Self>
+	{
+		let mut this = PmdJoint::new();
+		this.name = PmdName::load(reader, 20)?;
+		this.rigidbody_a = reader.read_u32::<LittleEndian>()?;
+		this.rigidbody_b = reader.read_u32::<LittleEndian>()?;
+		this.position = PmdVector3::load(reader)?;
+		this.rotation = PmdVector3::load(reader)?;
+		this.movement_lower_limit = PmdVector3::load(reader)?;
+		this.movement_upper_limit = PmdVector3::load(reader)?;
+		this.rotation_lower_limit = PmdVector3::load(reader)?;
+		this.rotation_upper_limit = PmdVector3::load(reader)?;
+		this.movement_spring_constant = PmdVector3::load(reader)?;
+		this.rotation_spring_constant = PmdVector3::load(reader)?;
+
+		this.valid()
+	}
+
+	pub fn load_arrays(reader:&mut Cursor<&[u8]>) -> Result<Vec<Self>>
+	{
+		let count = reader.read_u32::<LittleEndian>()? as usize;
+		let count = read_count(reader, count, 124, "joints", "PMD")?;
+
+		let mut joints = Vec::with_capacity(count);
+		for _ in 0..count
+		{
+			joints.push(PmdJoint::load(reader)?);
+		}
+
+		Ok(joints)
+	}
+}
+
+impl PMDFile
+{
+	pub fn new() -> Self
+	{
+		Self
+		{
+			header:PmdHeader::new(),
+			vertices:Vec::new(),
+			indices:Vec::new(),
+			materials:Vec::new(),
+			bones:Vec::new(),
+			iks:Vec::new(),
+			morphs:Vec::new(),
+			morph_display:Vec::new(),
+			frame_names:Vec::new(),
+			bone_display:Vec::new(),
+			description:None,
+			toons:Vec::new(),
+			rigidbodies:Vec::new(),
+			joints:Vec::new(),
+		}
+	}
+
+	pub fn load(buf:&[u8]) -> Result<Self>
+	{
+		let mut reader = Cursor::new(buf);
+		let mut pmd = PMDFile::new();
+		pmd.header = PmdHeader::load(&mut reader)?;
+		pmd.vertices = PmdVertex::load_arrays(&mut reader)?;
+
+		let num_indices = reader.read_u32::<LittleEndian>()?;
+		pmd.indices = Vec::with_capacity(read_count(&reader, num_indices as usize, 2, "indices", "PMD")?);
+		for _ in 0..num_indices
+		{
+			pmd.indices.push(reader.read_u16::<LittleEndian>()?);
+		}
+
+		pmd.materials = PmdMaterial::load_arrays(&mut reader)?;
+		pmd.bones = PmdBone::load_arrays(&mut reader)?;
+		pmd.iks = PmdIK::load_arrays(&mut reader)?;
+		pmd.morphs = PmdMorph::load_arrays(&mut reader)?;
+
+		for _ in 0..reader.read_u8()?
+		{
+			pmd.morph_display.push(reader.read_u16::<LittleEndian>()?);
+		}
+
+		for _ in 0..reader.read_u8()?
+		{
+			pmd.frame_names.push(PmdName::load(&mut reader, 50)?);
+		}
+
+		for _ in 0..reader.read_u32::<LittleEndian>()?
+		{
+			let bone_index = reader.read_u16::<LittleEndian>()?;
+			let frame_index = reader.read_u8()?;
+			pmd.bone_display.push(PmdBoneDisplay { bone_index, frame_index });
+		}
+
+		// Everything below was added by later versions of MikuMikuDance and is optional.
+		if PMDFile::eof(&reader) { return Ok(pmd); }
+
+		if reader.read_u8()? > 0
+		{
+			pmd.description = Some(PmdDescription::load(&mut reader, pmd.bones.len(), pmd.morphs.len(), pmd.frame_names.len())?);
+		}
+
+		if PMDFile::eof(&reader) { return Ok(pmd); }
+
+		for _ in 0..PMD_TOON_COUNT
+		{
+			pmd.toons.push(PmdName::load(&mut reader, 100)?);
+		}
+
+		if PMDFile::eof(&reader) { return Ok(pmd); }
+
+		pmd.rigidbodies = PmdRigidbody::load_arrays(&mut reader)?;
+		pmd.joints = PmdJoint::load_arrays(&mut reader)?;
+
+		Ok(pmd)
+	}
+
+	fn eof(reader:&Cursor<&[u8]>) -> bool
+	{
+		reader.position() as usize >= reader.get_ref().len()
+	}
+}
+
+#[derive(Debug)]
+pub struct PMDLoader {}
+
+impl PMDLoader
+{
+	pub fn new() -> Self
+	{
+		Self
+		{
+		}
+	}
+
+	fn add_texture(model:&mut Model, name:&str) -> usize
+	{
+		match model.textures.iter().position(|texture| texture == name)
+		{
+			Some(index) => index,
+			None =>
+			{
+				model.add_texture(name.to_string());
+				model.textures.len() - 1
+			}
+		}
+	}
+}
+
+impl Loader for PMDLoader
+{
+	fn can_read(&self, buf:&[u8]) -> bool
+	{
+		PmdHeader::load(&mut Cursor::new(buf)).is_ok()
+	}
+
+	fn do_load(&self, buf:&[u8]) -> Result<Model>
+	{
+		let pmd = PMDFile::load(buf)?;
+
+		if pmd.indices.iter().any(|&index| index as usize >= pmd.vertices.len())
+		{
+			return Err(Error("Invalid vertex index in PMD".to_string()));
+		}
+
+		if pmd.materials.iter().map(|it| it.face_count as usize).sum::<usize>() > pmd.indices.len()
+		{
+			return Err(Error("Invalid face count in PMD Material".to_string()));
+		}
+
+		let mut model = Model::new();
+		model.name = pmd.header.name.clone();
+
+		// Every other morph indexes into the vertices of the base morph.
+		if let Some(base) = pmd.morphs.iter().find(|morph| morph.kind == PMD_MORPH_BASE)
+		{
+			for it in pmd.morphs.iter().filter(|morph| morph.kind != PMD_MORPH_BASE)
+			{
+				let mut morph = Morph::new();
+				morph.name = it.name.clone();
+				morph.panel = it.kind;
+				morph.data = MorphData::Vertex(it.vertices.iter()
+					.filter_map(|v| base.vertices.get(v.index as usize).map(|b| VertexMorph { index:b.index, offset:v.offset }))
+					.collect());
+
+				model.add_morph(morph);
+			}
+		}
+
+		let targets:Vec<MorphTarget> = (0..model.morphs.len()).map(|i| MorphTarget::flatten(&model.morphs, i)).collect();
+
+		let mut start_indices:usize = 0;
+
+		for (material_id, it) in pmd.materials.iter().enumerate()
+		{
+			let mut mesh = Mesh::new();
+			mesh.name = format!("{}", material_id);
+			mesh.material_id = Some(material_id);
+			mesh.indices = Vec::with_capacity(it.face_count as usize);
+
+			let mut map:HashMap<u32,u32> = HashMap::new();
+			for i in start_indices..start_indices + it.face_count as usize
+			{
+				let index = pmd.indices[i] as u32;
+
+				if let Some(n) = map.get(&index)
+				{
+					mesh.indices.push(*n);
+					continue;
+				}
+
+				let len = map.len();
+				map.insert(index, len as u32);
+				mesh.indices.push(len as u32);
+			}
+
+			start_indices = start_indices + it.face_count as usize;
+
+			mesh.positions.resize(map.len() * 3, 0.0);
+			mesh.normals.resize(map.len() * 3, 0.0);
+			mesh.texcoords.resize(map.len() * 2, 0.0);
+
+			if pmd.bones.len() > 0
+			{
+				mesh.weights.resize(map.len(), VertexWeight::new());
+			}
+
+			for (&read, &write) in map.iter()
+			{
+				let vertex = &pmd.vertices[read as usize];
+				let v = vertex.position;
+				let n = vertex.normal;
+				let uv = vertex.coord;
+
+				mesh.positions[(write * 3) as usize] = v.0;
+				mesh.positions[(write * 3 + 1) as usize] = v.1;
+				mesh.positions[(write * 3 + 2) as usize] = v.2;
+
+				mesh.normals[(write * 3) as usize] = n.0;
+				mesh.normals[(write * 3 + 1) as usize] = n.1;
+				mesh.normals[(write * 3 + 2) as usize] = n.2;
+
+				mesh.texcoords[(write * 2) as usize] = uv.0;
+				mesh.texcoords[(write * 2 + 1) as usize] = uv.1;
+
+				if mesh.weights.len() > 0
+				{
+					let weight = vertex.weight as f32 / 100.0;
+
+					mesh.weights[write as usize] =
+						VertexWeight
+						{
+							bone:[vertex.bone.0,vertex.bone.1,0,0],
+							weight:[weight,1.0 - weight,0.0,0.0],
+						}
+				}
+			}
+
+			mesh.morphs = targets.iter()
+				.map(|target| target.remap(&map, material_id))
+				.filter(|target| !target.is_empty())
+				.collect();
+
+			model.add_mesh(mesh);
+		}
+
+		for it in pmd.materials.iter()
+		{
+			let mut material = Material::new();
+			material.ambient = [it.ambient.0, it.ambient.1, it.ambient.2];
+			material.diffuse = [it.diffuse.0, it.diffuse.1, it.diffuse.2];
+			material.specular = [it.specular.0, it.specular.1, it.specular.2];
+			material.shininess = it.shininess;
+			material.dissolve = it.opacity;
+			material.edge = it.edge > 0;
+			material.double_sided = it.opacity < 1.0;
+			material.ground_shadow = true;
+			// MikuMikuDance treats an opacity of exactly 0.98 as "no self shadow".
+			material.cast_shadow = (it.opacity - 0.98).abs() > 1e-4;
+			material.receive_shadow = material.cast_shadow;
+
+			if let Some(name) = it.diffuse_texture()
+			{
+				material.diffuse_texture = Some(PMDLoader::add_texture(&mut model, name));
+			}
+
+			if let Some(name) = it.sphere_texture()
+			{
+				material.sphere_texture = Some(PMDLoader::add_texture(&mut model, name));
+				material.sphere_mode = if name.to_lowercase().ends_with(".spa") { SphereMode::Additive } else { SphereMode::Multiply };
+			}
+
+			if it.toon_index != PMD_TOON_NONE && (it.toon_index as usize) < PMD_TOON_COUNT
+			{
+				// Models may replace the shared toon textures with their own files.
+				material.toon = match pmd.toons.get(it.toon_index as usize)
+				{
+					Some(name) if !name.is_empty() && name.to_lowercase() != Toon::shared_name(it.toon_index) =>
+					{
+						Toon::Texture(PMDLoader::add_texture(&mut model, name))
+					},
+					_ => Toon::Shared(it.toon_index)
+				};
+			}
+
+			model.add_material(material);
+		}
+
+		for it in pmd.bones.iter()
+		{
+			let mut bone = Bone::new();
+			bone.name = it.name.clone();
+			bone.parent = if it.parent != PMD_BONE_NONE { Some(it.parent) } else { None };
+			bone.position = it.position;
+			bone.movable = it.kind == PMD_BONE_ROTATE_MOVE || it.kind == PMD_BONE_IK;
+			bone.visible = it.kind != PMD_BONE_INVISIBLE && it.kind != PMD_BONE_IK_TARGET;
+
+			// The child is the tail bone, except for rotate ratio bones and the 0 most tools write for none.
+			if it.kind != PMD_BONE_ROTATE_RATIO && it.child != PMD_BONE_NONE && it.child != 0
+			{
+				bone.tail = BoneTail::Bone(it.child);
+			}
+
+			// Rotate effect bones follow the IK chain they are attached to, rotate ratio bones store the ratio in percent as the child.
+			if it.ik_parent != PMD_BONE_NONE && (it.kind == PMD_BONE_ROTATE_EFFECT || it.kind == PMD_BONE_ROTATE_RATIO)
+			{
+				let mut grant = BoneGrant::new();
+				grant.parent = it.ik_parent;
+				grant.ratio = if it.kind == PMD_BONE_ROTATE_RATIO { it.child as f32 * 0.01 } else { 1.0 };
+				grant.rotation = true;
+				bone.grant = Some(grant);
+			}
+
+			model.add_bone(bone);
+		}
+
+		for it in pmd.iks.iter()
+		{
+			let mut ik = Solver::new();
+			ik.bone = it.bone_index as _;
+			ik.target_bone = it.target_bone_index as _;
+			ik.loop_count = it.loop_count as u32;
+			ik.chain_length = it.chain_length as u32;
+			// PMD stores the limit angle per iteration in units of 4 radians.
+			ik.limited_radian = it.limited_radian * 4.0;
+
+			for child in it.child_bone_index.iter()
+			{
+				let mut link = BoneLink::new();
+				link.bone = *child as _;
+
+				// PMD has no per-link limits, MikuMikuDance only restricts knees to bend forward.
+				if let Some(bone) = pmd.bones.get(*child as usize)
+				{
+					if bone.name.contains("ひざ")
+					{
+						link.rotate_limited = true;
+						link.minimum_radian = (-f32::consts::PI, 0.0, 0.0);
+						link.maximum_radian = (-0.5f32.to_radians(), 0.0, 0.0);
+					}
+				}
+
+				ik.links.push(link);
+			}
+
+			model.add_ik(ik);
+		}
+
+		for it in pmd.rigidbodies.iter()
+		{
+			// PMD rigidbodies are placed relative to their bone, bodies without one follow the first bone.
+			let bone = if it.bone != PMD_BONE_NONE { it.bone } else { 0 };
+			let origin = pmd.bones.get(bone as usize).map(|b| b.position).unwrap_or((0.0,0.0,0.0));
+
+			let mut rigidbody = RigidBody::new();
+			rigidbody.name = it.name.clone();
+			rigidbody.bone = if it.bone != PMD_BONE_NONE { Some(it.bone) } else { None };
+			rigidbody.group = it.group;
+			rigidbody.group_mask = it.group_mask;
+			rigidbody.shape = it.shape;
+			rigidbody.size = it.scale;
+			rigidbody.position = (origin.0 + it.position.0, origin.1 + it.position.1, origin.2 + it.position.2);
+			rigidbody.rotation = it.rotation;
+			rigidbody.mass = it.mass;
+			rigidbody.linear_damping = it.movement_decay;
+			rigidbody.angular_damping = it.rotation_decay;
+			rigidbody.restitution = it.elasticity;
+			rigidbody.friction = it.friction;
+			rigidbody.physics_operation = it.physics_operation;
+			model.add_rigidbody(rigidbody);
+		}
+
+		for it in pmd.joints.iter()
+		{
+			let mut joint = Joint::new();
+			joint.name = it.name.clone();
+			joint.rigidbody_a = it.rigidbody_a;
+			joint.rigidbody_b = it.rigidbody_b;
+			joint.position = it.position;
+			joint.rotation = it.rotation;
+			joint.translate_lower_limit = it.movement_lower_limit;
+			joint.translate_upper_limit = it.movement_upper_limit;
+			joint.rotation_lower_limit = it.rotation_lower_limit;
+			joint.rotation_upper_limit = it.rotation_upper_limit;
+			joint.translate_spring = it.movement_spring_constant;
+			joint.rotation_spring = it.rotation_spring_constant;
+			model.add_joint(joint);
+		}
+
+		Ok(model)
+	}
+}
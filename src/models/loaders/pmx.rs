@@ -1,19 +1,64 @@
-use std::io::{Cursor};
+use std::io::{Cursor, Write};
 use std::collections::HashMap;
 use std::f32;
-use byteorder::{LittleEndian, ReadBytesExt};
-use super::super::{Loader, Model, Mesh, Material, VertexWeight, Bone, BoneLink, Solver, Error, Result};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use crate::binary::read_count;
+use super::super::{Loader, Model, Mesh, Material, SphereMode, Toon, VertexWeight, Bone, BoneTail, BoneGrant, BoneLink, Solver, Error, Result};
+use super::super::{Morph, MorphData, MorphTarget, VertexMorph, UVMorph, BoneMorph, MaterialMorph, GroupMorph, ImpulseMorph, RigidBody, Joint, SoftBody, SoftBodyAnchor};
 
 pub const PMX_VERSION_2_0: f32 = 2.0;
+pub const PMX_VERSION_2_1: f32 = 2.1;
+pub const PMX_ENCODE_UTF16: u8 = 0;
+pub const PMX_ENCODE_UTF8: u8 = 1;
 pub const PMX_BONE_INDEX: u16 = 1 << 0;
 pub const PMX_BONE_MOVE: u16 = 1 << 1;
 pub const PMX_BONE_DISPLAY: u16 = 1 << 2;
 pub const PMX_BONE_OPERATOR: u16 = 1 << 3;
 pub const PMX_BONE_ROOT: u16 = 1 << 4;
+pub const PMX_BONE_ROTATABLE: u16 = 1 << 1;
+pub const PMX_BONE_MOVABLE: u16 = 1 << 2;
+pub const PMX_BONE_VISIBLE: u16 = 1 << 3;
+pub const PMX_BONE_OPERABLE: u16 = 1 << 4;
 pub const PMX_BONE_IK: u16 = 1 << 5;
 pub const PMX_BONE_PARENT: u16 = 1 << 8;
+pub const PMX_BONE_MOVE_PARENT: u16 = 1 << 9;
 pub const PMX_BONE_AXIS: u16 = 1 << 10;
 pub const PMX_BONE_ROTATE: u16 = 1 << 11;
+pub const PMX_BONE_PHYSICS_AFTER: u16 = 1 << 12;
+pub const PMX_BONE_EXTERNAL_PARENT: u16 = 1 << 13;
+
+// PmxMaterialFlag
+pub const PMX_MATERIAL_DOUBLE_SIDED:u8 = 1 << 0;
+pub const PMX_MATERIAL_GROUND_SHADOW:u8 = 1 << 1;
+pub const PMX_MATERIAL_CAST_SHADOW:u8 = 1 << 2;
+pub const PMX_MATERIAL_RECEIVE_SHADOW:u8 = 1 << 3;
+pub const PMX_MATERIAL_EDGE:u8 = 1 << 4;
+pub const PMX_MATERIAL_VERTEX_COLOR:u8 = 1 << 5;
+pub const PMX_MATERIAL_POINT_DRAW:u8 = 1 << 6;
+pub const PMX_MATERIAL_LINE_DRAW:u8 = 1 << 7;
+
+// PmxSphereMode
+pub const PMX_SPHERE_NONE:u8 = 0;
+pub const PMX_SPHERE_MULTIPLY:u8 = 1;
+pub const PMX_SPHERE_ADDITIVE:u8 = 2;
+pub const PMX_SPHERE_SUB_TEXTURE:u8 = 3;
+
+// PmxMorphType
+pub const PMX_MORPH_GROUP:u8 = 0;
+pub const PMX_MORPH_VERTEX:u8 = 1;
+pub const PMX_MORPH_BONE:u8 = 2;
+pub const PMX_MORPH_UV:u8 = 3;
+pub const PMX_MORPH_UV1:u8 = 4;
+pub const PMX_MORPH_UV2:u8 = 5;
+pub const PMX_MORPH_UV3:u8 = 6;
+pub const PMX_MORPH_UV4:u8 = 7;
+pub const PMX_MORPH_MATERIAL:u8 = 8;
+pub const PMX_MORPH_FLIP:u8 = 9;
+pub const PMX_MORPH_IMPULSE:u8 = 10;
+
+// PmxFrameElementType
+pub const PMX_FRAME_BONE:u8 = 0;
+pub const PMX_FRAME_MORPH:u8 = 1;
 
 // PmxVertexSkinningType
 pub const PMX_BDEF1:u8 = 0;
@@ -40,7 +85,7 @@ pub struct PmxHeader
 	pub sizeof_indices:u8,  // ( 1 or 2 or 4 )
 	pub sizeof_texture:u8,  // ( 1 or 2 or 4 )
 	pub sizeof_material:u8, // ( 1 or 2 or 4 )
-	pub sizeof_bone:u8,     // ( 1 or 2 )
+	pub sizeof_bone:u8,     // ( 1 or 2 or 4 )
 	pub sizeof_morph:u8,    // ( 1 or 2 or 4 )
 	pub sizeof_body:u8,     // ( 1 or 2 or 4 )
 }
@@ -147,12 +192,174 @@ pub struct PmxBone
 	pub axis_direction:(f32,f32,f32),
 	pub dimention_direction_x:(f32,f32,f32),
 	pub dimention_direction_z:(f32,f32,f32),
+	pub external_parent_key:i32,
 	pub ik_target_bone_index:i16,
 	pub ik_loop_count:u32,
 	pub ik_limited_radian:f32,
 	pub ik_links:Vec<PmxIK>,
 }
 
+#[derive(Debug)]
+pub struct PmxMorphVertex
+{
+	pub index:u32,
+	pub offset:(f32,f32,f32),
+}
+
+#[derive(Debug)]
+pub struct PmxMorphUV
+{
+	pub index:u32,
+	pub offset:(f32,f32,f32,f32),
+}
+
+#[derive(Debug)]
+pub struct PmxMorphBone
+{
+	pub bone_index:i16,
+	pub translate:(f32,f32,f32),
+	pub rotation:(f32,f32,f32,f32),
+}
+
+#[derive(Debug)]
+pub struct PmxMorphMaterial
+{
+	pub index:i32, // -1 : All materials
+	pub operation:u8, // 0 : Multiply, 1 : Additive
+	pub diffuse:(f32,f32,f32,f32),
+	pub specular:(f32,f32,f32),
+	pub shininess:f32,
+	pub ambient:(f32,f32,f32),
+	pub edge_color:(f32,f32,f32,f32),
+	pub edge_size:f32,
+	pub texture:(f32,f32,f32,f32),
+	pub sphere:(f32,f32,f32,f32),
+	pub toon:(f32,f32,f32,f32),
+}
+
+#[derive(Debug)]
+pub struct PmxMorphGroup
+{
+	pub index:i32,
+	pub ratio:f32,
+}
+
+#[derive(Debug)]
+pub struct PmxMorphImpulse
+{
+	pub index:i32,
+	pub local:u8,
+	pub velocity:(f32,f32,f32),
+	pub torque:(f32,f32,f32),
+}
+
+#[derive(Debug)]
+pub enum PmxMorphData
+{
+	Group(Vec<PmxMorphGroup>),
+	Vertex(Vec<PmxMorphVertex>),
+	Bone(Vec<PmxMorphBone>),
+	UV(Vec<PmxMorphUV>),
+	Material(Vec<PmxMorphMaterial>),
+	Flip(Vec<PmxMorphGroup>),
+	Impulse(Vec<PmxMorphImpulse>),
+}
+
+#[derive(Debug)]
+pub struct PmxMorph
+{
+	pub name:String,
+	pub name_eng:String,
+	pub panel:u8,
+	pub kind:u8, // PmxMorphType
+	pub data:PmxMorphData,
+}
+
+#[derive(Debug)]
+pub struct PmxFrameElement
+{
+	pub kind:u8, // PmxFrameElementType
+	pub index:i32,
+}
+
+#[derive(Debug)]
+pub struct PmxFrame
+{
+	pub name:String,
+	pub name_eng:String,
+	pub special:u8,
+	pub elements:Vec<PmxFrameElement>,
+}
+
+#[derive(Debug)]
+pub struct PmxRigidbody
+{
+	pub name:String,
+	pub name_eng:String,
+	pub bone:i16,
+	pub group:u8,
+	pub group_mask:u16,
+	pub shape:u8, // 0 : Sphere, 1 : Box, 2 : Capsule
+	pub scale:(f32,f32,f32),
+	pub position:(f32,f32,f32),
+	pub rotation:(f32,f32,f32),
+	pub mass:f32,
+	pub movement_decay:f32,
+	pub rotation_decay:f32,
+	pub elasticity:f32,
+	pub friction:f32,
+	pub physics_operation:u8, // 0 : Static, 1 : Dynamic, 2 : Dynamic with bone
+}
+
+#[derive(Debug)]
+pub struct PmxJoint
+{
+	pub name:String,
+	pub name_eng:String,
+	pub kind:u8, // 0 : Spring6DOF, 2.1 only : 1 : 6DOF, 2 : P2P, 3 : ConeTwist, 4 : Slider, 5 : Hinge
+	pub rigidbody_a:i32,
+	pub rigidbody_b:i32,
+	pub position:(f32,f32,f32),
+	pub rotation:(f32,f32,f32),
+	pub movement_lower_limit:(f32,f32,f32),
+	pub movement_upper_limit:(f32,f32,f32),
+	pub rotation_lower_limit:(f32,f32,f32),
+	pub rotation_upper_limit:(f32,f32,f32),
+	pub movement_spring_constant:(f32,f32,f32),
+	pub rotation_spring_constant:(f32,f32,f32),
+}
+
+#[derive(Debug)]
+pub struct PmxSoftbodyAnchor
+{
+	pub rigidbody:i32,
+	pub vertex:u32,
+	pub near_mode:u8,
+}
+
+#[derive(Debug)]
+pub struct PmxSoftbody
+{
+	pub name:String,
+	pub name_eng:String,
+	pub shape:u8, // 0 : TriMesh, 1 : Rope
+	pub material:i32,
+	pub group:u8,
+	pub group_mask:u16,
+	pub flag:u8,
+	pub blink_length:i32,
+	pub num_clusters:i32,
+	pub total_mass:f32,
+	pub collision_margin:f32,
+	pub aero_model:i32,
+	pub config:[f32;12],  // VCF, DP, DG, LF, PR, VC, DF, MT, CHR, KHR, SHR, AHR
+	pub cluster:[f32;6],  // SRHR_CL, SKHR_CL, SSHR_CL, SR_SPLT_CL, SK_SPLT_CL, SS_SPLT_CL
+	pub iteration:[i32;4], // V_IT, P_IT, D_IT, C_IT
+	pub material_coefficient:[f32;3], // LST, AST, VST
+	pub anchors:Vec<PmxSoftbodyAnchor>,
+	pub pin_vertices:Vec<u32>,
+}
+
 #[derive(Debug)]
 pub struct PMXFile
 {
@@ -162,7 +369,12 @@ pub struct PMXFile
 	pub indices:PmxIndex,
 	pub textures:Vec<String>,
 	pub materials:Vec<PmxMaterial>,
-	pub bones:Vec<PmxBone>
+	pub bones:Vec<PmxBone>,
+	pub morphs:Vec<PmxMorph>,
+	pub frames:Vec<PmxFrame>,
+	pub rigidbodies:Vec<PmxRigidbody>,
+	pub joints:Vec<PmxJoint>,
+	pub softbodies:Vec<PmxSoftbody>,
 }
 
 impl PmxVector2
@@ -173,6 +385,13 @@ impl PmxVector2
 		let y = reader.read_f32::<LittleEndian>()?;
 		Ok((x, y))
 	}
+
+	pub fn save<W:Write>(writer:&mut W, v:(f32,f32)) -> Result<()>
+	{
+		writer.write_f32::<LittleEndian>(v.0)?;
+		writer.write_f32::<LittleEndian>(v.1)?;
+		Ok(())
+	}
 }
 
 impl PmxVector3
@@ -184,6 +403,14 @@ impl PmxVector3
 		let z = reader.read_f32::<LittleEndian>()?;
 		Ok((x, y, z))
 	}
+
+	pub fn save<W:Write>(writer:&mut W, v:(f32,f32,f32)) -> Result<()>
+	{
+		writer.write_f32::<LittleEndian>(v.0)?;
+		writer.write_f32::<LittleEndian>(v.1)?;
+		writer.write_f32::<LittleEndian>(v.2)?;
+		Ok(())
+	}
 }
 
 impl PmxVector4
@@ -196,33 +423,99 @@ impl PmxVector4
 		let w = reader.read_f32::<LittleEndian>()?;
 		Ok((x, y, z, w))
 	}
+
+	pub fn save<W:Write>(writer:&mut W, v:(f32,f32,f32,f32)) -> Result<()>
+	{
+		writer.write_f32::<LittleEndian>(v.0)?;
+		writer.write_f32::<LittleEndian>(v.1)?;
+		writer.write_f32::<LittleEndian>(v.2)?;
+		writer.write_f32::<LittleEndian>(v.3)?;
+		Ok(())
+	}
 }
 
 impl PmxName
 {
-	pub fn load(reader:&mut Cursor<&[u8]>) -> Result<String>
+	pub fn load(reader:&mut Cursor<&[u8]>, hdr:&PmxHeader) -> Result<String>
 	{
-		let length = reader.read_u32::<LittleEndian>()?;
-		let mut name = Vec::new();
+		let length = reader.read_u32::<LittleEndian>()? as usize;
+		let length = read_count(reader, length, 1, "name bytes", "PMX")?;
 
-		for _ in 0..length / 2
+		if hdr.encode == PMX_ENCODE_UTF8
 		{
-			name.push(reader.read_u16::<LittleEndian>()?);
+			let mut name = Vec::with_capacity(length);
+
+			for _ in 0..length
+			{
+				name.push(reader.read_u8()?);
+			}
+
+			Ok(String::from_utf8(name)?)
 		}
+		else
+		{
+			let mut name = Vec::with_capacity(length / 2);
+
+			for _ in 0..length / 2
+			{
+				name.push(reader.read_u16::<LittleEndian>()?);
+			}
 
-		Ok(String::from_utf16(&name)?)
+			Ok(String::from_utf16(&name)?)
+		}
 	}
 
-	pub fn load_arrays(reader:&mut Cursor<&[u8]>) -> Result<Vec<String>>
+	pub fn load_arrays(reader:&mut Cursor<&[u8]>, hdr:&PmxHeader) -> Result<Vec<String>>
 	{
-		let mut names = Vec::with_capacity(reader.read_u32::<LittleEndian>()? as usize);
-		for _ in 0..names.capacity()
+		let count = reader.read_u32::<LittleEndian>()? as usize;
+		let count = read_count(reader, count, 4, "names", "PMX")?;
+
+		let mut names = Vec::with_capacity(count);
+		for _ in 0..count
 		{
-			names.push(PmxName::load(reader)?);
+			names.push(PmxName::load(reader, hdr)?);
 		}
 
 		Ok(names)
 	}
+
+	pub fn encode(name:&str, hdr:&PmxHeader) -> Vec<u8>
+	{
+		if hdr.encode == PMX_ENCODE_UTF8
+		{
+			name.as_bytes().to_vec()
+		}
+		else
+		{
+			let mut bytes = Vec::with_capacity(name.len() * 2);
+			for it in name.encode_utf16()
+			{
+				bytes.push((it & 0xFF) as u8);
+				bytes.push((it >> 8) as u8);
+			}
+
+			bytes
+		}
+	}
+
+	pub fn save<W:Write>(writer:&mut W, name:&str, hdr:&PmxHeader) -> Result<()>
+	{
+		let bytes = PmxName::encode(name, hdr);
+		writer.write_u32::<LittleEndian>(bytes.len() as u32)?;
+		writer.write_all(&bytes)?;
+		Ok(())
+	}
+
+	pub fn save_arrays<W:Write>(writer:&mut W, names:&[String], hdr:&PmxHeader) -> Result<()>
+	{
+		writer.write_u32::<LittleEndian>(names.len() as u32)?;
+		for it in names
+		{
+			PmxName::save(writer, it, hdr)?;
+		}
+
+		Ok(())
+	}
 }
 
 impl PmxInteger
@@ -232,11 +525,54 @@ impl PmxInteger
 		let value;
 		if len == 1 { value = reader.read_i8()? as i32; }
 		else if len == 2 { value = reader.read_i16::<LittleEndian>()? as i32; }
-		else if len == 3 { value = reader.read_i32::<LittleEndian>()? as i32; }
+		else if len == 4 { value = reader.read_i32::<LittleEndian>()? as i32; }
+		else { return Err(Error("Invalid length of byte".to_string())); }
+
+		Ok(value)
+	}
+
+	pub fn load_unsigned(reader:&mut Cursor<&[u8]>, len:u8) -> Result<u32>
+	{
+		let value;
+		if len == 1 { value = reader.read_u8()? as u32; }
+		else if len == 2 { value = reader.read_u16::<LittleEndian>()? as u32; }
+		else if len == 4 { value = reader.read_u32::<LittleEndian>()?; }
 		else { return Err(Error("Invalid length of byte".to_string())); }
 
 		Ok(value)
 	}
+
+	pub fn save<W:Write>(writer:&mut W, len:u8, value:i32) -> Result<()>
+	{
+		if len == 1 { writer.write_i8(value as i8)?; }
+		else if len == 2 { writer.write_i16::<LittleEndian>(value as i16)?; }
+		else if len == 4 { writer.write_i32::<LittleEndian>(value)?; }
+		else { return Err(Error("Invalid length of byte".to_string())); }
+
+		Ok(())
+	}
+
+	pub fn save_unsigned<W:Write>(writer:&mut W, len:u8, value:u32) -> Result<()>
+	{
+		if len == 1 { writer.write_u8(value as u8)?; }
+		else if len == 2 { writer.write_u16::<LittleEndian>(value as u16)?; }
+		else if len == 4 { writer.write_u32::<LittleEndian>(value)?; }
+		else { return Err(Error("Invalid length of byte".to_string())); }
+
+		Ok(())
+	}
+
+	/// Smallest size able to hold a signed index where -1 means "none".
+	pub fn sizeof(count:usize) -> u8
+	{
+		if count <= 0x7F { 1 } else if count <= 0x7FFF { 2 } else { 4 }
+	}
+
+	/// Smallest size able to hold an unsigned vertex index.
+	pub fn sizeof_unsigned(count:usize) -> u8
+	{
+		if count <= 0xFF { 1 } else if count <= 0xFFFF { 2 } else { 4 }
+	}
 }
 
 impl PmxHeader
@@ -265,15 +601,15 @@ impl PmxHeader
 		if self.magic[0] != 'p' as u8 && self.magic[0] != 'P' as u8 { return Err(Error("Invalid magic in PMX Header".to_string())); }
 		if self.magic[1] != 'm' as u8 && self.magic[1] != 'M' as u8 { return Err(Error("Invalid magic in PMX Header".to_string())); }
 		if self.magic[2] != 'x' as u8 && self.magic[2] != 'X' as u8 { return Err(Error("Invalid magic in PMX Header".to_string())); }
-		if self.version != 2.0 { return Err(Error("Invalid version in PMX Header".to_string())); }
+		if self.version != PMX_VERSION_2_0 && self.version != PMX_VERSION_2_1 { return Err(Error("Invalid version in PMX Header".to_string())); }
 		if self.offset == 0 { return Err(Error("Invalid offset in PMX Header".to_string())); }
 		if self.data_size == 0 { return Err(Error("Invalid data_size in PMX Header".to_string())); }
 		if self.encode != 0x0 && self.encode != 0x1 { return Err(Error("Invalid encode in PMX Header".to_string())); }
 		if self.add_uv_count > 8 { return Err(Error("Invalid add_uv_count in PMX Header".to_string())); }
 		if self.sizeof_indices != 1 && self.sizeof_indices != 2 && self.sizeof_indices != 4 { return Err(Error("Invalid sizeof_indices in PMX Header".to_string())); }
-		if self.sizeof_texture != 1 && self.sizeof_texture != 2 { return Err(Error("Invalid sizeof_texture in PMX Header".to_string())); }
+		if self.sizeof_texture != 1 && self.sizeof_texture != 2 && self.sizeof_texture != 4 { return Err(Error("Invalid sizeof_texture in PMX Header".to_string())); }
 		if self.sizeof_material != 1 && self.sizeof_material != 2 && self.sizeof_material != 4 { return Err(Error("Invalid sizeof_material in PMX Header".to_string())); }
-		if self.sizeof_bone != 1 && self.sizeof_bone != 2 { return Err(Error("Invalid sizeof_bone in PMX Header".to_string())); }
+		if self.sizeof_bone != 1 && self.sizeof_bone != 2 && self.sizeof_bone != 4 { return Err(Error("Invalid sizeof_bone in PMX Header".to_string())); }
 		if self.sizeof_morph != 1 && self.sizeof_morph != 2 && self.sizeof_morph != 4 { return Err(Error("Invalid sizeof_morph in PMX Header".to_string())); }
 		if self.sizeof_body != 1 && self.sizeof_body != 2 && self.sizeof_body != 4 { return Err(Error("Invalid sizeof_body in PMX Header".to_string())); }
 
@@ -299,6 +635,23 @@ impl PmxHeader
 		this.sizeof_body = reader.read_u8()?;
 		this.valid()
 	}
+
+	pub fn save<W:Write>(&self, writer:&mut W) -> Result<()>
+	{
+		writer.write_all(&self.magic)?;
+		writer.write_u8(self.offset)?;
+		writer.write_f32::<LittleEndian>(self.version)?;
+		writer.write_u8(self.data_size)?;
+		writer.write_u8(self.encode)?;
+		writer.write_u8(self.add_uv_count)?;
+		writer.write_u8(self.sizeof_indices)?;
+		writer.write_u8(self.sizeof_texture)?;
+		writer.write_u8(self.sizeof_material)?;
+		writer.write_u8(self.sizeof_bone)?;
+		writer.write_u8(self.sizeof_morph)?;
+		writer.write_u8(self.sizeof_body)?;
+		Ok(())
+	}
 }
 
 impl PmxDescription
@@ -335,7 +688,7 @@ impl PmxDescription
 		this.english_model_length = reader.read_u32::<LittleEndian>()?;
 		for _ in 0..this.english_model_length
 		{
-			this.japan_model_name.push(reader.read_u8()?);
+			this.english_model_name.push(reader.read_u8()?);
 		}
 
 		this.japan_comment_length = reader.read_u32::<LittleEndian>()?;
@@ -347,11 +700,34 @@ impl PmxDescription
 		this.english_comment_length = reader.read_u32::<LittleEndian>()?;
 		for _ in 0..this.english_comment_length
 		{
-			this.japan_comment_name.push(reader.read_u8()?);
+			this.english_comment_name.push(reader.read_u8()?);
 		}
 
 		this.valid()
 	}
+
+	pub fn from_names(hdr:&PmxHeader, model_name:&str, comment:&str) -> Self
+	{
+		let mut this = PmxDescription::new();
+		this.japan_model_name = PmxName::encode(model_name, hdr);
+		this.japan_comment_name = PmxName::encode(comment, hdr);
+		this.japan_model_length = this.japan_model_name.len() as u32;
+		this.japan_comment_length = this.japan_comment_name.len() as u32;
+		this
+	}
+
+	pub fn save<W:Write>(&self, writer:&mut W) -> Result<()>
+	{
+		writer.write_u32::<LittleEndian>(self.japan_model_name.len() as u32)?;
+		writer.write_all(&self.japan_model_name)?;
+		writer.write_u32::<LittleEndian>(self.english_model_name.len() as u32)?;
+		writer.write_all(&self.english_model_name)?;
+		writer.write_u32::<LittleEndian>(self.japan_comment_name.len() as u32)?;
+		writer.write_all(&self.japan_comment_name)?;
+		writer.write_u32::<LittleEndian>(self.english_comment_name.len() as u32)?;
+		writer.write_all(&self.english_comment_name)?;
+		Ok(())
+	}
 }
 
 impl PmxBoneWeight
@@ -425,12 +801,17 @@ impl PmxBoneWeight
 				this.sdef_r1.2 = reader.read_f32::<LittleEndian>()?;
 				this.weight2 = 1.0 - this.weight1;
 			},
+			// Laid out like BDEF4, only the way the weights blend differs.
 			PMX_QDEF =>
 			{
 				this.bone1 = PmxInteger::load(reader, hdr.sizeof_bone)? as _;
 				this.bone2 = PmxInteger::load(reader, hdr.sizeof_bone)? as _;
+				this.bone3 = PmxInteger::load(reader, hdr.sizeof_bone)? as _;
+				this.bone4 = PmxInteger::load(reader, hdr.sizeof_bone)? as _;
 				this.weight1 = reader.read_f32::<LittleEndian>()?;
-				this.weight2 = 1.0 - this.weight1;
+				this.weight2 = reader.read_f32::<LittleEndian>()?;
+				this.weight3 = reader.read_f32::<LittleEndian>()?;
+				this.weight4 = reader.read_f32::<LittleEndian>()?;
 			},
 			_ => 
 			{
@@ -440,6 +821,66 @@ impl PmxBoneWeight
 
 		this.valid()
 	}
+
+	// Bones are kept unsigned, the -1 of a missing bone reads back as 0xFFFF.
+	fn bone_index(bone:u16, hdr:&PmxHeader) -> Result<i32>
+	{
+		if bone == std::u16::MAX
+		{
+			return Ok(-1);
+		}
+
+		if PmxInteger::sizeof(bone as usize) > hdr.sizeof_bone
+		{
+			return Err(Error(format!("Bone index {} doesn't fit in {} bytes", bone, hdr.sizeof_bone)));
+		}
+
+		Ok(bone as i32)
+	}
+
+	pub fn save<W:Write>(&self, writer:&mut W, hdr:&PmxHeader) -> Result<()>
+	{
+		writer.write_u8(self.kind)?;
+		match self.kind
+		{
+			PMX_BDEF1 =>
+			{
+				PmxInteger::save(writer, hdr.sizeof_bone, PmxBoneWeight::bone_index(self.bone1, hdr)?)?;
+			},
+			PMX_BDEF2 =>
+			{
+				PmxInteger::save(writer, hdr.sizeof_bone, PmxBoneWeight::bone_index(self.bone1, hdr)?)?;
+				PmxInteger::save(writer, hdr.sizeof_bone, PmxBoneWeight::bone_index(self.bone2, hdr)?)?;
+				writer.write_f32::<LittleEndian>(self.weight1)?;
+			},
+			PMX_BDEF4 | PMX_QDEF =>
+			{
+				PmxInteger::save(writer, hdr.sizeof_bone, PmxBoneWeight::bone_index(self.bone1, hdr)?)?;
+				PmxInteger::save(writer, hdr.sizeof_bone, PmxBoneWeight::bone_index(self.bone2, hdr)?)?;
+				PmxInteger::save(writer, hdr.sizeof_bone, PmxBoneWeight::bone_index(self.bone3, hdr)?)?;
+				PmxInteger::save(writer, hdr.sizeof_bone, PmxBoneWeight::bone_index(self.bone4, hdr)?)?;
+				writer.write_f32::<LittleEndian>(self.weight1)?;
+				writer.write_f32::<LittleEndian>(self.weight2)?;
+				writer.write_f32::<LittleEndian>(self.weight3)?;
+				writer.write_f32::<LittleEndian>(self.weight4)?;
+			},
+			PMX_SDEF =>
+			{
+				PmxInteger::save(writer, hdr.sizeof_bone, PmxBoneWeight::bone_index(self.bone1, hdr)?)?;
+				PmxInteger::save(writer, hdr.sizeof_bone, PmxBoneWeight::bone_index(self.bone2, hdr)?)?;
+				writer.write_f32::<LittleEndian>(self.weight1)?;
+				PmxVector3::save(writer, self.sdef_c)?;
+				PmxVector3::save(writer, self.sdef_r0)?;
+				PmxVector3::save(writer, self.sdef_r1)?;
+			},
+			_ =>
+			{
+				return Err(Error("Invalid Token bone type".to_string()));
+			}
+		}
+
+		Ok(())
+	}
 }
 
 impl PmxVertex
@@ -482,26 +923,58 @@ impl PmxVertex
 
 	pub fn load_arrays(reader:&mut Cursor<&[u8]>, hdr:&PmxHeader) -> Result<Vec<Self>>
 	{
-		let mut vertices = Vec::with_capacity(reader.read_u32::<LittleEndian>()? as usize);
-		for _ in 0..vertices.capacity()
+		let count = reader.read_u32::<LittleEndian>()? as usize;
+		let count = read_count(reader, count, 37 + 16 * hdr.add_uv_count as usize + hdr.sizeof_bone as usize, "vertices", "PMX")?;
+
+		let mut vertices = Vec::with_capacity(count);
+		for _ in 0..count
 		{
 			vertices.push(PmxVertex::load(reader, hdr)?);
 		}
 
 		Ok(vertices)
 	}
+
+	pub fn save<W:Write>(&self, writer:&mut W, hdr:&PmxHeader) -> Result<()>
+	{
+		PmxVector3::save(writer, self.position)?;
+		PmxVector3::save(writer, self.normal)?;
+		PmxVector2::save(writer, self.coord)?;
+
+		for i in 0..hdr.add_uv_count
+		{
+			PmxVector4::save(writer, self.add_coord[i as usize])?;
+		}
+
+		self.weight.save(writer, hdr)?;
+		writer.write_f32::<LittleEndian>(self.edge)?;
+		Ok(())
+	}
+
+	pub fn save_arrays<W:Write>(writer:&mut W, vertices:&[Self], hdr:&PmxHeader) -> Result<()>
+	{
+		writer.write_u32::<LittleEndian>(vertices.len() as u32)?;
+		for it in vertices
+		{
+			it.save(writer, hdr)?;
+		}
+
+		Ok(())
+	}
 }
 
 impl PmxIndex
 {
 	pub fn load_arrays(reader:&mut Cursor<&[u8]>, hdr:&PmxHeader) -> Result<Self>
 	{
-		let num_indices = reader.read_u32::<LittleEndian>()?;
+		let num_indices = reader.read_u32::<LittleEndian>()? as usize;
+		let num_indices = read_count(reader, num_indices, hdr.sizeof_indices.max(1) as usize, "indices", "PMX")?;
+
 		match hdr.sizeof_indices
 		{
 			1 =>
 			{
-				let mut indices = Vec::with_capacity(num_indices as usize);
+				let mut indices = Vec::with_capacity(num_indices);
 
 				for _ in 0..num_indices
 				{
@@ -512,7 +985,7 @@ impl PmxIndex
 			},
 			2 =>
 			{
-				let mut indices = Vec::with_capacity(num_indices as usize);
+				let mut indices = Vec::with_capacity(num_indices);
 
 				for _ in 0..num_indices
 				{
@@ -523,7 +996,7 @@ impl PmxIndex
 			},
 			4 =>
 			{
-				let mut indices = Vec::with_capacity(num_indices as usize);
+				let mut indices = Vec::with_capacity(num_indices);
 
 				for _ in 0..num_indices
 				{
@@ -538,6 +1011,37 @@ impl PmxIndex
 			}
 		}
 	}
+
+	pub fn len(&self) -> usize
+	{
+		match *self
+		{
+			PmxIndex::Uint8(ref v) => v.len(),
+			PmxIndex::Uint16(ref v) => v.len(),
+			PmxIndex::Uint32(ref v) => v.len(),
+		}
+	}
+
+	pub fn get(&self, i:usize) -> u32
+	{
+		match *self
+		{
+			PmxIndex::Uint8(ref v) => v[i] as u32,
+			PmxIndex::Uint16(ref v) => v[i] as u32,
+			PmxIndex::Uint32(ref v) => v[i],
+		}
+	}
+
+	pub fn save_arrays<W:Write>(&self, writer:&mut W, hdr:&PmxHeader) -> Result<()>
+	{
+		writer.write_u32::<LittleEndian>(self.len() as u32)?;
+		for i in 0..self.len()
+		{
+			PmxInteger::save_unsigned(writer, hdr.sizeof_indices, self.get(i))?;
+		}
+
+		Ok(())
+	}
 }
 
 impl PmxMaterial
@@ -574,8 +1078,8 @@ impl PmxMaterial
 	pub fn load(reader:&mut Cursor<&[u8]>, hdr:&PmxHeader) -> Result<Self>
 	{
 		let mut this = PmxMaterial::new();
-		this.name = PmxName::load(reader)?;
-		this.name_eng = PmxName::load(reader)?;
+		this.name = PmxName::load(reader, hdr)?;
+		this.name_eng = PmxName::load(reader, hdr)?;
 		this.diffuse = PmxVector3::load(reader)?;
 		this.opacity = reader.read_f32::<LittleEndian>()?;
 		this.specular = PmxVector3::load(reader)?;
@@ -598,7 +1102,7 @@ impl PmxMaterial
 			this.toon_texture = PmxInteger::load(reader, hdr.sizeof_texture)? as _;
 		}
 
-		this.mem = PmxName::load(reader)?;
+		this.mem = PmxName::load(reader, hdr)?;
 		this.face_count = reader.read_u32::<LittleEndian>()?;
 
 		this.valid()
@@ -606,14 +1110,59 @@ impl PmxMaterial
 
 	pub fn load_arrays(reader:&mut Cursor<&[u8]>, hdr:&PmxHeader) -> Result<Vec<Self>>
 	{
-		let mut materials = Vec::with_capacity(reader.read_u32::<LittleEndian>()? as usize);
-		for _ in 0..materials.capacity()
+		let count = reader.read_u32::<LittleEndian>()? as usize;
+		let count = read_count(reader, count, 84 + 2 * hdr.sizeof_texture as usize, "materials", "PMX")?;
+
+		let mut materials = Vec::with_capacity(count);
+		for _ in 0..count
 		{
 			materials.push(Self::load(reader, hdr)?);
 		}
 
 		Ok(materials)
 	}
+
+	pub fn save<W:Write>(&self, writer:&mut W, hdr:&PmxHeader) -> Result<()>
+	{
+		PmxName::save(writer, &self.name, hdr)?;
+		PmxName::save(writer, &self.name_eng, hdr)?;
+		PmxVector3::save(writer, self.diffuse)?;
+		writer.write_f32::<LittleEndian>(self.opacity)?;
+		PmxVector3::save(writer, self.specular)?;
+		writer.write_f32::<LittleEndian>(self.shininess)?;
+		PmxVector3::save(writer, self.ambient)?;
+		writer.write_u8(self.flag)?;
+		PmxVector4::save(writer, self.edge_color)?;
+		writer.write_f32::<LittleEndian>(self.edge_size)?;
+		PmxInteger::save(writer, hdr.sizeof_texture, self.texture_index as i32)?;
+		PmxInteger::save(writer, hdr.sizeof_texture, self.sphere_texture_index as i32)?;
+		writer.write_u8(self.sphere_mode)?;
+		writer.write_u8(self.toon_index)?;
+
+		if self.toon_index == 1
+		{
+			writer.write_u8(self.toon_texture as u8)?;
+		}
+		else
+		{
+			PmxInteger::save(writer, hdr.sizeof_texture, self.toon_texture as i32)?;
+		}
+
+		PmxName::save(writer, &self.mem, hdr)?;
+		writer.write_u32::<LittleEndian>(self.face_count)?;
+		Ok(())
+	}
+
+	pub fn save_arrays<W:Write>(writer:&mut W, materials:&[Self], hdr:&PmxHeader) -> Result<()>
+	{
+		writer.write_u32::<LittleEndian>(materials.len() as u32)?;
+		for it in materials
+		{
+			it.save(writer, hdr)?;
+		}
+
+		Ok(())
+	}
 }
 
 impl PmxIK
@@ -648,6 +1197,20 @@ impl PmxIK
 
 		this.valid()
 	}
+
+	pub fn save<W:Write>(&self, writer:&mut W, hdr:&PmxHeader) -> Result<()>
+	{
+		PmxInteger::save(writer, hdr.sizeof_bone, self.bone_index as i32)?;
+		writer.write_u8(self.rotate_limited)?;
+
+		if self.rotate_limited > 0
+		{
+			PmxVector3::save(writer, self.minimum_radian)?;
+			PmxVector3::save(writer, self.maximum_radian)?;
+		}
+
+		Ok(())
+	}
 }
 
 impl PmxBone
@@ -669,6 +1232,7 @@ impl PmxBone
 			axis_direction:(0.0,0.0,0.0),
 			dimention_direction_x:(0.0,0.0,0.0),
 			dimention_direction_z:(0.0,0.0,0.0),
+			external_parent_key:0,
 			ik_target_bone_index:0,
 			ik_loop_count:0,
 			ik_limited_radian:0.0,
@@ -684,8 +1248,8 @@ impl PmxBone
 	pub fn load(reader:&mut Cursor<&[u8]>, hdr:&PmxHeader) -> Result<Self>
 	{
 		let mut this = PmxBone::new();
-		this.name = PmxName::load(reader)?;
-		this.name_eng = PmxName::load(reader)?;
+		this.name = PmxName::load(reader, hdr)?;
+		this.name_eng = PmxName::load(reader, hdr)?;
 		this.position = PmxVector3::load(reader)?;
 		this.parent = PmxInteger::load(reader, hdr.sizeof_bone)? as _;
 		this.level = reader.read_u32::<LittleEndian>()?;
@@ -700,7 +1264,7 @@ impl PmxBone
 			this.offset = PmxVector3::load(reader)?;
 		}
 
-		if (this.flag & PMX_BONE_PARENT) > 0
+		if (this.flag & (PMX_BONE_PARENT | PMX_BONE_MOVE_PARENT)) > 0
 		{
 			this.provided_parent_bone_index = PmxInteger::load(reader, hdr.sizeof_bone)? as _;
 			this.provided_ratio = reader.read_f32::<LittleEndian>()?;
@@ -717,6 +1281,11 @@ impl PmxBone
 			this.dimention_direction_z = PmxVector3::load(reader)?;
 		}
 
+		if (this.flag & PMX_BONE_EXTERNAL_PARENT) > 0
+		{
+			this.external_parent_key = reader.read_i32::<LittleEndian>()?;
+		}
+
 		if (this.flag & PMX_BONE_IK) > 0
 		{
 			this.ik_target_bone_index = PmxInteger::load(reader, hdr.sizeof_bone)? as _;
@@ -734,29 +1303,767 @@ impl PmxBone
 
 	pub fn load_arrays(reader:&mut Cursor<&[u8]>, hdr:&PmxHeader) -> Result<Vec<Self>>
 	{
-		let mut bones = Vec::with_capacity(reader.read_u32::<LittleEndian>()? as usize);
-		for _ in 0..bones.capacity()
+		let count = reader.read_u32::<LittleEndian>()? as usize;
+		let count = read_count(reader, count, 26 + 2 * hdr.sizeof_bone as usize, "bones", "PMX")?;
+
+		let mut bones = Vec::with_capacity(count);
+		for _ in 0..count
 		{
 			bones.push(PmxBone::load(reader, hdr)?);
 		}
 
 		Ok(bones)
 	}
-}
 
-impl PMXFile
-{
-	pub fn new() -> Self
+	pub fn save<W:Write>(&self, writer:&mut W, hdr:&PmxHeader) -> Result<()>
 	{
-		Self
+		PmxName::save(writer, &self.name, hdr)?;
+		PmxName::save(writer, &self.name_eng, hdr)?;
+		PmxVector3::save(writer, self.position)?;
+		PmxInteger::save(writer, hdr.sizeof_bone, self.parent as i32)?;
+		writer.write_u32::<LittleEndian>(self.level)?;
+		writer.write_u16::<LittleEndian>(self.flag)?;
+
+		if (self.flag & PMX_BONE_INDEX) > 0
 		{
-			hdr:PmxHeader::new(),
-			description:PmxDescription::new(),
-			vertices:Vec::new(),
-			indices:PmxIndex::Uint16(Vec::new()),
-			textures:Vec::new(),
+			PmxInteger::save(writer, hdr.sizeof_bone, self.connected_bone_index as i32)?;
+		}
+		else
+		{
+			PmxVector3::save(writer, self.offset)?;
+		}
+
+		if (self.flag & (PMX_BONE_PARENT | PMX_BONE_MOVE_PARENT)) > 0
+		{
+			PmxInteger::save(writer, hdr.sizeof_bone, self.provided_parent_bone_index as i32)?;
+			writer.write_f32::<LittleEndian>(self.provided_ratio)?;
+		}
+
+		if (self.flag & PMX_BONE_AXIS) > 0
+		{
+			PmxVector3::save(writer, self.axis_direction)?;
+		}
+
+		if (self.flag & PMX_BONE_ROTATE) > 0
+		{
+			PmxVector3::save(writer, self.dimention_direction_x)?;
+			PmxVector3::save(writer, self.dimention_direction_z)?;
+		}
+
+		if (self.flag & PMX_BONE_EXTERNAL_PARENT) > 0
+		{
+			writer.write_i32::<LittleEndian>(self.external_parent_key)?;
+		}
+
+		if (self.flag & PMX_BONE_IK) > 0
+		{
+			PmxInteger::save(writer, hdr.sizeof_bone, self.ik_target_bone_index as i32)?;
+			writer.write_u32::<LittleEndian>(self.ik_loop_count)?;
+			writer.write_f32::<LittleEndian>(self.ik_limited_radian)?;
+			writer.write_u32::<LittleEndian>(self.ik_links.len() as u32)?;
+
+			for it in self.ik_links.iter()
+			{
+				it.save(writer, hdr)?;
+			}
+		}
+
+		Ok(())
+	}
+
+	pub fn save_arrays<W:Write>(writer:&mut W, bones:&[Self], hdr:&PmxHeader) -> Result<()>
+	{
+		writer.write_u32::<LittleEndian>(bones.len() as u32)?;
+		for it in bones
+		{
+			it.save(writer, hdr)?;
+		}
+
+		Ok(())
+	}
+}
+
+impl PmxMorphData
+{
+	pub fn load(reader:&mut Cursor<&[u8]>, hdr:&PmxHeader, kind:u8) -> Result<Self>
+	{
+		let count = reader.read_u32::<LittleEndian>()? as usize;
+		let size = match kind
+		{
+			PMX_MORPH_GROUP | PMX_MORPH_FLIP => hdr.sizeof_morph as usize + 4,
+			PMX_MORPH_VERTEX => hdr.sizeof_indices as usize + 12,
+			PMX_MORPH_BONE => hdr.sizeof_bone as usize + 28,
+			PMX_MORPH_MATERIAL => hdr.sizeof_material as usize + 113,
+			PMX_MORPH_IMPULSE => hdr.sizeof_body as usize + 25,
+			_ => hdr.sizeof_indices as usize + 16,
+		};
+		let count = read_count(reader, count, size, "morph offsets", "PMX")?;
+
+		match kind
+		{
+			PMX_MORPH_GROUP | PMX_MORPH_FLIP =>
+			{
+				let mut groups = Vec::with_capacity(count);
+				for _ in 0..count
+				{
+					let index = PmxInteger::load(reader, hdr.sizeof_morph)?;
+					let ratio = reader.read_f32::<LittleEndian>()?;
+					groups.push(PmxMorphGroup { index, ratio });
+				}
+
+				if kind == PMX_MORPH_GROUP { Ok(PmxMorphData::Group(groups)) } else { Ok(PmxMorphData::Flip(groups)) }
+			},
+			PMX_MORPH_VERTEX =>
+			{
+				let mut vertices = Vec::with_capacity(count);
+				for _ in 0..count
+				{
+					let index = PmxInteger::load_unsigned(reader, hdr.sizeof_indices)?;
+					let offset = PmxVector3::load(reader)?;
+					vertices.push(PmxMorphVertex { index, offset });
+				}
+
+				Ok(PmxMorphData::Vertex(vertices))
+			},
+			PMX_MORPH_BONE =>
+			{
+				let mut bones = Vec::with_capacity(count);
+				for _ in 0..count
+				{
+					let bone_index = PmxInteger::load(reader, hdr.sizeof_bone)? as _;
+					let translate = PmxVector3::load(reader)?;
+					let rotation = PmxVector4::load(reader)?;
+					bones.push(PmxMorphBone { bone_index, translate, rotation });
+				}
+
+				Ok(PmxMorphData::Bone(bones))
+			},
+			PMX_MORPH_UV | PMX_MORPH_UV1 | PMX_MORPH_UV2 | PMX_MORPH_UV3 | PMX_MORPH_UV4 =>
+			{
+				let mut uvs = Vec::with_capacity(count);
+				for _ in 0..count
+				{
+					let index = PmxInteger::load_unsigned(reader, hdr.sizeof_indices)?;
+					let offset = PmxVector4::load(reader)?;
+					uvs.push(PmxMorphUV { index, offset });
+				}
+
+				Ok(PmxMorphData::UV(uvs))
+			},
+			PMX_MORPH_MATERIAL =>
+			{
+				let mut materials = Vec::with_capacity(count);
+				for _ in 0..count
+				{
+					materials.push(PmxMorphMaterial
+					{
+						index:PmxInteger::load(reader, hdr.sizeof_material)?,
+						operation:reader.read_u8()?,
+						diffuse:PmxVector4::load(reader)?,
+						specular:PmxVector3::load(reader)?,
+						shininess:reader.read_f32::<LittleEndian>()?,
+						ambient:PmxVector3::load(reader)?,
+						edge_color:PmxVector4::load(reader)?,
+						edge_size:reader.read_f32::<LittleEndian>()?,
+						texture:PmxVector4::load(reader)?,
+						sphere:PmxVector4::load(reader)?,
+						toon:PmxVector4::load(reader)?,
+					});
+				}
+
+				Ok(PmxMorphData::Material(materials))
+			},
+			PMX_MORPH_IMPULSE =>
+			{
+				let mut impulses = Vec::with_capacity(count);
+				for _ in 0..count
+				{
+					impulses.push(PmxMorphImpulse
+					{
+						index:PmxInteger::load(reader, hdr.sizeof_body)?,
+						local:reader.read_u8()?,
+						velocity:PmxVector3::load(reader)?,
+						torque:PmxVector3::load(reader)?,
+					});
+				}
+
+				Ok(PmxMorphData::Impulse(impulses))
+			},
+			_ =>
+			{
+				Err(Error("Invalid Token morph type".to_string()))
+			}
+		}
+	}
+
+	pub fn save<W:Write>(&self, writer:&mut W, hdr:&PmxHeader) -> Result<()>
+	{
+		match *self
+		{
+			PmxMorphData::Group(ref v) | PmxMorphData::Flip(ref v) =>
+			{
+				writer.write_u32::<LittleEndian>(v.len() as u32)?;
+				for it in v
+				{
+					PmxInteger::save(writer, hdr.sizeof_morph, it.index)?;
+					writer.write_f32::<LittleEndian>(it.ratio)?;
+				}
+			},
+			PmxMorphData::Vertex(ref v) =>
+			{
+				writer.write_u32::<LittleEndian>(v.len() as u32)?;
+				for it in v
+				{
+					PmxInteger::save_unsigned(writer, hdr.sizeof_indices, it.index)?;
+					PmxVector3::save(writer, it.offset)?;
+				}
+			},
+			PmxMorphData::Bone(ref v) =>
+			{
+				writer.write_u32::<LittleEndian>(v.len() as u32)?;
+				for it in v
+				{
+					PmxInteger::save(writer, hdr.sizeof_bone, it.bone_index as i32)?;
+					PmxVector3::save(writer, it.translate)?;
+					PmxVector4::save(writer, it.rotation)?;
+				}
+			},
+			PmxMorphData::UV(ref v) =>
+			{
+				writer.write_u32::<LittleEndian>(v.len() as u32)?;
+				for it in v
+				{
+					PmxInteger::save_unsigned(writer, hdr.sizeof_indices, it.index)?;
+					PmxVector4::save(writer, it.offset)?;
+				}
+			},
+			PmxMorphData::Material(ref v) =>
+			{
+				writer.write_u32::<LittleEndian>(v.len() as u32)?;
+				for it in v
+				{
+					PmxInteger::save(writer, hdr.sizeof_material, it.index)?;
+					writer.write_u8(it.operation)?;
+					PmxVector4::save(writer, it.diffuse)?;
+					PmxVector3::save(writer, it.specular)?;
+					writer.write_f32::<LittleEndian>(it.shininess)?;
+					PmxVector3::save(writer, it.ambient)?;
+					PmxVector4::save(writer, it.edge_color)?;
+					writer.write_f32::<LittleEndian>(it.edge_size)?;
+					PmxVector4::save(writer, it.texture)?;
+					PmxVector4::save(writer, it.sphere)?;
+					PmxVector4::save(writer, it.toon)?;
+				}
+			},
+			PmxMorphData::Impulse(ref v) =>
+			{
+				writer.write_u32::<LittleEndian>(v.len() as u32)?;
+				for it in v
+				{
+					PmxInteger::save(writer, hdr.sizeof_body, it.index)?;
+					writer.write_u8(it.local)?;
+					PmxVector3::save(writer, it.velocity)?;
+					PmxVector3::save(writer, it.torque)?;
+				}
+			},
+		}
+
+		Ok(())
+	}
+}
+
+impl PmxMorph
+{
+	pub fn new() -> Self
+	{
+		Self
+		{
+			name:String::new(),
+			name_eng:String::new(),
+			panel:0,
+			kind:PMX_MORPH_VERTEX,
+			data:PmxMorphData::Vertex(Vec::new()),
+		}
+	}
+
+	pub fn valid(self) -> Result<Self>
+	{
+		Ok(self)
+	}
+
+	pub fn load(reader:&mut Cursor<&[u8]>, hdr:&PmxHeader) -> Result<Self>
+	{
+		let mut this = PmxMorph::new();
+		this.name = PmxName::load(reader, hdr)?;
+		this.name_eng = PmxName::load(reader, hdr)?;
+		this.panel = reader.read_u8()?;
+		this.kind = reader.read_u8()?;
+		this.data = PmxMorphData::load(reader, hdr, this.kind)?;
+
+		this.valid()
+	}
+
+	pub fn load_arrays(reader:&mut Cursor<&[u8]>, hdr:&PmxHeader) -> Result<Vec<Self>>
+	{
+		let count = reader.read_u32::<LittleEndian>()? as usize;
+		let count = read_count(reader, count, 14, "morphs", "PMX")?;
+
+		let mut morphs = Vec::with_capacity(count);
+		for _ in 0..count
+		{
+			morphs.push(PmxMorph::load(reader, hdr)?);
+		}
+
+		Ok(morphs)
+	}
+
+	pub fn save<W:Write>(&self, writer:&mut W, hdr:&PmxHeader) -> Result<()>
+	{
+		PmxName::save(writer, &self.name, hdr)?;
+		PmxName::save(writer, &self.name_eng, hdr)?;
+		writer.write_u8(self.panel)?;
+		writer.write_u8(self.kind)?;
+		self.data.save(writer, hdr)
+	}
+
+	pub fn save_arrays<W:Write>(writer:&mut W, morphs:&[Self], hdr:&PmxHeader) -> Result<()>
+	{
+		writer.write_u32::<LittleEndian>(morphs.len() as u32)?;
+		for it in morphs
+		{
+			it.save(writer, hdr)?;
+		}
+
+		Ok(())
+	}
+}
+
+impl PmxFrame
+{
+	pub fn new() -> Self
+	{
+		Self
+		{
+			name:String::new(),
+			name_eng:String::new(),
+			special:0,
+			elements:Vec::new(),
+		}
+	}
+
+	pub fn valid(self) -> Result<Self>
+	{
+		Ok(self)
+	}
+
+	pub fn load(reader:&mut Cursor<&[u8]>, hdr:&PmxHeader) -> Result<Self>
+	{
+		let mut this = PmxFrame::new();
+		this.name = PmxName::load(reader, hdr)?;
+		this.name_eng = PmxName::load(reader, hdr)?;
+		this.special = reader.read_u8()?;
+
+		for _ in 0..reader.read_u32::<LittleEndian>()?
+		{
+			let kind = reader.read_u8()?;
+			let index = match kind
+			{
+				PMX_FRAME_BONE => PmxInteger::load(reader, hdr.sizeof_bone)?,
+				PMX_FRAME_MORPH => PmxInteger::load(reader, hdr.sizeof_morph)?,
+				_ => { return Err(Error("Invalid Token frame element type".to_string())); }
+			};
+
+			this.elements.push(PmxFrameElement { kind, index });
+		}
+
+		this.valid()
+	}
+
+	pub fn load_arrays(reader:&mut Cursor<&[u8]>, hdr:&PmxHeader) -> Result<Vec<Self>>
+	{
+		let count = reader.read_u32::<LittleEndian>()? as usize;
+		let count = read_count(reader, count, 13, "frames", "PMX")?;
+
+		let mut frames = Vec::with_capacity(count);
+		for _ in 0..count
+		{
+			frames.push(PmxFrame::load(reader, hdr)?);
+		}
+
+		Ok(frames)
+	}
+
+	pub fn save<W:Write>(&self, writer:&mut W, hdr:&PmxHeader) -> Result<()>
+	{
+		PmxName::save(writer, &self.name, hdr)?;
+		PmxName::save(writer, &self.name_eng, hdr)?;
+		writer.write_u8(self.special)?;
+		writer.write_u32::<LittleEndian>(self.elements.len() as u32)?;
+
+		for it in self.elements.iter()
+		{
+			writer.write_u8(it.kind)?;
+			match it.kind
+			{
+				PMX_FRAME_BONE => PmxInteger::save(writer, hdr.sizeof_bone, it.index)?,
+				PMX_FRAME_MORPH => PmxInteger::save(writer, hdr.sizeof_morph, it.index)?,
+				_ => { return Err(Error("Invalid Token frame element type".to_string())); }
+			}
+		}
+
+		Ok(())
+	}
+
+	pub fn save_arrays<W:Write>(writer:&mut W, frames:&[Self], hdr:&PmxHeader) -> Result<()>
+	{
+		writer.write_u32::<LittleEndian>(frames.len() as u32)?;
+		for it in frames
+		{
+			it.save(writer, hdr)?;
+		}
+
+		Ok(())
+	}
+}
+
+impl PmxRigidbody
+{
+	pub fn new() -> Self
+	{
+		Self
+		{
+			name:String::new(),
+			name_eng:String::new(),
+			bone:0,
+			group:0,
+			group_mask:0,
+			shape:0,
+			scale:(0.0,0.0,0.0),
+			position:(0.0,0.0,0.0),
+			rotation:(0.0,0.0,0.0),
+			mass:0.0,
+			movement_decay:0.0,
+			rotation_decay:0.0,
+			elasticity:0.0,
+			friction:0.0,
+			physics_operation:0,
+		}
+	}
+
+	pub fn valid(self) -> Result<Self>
+	{
+		Ok(self)
+	}
+
+	pub fn load(reader:&mut Cursor<&[u8]>, hdr:&PmxHeader) -> Result<Self>
+	{
+		let mut this = PmxRigidbody::new();
+		this.name = PmxName::load(reader, hdr)?;
+		this.name_eng = PmxName::load(reader, hdr)?;
+		this.bone = PmxInteger::load(reader, hdr.sizeof_bone)? as _;
+		this.group = reader.read_u8()?;
+		this.group_mask = reader.read_u16::<LittleEndian>()?;
+		this.shape = reader.read_u8()?;
+		this.scale = PmxVector3::load(reader)?;
+		this.position = PmxVector3::load(reader)?;
+		this.rotation = PmxVector3::load(reader)?;
+		this.mass = reader.read_f32::<LittleEndian>()?;
+		this.movement_decay = reader.read_f32::<LittleEndian>()?;
+		this.rotation_decay = reader.read_f32::<LittleEndian>()?;
+		this.elasticity = reader.read_f32::<LittleEndian>()?;
+		this.friction = reader.read_f32::<LittleEndian>()?;
+		this.physics_operation = reader.read_u8()?;
+
+		this.valid()
+	}
+
+	pub fn load_arrays(reader:&mut Cursor<&[u8]>, hdr:&PmxHeader) -> Result<Vec<Self>>
+	{
+		let count = reader.read_u32::<LittleEndian>()? as usize;
+		let count = read_count(reader, count, 69 + hdr.sizeof_bone as usize, "rigidbodies", "PMX")?;
+
+		let mut rigidbodies = Vec::with_capacity(count);
+		for _ in 0..count
+		{
+			rigidbodies.push(PmxRigidbody::load(reader, hdr)?);
+		}
+
+		Ok(rigidbodies)
+	}
+
+	pub fn save<W:Write>(&self, writer:&mut W, hdr:&PmxHeader) -> Result<()>
+	{
+		PmxName::save(writer, &self.name, hdr)?;
+		PmxName::save(writer, &self.name_eng, hdr)?;
+		PmxInteger::save(writer, hdr.sizeof_bone, self.bone as i32)?;
+		writer.write_u8(self.group)?;
+		writer.write_u16::<LittleEndian>(self.group_mask)?;
+		writer.write_u8(self.shape)?;
+		PmxVector3::save(writer, self.scale)?;
+		PmxVector3::save(writer, self.position)?;
+		PmxVector3::save(writer, self.rotation)?;
+		writer.write_f32::<LittleEndian>(self.mass)?;
+		writer.write_f32::<LittleEndian>(self.movement_decay)?;
+		writer.write_f32::<LittleEndian>(self.rotation_decay)?;
+		writer.write_f32::<LittleEndian>(self.elasticity)?;
+		writer.write_f32::<LittleEndian>(self.friction)?;
+		writer.write_u8(self.physics_operation)?;
+		Ok(())
+	}
+
+	pub fn save_arrays<W:Write>(writer:&mut W, rigidbodies:&[Self], hdr:&PmxHeader) -> Result<()>
+	{
+		writer.write_u32::<LittleEndian>(rigidbodies.len() as u32)?;
+		for it in rigidbodies
+		{
+			it.save(writer, hdr)?;
+		}
+
+		Ok(())
+	}
+}
+
+impl PmxJoint
+{
+	pub fn new() -> Self
+	{
+		Self
+		{
+			name:String::new(),
+			name_eng:String::new(),
+			kind:0,
+			rigidbody_a:0,
+			rigidbody_b:0,
+			position:(0.0,0.0,0.0),
+			rotation:(0.0,0.0,0.0),
+			movement_lower_limit:(0.0,0.0,0.0),
+			movement_upper_limit:(0.0,0.0,0.0),
+			rotation_lower_limit:(0.0,0.0,0.0),
+			rotation_upper_limit:(0.0,0.0,0.0),
+			movement_spring_constant:(0.0,0.0,0.0),
+			rotation_spring_constant:(0.0,0.0,0.0),
+		}
+	}
+
+	pub fn valid(self) -> Result<Self>
+	{
+		Ok(self)
+	}
+
+	pub fn load(reader:&mut Cursor<&[u8]>, hdr:&PmxHeader) -> Result<Self>
+	{
+		let mut this = PmxJoint::new();
+		this.name = PmxName::load(reader, hdr)?;
+		this.name_eng = PmxName::load(reader, hdr)?;
+		this.kind = reader.read_u8()?;
+		this.rigidbody_a = PmxInteger::load(reader, hdr.sizeof_body)?;
+		this.rigidbody_b = PmxInteger::load(reader, hdr.sizeof_body)?;
+		this.position = PmxVector3::load(reader)?;
+		this.rotation = PmxVector3::load(reader)?;
+		this.movement_lower_limit = PmxVector3::load(reader)?;
+		this.movement_upper_limit = PmxVector3::load(reader)?;
+		this.rotation_lower_limit = PmxVector3::load(reader)?;
+		this.rotation_upper_limit = PmxVector3::load(reader)?;
+		this.movement_spring_constant = PmxVector3::load(reader)?;
+		this.rotation_spring_constant = PmxVector3::load(reader)?;
+
+		this.valid()
+	}
+
+	pub fn load_arrays(reader:&mut Cursor<&[u8]>, hdr:&PmxHeader) -> Result<Vec<Self>>
+	{
+		let count = reader.read_u32::<LittleEndian>()? as usize;
+		let count = read_count(reader, count, 105 + 2 * hdr.sizeof_body as usize, "joints", "PMX")?;
+
+		let mut joints = Vec::with_capacity(count);
+		for _ in 0..count
+		{
+			joints.push(PmxJoint::load(reader, hdr)?);
+		}
+
+		Ok(joints)
+	}
+
+	pub fn save<W:Write>(&self, writer:&mut W, hdr:&PmxHeader) -> Result<()>
+	{
+		PmxName::save(writer, &self.name, hdr)?;
+		PmxName::save(writer, &self.name_eng, hdr)?;
+		writer.write_u8(self.kind)?;
+		PmxInteger::save(writer, hdr.sizeof_body, self.rigidbody_a)?;
+		PmxInteger::save(writer, hdr.sizeof_body, self.rigidbody_b)?;
+		PmxVector3::save(writer, self.position)?;
+		PmxVector3::save(writer, self.rotation)?;
+		PmxVector3::save(writer, self.movement_lower_limit)?;
+		PmxVector3::save(writer, self.movement_upper_limit)?;
+		PmxVector3::save(writer, self.rotation_lower_limit)?;
+		PmxVector3::save(writer, self.rotation_upper_limit)?;
+		PmxVector3::save(writer, self.movement_spring_constant)?;
+		PmxVector3::save(writer, self.rotation_spring_constant)?;
+		Ok(())
+	}
+
+	pub fn save_arrays<W:Write>(writer:&mut W, joints:&[Self], hdr:&PmxHeader) -> Result<()>
+	{
+		writer.write_u32::<LittleEndian>(joints.len() as u32)?;
+		for it in joints
+		{
+			it.save(writer, hdr)?;
+		}
+
+		Ok(())
+	}
+}
+
+impl PmxSoftbody
+{
+	pub fn new() -> Self
+	{
+		Self
+		{
+			name:String::new(),
+			name_eng:String::new(),
+			shape:0,
+			material:0,
+			group:0,
+			group_mask:0,
+			flag:0,
+			blink_length:0,
+			num_clusters:0,
+			total_mass:0.0,
+			collision_margin:0.0,
+			aero_model:0,
+			config:[0.0;12],
+			cluster:[0.0;6],
+			iteration:[0;4],
+			material_coefficient:[0.0;3],
+			anchors:Vec::new(),
+			pin_vertices:Vec::new(),
+		}
+	}
+
+	pub fn valid(self) -> Result<Self>
+	{
+		Ok(self)
+	}
+
+	pub fn load(reader:&mut Cursor<&[u8]>, hdr:&PmxHeader) -> Result<Self>
+	{
+		let mut this = PmxSoftbody::new();
+		this.name = PmxName::load(reader, hdr)?;
+		this.name_eng = PmxName::load(reader, hdr)?;
+		this.shape = reader.read_u8()?;
+		this.material = PmxInteger::load(reader, hdr.sizeof_material)?;
+		this.group = reader.read_u8()?;
+		this.group_mask = reader.read_u16::<LittleEndian>()?;
+		this.flag = reader.read_u8()?;
+		this.blink_length = reader.read_i32::<LittleEndian>()?;
+		this.num_clusters = reader.read_i32::<LittleEndian>()?;
+		this.total_mass = reader.read_f32::<LittleEndian>()?;
+		this.collision_margin = reader.read_f32::<LittleEndian>()?;
+		this.aero_model = reader.read_i32::<LittleEndian>()?;
+
+		for it in this.config.iter_mut() { *it = reader.read_f32::<LittleEndian>()?; }
+		for it in this.cluster.iter_mut() { *it = reader.read_f32::<LittleEndian>()?; }
+		for it in this.iteration.iter_mut() { *it = reader.read_i32::<LittleEndian>()?; }
+		for it in this.material_coefficient.iter_mut() { *it = reader.read_f32::<LittleEndian>()?; }
+
+		for _ in 0..reader.read_u32::<LittleEndian>()?
+		{
+			let rigidbody = PmxInteger::load(reader, hdr.sizeof_body)?;
+			let vertex = PmxInteger::load_unsigned(reader, hdr.sizeof_indices)?;
+			let near_mode = reader.read_u8()?;
+			this.anchors.push(PmxSoftbodyAnchor { rigidbody, vertex, near_mode });
+		}
+
+		for _ in 0..reader.read_u32::<LittleEndian>()?
+		{
+			this.pin_vertices.push(PmxInteger::load_unsigned(reader, hdr.sizeof_indices)?);
+		}
+
+		this.valid()
+	}
+
+	pub fn load_arrays(reader:&mut Cursor<&[u8]>, hdr:&PmxHeader) -> Result<Vec<Self>>
+	{
+		let count = reader.read_u32::<LittleEndian>()? as usize;
+		let count = read_count(reader, count, 141 + hdr.sizeof_material as usize, "softbodies", "PMX")?;
+
+		let mut softbodies = Vec::with_capacity(count);
+		for _ in 0..count
+		{
+			softbodies.push(PmxSoftbody::load(reader, hdr)?);
+		}
+
+		Ok(softbodies)
+	}
+
+	pub fn save<W:Write>(&self, writer:&mut W, hdr:&PmxHeader) -> Result<()>
+	{
+		PmxName::save(writer, &self.name, hdr)?;
+		PmxName::save(writer, &self.name_eng, hdr)?;
+		writer.write_u8(self.shape)?;
+		PmxInteger::save(writer, hdr.sizeof_material, self.material)?;
+		writer.write_u8(self.group)?;
+		writer.write_u16::<LittleEndian>(self.group_mask)?;
+		writer.write_u8(self.flag)?;
+		writer.write_i32::<LittleEndian>(self.blink_length)?;
+		writer.write_i32::<LittleEndian>(self.num_clusters)?;
+		writer.write_f32::<LittleEndian>(self.total_mass)?;
+		writer.write_f32::<LittleEndian>(self.collision_margin)?;
+		writer.write_i32::<LittleEndian>(self.aero_model)?;
+
+		for it in self.config.iter() { writer.write_f32::<LittleEndian>(*it)?; }
+		for it in self.cluster.iter() { writer.write_f32::<LittleEndian>(*it)?; }
+		for it in self.iteration.iter() { writer.write_i32::<LittleEndian>(*it)?; }
+		for it in self.material_coefficient.iter() { writer.write_f32::<LittleEndian>(*it)?; }
+
+		writer.write_u32::<LittleEndian>(self.anchors.len() as u32)?;
+		for it in self.anchors.iter()
+		{
+			PmxInteger::save(writer, hdr.sizeof_body, it.rigidbody)?;
+			PmxInteger::save_unsigned(writer, hdr.sizeof_indices, it.vertex)?;
+			writer.write_u8(it.near_mode)?;
+		}
+
+		writer.write_u32::<LittleEndian>(self.pin_vertices.len() as u32)?;
+		for it in self.pin_vertices.iter()
+		{
+			PmxInteger::save_unsigned(writer, hdr.sizeof_indices, *it)?;
+		}
+
+		Ok(())
+	}
+
+	pub fn save_arrays<W:Write>(writer:&mut W, softbodies:&[Self], hdr:&PmxHeader) -> Result<()>
+	{
+		writer.write_u32::<LittleEndian>(softbodies.len() as u32)?;
+		for it in softbodies
+		{
+			it.save(writer, hdr)?;
+		}
+
+		Ok(())
+	}
+}
+
+impl PMXFile
+{
+	pub fn new() -> Self
+	{
+		Self
+		{
+			hdr:PmxHeader::new(),
+			description:PmxDescription::new(),
+			vertices:Vec::new(),
+			indices:PmxIndex::Uint16(Vec::new()),
+			textures:Vec::new(),
 			materials:Vec::new(),
 			bones:Vec::new(),
+			morphs:Vec::new(),
+			frames:Vec::new(),
+			rigidbodies:Vec::new(),
+			joints:Vec::new(),
+			softbodies:Vec::new(),
 		}
 	}
 
@@ -767,9 +2074,22 @@ impl PMXFile
 		let description = PmxDescription::load(&mut reader)?;
 		let vertices = PmxVertex::load_arrays(&mut reader, &hdr)?;
 		let indices = PmxIndex::load_arrays(&mut reader, &hdr)?;
-		let textures = PmxName::load_arrays(&mut reader)?;
+		let textures = PmxName::load_arrays(&mut reader, &hdr)?;
 		let materials = PmxMaterial::load_arrays(&mut reader, &hdr)?;
 		let bones = PmxBone::load_arrays(&mut reader, &hdr)?;
+		let morphs = PmxMorph::load_arrays(&mut reader, &hdr)?;
+		let frames = PmxFrame::load_arrays(&mut reader, &hdr)?;
+		let rigidbodies = PmxRigidbody::load_arrays(&mut reader, &hdr)?;
+		let joints = PmxJoint::load_arrays(&mut reader, &hdr)?;
+
+		let softbodies = if hdr.version >= PMX_VERSION_2_1
+		{
+			PmxSoftbody::load_arrays(&mut reader, &hdr)?
+		}
+		else
+		{
+			Vec::new()
+		};
 
 		Ok(Self
 		{
@@ -779,9 +2099,389 @@ impl PMXFile
 			indices:indices,
 			textures:textures,
 			materials:materials,
-			bones:bones
+			bones:bones,
+			morphs:morphs,
+			frames:frames,
+			rigidbodies:rigidbodies,
+			joints:joints,
+			softbodies:softbodies,
 		})
 	}
+
+	pub fn save(&self) -> Result<Vec<u8>>
+	{
+		let mut writer = Vec::new();
+		let hdr = &self.hdr;
+		hdr.save(&mut writer)?;
+		self.description.save(&mut writer)?;
+		PmxVertex::save_arrays(&mut writer, &self.vertices, hdr)?;
+		self.indices.save_arrays(&mut writer, hdr)?;
+		PmxName::save_arrays(&mut writer, &self.textures, hdr)?;
+		PmxMaterial::save_arrays(&mut writer, &self.materials, hdr)?;
+		PmxBone::save_arrays(&mut writer, &self.bones, hdr)?;
+		PmxMorph::save_arrays(&mut writer, &self.morphs, hdr)?;
+		PmxFrame::save_arrays(&mut writer, &self.frames, hdr)?;
+		PmxRigidbody::save_arrays(&mut writer, &self.rigidbodies, hdr)?;
+		PmxJoint::save_arrays(&mut writer, &self.joints, hdr)?;
+
+		if hdr.version >= PMX_VERSION_2_1
+		{
+			PmxSoftbody::save_arrays(&mut writer, &self.softbodies, hdr)?;
+		}
+
+		Ok(writer)
+	}
+
+	/// Picks the smallest index sizes and the lowest version which can hold the file.
+	pub fn update_header(&mut self)
+	{
+		let requires_2_1 =
+			self.softbodies.len() > 0 ||
+			self.joints.iter().any(|joint| joint.kind != 0) ||
+			self.morphs.iter().any(|morph| morph.kind == PMX_MORPH_FLIP || morph.kind == PMX_MORPH_IMPULSE) ||
+			self.vertices.iter().any(|vertex| vertex.weight.kind == PMX_QDEF) ||
+			self.materials.iter().any(|material| (material.flag & (PMX_MATERIAL_VERTEX_COLOR | PMX_MATERIAL_POINT_DRAW | PMX_MATERIAL_LINE_DRAW)) > 0);
+
+		self.hdr.magic = ['P' as u8, 'M' as u8, 'X' as u8];
+		self.hdr.offset = 0x20;
+		self.hdr.version = if requires_2_1 { PMX_VERSION_2_1 } else { PMX_VERSION_2_0 };
+		self.hdr.data_size = 8;
+		self.hdr.sizeof_indices = PmxInteger::sizeof_unsigned(self.vertices.len());
+		self.hdr.sizeof_texture = PmxInteger::sizeof(self.textures.len());
+		self.hdr.sizeof_material = PmxInteger::sizeof(self.materials.len());
+		self.hdr.sizeof_bone = PmxInteger::sizeof(self.bones.len());
+		self.hdr.sizeof_morph = PmxInteger::sizeof(self.morphs.len());
+		self.hdr.sizeof_body = PmxInteger::sizeof(self.rigidbodies.len());
+
+		let num_indices = self.indices.len();
+		let indices:Vec<u32> = (0..num_indices).map(|i| self.indices.get(i)).collect();
+
+		self.indices = match self.hdr.sizeof_indices
+		{
+			1 => PmxIndex::Uint8(indices.iter().map(|&i| i as u8).collect()),
+			2 => PmxIndex::Uint16(indices.iter().map(|&i| i as u16).collect()),
+			_ => PmxIndex::Uint32(indices),
+		};
+	}
+
+	pub fn from_model(model:&Model, encode:u8) -> Result<Self>
+	{
+		if encode != PMX_ENCODE_UTF16 && encode != PMX_ENCODE_UTF8 { return Err(Error("Invalid encode in PMX Header".to_string())); }
+
+		// Bones refer to each other with 16 bit indices.
+		if model.bones.len() > std::i16::MAX as usize + 1 { return Err(Error(format!("{} bones don't fit in a PMX file", model.bones.len()))); }
+
+		let mut pmx = PMXFile::new();
+		pmx.hdr.encode = encode;
+		pmx.description = PmxDescription::from_names(&pmx.hdr, &model.name, "");
+		pmx.textures = model.textures.clone();
+
+		let mut indices = Vec::new();
+		let mut bases = Vec::new();
+
+		for mesh in model.meshes.iter()
+		{
+			let base = pmx.vertices.len() as u32;
+			bases.push(base);
+			let num_vertices = mesh.positions.len() / 3;
+
+			for i in 0..num_vertices
+			{
+				let mut vertex = PmxVertex::new();
+				vertex.position = (mesh.positions[i * 3], mesh.positions[i * 3 + 1], mesh.positions[i * 3 + 2]);
+
+				if mesh.normals.len() >= (i + 1) * 3
+				{
+					vertex.normal = (mesh.normals[i * 3], mesh.normals[i * 3 + 1], mesh.normals[i * 3 + 2]);
+				}
+
+				if mesh.texcoords.len() >= (i + 1) * 2
+				{
+					vertex.coord = (mesh.texcoords[i * 2], mesh.texcoords[i * 2 + 1]);
+				}
+
+				if let Some(weight) = mesh.weights.get(i)
+				{
+					let w = &mut vertex.weight;
+					w.bone1 = weight.bone[0];
+					w.bone2 = weight.bone[1];
+					w.bone3 = weight.bone[2];
+					w.bone4 = weight.bone[3];
+					w.weight1 = weight.weight[0];
+					w.weight2 = weight.weight[1];
+					w.weight3 = weight.weight[2];
+					w.weight4 = weight.weight[3];
+					w.kind = if weight.weight[2] != 0.0 || weight.weight[3] != 0.0 { PMX_BDEF4 } else if weight.weight[1] != 0.0 { PMX_BDEF2 } else { PMX_BDEF1 };
+				}
+				else
+				{
+					vertex.weight.kind = PMX_BDEF1;
+					vertex.weight.weight1 = 1.0;
+				}
+
+				pmx.vertices.push(vertex);
+			}
+
+			indices.extend(mesh.indices.iter().map(|i| i + base));
+
+			let source = match mesh.material_id.and_then(|id| model.materials.get(id))
+			{
+				Some(material) => material.clone(),
+				None => Material::new(),
+			};
+
+			let mut material = PmxMaterial::new();
+			material.name = if source.name.is_empty() { mesh.name.clone() } else { source.name.clone() };
+			material.diffuse = (source.diffuse[0], source.diffuse[1], source.diffuse[2]);
+			material.opacity = source.dissolve;
+			material.specular = (source.specular[0], source.specular[1], source.specular[2]);
+			material.shininess = source.shininess;
+			material.ambient = (source.ambient[0], source.ambient[1], source.ambient[2]);
+			material.edge_color = (source.edge_color[0], source.edge_color[1], source.edge_color[2], source.edge_color[3]);
+			material.edge_size = source.edge_size;
+			material.texture_index = source.diffuse_texture.map_or(-1, |i| i as i16);
+			material.sphere_texture_index = source.sphere_texture.map_or(-1, |i| i as i16);
+			material.face_count = mesh.indices.len() as u32;
+
+			if source.double_sided { material.flag |= PMX_MATERIAL_DOUBLE_SIDED; }
+			if source.ground_shadow { material.flag |= PMX_MATERIAL_GROUND_SHADOW; }
+			if source.cast_shadow { material.flag |= PMX_MATERIAL_CAST_SHADOW; }
+			if source.receive_shadow { material.flag |= PMX_MATERIAL_RECEIVE_SHADOW; }
+			if source.edge { material.flag |= PMX_MATERIAL_EDGE; }
+			if source.vertex_color { material.flag |= PMX_MATERIAL_VERTEX_COLOR; }
+			if source.point_draw { material.flag |= PMX_MATERIAL_POINT_DRAW; }
+			if source.line_draw { material.flag |= PMX_MATERIAL_LINE_DRAW; }
+
+			material.sphere_mode = match source.sphere_mode
+			{
+				SphereMode::None => PMX_SPHERE_NONE,
+				SphereMode::Multiply => PMX_SPHERE_MULTIPLY,
+				SphereMode::Additive => PMX_SPHERE_ADDITIVE,
+				SphereMode::SubTexture => PMX_SPHERE_SUB_TEXTURE,
+			};
+
+			match source.toon
+			{
+				Toon::None => { material.toon_index = 0; material.toon_texture = -1; },
+				Toon::Shared(index) => { material.toon_index = 1; material.toon_texture = index as i16; },
+				Toon::Texture(index) => { material.toon_index = 0; material.toon_texture = index as i16; },
+			}
+
+			pmx.materials.push(material);
+		}
+
+		pmx.indices = PmxIndex::Uint32(indices);
+
+		for (bone_index, it) in model.bones.iter().enumerate()
+		{
+			let mut bone = PmxBone::new();
+			bone.name = it.name.clone();
+			bone.position = it.position;
+			bone.parent = it.parent.map_or(-1, |parent| parent as i16);
+			bone.level = it.order as u32;
+
+			if it.rotatable { bone.flag |= PMX_BONE_ROTATABLE; }
+			if it.movable { bone.flag |= PMX_BONE_MOVABLE; }
+			if it.visible { bone.flag |= PMX_BONE_VISIBLE; }
+			if it.operable { bone.flag |= PMX_BONE_OPERABLE; }
+			if it.after_physics { bone.flag |= PMX_BONE_PHYSICS_AFTER; }
+
+			match it.tail
+			{
+				BoneTail::Offset(x, y, z) => { bone.offset = (x, y, z); },
+				BoneTail::Bone(index) => { bone.flag |= PMX_BONE_INDEX; bone.connected_bone_index = index as i16; },
+			}
+
+			if let Some(ref grant) = it.grant
+			{
+				if grant.rotation { bone.flag |= PMX_BONE_PARENT; }
+				if grant.translation { bone.flag |= PMX_BONE_MOVE_PARENT; }
+				bone.provided_parent_bone_index = grant.parent as i16;
+				bone.provided_ratio = grant.ratio;
+			}
+
+			if let Some(ik) = model.iks.iter().find(|ik| ik.bone as usize == bone_index)
+			{
+				bone.flag |= PMX_BONE_IK;
+				bone.ik_target_bone_index = ik.target_bone;
+				bone.ik_loop_count = ik.loop_count;
+				bone.ik_limited_radian = ik.limited_radian;
+
+				for link in ik.links.iter()
+				{
+					let mut child = PmxIK::new();
+					child.bone_index = link.bone;
+					child.rotate_limited = if link.rotate_limited { 1 } else { 0 };
+					child.minimum_radian = link.minimum_radian;
+					child.maximum_radian = link.maximum_radian;
+					bone.ik_links.push(child);
+				}
+			}
+
+			pmx.bones.push(bone);
+		}
+
+		// Morph indices of the model point into the vertices of the file it was loaded from, the meshes were laid out
+		// again above so vertex and uv morphs are rebuilt from the targets of each mesh, moved to where the mesh starts.
+		// Names may repeat, targets are matched by the morph they came from and only by name when they don't know it.
+		let targets = |index:usize, name:&str| model.meshes.iter().zip(bases.iter())
+			.filter_map(|(mesh, &base)| mesh.morphs.iter().find(|target| target.morph.map_or(target.name == name, |morph| morph == index)).map(|target| (target, base)))
+			.collect::<Vec<_>>();
+
+		for (index, it) in model.morphs.iter().enumerate()
+		{
+			let mut morph = PmxMorph::new();
+			morph.name = it.name.clone();
+			morph.panel = it.panel;
+
+			let (kind, data) = match it.data
+			{
+				MorphData::Group(ref v) => (PMX_MORPH_GROUP, PmxMorphData::Group(v.iter().map(|x| PmxMorphGroup { index:x.morph as i32, ratio:x.weight }).collect())),
+				MorphData::Flip(ref v) => (PMX_MORPH_FLIP, PmxMorphData::Flip(v.iter().map(|x| PmxMorphGroup { index:x.morph as i32, ratio:x.weight }).collect())),
+				MorphData::Vertex(_) =>
+				{
+					(PMX_MORPH_VERTEX, PmxMorphData::Vertex(targets(index, &it.name).into_iter().flat_map(|(target, base)| target.positions.iter().map(move |x| PmxMorphVertex { index:x.index + base, offset:x.offset })).collect()))
+				},
+				MorphData::UV(0, _) =>
+				{
+					(PMX_MORPH_UV, PmxMorphData::UV(targets(index, &it.name).into_iter().flat_map(|(target, base)| target.texcoords.iter().map(move |x| PmxMorphUV { index:x.index + base, offset:x.offset })).collect()))
+				},
+				// Meshes keep no additional uvs, the morph stays so group morphs still find their children.
+				MorphData::UV(channel, _) => (PMX_MORPH_UV + channel, PmxMorphData::UV(Vec::new())),
+				MorphData::Bone(ref v) =>
+				{
+					(PMX_MORPH_BONE, PmxMorphData::Bone(v.iter().map(|x| PmxMorphBone { bone_index:x.bone as i16, translate:x.translate, rotation:x.rotation }).collect()))
+				},
+				MorphData::Material(ref v) =>
+				{
+					(PMX_MORPH_MATERIAL, PmxMorphData::Material(v.iter().map(|x|
+						PmxMorphMaterial
+						{
+							index:x.material.map_or(-1, |i| i as i32),
+							operation:x.operation,
+							diffuse:x.diffuse,
+							specular:x.specular,
+							shininess:x.shininess,
+							ambient:x.ambient,
+							edge_color:x.edge_color,
+							edge_size:x.edge_size,
+							texture:x.texture,
+							sphere:x.sphere,
+							toon:x.toon,
+						}).collect()))
+				},
+				MorphData::Impulse(ref v) =>
+				{
+					(PMX_MORPH_IMPULSE, PmxMorphData::Impulse(v.iter().map(|x|
+						PmxMorphImpulse
+						{
+							index:x.rigidbody as i32,
+							local:if x.local { 1 } else { 0 },
+							velocity:x.velocity,
+							torque:x.torque,
+						}).collect()))
+				},
+			};
+
+			morph.kind = kind;
+			morph.data = data;
+			pmx.morphs.push(morph);
+		}
+
+		// MikuMikuDance expects the two special frames holding the root bone and the expressions.
+		let mut root = PmxFrame::new();
+		root.name = "Root".to_string();
+		root.name_eng = "Root".to_string();
+		root.special = 1;
+
+		if pmx.bones.len() > 0
+		{
+			root.elements.push(PmxFrameElement { kind:PMX_FRAME_BONE, index:0 });
+		}
+
+		let mut expression = PmxFrame::new();
+		expression.name = "表情".to_string();
+		expression.name_eng = "Exp".to_string();
+		expression.special = 1;
+		expression.elements = (0..pmx.morphs.len()).map(|i| PmxFrameElement { kind:PMX_FRAME_MORPH, index:i as i32 }).collect();
+
+		pmx.frames.push(root);
+		pmx.frames.push(expression);
+
+		for it in model.rigidbodies.iter()
+		{
+			let mut rigidbody = PmxRigidbody::new();
+			rigidbody.name = it.name.clone();
+			rigidbody.bone = it.bone.map_or(-1, |bone| bone as i16);
+			rigidbody.group = it.group;
+			rigidbody.group_mask = it.group_mask;
+			rigidbody.shape = it.shape;
+			rigidbody.scale = it.size;
+			rigidbody.position = it.position;
+			rigidbody.rotation = it.rotation;
+			rigidbody.mass = it.mass;
+			rigidbody.movement_decay = it.linear_damping;
+			rigidbody.rotation_decay = it.angular_damping;
+			rigidbody.elasticity = it.restitution;
+			rigidbody.friction = it.friction;
+			rigidbody.physics_operation = it.physics_operation;
+			pmx.rigidbodies.push(rigidbody);
+		}
+
+		for it in model.joints.iter()
+		{
+			let mut joint = PmxJoint::new();
+			joint.name = it.name.clone();
+			joint.kind = it.kind;
+			joint.rigidbody_a = it.rigidbody_a as i32;
+			joint.rigidbody_b = it.rigidbody_b as i32;
+			joint.position = it.position;
+			joint.rotation = it.rotation;
+			joint.movement_lower_limit = it.translate_lower_limit;
+			joint.movement_upper_limit = it.translate_upper_limit;
+			joint.rotation_lower_limit = it.rotation_lower_limit;
+			joint.rotation_upper_limit = it.rotation_upper_limit;
+			joint.movement_spring_constant = it.translate_spring;
+			joint.rotation_spring_constant = it.rotation_spring;
+			pmx.joints.push(joint);
+		}
+
+		for it in model.softbodies.iter()
+		{
+			// Each mesh was written with a material of its own, the soft body follows the first mesh drawn with its material.
+			let mesh = it.material.and_then(|material| model.meshes.iter().position(|mesh| mesh.material_id == Some(material)));
+			let base = mesh.map_or(0, |mesh| bases[mesh]);
+
+			let mut softbody = PmxSoftbody::new();
+			softbody.name = it.name.clone();
+			softbody.shape = it.shape;
+			softbody.material = mesh.map_or(-1, |mesh| mesh as i32);
+			softbody.group = it.group;
+			softbody.group_mask = it.group_mask;
+			softbody.flag = it.flag;
+			softbody.blink_length = it.blink_length;
+			softbody.num_clusters = it.num_clusters;
+			softbody.total_mass = it.total_mass;
+			softbody.collision_margin = it.collision_margin;
+			softbody.aero_model = it.aero_model;
+			softbody.config = it.config;
+			softbody.cluster = it.cluster;
+			softbody.iteration = it.iteration;
+			softbody.material_coefficient = it.material_coefficient;
+
+			if mesh.is_some()
+			{
+				softbody.anchors = it.anchors.iter().map(|x| PmxSoftbodyAnchor { rigidbody:x.rigidbody as i32, vertex:x.vertex + base, near_mode:if x.near_mode { 1 } else { 0 } }).collect();
+				softbody.pin_vertices = it.pin_vertices.iter().map(|&x| x + base).collect();
+			}
+
+			pmx.softbodies.push(softbody);
+		}
+
+		pmx.update_header();
+
+		Ok(pmx)
+	}
 }
 
 #[derive(Debug)]
@@ -796,9 +2496,9 @@ impl PMXLoader
 		}
 	}
 
-	pub fn do_save(_buf:&[u8], _model:&mut Model) -> Result<()>
+	pub fn do_save(&self, model:&Model) -> Result<Vec<u8>>
 	{
-		Err(Error("Not Implmention yet".to_string()))
+		PMXFile::from_model(model, PMX_ENCODE_UTF16)?.save()
 	}
 }
 
@@ -812,8 +2512,72 @@ impl Loader for PMXLoader
 	fn do_load(&self, buf:&[u8]) -> Result<Model>
 	{
 		let pmx = PMXFile::load(buf)?;
+
+		if (0..pmx.indices.len()).any(|i| pmx.indices.get(i) as usize >= pmx.vertices.len())
+		{
+			return Err(Error("Invalid vertex index in PMX".to_string()));
+		}
+
+		if pmx.materials.iter().map(|it| it.face_count as usize).sum::<usize>() > pmx.indices.len()
+		{
+			return Err(Error("Invalid face count in PMX Material".to_string()));
+		}
+
 		let mut model = Model::new();
+
+		for it in pmx.morphs
+		{
+			let mut morph = Morph::new();
+			morph.name = it.name;
+			morph.panel = it.panel;
+			morph.data = match it.data
+			{
+				PmxMorphData::Group(ref v) => MorphData::Group(v.iter().map(|x| GroupMorph { morph:x.index as u32, weight:x.ratio }).collect()),
+				PmxMorphData::Flip(ref v) => MorphData::Flip(v.iter().map(|x| GroupMorph { morph:x.index as u32, weight:x.ratio }).collect()),
+				PmxMorphData::Vertex(ref v) => MorphData::Vertex(v.iter().map(|x| VertexMorph { index:x.index, offset:x.offset }).collect()),
+				PmxMorphData::UV(ref v) => MorphData::UV(it.kind - PMX_MORPH_UV, v.iter().map(|x| UVMorph { index:x.index, offset:x.offset }).collect()),
+				PmxMorphData::Bone(ref v) =>
+				{
+					MorphData::Bone(v.iter().map(|x| BoneMorph { bone:x.bone_index as u16, translate:x.translate, rotation:x.rotation }).collect())
+				},
+				PmxMorphData::Material(ref v) =>
+				{
+					MorphData::Material(v.iter().map(|x|
+						MaterialMorph
+						{
+							material:if x.index >= 0 { Some(x.index as usize) } else { None },
+							operation:x.operation,
+							diffuse:x.diffuse,
+							specular:x.specular,
+							shininess:x.shininess,
+							ambient:x.ambient,
+							edge_color:x.edge_color,
+							edge_size:x.edge_size,
+							texture:x.texture,
+							sphere:x.sphere,
+							toon:x.toon,
+						}).collect())
+				},
+				PmxMorphData::Impulse(ref v) =>
+				{
+					MorphData::Impulse(v.iter().map(|x|
+						ImpulseMorph
+						{
+							rigidbody:x.index as u32,
+							local:x.local > 0,
+							velocity:x.velocity,
+							torque:x.torque,
+						}).collect())
+				},
+			};
+
+			model.add_morph(morph);
+		}
+
+		let targets:Vec<MorphTarget> = (0..model.morphs.len()).map(|i| MorphTarget::flatten(&model.morphs, i)).collect();
+
 		let mut start_indices:usize = 0;
+		let mut maps = Vec::with_capacity(pmx.materials.len());
 
 		for (material_id, it) in pmx.materials.iter().enumerate()
 		{
@@ -855,7 +2619,7 @@ impl Loader for PMXLoader
 				mesh.weights.resize(map.len(), VertexWeight::new());
 			}
 
-			for (read, write) in map
+			for (&read, &write) in map.iter()
 			{
 				let v = pmx.vertices[read as usize].position;
 				let n = pmx.vertices[read as usize].normal;
@@ -884,7 +2648,13 @@ impl Loader for PMXLoader
 				}
 			}
 
+			mesh.morphs = targets.iter()
+				.map(|target| target.remap(&map, material_id))
+				.filter(|target| !target.is_empty())
+				.collect();
+
 			model.add_mesh(mesh);
+			maps.push(map);
 		}
 
 		for it in pmx.textures
@@ -900,10 +2670,45 @@ impl Loader for PMXLoader
 			material.diffuse = [it.diffuse.0, it.diffuse.1, it.diffuse.2];
 			material.specular = [it.specular.0, it.specular.1, it.specular.2];
 			material.shininess = it.shininess;
-			material.optical_density = it.opacity;
+			material.dissolve = it.opacity;
+			material.edge_color = [it.edge_color.0, it.edge_color.1, it.edge_color.2, it.edge_color.3];
+			material.edge_size = it.edge_size;
+			material.double_sided = (it.flag & PMX_MATERIAL_DOUBLE_SIDED) > 0;
+			material.ground_shadow = (it.flag & PMX_MATERIAL_GROUND_SHADOW) > 0;
+			material.cast_shadow = (it.flag & PMX_MATERIAL_CAST_SHADOW) > 0;
+			material.receive_shadow = (it.flag & PMX_MATERIAL_RECEIVE_SHADOW) > 0;
+			material.edge = (it.flag & PMX_MATERIAL_EDGE) > 0;
+			material.vertex_color = (it.flag & PMX_MATERIAL_VERTEX_COLOR) > 0;
+			material.point_draw = (it.flag & PMX_MATERIAL_POINT_DRAW) > 0;
+			material.line_draw = (it.flag & PMX_MATERIAL_LINE_DRAW) > 0;
 
 			if it.texture_index >= 0 { material.diffuse_texture = Some(it.texture_index as usize); }
 
+			if it.sphere_texture_index >= 0
+			{
+				material.sphere_texture = Some(it.sphere_texture_index as usize);
+				material.sphere_mode = match it.sphere_mode
+				{
+					PMX_SPHERE_MULTIPLY => SphereMode::Multiply,
+					PMX_SPHERE_ADDITIVE => SphereMode::Additive,
+					PMX_SPHERE_SUB_TEXTURE => SphereMode::SubTexture,
+					_ => SphereMode::None,
+				};
+			}
+
+			material.toon = if it.toon_index == 1
+			{
+				Toon::Shared(it.toon_texture as u8)
+			}
+			else if it.toon_texture >= 0
+			{
+				Toon::Texture(it.toon_texture as usize)
+			}
+			else
+			{
+				Toon::None
+			};
+
 			model.add_material(material);
 		}
 
@@ -913,6 +2718,29 @@ impl Loader for PMXLoader
 			bone.name = (*it.name).to_string();
 			bone.parent = if it.parent >= 0 { Some(it.parent as u16) } else { None };
 			bone.position = it.position;
+			bone.order = it.level as i32;
+			bone.after_physics = (it.flag & PMX_BONE_PHYSICS_AFTER) > 0;
+			bone.rotatable = (it.flag & PMX_BONE_ROTATABLE) > 0;
+			bone.movable = (it.flag & PMX_BONE_MOVABLE) > 0;
+			bone.visible = (it.flag & PMX_BONE_VISIBLE) > 0;
+			bone.operable = (it.flag & PMX_BONE_OPERABLE) > 0;
+			bone.tail = match (it.flag & PMX_BONE_INDEX) > 0
+			{
+				true if it.connected_bone_index >= 0 => BoneTail::Bone(it.connected_bone_index as u16),
+				true => BoneTail::Offset(0.0, 0.0, 0.0),
+				false => BoneTail::Offset(it.offset.0, it.offset.1, it.offset.2),
+			};
+
+			if (it.flag & (PMX_BONE_PARENT | PMX_BONE_MOVE_PARENT)) > 0 && it.provided_parent_bone_index >= 0
+			{
+				let mut grant = BoneGrant::new();
+				grant.parent = it.provided_parent_bone_index as u16;
+				grant.ratio = it.provided_ratio;
+				grant.rotation = (it.flag & PMX_BONE_PARENT) > 0;
+				grant.translation = (it.flag & PMX_BONE_MOVE_PARENT) > 0;
+				bone.grant = Some(grant);
+			}
+
 			model.add_bone(bone);
 
 			if (it.flag & PMX_BONE_IK) > 0
@@ -943,6 +2771,73 @@ impl Loader for PMXLoader
 			}
 		}
 
+		for it in pmx.rigidbodies
+		{
+			let mut rigidbody = RigidBody::new();
+			rigidbody.name = it.name;
+			rigidbody.bone = if it.bone >= 0 { Some(it.bone as u16) } else { None };
+			rigidbody.group = it.group;
+			rigidbody.group_mask = it.group_mask;
+			rigidbody.shape = it.shape;
+			rigidbody.size = it.scale;
+			rigidbody.position = it.position;
+			rigidbody.rotation = it.rotation;
+			rigidbody.mass = it.mass;
+			rigidbody.linear_damping = it.movement_decay;
+			rigidbody.angular_damping = it.rotation_decay;
+			rigidbody.restitution = it.elasticity;
+			rigidbody.friction = it.friction;
+			rigidbody.physics_operation = it.physics_operation;
+			model.add_rigidbody(rigidbody);
+		}
+
+		for it in pmx.joints
+		{
+			let mut joint = Joint::new();
+			joint.name = it.name;
+			joint.kind = it.kind;
+			joint.rigidbody_a = it.rigidbody_a as u32;
+			joint.rigidbody_b = it.rigidbody_b as u32;
+			joint.position = it.position;
+			joint.rotation = it.rotation;
+			joint.translate_lower_limit = it.movement_lower_limit;
+			joint.translate_upper_limit = it.movement_upper_limit;
+			joint.rotation_lower_limit = it.rotation_lower_limit;
+			joint.rotation_upper_limit = it.rotation_upper_limit;
+			joint.translate_spring = it.movement_spring_constant;
+			joint.rotation_spring = it.rotation_spring_constant;
+			model.add_joint(joint);
+		}
+
+		// The vertices of a soft body are moved into the mesh of its material, those the mesh does not draw are dropped.
+		for it in pmx.softbodies
+		{
+			let map = if it.material >= 0 { maps.get(it.material as usize) } else { None };
+			let local = |vertex:u32| map.and_then(|map| map.get(&vertex)).cloned();
+
+			let mut softbody = SoftBody::new();
+			softbody.name = it.name;
+			softbody.shape = it.shape;
+			softbody.material = if map.is_some() { Some(it.material as usize) } else { None };
+			softbody.group = it.group;
+			softbody.group_mask = it.group_mask;
+			softbody.flag = it.flag;
+			softbody.blink_length = it.blink_length;
+			softbody.num_clusters = it.num_clusters;
+			softbody.total_mass = it.total_mass;
+			softbody.collision_margin = it.collision_margin;
+			softbody.aero_model = it.aero_model;
+			softbody.config = it.config;
+			softbody.cluster = it.cluster;
+			softbody.iteration = it.iteration;
+			softbody.material_coefficient = it.material_coefficient;
+			softbody.anchors = it.anchors.iter()
+				.filter_map(|x| local(x.vertex).map(|vertex| SoftBodyAnchor { rigidbody:x.rigidbody as u32, vertex:vertex, near_mode:x.near_mode > 0 }))
+				.collect();
+			softbody.pin_vertices = it.pin_vertices.iter().filter_map(|&x| local(x)).collect();
+			model.add_softbody(softbody);
+		}
+
 		Ok(model)
 	}
 }
\ No newline at end of file
@@ -0,0 +1,767 @@
+use std::collections::HashMap;
+use encoding::{Encoding, DecoderTrap};
+use encoding::all::WINDOWS_31J;
+use super::super::{Loader, Model, Mesh, Material, SphereMode, Error, Result};
+
+pub const X_MAGIC:&'static [u8] = b"xof ";
+pub const X_FORMAT_TEXT:&'static [u8] = b"txt ";
+pub const X_HEADER_SIZE:usize = 16;
+
+const X_IDENTITY:[f32;16] = [1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum XToken
+{
+	Name(String),
+	Number(f32),
+	Text(String),
+	Open,
+	Close,
+}
+
+#[derive(Debug, Clone)]
+pub struct XMaterial
+{
+	pub color:(f32,f32,f32,f32),
+	pub power:f32,
+	pub specular:(f32,f32,f32),
+	pub emissive:(f32,f32,f32),
+	pub texture:Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct XMesh
+{
+	pub name:String,
+	pub positions:Vec<(f32,f32,f32)>,
+	pub faces:Vec<Vec<u32>>,
+	pub normals:Vec<(f32,f32,f32)>,
+	pub face_normals:Vec<Vec<u32>>,
+	pub texcoords:Vec<(f32,f32)>,
+	pub face_materials:Vec<u32>,
+	pub materials:Vec<XMaterial>,
+}
+
+/// The meshes of a text DirectX file with their frame transforms applied, binary and compressed files are not read.
+#[derive(Debug, Clone)]
+pub struct XFile
+{
+	pub meshes:Vec<XMesh>,
+}
+
+// Separators carry nothing the counts in the data don't already say, so the tokens skip them.
+struct XReader
+{
+	tokens:Vec<XToken>,
+	pos:usize,
+	materials:HashMap<String, XMaterial>,
+}
+
+impl XMaterial
+{
+	pub fn new() -> Self
+	{
+		Self
+		{
+			color:(1.0, 1.0, 1.0, 1.0),
+			power:0.0,
+			specular:(0.0, 0.0, 0.0),
+			emissive:(0.0, 0.0, 0.0),
+			texture:None,
+		}
+	}
+
+	/// MikuMikuDance writes a sphere map after the texture as "texture.png*sphere.sph".
+	pub fn textures(&self) -> (Option<&str>, Option<&str>)
+	{
+		match self.texture
+		{
+			Some(ref texture) =>
+			{
+				let mut names = texture.splitn(2, '*').filter(|name| !name.is_empty());
+				let first = names.next();
+				let second = names.next();
+
+				match (first, second)
+				{
+					(Some(name), None) if XMaterial::is_sphere(name) => (None, Some(name)),
+					_ => (first, second),
+				}
+			},
+			None => (None, None),
+		}
+	}
+
+	fn is_sphere(name:&str) -> bool
+	{
+		let name = name.to_lowercase();
+		name.ends_with(".sph") || name.ends_with(".spa")
+	}
+}
+
+impl XMesh
+{
+	pub fn new() -> Self
+	{
+		Self
+		{
+			name:String::new(),
+			positions:Vec::new(),
+			faces:Vec::new(),
+			normals:Vec::new(),
+			face_normals:Vec::new(),
+			texcoords:Vec::new(),
+			face_materials:Vec::new(),
+			materials:Vec::new(),
+		}
+	}
+
+	fn transform(&mut self, m:&[f32;16])
+	{
+		for p in self.positions.iter_mut()
+		{
+			*p = (
+				p.0 * m[0] + p.1 * m[4] + p.2 * m[8] + m[12],
+				p.0 * m[1] + p.1 * m[5] + p.2 * m[9] + m[13],
+				p.0 * m[2] + p.1 * m[6] + p.2 * m[10] + m[14]);
+		}
+
+		for n in self.normals.iter_mut()
+		{
+			*n = normalize((
+				n.0 * m[0] + n.1 * m[4] + n.2 * m[8],
+				n.0 * m[1] + n.1 * m[5] + n.2 * m[9],
+				n.0 * m[2] + n.1 * m[6] + n.2 * m[10]));
+		}
+	}
+
+	// Files without normals get smooth ones, each position sums the normals of the faces around it.
+	fn smooth_normals(&mut self)
+	{
+		let mut normals = vec![(0.0, 0.0, 0.0); self.positions.len()];
+
+		for face in self.faces.iter()
+		{
+			for i in 1..face.len().saturating_sub(1)
+			{
+				let a = self.positions[face[0] as usize];
+				let b = self.positions[face[i] as usize];
+				let c = self.positions[face[i + 1] as usize];
+
+				let u = (b.0 - a.0, b.1 - a.1, b.2 - a.2);
+				let v = (c.0 - a.0, c.1 - a.1, c.2 - a.2);
+				let n = (u.1 * v.2 - u.2 * v.1, u.2 * v.0 - u.0 * v.2, u.0 * v.1 - u.1 * v.0);
+
+				for &index in [face[0], face[i], face[i + 1]].iter()
+				{
+					let sum:&mut (f32,f32,f32) = &mut normals[index as usize];
+					*sum = (sum.0 + n.0, sum.1 + n.1, sum.2 + n.2);
+				}
+			}
+		}
+
+		self.normals = normals.into_iter().map(normalize).collect();
+		self.face_normals = self.faces.clone();
+	}
+
+	fn validate_positions(&self) -> Result<()>
+	{
+		let positions = self.positions.len() as u32;
+		if self.faces.iter().any(|face| face.iter().any(|&index| index >= positions))
+		{
+			return Err(Error("Invalid vertex index in X".to_string()));
+		}
+
+		Ok(())
+	}
+
+	fn validate(&self) -> Result<()>
+	{
+		let normals = self.normals.len() as u32;
+
+		self.validate_positions()?;
+
+		if self.face_normals.len() != self.faces.len() || self.face_normals.iter().zip(self.faces.iter()).any(|(normal, face)| normal.len() != face.len())
+		{
+			return Err(Error("Invalid face normals in X".to_string()));
+		}
+
+		if self.face_normals.iter().any(|face| face.iter().any(|&index| index >= normals))
+		{
+			return Err(Error("Invalid normal index in X".to_string()));
+		}
+
+		if self.face_materials.iter().any(|&index| index as usize >= self.materials.len().max(1))
+		{
+			return Err(Error("Invalid material index in X".to_string()));
+		}
+
+		Ok(())
+	}
+}
+
+fn normalize(n:(f32,f32,f32)) -> (f32,f32,f32)
+{
+	let length = (n.0 * n.0 + n.1 * n.1 + n.2 * n.2).sqrt();
+	if length > 0.0 { (n.0 / length, n.1 / length, n.2 / length) } else { n }
+}
+
+// Matrices are row major with the translation in the last row, a child frame applies its own before its parent's.
+fn multiply(a:&[f32;16], b:&[f32;16]) -> [f32;16]
+{
+	let mut m = [0.0; 16];
+
+	for row in 0..4
+	{
+		for column in 0..4
+		{
+			m[row * 4 + column] = (0..4).map(|k| a[row * 4 + k] * b[k * 4 + column]).sum();
+		}
+	}
+
+	m
+}
+
+impl XReader
+{
+	fn new(buf:&[u8]) -> Result<Self>
+	{
+		let mut tokens = Vec::new();
+		let mut i = 0;
+
+		while i < buf.len()
+		{
+			let c = buf[i];
+
+			match c
+			{
+				b'{' => { tokens.push(XToken::Open); i += 1; },
+				b'}' => { tokens.push(XToken::Close); i += 1; },
+				b'#' => { while i < buf.len() && buf[i] != b'\n' { i += 1; } },
+				b'/' if buf.get(i + 1) == Some(&b'/') => { while i < buf.len() && buf[i] != b'\n' { i += 1; } },
+				b'<' =>
+				{
+					while i < buf.len() && buf[i] != b'>' { i += 1; }
+					i += 1;
+				},
+				b'"' =>
+				{
+					let start = i + 1;
+					i = start;
+					while i < buf.len() && buf[i] != b'"' { i += 1; }
+
+					if i >= buf.len()
+					{
+						return Err(Error("Unterminated string in X".to_string()));
+					}
+
+					tokens.push(XToken::Text(WINDOWS_31J.decode(&buf[start..i], DecoderTrap::Ignore).unwrap()));
+					i += 1;
+				},
+				b'0'..=b'9' | b'-' | b'+' | b'.' =>
+				{
+					let start = i;
+					while i < buf.len() && (buf[i].is_ascii_digit() || b"-+.eE".contains(&buf[i])) { i += 1; }
+
+					let text = std::str::from_utf8(&buf[start..i]).unwrap();
+					match text.parse::<f32>()
+					{
+						Ok(value) => tokens.push(XToken::Number(value)),
+						Err(_) => return Err(Error(format!("Invalid number {} in X", text))),
+					}
+				},
+				c if c == b'_' || c.is_ascii_alphabetic() || c >= 0x80 =>
+				{
+					let start = i;
+					while i < buf.len() && (buf[i] == b'_' || buf[i] == b'-' || buf[i] == b'.' || buf[i].is_ascii_alphanumeric() || buf[i] >= 0x80) { i += 1; }
+
+					tokens.push(XToken::Name(WINDOWS_31J.decode(&buf[start..i], DecoderTrap::Ignore).unwrap()));
+				},
+				_ => { i += 1; },
+			}
+		}
+
+		Ok(Self
+		{
+			tokens:tokens,
+			pos:0,
+			materials:HashMap::new(),
+		})
+	}
+
+	fn peek(&self) -> Option<&XToken>
+	{
+		self.tokens.get(self.pos)
+	}
+
+	fn next(&mut self) -> Result<XToken>
+	{
+		match self.tokens.get(self.pos).cloned()
+		{
+			Some(token) => { self.pos += 1; Ok(token) },
+			None => Err(Error("Unexpected end of X".to_string())),
+		}
+	}
+
+	fn number(&mut self) -> Result<f32>
+	{
+		match self.next()?
+		{
+			XToken::Number(value) => Ok(value),
+			token => Err(Error(format!("Expected a number in X, found {:?}", token))),
+		}
+	}
+
+	fn index(&mut self) -> Result<u32>
+	{
+		let value = self.number()?;
+		if value < 0.0 || value.fract() != 0.0 || value > u32::max_value() as f32
+		{
+			return Err(Error(format!("Invalid index {} in X", value)));
+		}
+
+		Ok(value as u32)
+	}
+
+	// Counts come from the file, each element takes one token at least, so more than are left is an error.
+	fn count(&mut self, size:usize, what:&str) -> Result<usize>
+	{
+		let count = self.index()? as usize;
+		if count.saturating_mul(size) > self.tokens.len() - self.pos
+		{
+			return Err(Error(format!("Invalid count of {} in X", what)));
+		}
+
+		Ok(count)
+	}
+
+	fn vector3(&mut self) -> Result<(f32,f32,f32)>
+	{
+		Ok((self.number()?, self.number()?, self.number()?))
+	}
+
+	fn faces(&mut self) -> Result<Vec<Vec<u32>>>
+	{
+		let count = self.count(4, "faces")?;
+		let mut faces = Vec::with_capacity(count);
+
+		for _ in 0..count
+		{
+			let num = self.count(1, "face indices")?;
+			let mut face = Vec::with_capacity(num);
+
+			for _ in 0..num
+			{
+				face.push(self.index()?);
+			}
+
+			faces.push(face);
+		}
+
+		Ok(faces)
+	}
+
+	// Reads the optional name of a data object up to its opening brace.
+	fn open(&mut self) -> Result<Option<String>>
+	{
+		let name = match self.next()?
+		{
+			XToken::Open => return Ok(None),
+			XToken::Name(name) => name,
+			token => return Err(Error(format!("Expected an object in X, found {:?}", token))),
+		};
+
+		match self.next()?
+		{
+			XToken::Open => Ok(Some(name)),
+			token => Err(Error(format!("Expected an object in X, found {:?}", token))),
+		}
+	}
+
+	fn skip(&mut self) -> Result<()>
+	{
+		let mut depth = 1;
+
+		while depth > 0
+		{
+			match self.next()?
+			{
+				XToken::Open => depth += 1,
+				XToken::Close => depth -= 1,
+				_ => {},
+			}
+		}
+
+		Ok(())
+	}
+
+	fn load(&mut self) -> Result<Vec<XMesh>>
+	{
+		let mut meshes = Vec::new();
+
+		while self.peek().is_some()
+		{
+			match self.next()?
+			{
+				XToken::Name(kind) => self.object(&kind, &X_IDENTITY, &mut meshes)?,
+				XToken::Open => self.skip()?,
+				token => return Err(Error(format!("Expected an object in X, found {:?}", token))),
+			}
+		}
+
+		Ok(meshes)
+	}
+
+	fn object(&mut self, kind:&str, transform:&[f32;16], meshes:&mut Vec<XMesh>) -> Result<()>
+	{
+		let name = self.open()?;
+
+		match kind
+		{
+			"Frame" => self.frame(transform, meshes),
+			"Mesh" =>
+			{
+				let mut mesh = self.mesh()?;
+				mesh.name = name.unwrap_or_default();
+				mesh.transform(transform);
+				meshes.push(mesh);
+				Ok(())
+			},
+			"Material" =>
+			{
+				let material = self.material()?;
+				if let Some(name) = name
+				{
+					self.materials.insert(name, material);
+				}
+				Ok(())
+			},
+			_ => self.skip(),
+		}
+	}
+
+	fn frame(&mut self, parent:&[f32;16], meshes:&mut Vec<XMesh>) -> Result<()>
+	{
+		let mut transform = *parent;
+
+		loop
+		{
+			match self.next()?
+			{
+				XToken::Close => return Ok(()),
+				XToken::Open => self.skip()?,
+				XToken::Name(ref kind) if kind == "FrameTransformMatrix" =>
+				{
+					self.open()?;
+
+					let mut local = [0.0; 16];
+					for value in local.iter_mut()
+					{
+						*value = self.number()?;
+					}
+
+					self.skip()?;
+					transform = multiply(&local, parent);
+				},
+				XToken::Name(kind) => self.object(&kind, &transform, meshes)?,
+				token => return Err(Error(format!("Expected an object in X, found {:?}", token))),
+			}
+		}
+	}
+
+	fn mesh(&mut self) -> Result<XMesh>
+	{
+		let mut mesh = XMesh::new();
+
+		let count = self.count(3, "vertices")?;
+		mesh.positions.reserve(count);
+		for _ in 0..count
+		{
+			mesh.positions.push(self.vector3()?);
+		}
+
+		mesh.faces = self.faces()?;
+
+		loop
+		{
+			match self.next()?
+			{
+				XToken::Close => break,
+				XToken::Open => self.skip()?,
+				XToken::Name(kind) =>
+				{
+					self.open()?;
+
+					match kind.as_str()
+					{
+						"MeshNormals" =>
+						{
+							let count = self.count(3, "normals")?;
+							mesh.normals.reserve(count);
+							for _ in 0..count
+							{
+								mesh.normals.push(self.vector3()?);
+							}
+
+							mesh.face_normals = self.faces()?;
+							self.skip()?;
+						},
+						"MeshTextureCoords" =>
+						{
+							let count = self.count(2, "texture coords")?;
+							mesh.texcoords.reserve(count);
+							for _ in 0..count
+							{
+								mesh.texcoords.push((self.number()?, self.number()?));
+							}
+
+							self.skip()?;
+						},
+						"MeshMaterialList" => self.material_list(&mut mesh)?,
+						_ => self.skip()?,
+					}
+				},
+				token => return Err(Error(format!("Expected an object in X, found {:?}", token))),
+			}
+		}
+
+		if mesh.normals.is_empty()
+		{
+			mesh.validate_positions()?;
+			mesh.smooth_normals();
+		}
+
+		mesh.validate()?;
+
+		Ok(mesh)
+	}
+
+	fn material_list(&mut self, mesh:&mut XMesh) -> Result<()>
+	{
+		self.count(1, "materials")?;
+
+		let count = self.count(1, "face materials")?;
+		mesh.face_materials.reserve(count);
+		for _ in 0..count
+		{
+			mesh.face_materials.push(self.index()?);
+		}
+
+		loop
+		{
+			match self.next()?
+			{
+				XToken::Close => return Ok(()),
+				XToken::Open =>
+				{
+					// A reference to a material declared before the mesh, as "{ name }".
+					let name = match self.next()?
+					{
+						XToken::Name(name) => name,
+						token => return Err(Error(format!("Expected a material name in X, found {:?}", token))),
+					};
+
+					self.skip()?;
+
+					match self.materials.get(&name)
+					{
+						Some(material) => mesh.materials.push(material.clone()),
+						None => return Err(Error(format!("Unknown material {} in X", name))),
+					}
+				},
+				XToken::Name(ref kind) if kind == "Material" =>
+				{
+					self.open()?;
+					let material = self.material()?;
+					mesh.materials.push(material);
+				},
+				XToken::Name(_) => { self.open()?; self.skip()?; },
+				token => return Err(Error(format!("Expected a material in X, found {:?}", token))),
+			}
+		}
+	}
+
+	fn material(&mut self) -> Result<XMaterial>
+	{
+		let mut material = XMaterial::new();
+		material.color = (self.number()?, self.number()?, self.number()?, self.number()?);
+		material.power = self.number()?;
+		material.specular = self.vector3()?;
+		material.emissive = self.vector3()?;
+
+		loop
+		{
+			match self.next()?
+			{
+				XToken::Close => return Ok(material),
+				XToken::Open => self.skip()?,
+				XToken::Name(kind) =>
+				{
+					self.open()?;
+
+					if kind == "TextureFilename" || kind == "TextureFileName"
+					{
+						if let XToken::Text(name) = self.next()?
+						{
+							material.texture = Some(name);
+						}
+					}
+
+					self.skip()?;
+				},
+				token => return Err(Error(format!("Expected an object in X, found {:?}", token))),
+			}
+		}
+	}
+}
+
+impl XFile
+{
+	pub fn load(buf:&[u8]) -> Result<Self>
+	{
+		if buf.len() < X_HEADER_SIZE || &buf[0..4] != X_MAGIC
+		{
+			return Err(Error("Invalid header of magic.".to_string()));
+		}
+
+		if &buf[8..12] != X_FORMAT_TEXT
+		{
+			return Err(Error("Only text X files are supported".to_string()));
+		}
+
+		Ok(Self
+		{
+			meshes:XReader::new(&buf[X_HEADER_SIZE..])?.load()?
+		})
+	}
+}
+
+#[derive(Debug)]
+pub struct XLoader {}
+
+impl XLoader
+{
+	pub fn new() -> Self
+	{
+		Self
+		{
+		}
+	}
+
+	fn add_texture(model:&mut Model, name:&str) -> usize
+	{
+		match model.textures.iter().position(|texture| texture == name)
+		{
+			Some(index) => index,
+			None =>
+			{
+				model.add_texture(name.to_string());
+				model.textures.len() - 1
+			}
+		}
+	}
+
+	fn add_material(model:&mut Model, it:&XMaterial) -> usize
+	{
+		let mut material = Material::new();
+		material.diffuse = [it.color.0, it.color.1, it.color.2];
+		material.dissolve = it.color.3;
+		material.specular = [it.specular.0, it.specular.1, it.specular.2];
+		material.shininess = it.power;
+		// MikuMikuDance lights accessories with the emissive color as their ambient.
+		material.ambient = [it.emissive.0, it.emissive.1, it.emissive.2];
+		material.double_sided = it.color.3 < 1.0;
+		material.ground_shadow = true;
+		material.cast_shadow = true;
+		material.receive_shadow = true;
+
+		let (diffuse, sphere) = it.textures();
+
+		if let Some(name) = diffuse
+		{
+			material.diffuse_texture = Some(XLoader::add_texture(model, name));
+		}
+
+		if let Some(name) = sphere
+		{
+			material.sphere_texture = Some(XLoader::add_texture(model, name));
+			material.sphere_mode = if name.to_lowercase().ends_with(".spa") { SphereMode::Additive } else { SphereMode::Multiply };
+		}
+
+		model.add_material(material);
+		model.materials.len() - 1
+	}
+}
+
+impl Loader for XLoader
+{
+	fn can_read(&self, buf:&[u8]) -> bool
+	{
+		buf.len() >= X_HEADER_SIZE && &buf[0..4] == X_MAGIC && &buf[8..12] == X_FORMAT_TEXT
+	}
+
+	/// Every material of every mesh becomes one mesh, faces with more than three corners are split into fans.
+	fn do_load(&self, buf:&[u8]) -> Result<Model>
+	{
+		let x = XFile::load(buf)?;
+		let mut model = Model::new();
+
+		for it in x.meshes.iter()
+		{
+			let materials:Vec<XMaterial> = if it.materials.is_empty() { vec![XMaterial::new()] } else { it.materials.clone() };
+
+			for (slot, material) in materials.iter().enumerate()
+			{
+				let mut mesh = Mesh::new();
+				mesh.name = if materials.len() > 1 { format!("{}{}", it.name, slot) } else { it.name.clone() };
+
+				// A corner is a position with one of its normals, positions are repeated for each normal they have.
+				let mut map:HashMap<(u32,u32),u32> = HashMap::new();
+
+				for (f, face) in it.faces.iter().enumerate()
+				{
+					if it.face_materials.get(f).cloned().unwrap_or(0) as usize != slot
+					{
+						continue;
+					}
+
+					let mut corners = Vec::with_capacity(face.len());
+
+					for (&position, &normal) in face.iter().zip(it.face_normals[f].iter())
+					{
+						let len = map.len() as u32;
+						let index = *map.entry((position, normal)).or_insert(len);
+
+						if index == len
+						{
+							let p = it.positions[position as usize];
+							let n = it.normals[normal as usize];
+							let uv = it.texcoords.get(position as usize).cloned().unwrap_or((0.0, 0.0));
+
+							mesh.positions.extend_from_slice(&[p.0, p.1, p.2]);
+							mesh.normals.extend_from_slice(&[n.0, n.1, n.2]);
+							mesh.texcoords.extend_from_slice(&[uv.0, uv.1]);
+						}
+
+						corners.push(index);
+					}
+
+					for i in 1..corners.len().saturating_sub(1)
+					{
+						mesh.indices.extend_from_slice(&[corners[0], corners[i], corners[i + 1]]);
+					}
+				}
+
+				if mesh.indices.is_empty()
+				{
+					continue;
+				}
+
+				mesh.material_id = Some(XLoader::add_material(&mut model, material));
+				model.add_mesh(mesh);
+			}
+		}
+
+		Ok(model)
+	}
+}
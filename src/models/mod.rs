@@ -9,7 +9,7 @@ use std::fs::File;
 
 pub fn load_from_memory(buf:&[u8]) -> Result<Model>
 {
-	let loaders = vec![Box::new(PMXLoader::new())];
+	let loaders:Vec<Box<Loader>> = vec![Box::new(PMXLoader::new()), Box::new(PMDLoader::new()), Box::new(XLoader::new())];
 
 	for loader in loaders
 	{
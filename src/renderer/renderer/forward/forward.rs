@@ -3,14 +3,15 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 use crate::math::*;
-use crate::math::type_size::*;
+use crate::models;
 
 use crate::scene::core::*;
 use crate::scene::scene::{Scene, SceneNode};
+use crate::scene::materials::SKINNING_MAX_JOINTS;
 
 use super::uniforms::*;
 use super::shaders::*;
-use super::super::super::webgl::{ WebGLRenderingContext as gl, WebGLProgram, WebGLTexture, WebGLBuffer };
+use super::super::super::webgl::{ WebGLRenderingContext as gl, GLenum, WebGLProgram, WebGLTexture, WebGLBuffer };
 
 pub struct ForwardRenderer
 {
@@ -18,7 +19,8 @@ pub struct ForwardRenderer
 	h:u32,
 	context:gl,
 	texture:WebGLTexture,
-	shapes:HashMap<uuid::Uuid, GeometryUniform>,
+	// Buffers are packed in the input layout of a material, so one geometry drawn with two layouts keeps two sets.
+	shapes:HashMap<uuid::Uuid, HashMap<Vec<VertexAttrib>, Vec<GeometryUniform>>>,
 	materials:HashMap<uuid::Uuid, MaterialUniform>,
 	textures:HashMap<uuid::Uuid, TextureUniform>,
 	depth_enable:bool,
@@ -27,6 +29,113 @@ pub struct ForwardRenderer
 	cull_mode:CullMode,
 	front_face:FrontFace,
 	polygon_mode:PolygonMode,
+	element_index_uint:bool,
+}
+
+// Desktop GL always draws 32 bit indices.
+#[cfg(not(any(target_arch = "wasm32", target_arch = "asmjs")))]
+fn has_element_index_uint(_context:&gl) -> bool
+{
+	true
+}
+
+// WebGL 1 only draws 32 bit indices once OES_element_index_uint is enabled, which getExtension does.
+#[cfg(any(target_arch = "wasm32", target_arch = "asmjs"))]
+fn has_element_index_uint(context:&gl) -> bool
+{
+	let enabled = js! { return @{context}.getExtension("OES_element_index_uint") != null; };
+	enabled == stdweb::Value::Bool(true)
+}
+
+// Bone indices are read as floats in WebGL 1, which has no integer attributes.
+fn attrib_type(format:&Format) -> GLenum
+{
+	match format
+	{
+		Format::RUInt(8) | Format::RGUInt(8,8) | Format::RGBUInt(8,8,8) | Format::RGBAUInt(8,8,8,8) => gl::UNSIGNED_BYTE,
+		Format::RUInt(16) | Format::RGUInt(16,16) | Format::RGBUInt(16,16,16) | Format::RGBAUInt(16,16,16,16) => gl::UNSIGNED_SHORT,
+		_ => gl::FLOAT,
+	}
+}
+
+// Position, normal, texcoord, bone weights and bone indices, in the order of the input layouts.
+// Positions and texcoords come with the morphs applied, bone indices point into the palette of the batch the vertex is drawn with.
+fn vertex_attrib(geometry:&Arc<Geometry>, morphed:&(float3s, float2s), joints:&[u16], index:u8, i:usize) -> [f32;4]
+{
+	match index
+	{
+		0 => { let v = morphed.0[i]; [v.x, v.y, v.z, 1.0] },
+		1 => { let n = geometry.normals()[i]; [n.x, n.y, n.z, 0.0] },
+		2 => { let uv = morphed.1[i]; [uv.x, uv.y, 0.0, 0.0] },
+		3 => geometry.weights().get(i).map(|w| w.weight).unwrap_or([1.0, 0.0, 0.0, 0.0]),
+		4 =>
+		{
+			let bones = match geometry.weights().get(i)
+			{
+				Some(w) if joints.len() > 0 => local_bones(joints, w),
+				Some(w) => w.bone,
+				None => [0; 4],
+			};
+
+			[bones[0] as f32, bones[1] as f32, bones[2] as f32, bones[3] as f32]
+		},
+		_ => [0.0; 4],
+	}
+}
+
+// Packs every vertex the way the input layout of the material reads it.
+fn pack_vertices(geometry:&Arc<Geometry>, morphed:&(float3s, float2s), layouts:&[VertexAttrib], joints:&[u16], vertices:&[u32]) -> Vec<u8>
+{
+	let stride = layouts.first().map(|layout| layout.stride as usize).unwrap_or(0);
+	let mut data:Vec<u8> = vec![0; vertices.len() * stride];
+
+	for (n, &i) in vertices.iter().enumerate()
+	{
+		for layout in layouts
+		{
+			let values = vertex_attrib(geometry, morphed, joints, layout.index, i as usize);
+			let offset = n * stride + layout.offset as usize;
+
+			for (k, value) in values.iter().take(layout.count as usize).enumerate()
+			{
+				match attrib_type(&layout.format)
+				{
+					gl::UNSIGNED_BYTE => { data[offset + k] = *value as u8; },
+					gl::UNSIGNED_SHORT => { data[offset + k * 2..offset + k * 2 + 2].copy_from_slice(&(*value as u16).to_ne_bytes()); },
+					_ => { data[offset + k * 4..offset + k * 4 + 4].copy_from_slice(&value.to_ne_bytes()); },
+				}
+			}
+		}
+	}
+
+	data
+}
+
+// The albedo and specular of a material with the material morphs of the geometry applied, none while no such morph is weighted.
+fn morph_colors(geometry:&Arc<Geometry>, material:&Arc<Material>) -> Option<(float3, float3)>
+{
+	let weighted = geometry.morph_targets().iter().zip(geometry.morph_weights()).any(|(target, weight)| weight != 0.0 && target.materials.len() > 0);
+	if !weighted
+	{
+		return None;
+	}
+
+	let mut base = models::Material::new();
+	if let Some(Variant::Float3(v)) = material.uniforms().get("albedo") { base.diffuse = [v.x, v.y, v.z]; }
+	if let Some(Variant::Float3(v)) = material.uniforms().get("specular") { base.specular = [v.x, v.y, v.z]; }
+
+	let morphed = geometry.morph_material(&base);
+	Some((float3::new(morphed.diffuse[0], morphed.diffuse[1], morphed.diffuse[2]), float3::new(morphed.specular[0], morphed.specular[1], morphed.specular[2])))
+}
+
+fn joint_array(joints:&[float4x4]) -> Vec<f32>
+{
+	let mut data = Vec::with_capacity(joints.len() * 16);
+	for m in joints
+	{
+		data.extend_from_slice(&m.to_array()[..]);
+	}
+	data
 }
 
 impl ForwardRenderer
@@ -39,6 +148,8 @@ impl ForwardRenderer
 		context.tex_parameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as _);
 		context.tex_image2d(gl::TEXTURE_2D, 0, gl::RGBA, 1, 1, 0, gl::RGBA, gl::UNSIGNED_BYTE, &vec![255,255,0,255][..]);
 
+		let element_index_uint = has_element_index_uint(&context);
+
 		Self
 		{
 			w:width,
@@ -53,7 +164,8 @@ impl ForwardRenderer
 			depth_func:ComparisonFunc::Lequal,
 			cull_mode:CullMode::None,
 			front_face:FrontFace::CCW,
-			polygon_mode:PolygonMode::Solid
+			polygon_mode:PolygonMode::Solid,
+			element_index_uint:element_index_uint,
 		}
 	}
 
@@ -67,47 +179,90 @@ impl ForwardRenderer
 		self.h = height;
 	}
 
-	fn init_geometry(&mut self, geometry:&Arc<Geometry>)
+	fn init_geometry(&mut self, geometry:&Arc<Geometry>, material:&Arc<Material>)
 	{
-		if !self.shapes.contains_key(geometry.uuid())
+		let layouts = material.input_layout();
+
+		if self.shapes.get(geometry.uuid()).and_then(|shape| shape.get(layouts)).is_none()
 		{
-			let count = geometry.num_vertices();
-			let stride = float3::type_size() + float3::type_size() + float2::type_size();
-			let mut vertices:Vec<f32> = Vec::with_capacity(count * stride);
+			let indices = geometry.indices();
+			let all:Vec<u32> = (0..geometry.num_vertices() as u32).collect();
+			let morphed = (geometry.morph_vertices(), geometry.morph_texcoords());
 
-			for i in 0..count
+			// Skinned meshes are drawn in batches whose palettes fit the uniforms,
+			// without 32 bit indices the mesh is drawn in pieces every 16 bit index reaches.
+			let buffers = match (material.kind(), indices)
 			{
-				let v = &geometry.vertices()[i];
-				let n = &geometry.normals()[i];
-				let uv  = &geometry.texcoords()[i];
+				(MaterialType::Skinning(_), _) =>
+				{
+					indices.split_joints(geometry.weights(), SKINNING_MAX_JOINTS, std::u16::MAX as usize + 1).iter()
+						.map(|batch| self.create_geometry_buffer(geometry, &morphed, layouts, &batch.joints, &batch.vertices, &Indices::U16(batch.indices.clone())))
+						.collect()
+				},
+				(_, Indices::U32(_)) if !self.element_index_uint =>
+				{
+					indices.split(std::u16::MAX as usize + 1).into_iter()
+						.map(|(vertices, data)| self.create_geometry_buffer(geometry, &morphed, layouts, &[], &vertices, &Indices::U16(data)))
+						.collect()
+				},
+				_ => vec![self.create_geometry_buffer(geometry, &morphed, layouts, &[], &all, indices)]
+			};
 
-				vertices.push(v.x);
-				vertices.push(v.y);
-				vertices.push(v.z);
+			self.shapes.entry(geometry.uuid().clone()).or_insert_with(HashMap::new).insert(layouts.to_vec(), buffers);
+		}
+		else
+		{
+			self.update_geometry(geometry, layouts);
+		}
+	}
 
-				vertices.push(n.x);
-				vertices.push(n.y);
-				vertices.push(n.z);
+	// Packs the vertices again once the morph weights of the geometry changed since they were uploaded.
+	fn update_geometry(&mut self, geometry:&Arc<Geometry>, layouts:&[VertexAttrib])
+	{
+		let weights = geometry.morph_weights();
+		let buffers = self.shapes.get_mut(geometry.uuid()).and_then(|shape| shape.get_mut(layouts)).unwrap();
 
-				vertices.push(uv.x);
-				vertices.push(uv.y);
-			}
+		if buffers.iter().all(|buffer| buffer.weights == weights)
+		{
+			return;
+		}
 
-			// Create and store data into vertex buffer
-			let vertex_buffer = self.context.create_buffer().unwrap();
-			let index_buffer = self.context.create_buffer().unwrap();
+		let morphed = (geometry.morph_vertices(), geometry.morph_texcoords());
 
-			self.context.bind_buffer(gl::ARRAY_BUFFER, &vertex_buffer);
-			self.context.buffer_data(gl::ARRAY_BUFFER, &vertices[..], gl::STATIC_DRAW);
+		for buffer in buffers.iter_mut()
+		{
+			let data = pack_vertices(geometry, &morphed, layouts, &buffer.joints, &buffer.vertices);
 
-			// Create and store data into index buffer
-			self.context.bind_buffer(gl::ELEMENT_ARRAY_BUFFER, &index_buffer);
-			self.context.buffer_data(gl::ELEMENT_ARRAY_BUFFER, geometry.indices(), gl::STATIC_DRAW);
+			self.context.bind_buffer(gl::ARRAY_BUFFER, &buffer.vertex_buffer);
+			self.context.buffer_data(gl::ARRAY_BUFFER, &data[..], gl::DYNAMIC_DRAW);
 
-			self.shapes.insert(geometry.uuid().clone(), GeometryUniform::new(vertex_buffer, index_buffer, geometry.num_indices() as i32));
+			buffer.weights = weights.clone();
 		}
 	}
 
+	fn create_geometry_buffer(&self, geometry:&Arc<Geometry>, morphed:&(float3s, float2s), layouts:&[VertexAttrib], joints:&[u16], vertices:&[u32], indices:&Indices) -> GeometryUniform
+	{
+		let data = pack_vertices(geometry, morphed, layouts, joints, vertices);
+
+		// Create and store data into vertex buffer
+		let vertex_buffer = self.context.create_buffer().unwrap();
+		let index_buffer = self.context.create_buffer().unwrap();
+
+		self.context.bind_buffer(gl::ARRAY_BUFFER, &vertex_buffer);
+		self.context.buffer_data(gl::ARRAY_BUFFER, &data[..], gl::STATIC_DRAW);
+
+		// Create and store data into index buffer
+		self.context.bind_buffer(gl::ELEMENT_ARRAY_BUFFER, &index_buffer);
+		let format = match indices
+		{
+			Indices::U16(data) => { self.context.buffer_data(gl::ELEMENT_ARRAY_BUFFER, &data[..], gl::STATIC_DRAW); gl::UNSIGNED_SHORT },
+			Indices::U32(data) => { self.context.buffer_data(gl::ELEMENT_ARRAY_BUFFER, &data[..], gl::STATIC_DRAW); gl::UNSIGNED_INT },
+		};
+
+		GeometryUniform::new(vertex_buffer, index_buffer, indices.len() as i32, format, joints.to_vec())
+			.with_vertices(vertices.to_vec(), geometry.morph_weights())
+	}
+
 	fn init_texture(&mut self, image:&Arc<Texture>)
 	{
 		if !self.textures.contains_key(image.uuid())
@@ -141,6 +296,12 @@ impl ForwardRenderer
 		vs += SHADER_ATTRIB_POSITION;
 		vs += SHADER_ATTRIB_NORMAL;
 		vs += SHADER_ATTRIB_TEXCOORD0;
+
+		if let MaterialType::Skinning(_) = material.kind()
+		{
+			vs += SHADER_ATTRIB_SKINNING;
+		}
+
 		vs += SHADER_UNIORMS_MODEL;
 		vs += SHADER_UNIORMS_VIEW;
 		vs += SHADER_UNIORMS_VIEWPROJECT;
@@ -234,6 +395,7 @@ impl ForwardRenderer
 		let viewproject = self.context.get_uniform_location(&program, "matViewProject");
 		let eye_position = self.context.get_uniform_location(&program, "CameraPosition");
 		let exposure = self.context.get_uniform_location(&program, "Exposure");
+		let bindpose = self.context.get_uniform_location(&program, "bindpose");
 
 		let mut lights_vec = Vec::with_capacity(lights.len());
 		for i in 0..lights.len()
@@ -277,6 +439,7 @@ impl ForwardRenderer
 				Variant::Float2x2(m) => { self.context.uniform_matrix2fv(location.as_ref(), false, &m.to_array()[..]); },
 				Variant::Float3x3(m) => { self.context.uniform_matrix3fv(location.as_ref(), false, &m.to_array()[..]); },
 				Variant::Float4x4(m) => { self.context.uniform_matrix4fv(location.as_ref(), false, &m.to_array()[..]); },
+				Variant::Float4x4s(m) if m.len() > 0 => { self.context.uniform_matrix4fv(location.as_ref(), false, &joint_array(m)[..]); },
 				Variant::Texture(texture) => 
 				{
 					if texture.is_some()
@@ -301,6 +464,7 @@ impl ForwardRenderer
 			viewproject:viewproject,
 			eye_position:eye_position,
 			exposure:exposure,
+			bindpose:bindpose,
 			locations:locations,
 			lights:lights_vec 
 		};
@@ -325,8 +489,9 @@ impl ForwardRenderer
 	{
 		for shape in shapes
 		{
-			self.init_geometry(&shape.geometry().unwrap());
-			self.init_material(&shape.material().unwrap(), lights);
+			let material = shape.material().unwrap();
+			self.init_geometry(&shape.geometry().unwrap(), &material);
+			self.init_material(&material, lights);
 		}
 	}
 
@@ -368,7 +533,7 @@ impl ForwardRenderer
 			self.context.vertex_attrib_pointer(
 				layout.index as _,
 				layout.count as _,
-				gl::FLOAT as _,
+				attrib_type(&layout.format),
 				false,
 				layout.stride as _,
 				layout.offset as _
@@ -413,17 +578,20 @@ impl ForwardRenderer
 	{
 		if uniform.model.is_some()
 		{
-			let mov_matrix = shape.transform().to_array();
+			let mov_matrix = shape.world_transform().to_array();
 			self.context.uniform_matrix4fv(uniform.model.as_ref(), false, &mov_matrix[..]);
 		}
 	}
 
-	fn set_material_uniforms(&self, material:&Arc<Material>, uniforms:&MaterialUniform)
+	fn set_material_uniforms(&self, geometry:&Arc<Geometry>, material:&Arc<Material>, uniforms:&MaterialUniform)
 	{
 		let mut unit = 0;
 		let mut index = 0;
 
-		for (_key, value) in material.uniforms()
+		// Material morphs tint the colors of the shape, the material itself is shared and keeps its own.
+		let morphed = morph_colors(geometry, material);
+
+		for (key, value) in material.uniforms()
 		{
 			let location = uniforms.locations[index].as_ref();
 			index += 1;
@@ -431,6 +599,15 @@ impl ForwardRenderer
 				continue;
 			}
 
+			let morphed = match (key.as_str(), morphed)
+			{
+				("albedo", Some((albedo, _))) => Some(Variant::Float3(albedo)),
+				("specular", Some((_, specular))) => Some(Variant::Float3(specular)),
+				_ => None,
+			};
+
+			let value = morphed.as_ref().unwrap_or(value);
+
 			match value
 			{
 				Variant::Boolean(v) => { self.context.uniform1i(location, *v as _); },
@@ -445,6 +622,7 @@ impl ForwardRenderer
 				Variant::Float2x2(m) => { self.context.uniform_matrix2fv(location, false, &m.to_array()[..]); },
 				Variant::Float3x3(m) => { self.context.uniform_matrix3fv(location, false, &m.to_array()[..]); },
 				Variant::Float4x4(m) => { self.context.uniform_matrix4fv(location, false, &m.to_array()[..]); },
+				Variant::Float4x4s(m) if m.len() > 0 => { self.context.uniform_matrix4fv(location, false, &joint_array(m)[..]); },
 				Variant::Texture(texture) => 
 				{
 					if texture.is_some()
@@ -613,10 +791,26 @@ impl ForwardRenderer
 		self.context.use_program(&uniform.program);
 		self.set_camera_uniforms(camera, uniform);
 		self.set_geometry_uniforms(shape, uniform);
-		self.set_material_uniforms(material, uniform);
+		self.set_material_uniforms(&shape.geometry().unwrap(), material, uniform);
 		self.set_lights_uniforms(lights, material, uniform);
 	}
 
+	// Uploads the palette of the bones one batch reaches, shapes which were never posed are drawn in the rest pose.
+	fn set_joint_uniforms(&self, shape:&SceneNode, material:&Arc<Material>, buffer:&GeometryUniform)
+	{
+		let uniform = self.materials.get(material.uuid()).unwrap();
+
+		if uniform.bindpose.is_some() && buffer.joints.len() > 0
+		{
+			let palette = shape.joints();
+			let joints:Vec<float4x4> = buffer.joints.iter()
+				.map(|&joint| palette.as_ref().and_then(|palette| palette.get(joint as usize).cloned()).unwrap_or(float4x4::one()))
+				.collect();
+
+			self.context.uniform_matrix4fv(uniform.bindpose.as_ref(), false, &joint_array(&joints)[..]);
+		}
+	}
+
 	fn set_viewport(&self, v:&(f32,f32,f32,f32))
 	{
 		let (x, y) = (self.width() as f32 * v.0, self.height() as f32 * v.1);
@@ -913,11 +1107,14 @@ impl Canvas for ForwardRenderer
 				self.set_render_uniforms(scene.lights(), &camera, &shape, &material);
 				self.set_render_state(&material);
 
-				let buffer = self.shapes.get(geometry.uuid()).unwrap();
-				self.set_draw_buffer(&buffer.vertex_buffer, &buffer.index_buffer);
-				self.set_input_layout(material.input_layout());
+				for buffer in self.shapes[geometry.uuid()][material.input_layout()].iter()
+				{
+					self.set_draw_buffer(&buffer.vertex_buffer, &buffer.index_buffer);
+					self.set_input_layout(material.input_layout());
+					self.set_joint_uniforms(&shape, &material, buffer);
 
-				self.context.draw_elements(gl::TRIANGLES, buffer.count, gl::UNSIGNED_SHORT, 0);
+					self.context.draw_elements(gl::TRIANGLES, buffer.count, buffer.format, 0);
+				}
 			}
 		}
 	}
@@ -61,6 +61,11 @@ pub static SHADER_ATTRIB_TEXCOORD4:&'static str = r#"attribute vec3 coord4;"#;
 pub static SHADER_ATTRIB_TEXCOORD5:&'static str = r#"attribute vec3 coord5;"#;
 pub static SHADER_ATTRIB_TEXCOORD6:&'static str = r#"attribute vec3 coord6;"#;
 pub static SHADER_ATTRIB_TEXCOORD7:&'static str = r#"attribute vec3 coord7;"#;
+pub static SHADER_ATTRIB_SKINNING:&'static str = r#"
+	#define SHADER_ATTRIB_SKINNING 1
+	attribute vec4 weight;
+	attribute vec4 bone;
+"#;
 
 pub static SHADER_CODE_BEGIN:&'static str = r#"
 	#define float2 vec2
@@ -135,6 +140,10 @@ pub static SHADER_CODE_END:&'static str = r#"
 	#ifdef SHADER_VARYING_TEXCOORD7
 		float3 coord7;
 	#endif
+	#ifdef SHADER_ATTRIB_SKINNING
+		float4 weight;
+		float4 bone;
+	#endif
 	};
 
 	struct Result
@@ -239,6 +248,10 @@ pub static VERT_CODE_BODY:&'static str = r#"
 	#ifdef SHADER_VARYING_TEXCOORD7
 		args.coord7 = coord7;
 	#endif
+	#ifdef SHADER_ATTRIB_SKINNING
+		args.weight = weight;
+		args.bone = bone;
+	#endif
 
 		Result result = main_vs(args);
 
@@ -1,4 +1,4 @@
-use super::super::super::webgl::{ WebGLTexture, WebGLBuffer, WebGLProgram, WebGLUniformLocation };
+use super::super::super::webgl::{ GLenum, WebGLTexture, WebGLBuffer, WebGLProgram, WebGLUniformLocation };
 
 pub struct LightUniform
 {
@@ -18,6 +18,7 @@ pub struct MaterialUniform
 	pub viewproject: Option<WebGLUniformLocation>,
 	pub eye_position: Option<WebGLUniformLocation>,
 	pub exposure:  Option<WebGLUniformLocation>,
+	pub bindpose: Option<WebGLUniformLocation>,
 	pub lights:Vec<LightUniform>,
 	pub locations: Vec<Option<WebGLUniformLocation>>,
 }
@@ -32,6 +33,10 @@ pub struct GeometryUniform
 	pub vertex_buffer: WebGLBuffer,
 	pub index_buffer: WebGLBuffer,
 	pub count:i32,
+	pub format:GLenum,
+	pub joints:Vec<u16>,
+	pub vertices:Vec<u32>,
+	pub weights:Vec<f32>,
 }
 
 impl TextureUniform
@@ -47,13 +52,26 @@ impl TextureUniform
 
 impl GeometryUniform
 {
-	pub fn new(vertex_buffer:WebGLBuffer, index_buffer:WebGLBuffer, count:i32) -> Self
+	/// `joints` are the bones of a skinned batch in the order of its palette, empty for other shapes.
+	pub fn new(vertex_buffer:WebGLBuffer, index_buffer:WebGLBuffer, count:i32, format:GLenum, joints:Vec<u16>) -> Self
 	{
 		Self
 		{
 			vertex_buffer: vertex_buffer,
 			index_buffer: index_buffer,
-			count:count
+			count:count,
+			format:format,
+			joints:joints,
+			vertices:Vec::new(),
+			weights:Vec::new(),
 		}
 	}
+
+	/// `vertices` are the vertices of the geometry the buffer holds, packed again with `weights` when they change.
+	pub fn with_vertices(mut self, vertices:Vec<u32>, weights:Vec<f32>) -> Self
+	{
+		self.vertices = vertices;
+		self.weights = weights;
+		self
+	}
 }
\ No newline at end of file
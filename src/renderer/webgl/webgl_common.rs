@@ -21,6 +21,12 @@ pub type Int32List = Vec<i32>;
 pub type Uint32List = Vec<u32>;
 pub type Float32List = Vec<f32>;
 
+/// The number of size x size matrices in a flat array of floats, what glUniformMatrix*fv takes as count.
+pub fn matrix_count(value:&[f32], size:usize) -> GLsizei
+{
+    (value.len() / (size * size)) as GLsizei
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct GLContext{ pub reference: Reference }
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -819,7 +819,7 @@ impl GLContext
     {
         unsafe
         {
-            gl::UniformMatrix4fv(*location.unwrap().deref() as i32, 1, transpose as _, value.as_ptr());
+            gl::UniformMatrix4fv(*location.unwrap().deref() as i32, matrix_count(value, 4), transpose as _, value.as_ptr());
         }
         check_gl_error("uniform_matrix_4fv");
     }
@@ -837,7 +837,7 @@ impl GLContext
     {
         unsafe
         {
-            gl::UniformMatrix3fv(*location.unwrap().deref() as i32, 1, transpose as _, value.as_ptr());
+            gl::UniformMatrix3fv(*location.unwrap().deref() as i32, matrix_count(value, 3), transpose as _, value.as_ptr());
         }
         check_gl_error("uniform_matrix_3fv");
     }
@@ -855,7 +855,7 @@ impl GLContext
     {
         unsafe
         {
-            gl::UniformMatrix2fv(*location.unwrap().deref() as i32, 1, transpose as _, value.as_ptr());
+            gl::UniformMatrix2fv(*location.unwrap().deref() as i32, matrix_count(value, 2), transpose as _, value.as_ptr());
         }
         check_gl_error("uniform_matrix_2fv");
     }
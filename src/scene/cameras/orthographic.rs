@@ -7,7 +7,7 @@ use std::any::Any;
 use crate::math::*;
 
 use super::super::scene::{ SceneNode, SceneSubData };
-use super::super::core::{Object, Downcast, Resource, Camera, CameraData, CameraType, Canvas, Dimensions};
+use super::super::core::{Object, Downcast, Resource, Camera, CameraData, CameraType, Projection, Canvas, Dimensions};
 use super::super::materials::{ CustomMaterial };
 
 #[derive(Debug)]
@@ -44,9 +44,12 @@ impl OrthographicCamera
 		material.state.clear_color = Some((0.0, 0.0, 0.0, 0.0));
 		material.state.viewport = Some((0.0, 0.0, 1.0, 1.0));
 
+		let mut data = CameraData::new();
+		data.projection_type = Projection::Orthographic;
+
 		let mut node = SceneNode::new(SceneSubData::Camera);
 		node.set_material(Some(material.into()));
-		node.set_user_data(Box::new(CameraData::new()));
+		node.set_user_data(Box::new(data));
 
 		Self
 		{
@@ -199,18 +202,20 @@ impl OrthographicCamera
 			self.node.update(canvas);
 			self.projection = float4x4::ortho_lh(left, right, top, bottom, znear, zfar);
 			self.projection_inverse = self.projection.inverse();
-			self.view_projection = self.projection * self.node.transform_inverse();
+			self.view_projection = self.projection * self.node.world_transform_inverse();
 			self.view_projection_inverse = self.view_projection.inverse();
 
 			let userdata = CameraData
 			{
 				kind:self.kind(),
-				view:self.transform_inverse(),
-				view_inverse:self.transform(),
+				projection_type:Projection::Orthographic,
+				view:self.world_transform_inverse(),
+				view_inverse:self.world_transform(),
 				projection:self.projection,
 				projection_inverse:self.projection_inverse,
 				view_projection:self.view_projection,
 				view_projection_inverse:self.view_projection_inverse,
+				..CameraData::new()
 			};
 
 			self.node.set_user_data(Box::new(userdata));
@@ -279,13 +284,13 @@ impl Camera for OrthographicCamera
 	#[inline]
 	fn view(&self) -> float4x4
 	{
-		self.node.transform_inverse()
+		self.node.world_transform_inverse()
 	}
 
 	#[inline]
 	fn view_inverse(&self) -> float4x4
 	{
-		self.node.transform()
+		self.node.world_transform()
 	}
 
 	#[inline]
@@ -7,7 +7,7 @@ use std::any::Any;
 use crate::math::*;
 
 use super::super::scene::{ SceneData, SceneNode, SceneSubData };
-use super::super::core::{Object, Downcast, Resource, Camera, CameraType, CameraData, Canvas, Dimensions};
+use super::super::core::{Object, Downcast, Resource, Camera, CameraType, CameraData, Projection, Canvas, Dimensions};
 use super::super::materials::{ CustomMaterial };
 
 #[derive(Debug)]
@@ -45,25 +45,27 @@ impl PerspectiveCamera
 
 	    fn update(node:&mut SceneData, canvas:&Canvas)
 	    {
-			let fov = 60.0;
+			let fov = node.user_data.downcast_ref::<CameraData>().map(|data| data.fov).unwrap_or(60.0);
 			let ratio = canvas.width() as f32 / canvas.height() as f32;
 			let znear = 0.001;
 			let zfar = 65535.0;
 
 			let projection = float4x4::perspective_fov_lh(fov, ratio, znear, zfar);
 			let projection_inverse = projection.inverse();
-			let view_projection = projection * node.transform_inverse();
+			let view_projection = projection * node.world_transform_inverse();
 			let view_projection_inverse = view_projection.inverse();
 
 			let userdata = CameraData
 			{
 				kind:CameraType::Main,
-				view:node.transform_inverse(),
-				view_inverse:node.transform(),
+				projection_type:Projection::Perspective,
+				view:node.world_transform_inverse(),
+				view_inverse:node.world_transform(),
 				projection:projection,
 				projection_inverse:projection_inverse,
 				view_projection:view_projection,
 				view_projection_inverse:view_projection_inverse,
+				fov:fov,
 			};
 
 			node.set_user_data(Box::new(userdata));
@@ -193,6 +195,13 @@ impl PerspectiveCamera
 		let length = self.film_size / ratio;
 		self.focal_length = length;
 		self.need_update = true;
+
+		match self.node.data.borrow_mut().user_data.downcast_mut::<CameraData>()
+		{
+			Some(data) => { data.fov = fov; },
+			None => {},
+		}
+
 		self
 	}
 
@@ -238,18 +247,20 @@ impl PerspectiveCamera
 			self.node.update(canvas);
 			self.projection = float4x4::perspective_fov_lh(fov, ratio, znear, zfar);
 			self.projection_inverse = self.projection.inverse();
-			self.view_projection = self.projection * self.node.transform_inverse();
+			self.view_projection = self.projection * self.node.world_transform_inverse();
 			self.view_projection_inverse = self.view_projection.inverse();
 
 			let userdata = CameraData
 			{
 				kind:self.kind(),
-				view:self.transform_inverse(),
-				view_inverse:self.transform(),
+				projection_type:Projection::Perspective,
+				view:self.world_transform_inverse(),
+				view_inverse:self.world_transform(),
 				projection:self.projection,
 				projection_inverse:self.projection_inverse,
 				view_projection:self.view_projection,
 				view_projection_inverse:self.view_projection_inverse,
+				fov:fov,
 			};
 
 			self.node.set_user_data(Box::new(userdata));
@@ -318,13 +329,13 @@ impl Camera for PerspectiveCamera
 	#[inline]
 	fn view(&self) -> float4x4
 	{
-		self.node.transform_inverse()
+		self.node.world_transform_inverse()
 	}
 
 	#[inline]
 	fn view_inverse(&self) -> float4x4
 	{
-		self.node.transform()
+		self.node.world_transform()
 	}
 
 	#[inline]
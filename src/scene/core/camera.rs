@@ -35,16 +35,41 @@ impl CameraType
 	}
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, PartialEq, Clone, Serialize, Deserialize)]
+pub enum Projection
+{
+	Perspective,
+	Orthographic,
+}
+
+impl Default for Projection
+{
+	fn default() -> Self
+	{
+		Projection::Perspective
+	}
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
 pub struct CameraData
 {
 	pub kind:CameraType,
+	#[serde(default)]
+	pub projection_type:Projection,
 	pub view:float4x4,
 	pub view_inverse:float4x4,
 	pub projection:float4x4,
 	pub projection_inverse:float4x4,
 	pub view_projection:float4x4,
 	pub view_projection_inverse:float4x4,
+	/// Vertical field of view in degrees, used by perspective cameras.
+	#[serde(default = "default_fov")]
+	pub fov:f32,
+}
+
+fn default_fov() -> f32
+{
+	60.0
 }
 
 impl CameraData
@@ -54,12 +79,14 @@ impl CameraData
 		Self
 		{
 			kind:CameraType::Custom,
+			projection_type:Projection::Perspective,
 			view:float4x4::one(),
 			view_inverse:float4x4::one(),
 			projection:float4x4::one(),
 			projection_inverse:float4x4::one(),
 			view_projection:float4x4::one(),
 			view_projection_inverse:float4x4::one(),
+			fov:default_fov(),
 		}
 	}
 }
@@ -1,12 +1,15 @@
 use std::fmt;
 use std::io;
 use crate::models;
+use crate::animation;
 
 pub enum Error {
     IoError(io::Error),
     ImageError(image::ImageError),
     ModelError(models::Error),
-    LoaderError(String)
+    AnimationError(animation::Error),
+    LoaderError(String),
+    BindingError(String)
 }
 
 impl fmt::Display for Error {
@@ -15,7 +18,9 @@ impl fmt::Display for Error {
             Error::IoError(ref err) => write!(f, "IoError {{ {} }}", err),
             Error::ImageError(ref err) => write!(f, "ImageError {{ {} }}", err),
             Error::ModelError(ref err) => write!(f, "ModelError {{ {} }}", err),
+            Error::AnimationError(ref err) => write!(f, "AnimationError {{ {} }}", err),
             Error::LoaderError(ref err) => write!(f, "LoaderError {{ {} }}", err),
+            Error::BindingError(ref err) => write!(f, "BindingError {{ {} }}", err),
         }
     }
 }
@@ -32,7 +37,9 @@ impl std::error::Error for Error {
             Error::IoError(_) => "I/O Error",
             Error::ImageError(_) => "Image Error",
             Error::ModelError(_) => "Model Error",
+            Error::AnimationError(_) => "Animation Error",
             Error::LoaderError(_) => "Loader Error",
+            Error::BindingError(_) => "Binding Error",
         }
     }
 
@@ -60,6 +67,14 @@ impl From<models::Error> for Error
     }
 }
 
+impl From<animation::Error> for Error
+{
+    fn from(err: animation::Error) -> Self
+    {
+        Error::AnimationError(err)
+    }
+}
+
 impl From<String> for Error
 {
     fn from(err: String) -> Self
@@ -68,6 +83,22 @@ impl From<String> for Error
     }
 }
 
+impl From<serde_json::Error> for Error
+{
+    fn from(err: serde_json::Error) -> Self
+    {
+        Error::LoaderError(format!("Failed to parse the json: {}", err))
+    }
+}
+
+impl From<base64::DecodeError> for Error
+{
+    fn from(err: base64::DecodeError) -> Self
+    {
+        Error::LoaderError(format!("Failed to decode the base64: {}", err))
+    }
+}
+
 impl From<std::string::FromUtf8Error> for Error
 {
     fn from(_err: std::string::FromUtf8Error) -> Error
@@ -1,16 +1,281 @@
 use std::fmt::Debug;
+use std::collections::HashMap;
 use serde::ser::{Serialize, Serializer, SerializeStruct};
 use base64;
-use crate::math::{float2, float3};
-use crate::models::{VertexWeight};
+use byteorder::{NativeEndian, WriteBytesExt};
+use crate::math::{float2, float3, float2s, float3s};
+use crate::models::{self, VertexWeight, MorphTarget};
 use super::{Resource};
 
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum IndexFormat
+{
+	U16,
+	U32,
+}
+
+/// One draw of a skinned geometry, for targets whose uniforms can't hold the palette of every bone.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JointBatch
+{
+	/// The bones the draw reaches, its palette is uploaded in this order.
+	pub joints:Vec<u16>,
+	pub vertices:Vec<u32>,
+	pub indices:Vec<u16>,
+}
+
+impl JointBatch
+{
+	/// The bones of a vertex as indices into `joints`, bones without weight read the first joint.
+	pub fn local_bones(&self, weight:&VertexWeight) -> [u16;4]
+	{
+		local_bones(&self.joints, weight)
+	}
+}
+
+/// The bones of a vertex as indices into the palette of a batch reaching `joints`, bones without weight read the first joint.
+pub fn local_bones(joints:&[u16], weight:&VertexWeight) -> [u16;4]
+{
+	let mut bones = [0;4];
+	for i in 0..4
+	{
+		if weight.weight[i] > 0.0
+		{
+			bones[i] = joints.iter().position(|&joint| joint == weight.bone[i]).unwrap_or(0) as u16;
+		}
+	}
+
+	bones
+}
+
+/// Index buffer of a geometry, 32 bit indices are only kept when a vertex can't be reached with 16 bits.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Indices
+{
+	U16(Vec<u16>),
+	U32(Vec<u32>),
+}
+
+impl Indices
+{
+	/// Picks the narrowest format which reaches every vertex.
+	pub fn new(indices:Vec<u32>) -> Self
+	{
+		if indices.iter().all(|&index| index <= std::u16::MAX as u32)
+		{
+			Indices::U16(indices.into_iter().map(|index| index as u16).collect())
+		}
+		else
+		{
+			Indices::U32(indices)
+		}
+	}
+
+	pub fn format(&self) -> IndexFormat
+	{
+		match self
+		{
+			Indices::U16(_) => IndexFormat::U16,
+			Indices::U32(_) => IndexFormat::U32,
+		}
+	}
+
+	/// Bytes per index.
+	pub fn stride(&self) -> usize
+	{
+		match self
+		{
+			Indices::U16(_) => std::mem::size_of::<u16>(),
+			Indices::U32(_) => std::mem::size_of::<u32>(),
+		}
+	}
+
+	pub fn len(&self) -> usize
+	{
+		match self
+		{
+			Indices::U16(indices) => indices.len(),
+			Indices::U32(indices) => indices.len(),
+		}
+	}
+
+	pub fn is_empty(&self) -> bool
+	{
+		self.len() == 0
+	}
+
+	pub fn get(&self, n:usize) -> Option<u32>
+	{
+		match self
+		{
+			Indices::U16(indices) => indices.get(n).map(|&index| index as u32),
+			Indices::U32(indices) => indices.get(n).cloned(),
+		}
+	}
+
+	pub fn to_vec(&self) -> Vec<u32>
+	{
+		match self
+		{
+			Indices::U16(indices) => indices.iter().map(|&index| index as u32).collect(),
+			Indices::U32(indices) => indices.clone(),
+		}
+	}
+
+	/// Appends an index, widening the buffer to 32 bits when it doesn't fit.
+	pub fn push(&mut self, index:u32)
+	{
+		match self
+		{
+			Indices::U16(indices) if index <= std::u16::MAX as u32 => indices.push(index as u16),
+			Indices::U16(indices) =>
+			{
+				let mut wide:Vec<u32> = indices.iter().map(|&index| index as u32).collect();
+				wide.push(index);
+				*self = Indices::U32(wide);
+			},
+			Indices::U32(indices) => indices.push(index),
+		}
+	}
+
+	pub fn as_bytes(&self) -> &[u8]
+	{
+		unsafe
+		{
+			match self
+			{
+				Indices::U16(indices) => std::slice::from_raw_parts(indices.as_ptr() as *const u8, indices.len() * std::mem::size_of::<u16>()),
+				Indices::U32(indices) => std::slice::from_raw_parts(indices.as_ptr() as *const u8, indices.len() * std::mem::size_of::<u32>()),
+			}
+		}
+	}
+
+	/// Splits the triangles into 16 bit chunks of at most `max_vertices` vertices, for targets without 32 bit indices.
+	/// Each chunk lists the vertices it uses and its triangles indexing into that list.
+	pub fn split(&self, max_vertices:usize) -> Vec<(Vec<u32>, Vec<u16>)>
+	{
+		let max_vertices = max_vertices.max(3).min(std::u16::MAX as usize + 1);
+		let indices = self.to_vec();
+
+		let mut chunks = Vec::new();
+		let mut vertices = Vec::new();
+		let mut triangles = Vec::new();
+		let mut remap:HashMap<u32, u16> = HashMap::new();
+
+		for triangle in indices.chunks(3)
+		{
+			let added = triangle.iter().enumerate().filter(|&(i, index)| !remap.contains_key(index) && !triangle[..i].contains(index)).count();
+			if vertices.len() + added > max_vertices
+			{
+				chunks.push((std::mem::replace(&mut vertices, Vec::new()), std::mem::replace(&mut triangles, Vec::new())));
+				remap.clear();
+			}
+
+			for &index in triangle
+			{
+				let local = *remap.entry(index).or_insert_with(||
+				{
+					vertices.push(index);
+					(vertices.len() - 1) as u16
+				});
+
+				triangles.push(local);
+			}
+		}
+
+		if triangles.len() > 0
+		{
+			chunks.push((vertices, triangles));
+		}
+
+		chunks
+	}
+
+	/// Splits the triangles of a skinned geometry into 16 bit draws which reach at most `max_joints` bones
+	/// and `max_vertices` vertices. A triangle reaches up to 12 bones, so fewer than that aren't honoured.
+	pub fn split_joints(&self, weights:&[VertexWeight], max_joints:usize, max_vertices:usize) -> Vec<JointBatch>
+	{
+		let max_joints = max_joints.max(12);
+		let max_vertices = max_vertices.max(3).min(std::u16::MAX as usize + 1);
+		let indices = self.to_vec();
+
+		// Vertices without weights follow the first bone, as the vertex buffer writes them.
+		let bones = |index:u32| -> Vec<u16>
+		{
+			match weights.get(index as usize)
+			{
+				Some(weight) => (0..4).filter(|&i| weight.weight[i] > 0.0).map(|i| weight.bone[i]).collect(),
+				None => vec![0],
+			}
+		};
+
+		let mut batches = Vec::new();
+		let mut batch = JointBatch { joints:Vec::new(), vertices:Vec::new(), indices:Vec::new() };
+		let mut remap:HashMap<u32, u16> = HashMap::new();
+
+		for triangle in indices.chunks(3)
+		{
+			let mut joints:Vec<u16> = triangle.iter().flat_map(|&index| bones(index)).filter(|bone| !batch.joints.contains(bone)).collect();
+			joints.sort();
+			joints.dedup();
+
+			let added = triangle.iter().enumerate().filter(|&(i, index)| !remap.contains_key(index) && !triangle[..i].contains(index)).count();
+			if batch.joints.len() + joints.len() > max_joints || batch.vertices.len() + added > max_vertices
+			{
+				batches.push(std::mem::replace(&mut batch, JointBatch { joints:Vec::new(), vertices:Vec::new(), indices:Vec::new() }));
+				remap.clear();
+
+				joints = triangle.iter().flat_map(|&index| bones(index)).collect();
+				joints.sort();
+				joints.dedup();
+			}
+
+			batch.joints.extend(joints);
+
+			for &index in triangle
+			{
+				let vertices = &mut batch.vertices;
+				let local = *remap.entry(index).or_insert_with(||
+				{
+					vertices.push(index);
+					(vertices.len() - 1) as u16
+				});
+
+				batch.indices.push(local);
+			}
+		}
+
+		if batch.indices.len() > 0
+		{
+			batches.push(batch);
+		}
+
+		batches
+	}
+}
+
+impl From<Vec<u16>> for Indices
+{
+	fn from(indices:Vec<u16>) -> Self
+	{
+		Indices::U16(indices)
+	}
+}
+
+impl From<Vec<u32>> for Indices
+{
+	fn from(indices:Vec<u32>) -> Self
+	{
+		Indices::new(indices)
+	}
+}
+
 pub trait Geometry : Debug + Resource
 {
 	fn vertices(&self) -> &[float3];
 	fn normals(&self) -> &[float3];
 	fn texcoords(&self) -> &[float2];
-	fn indices(&self) -> &[u16];
+	fn indices(&self) -> &Indices;
 	fn weights(&self) -> &[VertexWeight];
 
 	#[inline(always)]
@@ -27,13 +292,90 @@ pub trait Geometry : Debug + Resource
 
 	#[inline(always)]
 	fn num_weights(&self) -> usize { self.weights().len() }
+
+	fn morph_targets(&self) -> &[MorphTarget] { &[] }
+	fn morph_weights(&self) -> Vec<f32> { vec![0.0; self.morph_targets().len()] }
+	fn set_morph_weight(&self, _name:&str, _weight:f32) -> bool { false }
+
+	#[inline(always)]
+	fn num_morph_targets(&self) -> usize { self.morph_targets().len() }
+
+	fn morph_weight(&self, name:&str) -> Option<f32>
+	{
+		let index = self.morph_targets().iter().position(|target| target.name == name)?;
+		self.morph_weights().get(index).cloned()
+	}
+
+	fn morph_vertices(&self) -> float3s
+	{
+		let mut vertices = self.vertices().to_vec();
+
+		for (target, weight) in self.morph_targets().iter().zip(self.morph_weights())
+		{
+			if weight == 0.0 { continue; }
+
+			for it in target.positions.iter()
+			{
+				if let Some(v) = vertices.get_mut(it.index as usize)
+				{
+					v.x += it.offset.0 * weight;
+					v.y += it.offset.1 * weight;
+					v.z += it.offset.2 * weight;
+				}
+			}
+		}
+
+		vertices
+	}
+
+	fn morph_texcoords(&self) -> float2s
+	{
+		let mut texcoords = self.texcoords().to_vec();
+
+		for (target, weight) in self.morph_targets().iter().zip(self.morph_weights())
+		{
+			if weight == 0.0 { continue; }
+
+			for it in target.texcoords.iter()
+			{
+				if let Some(uv) = texcoords.get_mut(it.index as usize)
+				{
+					uv.x += it.offset.0 * weight;
+					uv.y += it.offset.1 * weight;
+				}
+			}
+		}
+
+		texcoords
+	}
+
+	/// The material of the mesh with the weighted material morphs applied, multiplied deltas go before added ones.
+	fn morph_material(&self, material:&models::Material) -> models::Material
+	{
+		let mut material = material.clone();
+
+		for operation in 0..2
+		{
+			for (target, weight) in self.morph_targets().iter().zip(self.morph_weights())
+			{
+				if weight == 0.0 { continue; }
+
+				for it in target.materials.iter().filter(|it| it.operation == operation)
+				{
+					it.apply(&mut material, weight);
+				}
+			}
+		}
+
+		material
+	}
 }
 
 impl Serialize for Geometry
 {
 	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer
 	{
-		let mut s = serializer.serialize_struct("mesh", 4)?;
+		let mut s = serializer.serialize_struct("mesh", 6)?;
 		s.serialize_field("uuid", &self.uuid())?;
 
 		unsafe
@@ -41,12 +383,28 @@ impl Serialize for Geometry
 			let v = std::slice::from_raw_parts(self.vertices().as_ptr() as *const u8, self.vertices().len() * std::mem::size_of::<float3>());
 			let n = std::slice::from_raw_parts(self.normals().as_ptr() as *const u8, self.normals().len() * std::mem::size_of::<float3>());
 			let uv = std::slice::from_raw_parts(self.texcoords().as_ptr() as *const u8, self.texcoords().len() * std::mem::size_of::<float2>());
-			let i = std::slice::from_raw_parts(self.indices().as_ptr() as *const u8, self.indices().len() * std::mem::size_of::<u16>());
 
 			s.serialize_field("v", &base64::encode(v))?;
 			s.serialize_field("n", &base64::encode(n))?;
 			s.serialize_field("uv", &base64::encode(uv))?;
-			s.serialize_field("i", &base64::encode(i))?;
+			s.serialize_field("i", &base64::encode(self.indices().as_bytes()))?;
+		}
+
+		if self.indices().format() == IndexFormat::U32
+		{
+			s.serialize_field("format", &IndexFormat::U32)?;
+		}
+
+		if self.num_weights() > 0
+		{
+			let mut w = Vec::with_capacity(self.num_weights() * 24);
+			for weight in self.weights()
+			{
+				for bone in weight.bone.iter() { w.write_u16::<NativeEndian>(*bone).unwrap(); }
+				for value in weight.weight.iter() { w.write_f32::<NativeEndian>(*value).unwrap(); }
+			}
+
+			s.serialize_field("w", &base64::encode(&w))?;
 		}
 
 		s.end()
@@ -12,7 +12,7 @@ pub enum LightType
 	Spot,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct LightData
 {
 	pub kind:LightType,
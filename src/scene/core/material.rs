@@ -76,7 +76,7 @@ pub enum PolygonMode
 	Solid,
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct VertexAttrib
 {
 	pub index:u8,
@@ -103,6 +103,15 @@ impl VertexAttrib
 	}
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum MaterialType
+{
+	Standard,
+	Skinning(u16),
+	Skybox,
+	Custom,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RenderState
 {
@@ -162,6 +171,8 @@ pub trait Material :
 	+ AsMut<RenderState> 
 	+ Resource
 {
+	fn kind(&self) -> MaterialType { MaterialType::Custom }
+
 	fn input_layout(&self) -> &[VertexAttrib];
 	fn uniforms(&self) -> &HashMap<String, Variant>;
 
@@ -227,7 +238,9 @@ impl Serialize for Material
 {
 	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer
 	{
-		let mut s = serializer.serialize_struct("material", 4)?;
+		let mut s = serializer.serialize_struct("material", 5)?;
+		s.serialize_field("uuid", &self.uuid())?;
+		s.serialize_field("type", &self.kind())?;
 		s.serialize_field("attrib", &self.input_layout())?;
 		s.serialize_field("parameters", &self.uniforms())?;
 		s.serialize_field("state", &self.as_ref())?;
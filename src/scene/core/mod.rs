@@ -15,6 +15,7 @@ pub mod format;
 pub mod canvas;
 pub mod error;
 pub mod loader;
+pub mod resolver;
 pub mod log;
 
 pub use self::resource::*;
@@ -33,4 +34,5 @@ pub use self::variant::*;
 pub use self::format::*;
 pub use self::canvas::*;
 pub use self::error::*;
-pub use self::loader::*;
\ No newline at end of file
+pub use self::loader::*;
+pub use self::resolver::*;
\ No newline at end of file
@@ -1,5 +1,5 @@
 use std::fmt::Debug;
-use crate::math::{float3, float4x4};
+use crate::math::{float3, float4x4, Quaternion};
 use super::{Resource, Downcast, Canvas};
 use super::super::scene::{SceneNode, SceneData};
 
@@ -70,6 +70,24 @@ pub trait Object :
 		self.as_mut().set_rotation(rot)
 	}
 
+	#[inline(always)]
+	fn quaternion(&self) -> Quaternion
+	{
+		self.as_ref().quaternion()
+	}
+
+	#[inline(always)]
+	fn set_quaternion(&mut self, rot:Quaternion) -> &mut SceneNode
+	{
+		self.as_mut().set_quaternion(rot)
+	}
+
+	#[inline(always)]
+	fn look_at(&mut self, target:float3, up:float3) -> &mut SceneNode
+	{
+		self.as_mut().look_at(target, up)
+	}
+
 	#[inline(always)]
 	fn transform(&self) -> float4x4
 	{
@@ -81,6 +99,18 @@ pub trait Object :
 	{
 		self.as_ref().transform_inverse()
 	}
+
+	#[inline(always)]
+	fn world_transform(&self) -> float4x4
+	{
+		self.as_ref().world_transform()
+	}
+
+	#[inline(always)]
+	fn world_transform_inverse(&self) -> float4x4
+	{
+		self.as_ref().world_transform_inverse()
+	}
 	
 	#[inline(always)]
 	fn up(&self) -> float3
@@ -0,0 +1,122 @@
+use std::io;
+use std::fmt::Debug;
+use std::collections::HashMap;
+use super::super::Result;
+
+/// Finds the files a scene or model refers to, the paths inside are often written on another machine.
+pub trait Resolver : Debug
+{
+	/// Maps a path as written in a file to a path `read` accepts, None when there's nothing to read.
+	fn resolve(&self, path:&str) -> Option<String>;
+
+	/// Reads a path given by `resolve`.
+	fn read(&self, path:&str) -> Result<Vec<u8>>;
+
+	fn open(&self, path:&str) -> Result<Vec<u8>>
+	{
+		match self.resolve(path)
+		{
+			Some(path) => self.read(&path),
+			None => Err(io::Error::new(io::ErrorKind::NotFound, format!("{} not found", path)).into()),
+		}
+	}
+}
+
+/// Turns Windows paths into "/" separated ones, drops the drive letter and folds "." and "..".
+pub fn normalize_path(path:&str) -> String
+{
+	let path = path.trim().replace('\\', "/");
+	let bytes = path.as_bytes();
+
+	let (root, path) = if bytes.len() >= 2 && bytes[1] == b':' && (bytes[0] as char).is_ascii_alphabetic()
+	{
+		(true, &path[2..])
+	}
+	else
+	{
+		(path.starts_with('/'), &path[..])
+	};
+
+	let mut components:Vec<&str> = Vec::new();
+	for component in path.split('/')
+	{
+		match component
+		{
+			"" | "." => {},
+			".." if components.last().filter(|last| **last != "..").is_some() => { components.pop(); },
+			".." if root => {},
+			_ => components.push(component),
+		}
+	}
+
+	let path = components.join("/");
+	if root { format!("/{}", path) } else { path }
+}
+
+/// The directory part of a path, empty for a bare name.
+pub fn parent_path(path:&str) -> &str
+{
+	match path.rfind(|c| c == '/' || c == '\\')
+	{
+		Some(0) => &path[..1],
+		Some(n) => &path[..n],
+		None => "",
+	}
+}
+
+/// Joins a path found inside a file to the directory of that file, resolvers normalize the result.
+pub fn join_path(dir:&str, path:&str) -> String
+{
+	let absolute = path.starts_with('/') || path.starts_with('\\') || path.as_bytes().get(1) == Some(&b':');
+	if dir.is_empty() || absolute
+	{
+		path.to_string()
+	}
+	else
+	{
+		format!("{}/{}", dir.trim_end_matches(|c| c == '/' || c == '\\'), path)
+	}
+}
+
+/// Case insensitive lookup in a set of normalized paths, used by the resolvers which keep their files in memory.
+#[derive(Debug, Clone, Default)]
+pub struct PathIndex
+{
+	paths:HashMap<String, String>,
+}
+
+impl PathIndex
+{
+	pub fn new() -> Self
+	{
+		Self
+		{
+			paths:HashMap::new()
+		}
+	}
+
+	pub fn insert(&mut self, path:&str)
+	{
+		let path = normalize_path(path);
+		self.paths.insert(path.trim_start_matches('/').to_lowercase(), path);
+	}
+
+	/// The full path first, then shorter and shorter tails of it, so a project written for "C:\MMD\Model\a.pmx" finds "Model/a.pmx".
+	pub fn find(&self, path:&str) -> Option<String>
+	{
+		let path = normalize_path(path).to_lowercase();
+		let components:Vec<&str> = path.split('/').filter(|component| !component.is_empty()).collect();
+
+		(0..components.len())
+			.filter_map(|n| self.paths.get(&components[n..].join("/")))
+			.next()
+			.cloned()
+	}
+
+	pub fn paths(&self) -> Vec<String>
+	{
+		let mut paths:Vec<String> = self.paths.values().cloned().collect();
+		paths.sort();
+		paths
+	}
+}
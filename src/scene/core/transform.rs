@@ -1,13 +1,13 @@
 use std::cell::RefCell;
 use serde::ser::{Serialize, SerializeStruct, Serializer};
-use crate::math::{float3, Quaternion, float4x4, One, Zero};
+use crate::math::{float3, Quaternion, RotationOrder, float4x4, One, Zero};
 
 #[derive(Debug, Default, Clone)]
 pub struct Transform 
 {
 	translate:float3,
 	scale:float3,
-	rotation:float3,
+	rotation:Quaternion,
 	transform:RefCell<float4x4>,
 	transform_inverse:RefCell<float4x4>,
 	need_update:RefCell<bool>
@@ -21,7 +21,7 @@ impl Transform
 		{
 			translate:float3::zero(),
 			scale:float3::one(),
-			rotation:float3::zero(),
+			rotation:Quaternion::new(0.0, 0.0, 0.0, 1.0),
 			transform:RefCell::new(float4x4::one()),
 			transform_inverse:RefCell::new(float4x4::one()),
 			need_update:RefCell::new(true)
@@ -40,12 +40,25 @@ impl Transform
 		return self.scale;
 	}
 
+	/// Euler angles in XYZ order, see `euler` for the other orders.
 	#[inline(always)]
 	pub fn rotation(&self) -> float3
+	{
+		return self.euler(RotationOrder::XYZ);
+	}
+
+	#[inline(always)]
+	pub fn quaternion(&self) -> Quaternion
 	{
 		return self.rotation;
 	}
 
+	#[inline(always)]
+	pub fn euler(&self, order:RotationOrder) -> float3
+	{
+		return self.rotation.to_euler(order);
+	}
+
 	#[inline(always)]
 	pub fn transform(&self) -> float4x4
 	{
@@ -82,6 +95,12 @@ impl Transform
 
 	#[inline]
 	pub fn set_rotation(&mut self, rot:float3)
+	{
+		self.set_euler(rot, RotationOrder::XYZ);
+	}
+
+	#[inline]
+	pub fn set_quaternion(&mut self, rot:Quaternion)
 	{
 		if self.rotation != rot
 		{
@@ -90,13 +109,56 @@ impl Transform
 		}
 	}
 
+	#[inline]
+	pub fn set_euler(&mut self, euler:float3, order:RotationOrder)
+	{
+		self.set_quaternion(Quaternion::euler(&euler, order));
+	}
+
+	/// Replaces translate, rotation and scale with the decomposition of an affine matrix.
+	pub fn set_matrix(&mut self, m:&float4x4)
+	{
+		let (translate, rotation, scale) = m.decompose();
+		self.set_translate(translate);
+		self.set_quaternion(rotation);
+		self.set_scale(scale);
+	}
+
+	/// Turns the +Z axis towards a target, keeping +Y as close to `up` as possible.
+	pub fn look_at(&mut self, target:float3, up:float3)
+	{
+		let forward = target - self.translate;
+		if forward.length2() == 0.0
+		{
+			return;
+		}
+
+		let z = forward.normalize();
+		let mut x = up.cross(z);
+		if x.length2() < 1e-12
+		{
+			x = if z.y.abs() < 0.999 { float3::new(0.0, 1.0, 0.0).cross(z) } else { float3::new(1.0, 0.0, 0.0).cross(z) };
+		}
+
+		let x = x.normalize();
+		let y = z.cross(x);
+
+		let basis = float4x4::new(
+			x.x, x.y, x.z, 0.0,
+			y.x, y.y, y.z, 0.0,
+			z.x, z.y, z.z, 0.0,
+			0.0, 0.0, 0.0, 1.0);
+
+		self.set_quaternion(Quaternion::from(basis).normalize());
+	}
+
 	#[inline]
 	fn update(&self)
 	{
 		if *self.need_update.borrow()
 		{
 			let translate = float4x4::translate(self.translate.x, self.translate.y, self.translate.z);
-			let rotation:float4x4 = Quaternion::euler_xyz(&self.rotation).into();
+			let rotation:float4x4 = self.rotation.into();
 			let scale = float4x4::scale(self.scale.x, self.scale.y, self.scale.z);
 			let transform = translate * rotation * scale;
 
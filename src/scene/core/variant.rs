@@ -177,6 +177,15 @@ impl From<float4x4> for Variant
 	}
 }
 
+impl From<Vec<float4x4>> for Variant
+{
+	#[inline(always)]
+	fn from(v:Vec<float4x4>) -> Self
+	{
+		Variant::Float4x4s(v)
+	}
+}
+
 impl From<double2x2> for Variant
 {
 	#[inline(always)]
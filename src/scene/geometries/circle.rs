@@ -5,7 +5,7 @@ use std::cell::RefCell;
 use crate::math::*;
 use crate::models::VertexWeight;
 
-use super::super::core::{Resource, Geometry};
+use super::super::core::{Resource, Geometry, Indices};
 use super::super::util::uuid::OsRandNewV4;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -19,7 +19,7 @@ pub struct CircleGeometry
 	vertices:float3s,
 	normals:float3s,
 	texcoords:float2s,
-	indices:Vec<u16>,
+	indices:Indices,
 	weights:Vec<VertexWeight>
 }
 
@@ -56,9 +56,9 @@ impl CircleGeometry
 			let v2 = i + 1;
 			let v3 = 0;
 
-			_indices.push(v1 as u16);
-			_indices.push(v2 as u16);
-			_indices.push(v3 as u16);
+			_indices.push(v1 as u32);
+			_indices.push(v2 as u32);
+			_indices.push(v3 as u32);
 		}
 
 		Self
@@ -71,7 +71,7 @@ impl CircleGeometry
 			vertices:_vertices,
 			normals:_normals,
 			texcoords:_texcoords,
-			indices:_indices,
+			indices:Indices::new(_indices),
 			weights:Vec::new(),
 		}
 	}
@@ -119,9 +119,9 @@ impl Geometry for CircleGeometry
 		&self.texcoords[..]
 	}
 
-	fn indices(&self) -> &[u16]
+	fn indices(&self) -> &Indices
 	{
-		&self.indices[..]
+		&self.indices
 	}
 
 	fn weights(&self) -> &[VertexWeight]
@@ -5,7 +5,7 @@ use std::cell::RefCell;
 use crate::math::*;
 use crate::models::VertexWeight;
 
-use super::super::core::{Resource, Geometry};
+use super::super::core::{Resource, Geometry, Indices};
 use super::super::util::uuid::OsRandNewV4;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -20,7 +20,7 @@ pub struct ConeGeometry
 	vertices:float3s,
 	normals:float3s,
 	texcoords:float2s,
-	indices:Vec<u16>,
+	indices:Indices,
 	weights:Vec<VertexWeight>
 }
 
@@ -67,9 +67,9 @@ impl ConeGeometry
 			let v2 = 0;
 			let v3 = i + 1;
 
-			_indices.push(v1 as u16);
-			_indices.push(v2 as u16);
-			_indices.push(v3 as u16);
+			_indices.push(v1 as u32);
+			_indices.push(v2 as u32);
+			_indices.push(v3 as u32);
 		}
 
 		for i in 2..segments + 2
@@ -78,9 +78,9 @@ impl ConeGeometry
 			let v2 = 1;
 			let v3 = i + 1;
 
-			_indices.push(v3 as u16);
-			_indices.push(v2 as u16);
-			_indices.push(v1 as u16);
+			_indices.push(v3 as u32);
+			_indices.push(v2 as u32);
+			_indices.push(v1 as u32);
 		}
 
 		Self
@@ -94,7 +94,7 @@ impl ConeGeometry
 			vertices:_vertices,
 			normals:_normals,
 			texcoords:_texcoords,
-			indices:_indices,
+			indices:Indices::new(_indices),
 			weights:Vec::new()
 		}
 	}
@@ -147,9 +147,9 @@ impl Geometry for ConeGeometry
 		&self.texcoords[..]
 	}
 
-	fn indices(&self) -> &[u16]
+	fn indices(&self) -> &Indices
 	{
-		&self.indices[..]
+		&self.indices
 	}
 
 	fn weights(&self) -> &[VertexWeight]
@@ -5,7 +5,7 @@ use std::cell::RefCell;
 use crate::math::*;
 use crate::models::VertexWeight;
 
-use super::super::core::{Resource, Geometry};
+use super::super::core::{Resource, Geometry, Indices};
 use super::super::util::uuid::OsRandNewV4;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -21,7 +21,7 @@ pub struct CubeGeometry
 	vertices:float3s,
 	normals:float3s,
 	texcoords:float2s,
-	indices:Vec<u16>,
+	indices:Indices,
 	weights:Vec<VertexWeight>
 }
 
@@ -78,7 +78,7 @@ impl CubeGeometry
 			vertices:vertices,
 			normals:normals,
 			texcoords:texcoords,
-			indices:indices,
+			indices:Indices::U16(indices),
 			weights:Vec::new()
 		}
 	}
@@ -131,9 +131,9 @@ impl Geometry for CubeGeometry
 		&self.texcoords[..]
 	}
 
-	fn indices(&self) -> &[u16]
+	fn indices(&self) -> &Indices
 	{
-		&self.indices[..]
+		&self.indices
 	}
 
 	fn weights(&self) -> &[VertexWeight]
@@ -5,7 +5,7 @@ use std::cell::RefCell;
 use crate::math::*;
 use crate::models::VertexWeight;
 
-use super::super::core::{Resource, Geometry};
+use super::super::core::{Resource, Geometry, Indices};
 use super::super::util::uuid::OsRandNewV4;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -21,7 +21,7 @@ pub struct CylinderGeometry
 	vertices:float3s,
 	normals:float3s,
 	texcoords:float2s,
-	indices:Vec<u16>,
+	indices:Indices,
 	weights:Vec<VertexWeight>
 }
 
@@ -67,13 +67,13 @@ impl CylinderGeometry
 			let v3 = i + 1 + segments;
 			let v4 = i + 2 + segments;
 
-			_indices.push(v1 as u16);
-			_indices.push(v4 as u16);
-			_indices.push(v3 as u16);
+			_indices.push(v1 as u32);
+			_indices.push(v4 as u32);
+			_indices.push(v3 as u32);
 
-			_indices.push(v1 as u16);
-			_indices.push(v2 as u16);
-			_indices.push(v4 as u16);
+			_indices.push(v1 as u32);
+			_indices.push(v2 as u32);
+			_indices.push(v4 as u32);
 		}
 
 		Self
@@ -88,7 +88,7 @@ impl CylinderGeometry
 			vertices:_vertices,
 			normals:_normals,
 			texcoords:_texcoords,
-			indices:_indices,
+			indices:Indices::new(_indices),
 			weights:Vec::new()
 		}
 	}
@@ -190,9 +190,9 @@ impl Geometry for CylinderGeometry
 		&self.texcoords[..]
 	}
 
-	fn indices(&self) -> &[u16]
+	fn indices(&self) -> &Indices
 	{
-		&self.indices[..]
+		&self.indices
 	}
 
 	fn weights(&self) -> &[VertexWeight]
@@ -5,7 +5,7 @@ use std::cell::RefCell;
 use crate::math::*;
 use crate::models::VertexWeight;
 
-use super::super::core::{Resource, Geometry};
+use super::super::core::{Resource, Geometry, Indices};
 use super::super::util::uuid::OsRandNewV4;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -20,7 +20,7 @@ pub struct FanGeometry
 	vertices:float3s,
 	normals:float3s,
 	texcoords:float2s,
-	indices:Vec<u16>,
+	indices:Indices,
 	weights:Vec<VertexWeight>
 }
 
@@ -69,9 +69,9 @@ impl FanGeometry
 			let v3 = i + 1 + segments;
 			let v4 = i + 2 + segments;
 
-			_indices.push(v1 as u16);
-			_indices.push(v3 as u16);
-			_indices.push(v4 as u16);
+			_indices.push(v1 as u32);
+			_indices.push(v3 as u32);
+			_indices.push(v4 as u32);
 		}
 
 		for i in 0..segments
@@ -80,9 +80,9 @@ impl FanGeometry
 			let v2 = i + 1;
 			let v4 = i + 2 + segments;
 
-			_indices.push(v1 as u16);
-			_indices.push(v4 as u16);
-			_indices.push(v2 as u16);
+			_indices.push(v1 as u32);
+			_indices.push(v4 as u32);
+			_indices.push(v2 as u32);
 		}
 
 		Self
@@ -96,7 +96,7 @@ impl FanGeometry
 			vertices:_vertices,
 			normals:_normals,
 			texcoords:_texcoords,
-			indices:_indices,
+			indices:Indices::new(_indices),
 			weights:Vec::new()
 		}
 	}
@@ -149,9 +149,9 @@ impl Geometry for FanGeometry
 		&self.texcoords[..]
 	}
 
-	fn indices(&self) -> &[u16]
+	fn indices(&self) -> &Indices
 	{
-		&self.indices[..]
+		&self.indices
 	}
 
 	fn weights(&self) -> &[VertexWeight]
@@ -3,9 +3,9 @@ use std::sync::Arc;
 use std::cell::RefCell;
 
 use crate::math::*;
-use crate::models::VertexWeight;
+use crate::models::{VertexWeight, MorphTarget};
 
-use super::super::core::{Resource, Geometry};
+use super::super::core::{Resource, Geometry, Indices};
 use super::super::util::uuid::OsRandNewV4;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -15,13 +15,15 @@ pub struct MeshGeometry
 	vertices:float3s,
 	normals:float3s,
 	texcoords:float2s,
-	indices:Vec<u16>,
-	weights:Vec<VertexWeight>
+	indices:Indices,
+	weights:Vec<VertexWeight>,
+	morphs:Vec<MorphTarget>,
+	morph_weights:RefCell<Vec<f32>>,
 }
 
 impl MeshGeometry 
 {
-	pub fn new(vertices:float3s, normals:float3s, texcoords:float2s, weights:Vec<VertexWeight>, indices:Vec<u16>) -> Self 
+	pub fn new<I:Into<Indices>>(vertices:float3s, normals:float3s, texcoords:float2s, weights:Vec<VertexWeight>, indices:I) -> Self 
 	{
 		Self
 		{
@@ -29,11 +31,27 @@ impl MeshGeometry
 			vertices:vertices,
 			normals:normals,
 			texcoords:texcoords,
-			indices:indices,
-			weights:weights
+			indices:indices.into(),
+			weights:weights,
+			morphs:Vec::new(),
+			morph_weights:RefCell::new(Vec::new()),
 		}
 	}
 
+	/// Keeps the uuid of a geometry that was saved before, so shapes can still refer to it.
+	pub fn with_uuid(mut self, uuid:uuid::Uuid) -> Self
+	{
+		self.uuid = uuid;
+		self
+	}
+
+	pub fn with_morphs(mut self, morphs:Vec<MorphTarget>) -> Self
+	{
+		self.morph_weights = RefCell::new(vec![0.0; morphs.len()]);
+		self.morphs = morphs;
+		self
+	}
+
 	pub fn builder() -> MeshGeometryBuilder
 	{
 		MeshGeometryBuilder::new()
@@ -57,15 +75,38 @@ impl Geometry for MeshGeometry
 		&self.texcoords[..]
 	}
 
-	fn indices(&self) -> &[u16]
+	fn indices(&self) -> &Indices
 	{
-		&self.indices[..]
+		&self.indices
 	}
 
 	fn weights(&self) -> &[VertexWeight]
 	{
 		&self.weights[..]
 	}
+
+	fn morph_targets(&self) -> &[MorphTarget]
+	{
+		&self.morphs[..]
+	}
+
+	fn morph_weights(&self) -> Vec<f32>
+	{
+		self.morph_weights.borrow().clone()
+	}
+
+	fn set_morph_weight(&self, name:&str, weight:f32) -> bool
+	{
+		match self.morphs.iter().position(|target| target.name == name)
+		{
+			Some(index) =>
+			{
+				self.morph_weights.borrow_mut()[index] = weight;
+				true
+			},
+			None => false
+		}
+	}
 }
 
 impl Resource for MeshGeometry
@@ -114,8 +155,9 @@ pub struct MeshGeometryBuilder
 	vertices:float3s,
 	normals:float3s,
 	texcoords:float2s,
-	indices:Vec<u16>,
-	weights:Vec<VertexWeight>
+	indices:Indices,
+	weights:Vec<VertexWeight>,
+	morphs:Vec<MorphTarget>,
 }
 
 impl MeshGeometryBuilder
@@ -128,8 +170,9 @@ impl MeshGeometryBuilder
 			vertices:Vec::new(),
 			normals:Vec::new(),
 			texcoords:Vec::new(),
-			indices:Vec::new(),
+			indices:Indices::U16(Vec::new()),
 			weights:Vec::new(),
+			morphs:Vec::new(),
 		}
 	}
 
@@ -139,7 +182,7 @@ impl MeshGeometryBuilder
 		assert_eq!(self.vertices.len(), self.normals.len());
 		assert_eq!(self.vertices.len(), self.texcoords.len());
 
-		MeshGeometry::new(self.vertices, self.normals, self.texcoords, self.weights, self.indices)
+		MeshGeometry::new(self.vertices, self.normals, self.texcoords, self.weights, self.indices).with_morphs(self.morphs)
 	}
 
 	#[inline]
@@ -192,9 +235,9 @@ impl MeshGeometryBuilder
 	}
 
 	#[inline]
-	pub fn set_indices(mut self, indices:Vec<u16>) -> Self
+	pub fn set_indices<I:Into<Indices>>(mut self, indices:I) -> Self
 	{
-		self.indices = indices;
+		self.indices = indices.into();
 		self
 	}
 
@@ -205,6 +248,20 @@ impl MeshGeometryBuilder
 		self
 	}
 
+	#[inline]
+	pub fn set_morphs(mut self, morphs:Vec<MorphTarget>) -> Self
+	{
+		self.morphs = morphs;
+		self
+	}
+
+	#[inline]
+	pub fn add_morph(mut self, morph:MorphTarget) -> Self
+	{
+		self.morphs.push(morph);
+		self
+	}
+
 	#[inline]
 	pub fn add_vertex(mut self, v:float3) -> Self
 	{
@@ -227,7 +284,7 @@ impl MeshGeometryBuilder
 	}
 
 	#[inline]
-	pub fn add_indice(mut self, index:u16) -> Self
+	pub fn add_indice(mut self, index:u32) -> Self
 	{
 		self.indices.push(index);
 		self
@@ -5,7 +5,7 @@ use std::cell::RefCell;
 use crate::math::*;
 use crate::models::VertexWeight;
 
-use super::super::core::{Resource, Geometry};
+use super::super::core::{Resource, Geometry, Indices};
 use super::super::util::uuid::OsRandNewV4;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -19,7 +19,7 @@ pub struct PlaneGeometry
 	vertices:float3s,
 	normals:float3s,
 	texcoords:float2s,
-	indices:Vec<u16>,
+	indices:Indices,
 	weights:Vec<VertexWeight>
 }
 
@@ -32,7 +32,7 @@ impl PlaneGeometry
 
 		let mut _vertices = Vec::with_capacity(capacity_inc);
 		let mut _normals = Vec::with_capacity(capacity_inc);
-		let mut _texcoords = Vec::with_capacity(capacity * 4);
+		let mut _texcoords = Vec::with_capacity(capacity_inc);
 		let mut _indices = Vec::with_capacity(capacity * 6);
 
 		let grid = (width_segments, height_segments);
@@ -54,6 +54,7 @@ impl PlaneGeometry
 
 				_vertices.push(float!(x, 0.0, z));
 				_normals.push(normal);
+				_texcoords.push(float!(ix as f32 / grid.0 as f32, iz as f32 / grid.1 as f32));
 			}
 		}
 
@@ -61,26 +62,18 @@ impl PlaneGeometry
 		{
 			for ix in 0..grid.0
 			{
-				let fx = ix as f32;
-				let fy = iy as f32;
-
 				let a = ix + grid1.0 * iy;
 				let b = ix + grid1.0 * (iy + 1);
 				let c = ix + grid1.0 * (iy + 1) + 1;
 				let d = ix + grid1.0 * iy + 1;
 
-				_texcoords.push(float!((fx      ) / grid.0 as f32, (fy + 1.0) / grid.1 as f32));
-				_texcoords.push(float!((fx + 1.0) / grid.0 as f32, (fy + 1.0) / grid.1 as f32));
-				_texcoords.push(float!((fx      ) / grid.0 as f32, (fy      ) / grid.1 as f32));
-				_texcoords.push(float!((fx + 1.0) / grid.0 as f32, (fy      ) / grid.1 as f32));
-
-				_indices.push(a as u16);
-				_indices.push(b as u16);
-				_indices.push(c as u16);
+				_indices.push(a as u32);
+				_indices.push(b as u32);
+				_indices.push(c as u32);
 
-				_indices.push(c as u16);
-				_indices.push(d as u16);
-				_indices.push(a as u16);
+				_indices.push(c as u32);
+				_indices.push(d as u32);
+				_indices.push(a as u32);
 			}
 		}
 
@@ -94,7 +87,7 @@ impl PlaneGeometry
 			vertices:_vertices,
 			normals:_normals,
 			texcoords:_texcoords,
-			indices:_indices,
+			indices:Indices::new(_indices),
 			weights:Vec::new()
 		}
 	}
@@ -142,9 +135,9 @@ impl Geometry for PlaneGeometry
 		&self.texcoords[..]
 	}
 
-	fn indices(&self) -> &[u16]
+	fn indices(&self) -> &Indices
 	{
-		&self.indices[..]
+		&self.indices
 	}
 
 	fn weights(&self) -> &[VertexWeight]
@@ -52,6 +52,20 @@ impl DirectionalLight
 		DirectionalLightBuilder::new()
 	}
 
+	/// Wraps a node holding the data of a directional light, such as one loaded from a scene file.
+	pub fn from_node(node:SceneNode) -> Option<Self>
+	{
+		let data = node.user_data::<LightData>().ok().filter(|data| data.kind == LightType::Directional)?;
+
+		Some(Self
+		{
+			node:node,
+			spectrum:data.color,
+			intensity:data.intensity,
+			direction:data.direction,
+		})
+	}
+
 	#[inline(always)]
 	pub fn direction(&self) -> float3
 	{
@@ -49,6 +49,19 @@ impl PointLight
 	{
 		PointLightBuilder::new()
 	}
+
+	/// Wraps a node holding the data of a point light, such as one loaded from a scene file.
+	pub fn from_node(node:SceneNode) -> Option<Self>
+	{
+		let data = node.user_data::<LightData>().ok().filter(|data| data.kind == LightType::Point)?;
+
+		Some(Self
+		{
+			node:node,
+			spectrum:data.color,
+			intensity:data.intensity,
+		})
+	}
 }
 
 impl Light for PointLight
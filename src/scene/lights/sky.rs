@@ -50,6 +50,19 @@ impl SkyLight
 		SkyLightBuilder::new(irradiance, radiance)
 	}
 
+	/// Wraps a node holding the data of a sky light, such as one loaded from a scene file.
+	pub fn from_node(node:SceneNode) -> Option<Self>
+	{
+		let data = node.user_data::<LightData>().ok().filter(|data| data.kind == LightType::Sky)?;
+
+		Some(Self
+		{
+			node:node,
+			spectrum:data.color,
+			intensity:data.intensity,
+		})
+	}
+
 	#[inline(always)]
 	pub fn irradiance(&self) -> Arc<Texture>
 	{
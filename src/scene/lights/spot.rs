@@ -40,7 +40,7 @@ impl SpotLight
 
 		Self
 		{
-			node:SceneNode::new(SceneSubData::Light),
+			node:node,
 			spectrum:Spectrum::one(),
 			intensity:1.0,
 			direction:-float3::unit_y(),
@@ -54,6 +54,21 @@ impl SpotLight
 		SpotLightBuilder::new()
 	}
 
+	/// Wraps a node holding the data of a spot light, such as one loaded from a scene file.
+	pub fn from_node(node:SceneNode) -> Option<Self>
+	{
+		let data = node.user_data::<LightData>().ok().filter(|data| data.kind == LightType::Spot)?;
+
+		Some(Self
+		{
+			node:node,
+			spectrum:data.color,
+			intensity:data.intensity,
+			direction:data.direction,
+			angle:data.cos_angle.acos().to_radians(),
+		})
+	}
+
 	#[inline(always)]
 	pub fn direction(&self) -> float3
 	{
@@ -1,8 +1,151 @@
-use super::super::{Result, Loader, Scene};
+use std::io::Cursor;
+use std::sync::Arc;
+use std::collections::HashMap;
+use byteorder::{NativeEndian, ReadBytesExt};
+use serde_json::Value;
+
+use crate::math::*;
+use super::super::{Result, Error, Loader, Scene, SceneNode, SceneSubData, SCENE_VERSION};
+use super::super::{Texture, ColorType, Sampler, TextureLoader};
+use super::super::{Geometry, MeshGeometry, Indices, IndexFormat, Material, MaterialType, RenderState, VertexAttrib, Variant};
+use crate::models::VertexWeight;
+use super::super::{CameraData, Projection, LightData, LightType, Spectrum};
+use super::super::{StandardMaterial, SkinningMaterial, SkyboxMaterial, CustomMaterial};
+use super::super::{PerspectiveCamera, OrthographicCamera};
+use super::super::{DirectionalLight, PointLight, SpotLight, SkyLight};
 
 #[derive(Debug)]
 pub struct JsonLoader {}
 
+#[derive(Deserialize)]
+struct SceneJson
+{
+	#[serde(default)]
+	version:u32,
+	#[serde(default)]
+	textures:Vec<TextureJson>,
+	#[serde(default)]
+	model:Vec<GeometryJson>,
+	#[serde(default)]
+	materials:Vec<MaterialJson>,
+	#[serde(default)]
+	shape:Vec<NodeJson>,
+	#[serde(default)]
+	camera:Vec<NodeJson>,
+	#[serde(default)]
+	light:Vec<NodeJson>,
+	#[serde(default)]
+	group:Vec<NodeJson>,
+}
+
+#[derive(Deserialize)]
+struct TextureJson
+{
+	uuid:uuid::Uuid,
+	name:String,
+	sampler:Sampler,
+	width:u32,
+	height:u32,
+	color:ColorType,
+	image:Option<String>,
+	pixels:Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TextureRef
+{
+	uuid:uuid::Uuid,
+}
+
+#[derive(Deserialize)]
+struct GeometryJson
+{
+	uuid:uuid::Uuid,
+	v:String,
+	n:String,
+	uv:String,
+	i:String,
+	#[serde(default = "default_index_format")]
+	format:IndexFormat,
+	w:Option<String>,
+}
+
+fn default_index_format() -> IndexFormat
+{
+	IndexFormat::U16
+}
+
+#[derive(Deserialize)]
+struct MaterialJson
+{
+	uuid:uuid::Uuid,
+	#[serde(rename = "type")]
+	kind:MaterialType,
+	attrib:Vec<VertexAttrib>,
+	parameters:HashMap<String, Value>,
+	state:RenderState,
+}
+
+#[derive(Deserialize)]
+struct TransformJson
+{
+	translate:float3,
+	rotation:RotationJson,
+	scale:float3,
+}
+
+// Version 0 wrote XYZ euler angles, version 1 a quaternion.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RotationJson
+{
+	Quaternion(Quaternion),
+	Euler(float3),
+}
+
+#[derive(Deserialize)]
+struct LightJson
+{
+	kind:LightType,
+	color:Spectrum,
+	intensity:f32,
+	radiance:Option<TextureRef>,
+	irradiance:Option<TextureRef>,
+	direction:float3,
+	cos_angle:f32,
+}
+
+#[derive(Deserialize)]
+struct NodeJson
+{
+	#[serde(rename = "type")]
+	kind:SceneSubData,
+	uuid:uuid::Uuid,
+	name:String,
+	visible:bool,
+	transform:TransformJson,
+	model:Option<uuid::Uuid>,
+	material:Option<uuid::Uuid>,
+	camera:Option<CameraData>,
+	light:Option<LightJson>,
+	parent:Option<uuid::Uuid>,
+	#[serde(default)]
+	children:Vec<ChildJson>,
+}
+
+// Version 0 nested the child nodes, version 1 refers to them by uuid.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ChildJson
+{
+	Uuid(uuid::Uuid),
+	Node(NodeJson),
+}
+
+type Textures = HashMap<uuid::Uuid, Arc<Texture>>;
+type Geometries = HashMap<uuid::Uuid, Arc<Geometry + 'static>>;
+type Materials = HashMap<uuid::Uuid, Arc<Material + 'static>>;
+
 impl JsonLoader
 {
 	pub fn new() -> Self
@@ -11,18 +154,324 @@ impl JsonLoader
 		{
 		}
 	}
+
+	fn load_texture(json:TextureJson) -> Result<Arc<Texture>>
+	{
+		let mut texture = match (json.image, json.pixels)
+		{
+			(Some(image), _) => TextureLoader::from_png(&base64::decode(&image)?, json.color, None)?,
+			(None, Some(pixels)) => Texture::new(json.color, json.width, json.height, base64::decode(&pixels)?),
+			(None, None) => return Err(Error::LoaderError(format!("Texture {} has no image", json.uuid))),
+		};
+
+		texture.uuid = json.uuid;
+		texture.name = json.name;
+		texture.sampler = json.sampler;
+		Ok(Arc::new(texture))
+	}
+
+	fn load_geometry(json:GeometryJson) -> Result<Arc<Geometry + 'static>>
+	{
+		let vertices = read_floats(&base64::decode(&json.v)?)?;
+		let normals = read_floats(&base64::decode(&json.n)?)?;
+		let texcoords = read_floats(&base64::decode(&json.uv)?)?;
+
+		let bytes = base64::decode(&json.i)?;
+		let mut reader = Cursor::new(&bytes);
+		let indices = match json.format
+		{
+			IndexFormat::U16 =>
+			{
+				let mut indices = Vec::with_capacity(bytes.len() / 2);
+				for _ in 0..bytes.len() / 2 { indices.push(reader.read_u16::<NativeEndian>()?); }
+				Indices::U16(indices)
+			},
+			IndexFormat::U32 =>
+			{
+				let mut indices = Vec::with_capacity(bytes.len() / 4);
+				for _ in 0..bytes.len() / 4 { indices.push(reader.read_u32::<NativeEndian>()?); }
+				Indices::U32(indices)
+			},
+		};
+
+		let mut weights = Vec::new();
+		if let Some(w) = json.w
+		{
+			let bytes = base64::decode(&w)?;
+			let mut reader = Cursor::new(&bytes);
+			for _ in 0..bytes.len() / 24
+			{
+				let mut weight = VertexWeight::new();
+				for bone in weight.bone.iter_mut() { *bone = reader.read_u16::<NativeEndian>()?; }
+				for value in weight.weight.iter_mut() { *value = reader.read_f32::<NativeEndian>()?; }
+				weights.push(weight);
+			}
+		}
+
+		let vertices:float3s = vertices.chunks(3).filter(|v| v.len() == 3).map(|v| float3::new(v[0], v[1], v[2])).collect();
+		let normals:float3s = normals.chunks(3).filter(|v| v.len() == 3).map(|v| float3::new(v[0], v[1], v[2])).collect();
+		let texcoords:float2s = texcoords.chunks(2).filter(|v| v.len() == 2).map(|v| float2::new(v[0], v[1])).collect();
+
+		// The renderer reads every attribute of a vertex, the skin weights are the only optional one.
+		let count = vertices.len();
+		if normals.len() != count || texcoords.len() != count || (weights.len() > 0 && weights.len() != count)
+		{
+			return Err(Error::LoaderError(format!("Geometry {} has {} vertices but {} normals, {} texcoords and {} weights", json.uuid, count, normals.len(), texcoords.len(), weights.len())));
+		}
+
+		if let Some(index) = indices.to_vec().into_iter().find(|&index| index as usize >= count)
+		{
+			return Err(Error::LoaderError(format!("Geometry {} has index {} out of {} vertices", json.uuid, index, count)));
+		}
+
+		let geometry = MeshGeometry::new(vertices, normals, texcoords, weights, indices);
+
+		Ok(geometry.with_uuid(json.uuid).into())
+	}
+
+	fn load_material(json:MaterialJson, textures:&Textures) -> Result<Arc<Material + 'static>>
+	{
+		let mut uniforms = HashMap::new();
+		for (name, value) in json.parameters
+		{
+			// Texture parameters only carry the uuid of an entry in `textures`.
+			let variant = match value.get("Texture")
+			{
+				Some(texture) => Variant::Texture(serde_json::from_value::<Option<TextureRef>>(texture.clone())?.and_then(|texture| textures.get(&texture.uuid).cloned())),
+				None => serde_json::from_value::<Variant>(value)?,
+			};
+
+			uniforms.insert(name, variant);
+		}
+
+		let material:Arc<Material + 'static> = match json.kind
+		{
+			MaterialType::Standard =>
+			{
+				let mut material = StandardMaterial::new();
+				material.uuid = json.uuid;
+				material.state = json.state;
+				material.attribs = json.attrib;
+				material.uniforms = uniforms;
+				material.into()
+			},
+			MaterialType::Skinning(num_joint) =>
+			{
+				let mut material = SkinningMaterial::new(num_joint);
+				material.uuid = json.uuid;
+				material.state = json.state;
+				material.attribs = json.attrib;
+				material.uniforms = uniforms;
+				material.into()
+			},
+			MaterialType::Skybox =>
+			{
+				let mut material = SkyboxMaterial::new();
+				material.uuid = json.uuid;
+				material.state = json.state;
+				material.attribs = json.attrib;
+				material.uniforms = uniforms;
+				material.into()
+			},
+			MaterialType::Custom =>
+			{
+				let mut material = CustomMaterial::new();
+				material.uuid = json.uuid;
+				material.state = json.state;
+				material.attribs = json.attrib;
+				material.uniforms = uniforms;
+				material.into()
+			},
+		};
+
+		Ok(material)
+	}
+
+	// Creates the node and every nested child, the links are made once all nodes exist.
+	fn load_node(json:NodeJson, textures:&Textures, geometries:&Geometries, materials:&Materials, nodes:&mut Vec<(SceneNode, Option<uuid::Uuid>, Vec<uuid::Uuid>)>) -> uuid::Uuid
+	{
+		let mut node = match json.kind
+		{
+			SceneSubData::Camera =>
+			{
+				let data = json.camera.unwrap_or(CameraData::new());
+
+				let mut node = match data.projection_type
+				{
+					Projection::Perspective => PerspectiveCamera::new().node,
+					Projection::Orthographic => OrthographicCamera::new().node,
+				};
+
+				node.set_user_data(Box::new(data));
+				node
+			},
+			SceneSubData::Light =>
+			{
+				match json.light
+				{
+					Some(light) =>
+					{
+						let texture = |texture:Option<TextureRef>| texture.and_then(|texture| textures.get(&texture.uuid).cloned());
+						let (radiance, irradiance) = (texture(light.radiance), texture(light.irradiance));
+
+						let mut node = match (light.kind, &radiance, &irradiance)
+						{
+							(LightType::Directional, _, _) => DirectionalLight::new().node,
+							(LightType::Point, _, _) => PointLight::new().node,
+							(LightType::Spot, _, _) => SpotLight::new().node,
+							(LightType::Sky, Some(radiance), Some(irradiance)) => SkyLight::new(irradiance.clone(), radiance.clone()).node,
+							(LightType::Sky, _, _) => SceneNode::new(SceneSubData::Light),
+						};
+
+						node.set_user_data(Box::new(LightData
+						{
+							kind:light.kind,
+							color:light.color,
+							intensity:light.intensity,
+							radiance:radiance,
+							irradiance:irradiance,
+							direction:light.direction,
+							cos_angle:light.cos_angle,
+						}));
+						node
+					},
+					None => SceneNode::new(SceneSubData::Light),
+				}
+			},
+			kind => SceneNode::new(kind),
+		};
+
+		node.uuid = json.uuid;
+		node.data.borrow_mut().uuid = json.uuid;
+		node.set_name(&json.name);
+		node.set_visible(json.visible);
+		node.set_translate(json.transform.translate);
+		match json.transform.rotation
+		{
+			RotationJson::Quaternion(rotation) => node.set_quaternion(rotation),
+			RotationJson::Euler(rotation) => node.set_rotation(rotation),
+		};
+		node.set_scale(json.transform.scale);
+
+		if let Some(geometry) = json.model.and_then(|uuid| geometries.get(&uuid))
+		{
+			node.set_geometry(Some(geometry.clone()));
+		}
+
+		if let Some(material) = json.material.and_then(|uuid| materials.get(&uuid))
+		{
+			node.set_material(Some(material.clone()));
+		}
+
+		let index = nodes.len();
+		nodes.push((node, json.parent, Vec::new()));
+
+		for child in json.children
+		{
+			let uuid = match child
+			{
+				ChildJson::Uuid(uuid) => uuid,
+				ChildJson::Node(child) => JsonLoader::load_node(child, textures, geometries, materials, nodes),
+			};
+
+			nodes[index].2.push(uuid);
+		}
+
+		json.uuid
+	}
 }
 
 impl Loader for JsonLoader
 {
-	fn can_read(&self, _buf:&[u8]) -> bool
+	fn can_read(&self, buf:&[u8]) -> bool
 	{
-		false
+		match buf.iter().find(|c| !c.is_ascii_whitespace())
+		{
+			Some(b'{') => serde_json::from_slice::<SceneJson>(buf).is_ok(),
+			_ => false
+		}
 	}
 
-	fn do_load(&self, _buf:&[u8]) -> Result<Scene>
+	fn do_load(&self, buf:&[u8]) -> Result<Scene>
 	{
-		Ok(Scene::new())
+		let json:SceneJson = serde_json::from_slice(buf)?;
+		if json.version > SCENE_VERSION
+		{
+			return Err(Error::LoaderError(format!("Scene version {} is newer than the supported version {}", json.version, SCENE_VERSION)));
+		}
+
+		let mut textures = HashMap::new();
+		for texture in json.textures
+		{
+			let uuid = texture.uuid;
+			textures.insert(uuid, JsonLoader::load_texture(texture)?);
+		}
+
+		let mut geometries = HashMap::new();
+		for geometry in json.model
+		{
+			let uuid = geometry.uuid;
+			geometries.insert(uuid, JsonLoader::load_geometry(geometry)?);
+		}
+
+		let mut materials = HashMap::new();
+		for material in json.materials
+		{
+			let uuid = material.uuid;
+			materials.insert(uuid, JsonLoader::load_material(material, &textures)?);
+		}
+
+		let mut nodes = Vec::new();
+		for node in json.camera.into_iter().chain(json.light).chain(json.group).chain(json.shape)
+		{
+			JsonLoader::load_node(node, &textures, &geometries, &materials, &mut nodes);
+		}
+
+		let index:HashMap<uuid::Uuid, usize> = nodes.iter().enumerate().map(|(i, node)| (node.0.uuid, i)).collect();
+		let mut parents = HashMap::new();
+
+		for i in 0..nodes.len()
+		{
+			for uuid in nodes[i].2.clone()
+			{
+				if let Some(&child) = index.get(&uuid)
+				{
+					if !parents.contains_key(&child) && !is_ancestor(&parents, i, child)
+					{
+						parents.insert(child, i);
+						let child = nodes[child].0.clone();
+						nodes[i].0.add_child(child);
+					}
+				}
+			}
+		}
+
+		// A parent that doesn't list the child still owns it.
+		for i in 0..nodes.len()
+		{
+			if let Some(&parent) = nodes[i].1.and_then(|uuid| index.get(&uuid))
+			{
+				if !parents.contains_key(&i) && !is_ancestor(&parents, parent, i)
+				{
+					parents.insert(i, parent);
+					let child = nodes[i].0.clone();
+					nodes[parent].0.add_child(child);
+				}
+			}
+		}
+
+		// Adding a group also adds its children, any other node has to be added on its own.
+		let mut scene = Scene::new();
+		for i in 0..nodes.len()
+		{
+			match parents.get(&i)
+			{
+				Some(&parent) if nodes[parent].0.kind() == SceneSubData::Group => {},
+				_ => { scene.add(nodes[i].0.clone()); },
+			}
+		}
+
+		Ok(scene)
 	}
 
 	fn do_save(&self, scene:&Scene) -> Result<Vec<u8>>
@@ -30,4 +479,33 @@ impl Loader for JsonLoader
     	let serialized = serde_json::to_string(scene).unwrap();
     	Ok(serialized.into_bytes())
 	}
-}
\ No newline at end of file
+}
+
+// Walks up from `node`, a link to any of its ancestors would close a cycle.
+fn is_ancestor(parents:&HashMap<usize, usize>, node:usize, ancestor:usize) -> bool
+{
+	let mut node = Some(node);
+	while let Some(i) = node
+	{
+		if i == ancestor
+		{
+			return true;
+		}
+
+		node = parents.get(&i).cloned();
+	}
+
+	false
+}
+
+fn read_floats(bytes:&[u8]) -> Result<Vec<f32>>
+{
+	let mut reader = Cursor::new(bytes);
+	let mut floats = Vec::with_capacity(bytes.len() / 4);
+	for _ in 0..bytes.len() / 4
+	{
+		floats.push(reader.read_f32::<NativeEndian>()?);
+	}
+
+	Ok(floats)
+}
@@ -1,3 +1,4 @@
+use std::rc::Rc;
 use std::sync::Arc;
 use std::path::Path;
 use std::io::BufRead;
@@ -6,12 +7,14 @@ use log::*;
 
 use crate::math::*;
 use crate::models;
+use crate::animation;
 
-use super::super::core::Result;
+use super::super::core::{Error, Result, CullMode, Material, Resolver, parent_path, join_path};
 use super::super::geometries::MeshGeometry;
-use super::super::materials::StandardMaterial;
+use super::super::materials::{StandardMaterial, SkinningMaterial};
 use super::super::shapes::{ MeshShape, ModelShape };
 use super::super::loaders::TextureLoader;
+use super::super::resolvers::{FileResolver, ZipResolver};
 
 #[derive(Debug)]
 pub struct ModelLoader {}
@@ -19,9 +22,16 @@ pub struct ModelLoader {}
 impl ModelLoader
 {
 	pub fn load_from_model<P: AsRef<Path>>(model:models::Model, path:P) -> Result<ModelShape>
+	{
+		let resolver = FileResolver::builder().add_search_path(path.as_ref()).build();
+		ModelLoader::load_from_resolver(model, &resolver, "")
+	}
+
+	/// Builds the shapes of a model, its textures are looked up in `dir` through the resolver.
+	pub fn load_from_resolver(model:models::Model, resolver:&Resolver, dir:&str) -> Result<ModelShape>
 	{
 		let mut shapes = Vec::new();
-		let mut materials = Vec::new();
+		let mut materials:Vec<Arc<Material>> = Vec::new();
 		let mut textures = Vec::new();
 
 		info!("model count: {}", model.meshes.len());
@@ -32,7 +42,7 @@ impl ModelLoader
 
 		for name in &model.textures
 		{
-			let texture = TextureLoader::load(path.as_ref().join(&name));
+			let texture = TextureLoader::load_from_resolver(resolver, &join_path(dir, name));
 			if texture.is_ok()
 			{
 				info!("Loading: {} Ok", &name);
@@ -45,18 +55,33 @@ impl ModelLoader
 			}
 		}
 
+		// Models with bones are drawn with the skinning material, every shape shares one palette.
+		let num_joint = model.bones.len() as u16;
+
 		for material in &model.materials
 		{
-			let mut m = StandardMaterial::new();
-			m.set_albedo(float!(material.diffuse[0],material.diffuse[1],material.diffuse[2]));
-			m.set_specular(float!(material.specular[0],material.specular[1],material.specular[2]));
+			let albedo = float!(material.diffuse[0],material.diffuse[1],material.diffuse[2]);
+			let specular = float!(material.specular[0],material.specular[1],material.specular[2]);
+			let albedo_map = material.diffuse_texture.and_then(|i| textures[i].clone());
+
+			let cull_mode = if material.double_sided { CullMode::None } else { CullMode::Back };
 
-			if material.diffuse_texture.is_some()
+			let m:Box<Material> = if num_joint > 0
 			{
-				m.set_albedo_map(textures[material.diffuse_texture.unwrap()].clone());
+				let mut m = SkinningMaterial::new(num_joint);
+				m.set_albedo(albedo).set_specular(specular).set_albedo_map(albedo_map);
+				m.state.cull_mode = cull_mode;
+				Box::new(m)
 			}
+			else
+			{
+				let mut m = StandardMaterial::new();
+				m.set_albedo(albedo).set_specular(specular).set_albedo_map(albedo_map);
+				m.state.cull_mode = cull_mode;
+				Box::new(m)
+			};
 
-			materials.push(Arc::new(m));
+			materials.push(Arc::from(m));
 		}
 
 		for (i, mesh) in model.meshes.iter().enumerate()
@@ -67,17 +92,24 @@ impl ModelLoader
 			info!("model[{}].texcoords: {}", i, mesh.texcoords.len() / 2);
 			info!("model[{}].weights: {}", i, mesh.weights.len());
 			info!("model[{}].indices: {}", i, mesh.indices.len());
+			info!("model[{}].morphs: {}", i, mesh.morphs.len());
 
 			let geometry = MeshGeometry::builder()
 				.set_vertices(mesh.positions.chunks(3).map(|i| float!(i[0], i[1], i[2])).collect())
 				.set_normals(mesh.normals.chunks(3).map(|i| float!(i[0], i[1], i[2])).collect())
 				.set_texcoords(mesh.texcoords.chunks(2).map(|i| float!(i[0], i[1])).collect())
 				.set_weights(mesh.weights.clone())
-				.set_indices(mesh.indices.chunks(1).map(|i| i[0] as u16).collect());
+				.set_morphs(mesh.morphs.clone())
+				.set_indices(mesh.indices.clone());
 
 			shapes.push(MeshShape::new(Arc::new(geometry.build()), materials[mesh.material_id.unwrap() as usize].clone()));
 		}
 
+		if num_joint > 0
+		{
+			return Ok(ModelShape::new(shapes).with_skeleton(models::Skeleton::new(&model)));
+		}
+
 		return Ok(ModelShape::new(shapes));
 	}
 
@@ -91,13 +123,75 @@ impl ModelLoader
 	    ModelLoader::load_from_model(models::open(&path)?, path.as_ref().parent().unwrap())
 	}
 
+	/// Textures are looked up from the working directory through a `FileResolver`.
 	pub fn load_from_buf<R:BufRead + Seek>(r:R) -> Result<ModelShape>
 	{
-		ModelLoader::load_from_model(models::load_from_buf(r)?, "")
+		ModelLoader::load_from_resolver(models::load_from_buf(r)?, &FileResolver::new(), "")
 	}
 
+	/// Textures are looked up from the working directory through a `FileResolver`, see `load_from_memory_with`.
 	pub fn load_from_memory(buffer:&[u8]) -> Result<ModelShape>
 	{
-		ModelLoader::load_from_model(models::load_from_memory(buffer)?, "")
+		ModelLoader::load_from_memory_with(buffer, &FileResolver::new())
+	}
+
+	/// Loads a model kept in memory whose textures are found through the resolver.
+	pub fn load_from_memory_with(buffer:&[u8], resolver:&Resolver) -> Result<ModelShape>
+	{
+		ModelLoader::load_from_resolver(models::load_from_memory(buffer)?, resolver, "")
+	}
+
+	/// Opens a model through the resolver, textures are looked up next to where the model was found.
+	pub fn open_from(resolver:&Resolver, path:&str) -> Result<ModelShape>
+	{
+		match resolver.resolve(path)
+		{
+			Some(path) => ModelLoader::load_from_resolver(models::load_from_memory(&resolver.read(&path)?)?, resolver, parent_path(&path)),
+			None => Err(Error::LoaderError(format!("{} not found", path))),
+		}
 	}
-}
\ No newline at end of file
+
+	/// Loads the first PMX or PMD model of a zip archive with the textures packed next to it,
+	/// the first VMD of the archive becomes the motion of the model.
+	pub fn load_from_archive(buffer:Vec<u8>) -> Result<ModelShape>
+	{
+		let archive = ZipResolver::load_from_memory(buffer)?;
+		let paths = archive.paths();
+
+		let path = match find_extension(&paths, &["pmx", "pmd"])
+		{
+			Some(path) => path,
+			None => return Err(Error::LoaderError("No PMX or PMD model in the archive".to_string())),
+		};
+
+		info!("Loading: {} from the archive", path);
+
+		let mut shape = ModelLoader::open_from(&archive, path)?;
+
+		if let Some(path) = find_extension(&paths, &["vmd"])
+		{
+			info!("Loading: {} from the archive", path);
+			shape.node.set_motion(Some(Rc::new(animation::load_from_memory(&archive.read(path)?)?)));
+		}
+
+		Ok(shape)
+	}
+
+	pub fn open_archive<P: AsRef<Path>>(path:P) -> Result<ModelShape>
+	{
+		ModelLoader::load_from_archive(std::fs::read(path)?)
+	}
+}
+
+// The shallowest path with the first extension any path has, archives often keep spare copies in sub folders.
+fn find_extension<'a>(paths:&'a [String], extensions:&[&str]) -> Option<&'a str>
+{
+	extensions.iter().filter_map(|extension|
+	{
+		paths.iter()
+			.filter(|path| Path::new(path.as_str()).extension().map(|ext| ext.to_string_lossy().eq_ignore_ascii_case(extension)).unwrap_or(false))
+			.min_by_key(|path| (path.matches('/').count(), path.len()))
+	})
+	.next()
+	.map(|path| path.as_str())
+}
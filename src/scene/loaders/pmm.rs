@@ -1,11 +1,19 @@
 use std::f32;
+use std::rc::Rc;
+use std::collections::HashMap;
 use std::io::{Cursor, SeekFrom};
 use std::io::prelude::*;
-use byteorder::{LittleEndian, ReadBytesExt};
-use encoding::{Encoding, DecoderTrap};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use encoding::{Encoding, DecoderTrap, EncoderTrap};
 use encoding::all::WINDOWS_31J;
-use crate::math::float3;
-use super::super::{Error, Result, Loader, ModelLoader, Object, Scene, PerspectiveCamera};
+use log::*;
+use crate::math::{float3, Quaternion, RotationOrder};
+use crate::animation::{Motion, Animator, AnimationClip, AnimationCurve, Interpolator, FixedInterpolator, Evaluate, VMDMotion, VMD_BEZIER_LINEAR};
+use super::super::{Error, Result, Loader, ModelLoader, Object, Scene, SceneNode, PerspectiveCamera, DirectionalLight, ModelShape, Spectrum, LightData};
+use super::super::{Resolver, FileResolver};
+
+/// The bone MikuMikuDance places a whole model with.
+pub const PMM_ROOT_BONE:&str = "全ての親";
 
 // https://github.com/oigami/PMMEditor/blob/master/PMMEditor/MMDFileParser/PmmReader.cs
 pub struct PmmInt2();
@@ -192,6 +200,7 @@ pub struct PmmKeyFrame
 	pub frame_number:i32,
 	pub pre_index:i32,
 	pub next_index:i32,
+	pub data:PmmDataBody,
 	pub is_selected:u8
 }
 
@@ -362,6 +371,29 @@ impl PmmInt2
 
 		Ok(array)
 	}
+
+	pub fn save<W:Write>(writer:&mut W, v:(i32,i32)) -> Result<()>
+	{
+		writer.write_i32::<LittleEndian>(v.0)?;
+		writer.write_i32::<LittleEndian>(v.1)?;
+		Ok(())
+	}
+
+	pub fn save_arrays<W:Write>(writer:&mut W, array:&[(i32,i32)]) -> Result<()>
+	{
+		writer.write_u32::<LittleEndian>(array.len() as u32)?;
+		PmmInt2::save_fixed_arrays(writer, array)
+	}
+
+	pub fn save_fixed_arrays<W:Write>(writer:&mut W, array:&[(i32,i32)]) -> Result<()>
+	{
+		for it in array
+		{
+			PmmInt2::save(writer, *it)?;
+		}
+
+		Ok(())
+	}
 }
 
 impl PmmVector2
@@ -372,6 +404,13 @@ impl PmmVector2
 		let y = reader.read_f32::<LittleEndian>()?;
 		Ok((x, y))
 	}
+
+	pub fn save<W:Write>(writer:&mut W, v:(f32,f32)) -> Result<()>
+	{
+		writer.write_f32::<LittleEndian>(v.0)?;
+		writer.write_f32::<LittleEndian>(v.1)?;
+		Ok(())
+	}
 }
 
 impl PmmVector3
@@ -383,6 +422,14 @@ impl PmmVector3
 		let z = reader.read_f32::<LittleEndian>()?;
 		Ok((x, y, z))
 	}
+
+	pub fn save<W:Write>(writer:&mut W, v:(f32,f32,f32)) -> Result<()>
+	{
+		writer.write_f32::<LittleEndian>(v.0)?;
+		writer.write_f32::<LittleEndian>(v.1)?;
+		writer.write_f32::<LittleEndian>(v.2)?;
+		Ok(())
+	}
 }
 
 impl PmmVector4
@@ -395,6 +442,15 @@ impl PmmVector4
 		let w = reader.read_f32::<LittleEndian>()?;
 		Ok((x, y, z, w))
 	}
+
+	pub fn save<W:Write>(writer:&mut W, v:(f32,f32,f32,f32)) -> Result<()>
+	{
+		writer.write_f32::<LittleEndian>(v.0)?;
+		writer.write_f32::<LittleEndian>(v.1)?;
+		writer.write_f32::<LittleEndian>(v.2)?;
+		writer.write_f32::<LittleEndian>(v.3)?;
+		Ok(())
+	}
 }
 
 impl PmmName
@@ -438,6 +494,49 @@ impl PmmName
 
 		Ok(array)
 	}
+
+	/// Encodes a name to Shift-JIS and truncates it to whole characters within `length` bytes.
+	pub fn encode(name:&str, length:usize) -> Vec<u8>
+	{
+		let mut bytes = Vec::with_capacity(length);
+
+		for c in name.chars()
+		{
+			let mut buf = [0u8;4];
+			let encoded = WINDOWS_31J.encode(c.encode_utf8(&mut buf), EncoderTrap::Replace).unwrap();
+			if bytes.len() + encoded.len() > length { break; }
+			bytes.extend(encoded);
+		}
+
+		bytes
+	}
+
+	pub fn save<W:Write>(writer:&mut W, name:&str) -> Result<()>
+	{
+		let bytes = PmmName::encode(name, u8::max_value() as usize);
+		writer.write_u8(bytes.len() as u8)?;
+		writer.write_all(&bytes)?;
+		Ok(())
+	}
+
+	pub fn save_fixed_utf8<W:Write>(writer:&mut W, name:&str, len:usize) -> Result<()>
+	{
+		let mut bytes = PmmName::encode(name, len);
+		bytes.resize(len, 0);
+		writer.write_all(&bytes)?;
+		Ok(())
+	}
+
+	pub fn save_arrays<W:Write>(writer:&mut W, array:&[String]) -> Result<()>
+	{
+		writer.write_u32::<LittleEndian>(array.len() as u32)?;
+		for it in array
+		{
+			PmmName::save(writer, it)?;
+		}
+
+		Ok(())
+	}
 }
 
 impl PmmUint8
@@ -463,6 +562,19 @@ impl PmmUint8
 
 		Ok(array)
 	}
+
+	pub fn save_array_from_u8<W:Write>(writer:&mut W, array:&[u8]) -> Result<()>
+	{
+		writer.write_u8(array.len() as u8)?;
+		writer.write_all(array)?;
+		Ok(())
+	}
+
+	pub fn save_fixed_arrays<W:Write>(writer:&mut W, array:&[u8]) -> Result<()>
+	{
+		writer.write_all(array)?;
+		Ok(())
+	}
 }
 
 impl PmmInt32
@@ -477,6 +589,17 @@ impl PmmInt32
 
 		Ok(array)
 	}
+
+	pub fn save_arrays<W:Write>(writer:&mut W, array:&[i32]) -> Result<()>
+	{
+		writer.write_u32::<LittleEndian>(array.len() as u32)?;
+		for it in array
+		{
+			writer.write_i32::<LittleEndian>(*it)?;
+		}
+
+		Ok(())
+	}
 }
 
 impl PmmFloat
@@ -491,6 +614,17 @@ impl PmmFloat
 
 		Ok(array)
 	}
+
+	pub fn save_arrays<W:Write>(writer:&mut W, array:&[f32]) -> Result<()>
+	{
+		writer.write_u32::<LittleEndian>(array.len() as u32)?;
+		for it in array
+		{
+			writer.write_f32::<LittleEndian>(*it)?;
+		}
+
+		Ok(())
+	}
 }
 
 impl PmmHeader
@@ -551,6 +685,27 @@ impl PmmHeader
 
 		this.valid()
 	}
+
+	pub fn save<W:Write>(&self, writer:&mut W) -> Result<()>
+	{
+		PmmName::save_fixed_utf8(writer, &self.magic, 24)?;
+		writer.write_u16::<LittleEndian>(self.reserve1)?;
+		writer.write_u16::<LittleEndian>(self.reserve2)?;
+		writer.write_u16::<LittleEndian>(self.reserve3)?;
+		writer.write_u32::<LittleEndian>(self.view_width)?;
+		writer.write_u32::<LittleEndian>(self.view_height)?;
+		writer.write_u32::<LittleEndian>(self.frame_width)?;
+		writer.write_f32::<LittleEndian>(self.edit_view_angle)?;
+		writer.write_u8(self.is_edit_camera_light_accessory)?;
+		writer.write_u8(self.is_open_camera_panel)?;
+		writer.write_u8(self.is_open_light_panel)?;
+		writer.write_u8(self.is_open_accessory_panel)?;
+		writer.write_u8(self.is_open_bone_panel)?;
+		writer.write_u8(self.is_open_morph_panel)?;
+		writer.write_u8(self.is_open_selfshadow_panel)?;
+		writer.write_u8(self.selected_model_index)?;
+		Ok(())
+	}
 }
 
 impl PmmBoneFrame
@@ -609,6 +764,39 @@ impl PmmBoneFrame
 		let len = reader.read_u32::<LittleEndian>()? as usize;
 		PmmBoneFrame::load_fixed_arrays(reader, len, is_init)
 	}
+
+	pub fn save<W:Write>(&self, writer:&mut W, is_init:bool) -> Result<()>
+	{
+		if !is_init { writer.write_i32::<LittleEndian>(self.data_index)?; }
+		writer.write_i32::<LittleEndian>(self.frame_number)?;
+		writer.write_i32::<LittleEndian>(self.pre_index)?;
+		writer.write_i32::<LittleEndian>(self.next_index)?;
+		writer.write_all(&self.interpolation_x)?;
+		writer.write_all(&self.interpolation_y)?;
+		writer.write_all(&self.interpolation_z)?;
+		writer.write_all(&self.interpolation_rotation)?;
+		PmmVector3::save(writer, self.translation)?;
+		PmmVector4::save(writer, self.quaternion)?;
+		writer.write_u8(self.is_selected)?;
+		writer.write_u8(self.is_physics_disabled)?;
+		Ok(())
+	}
+
+	pub fn save_fixed_arrays<W:Write>(writer:&mut W, array:&[Self], is_init:bool) -> Result<()>
+	{
+		for it in array
+		{
+			it.save(writer, is_init)?;
+		}
+
+		Ok(())
+	}
+
+	pub fn save_arrays<W:Write>(writer:&mut W, array:&[Self], is_init:bool) -> Result<()>
+	{
+		writer.write_u32::<LittleEndian>(array.len() as u32)?;
+		PmmBoneFrame::save_fixed_arrays(writer, array, is_init)
+	}
 }
 
 impl PmmMorphFrame
@@ -655,6 +843,33 @@ impl PmmMorphFrame
 		let len = reader.read_u32::<LittleEndian>()? as usize;
 		PmmMorphFrame::load_fixed_arrays(reader, len, is_init)
 	}
+
+	pub fn save<W:Write>(&self, writer:&mut W, is_init:bool) -> Result<()>
+	{
+		if !is_init { writer.write_i32::<LittleEndian>(self.data_index)?; }
+		writer.write_i32::<LittleEndian>(self.frame_number)?;
+		writer.write_i32::<LittleEndian>(self.pre_index)?;
+		writer.write_i32::<LittleEndian>(self.next_index)?;
+		writer.write_f32::<LittleEndian>(self.value)?;
+		writer.write_u8(self.is_selected)?;
+		Ok(())
+	}
+
+	pub fn save_fixed_arrays<W:Write>(writer:&mut W, array:&[Self], is_init:bool) -> Result<()>
+	{
+		for it in array
+		{
+			it.save(writer, is_init)?;
+		}
+
+		Ok(())
+	}
+
+	pub fn save_arrays<W:Write>(writer:&mut W, array:&[Self], is_init:bool) -> Result<()>
+	{
+		writer.write_u32::<LittleEndian>(array.len() as u32)?;
+		PmmMorphFrame::save_fixed_arrays(writer, array, is_init)
+	}
 }
 
 impl PmmOpFrame
@@ -699,6 +914,31 @@ impl PmmOpFrame
 
 		Ok(array)
 	}
+
+	/// `is_ik_enabled` and `op_data` are written as they are, they have to match the IK and op counts of the model.
+	pub fn save<W:Write>(&self, writer:&mut W, is_init:bool) -> Result<()>
+	{
+		if !is_init { writer.write_i32::<LittleEndian>(self.data_index)?; }
+		writer.write_i32::<LittleEndian>(self.frame_number)?;
+		writer.write_i32::<LittleEndian>(self.pre_index)?;
+		writer.write_i32::<LittleEndian>(self.next_index)?;
+		writer.write_u8(self.is_display)?;
+		PmmUint8::save_fixed_arrays(writer, &self.is_ik_enabled)?;
+		PmmInt2::save_fixed_arrays(writer, &self.op_data)?;
+		writer.write_u8(self.is_selected)?;
+		Ok(())
+	}
+
+	pub fn save_arrays<W:Write>(writer:&mut W, array:&[Self], is_init:bool) -> Result<()>
+	{
+		writer.write_u32::<LittleEndian>(array.len() as u32)?;
+		for it in array
+		{
+			it.save(writer, is_init)?;
+		}
+
+		Ok(())
+	}
 }
 
 impl PmmGravityCurrentData
@@ -735,6 +975,26 @@ impl PmmGravityCurrentData
 
 		Ok(array)
 	}
+
+	pub fn save<W:Write>(&self, writer:&mut W) -> Result<()>
+	{
+		writer.write_f32::<LittleEndian>(self.acceleration)?;
+		writer.write_u32::<LittleEndian>(self.noize_amount)?;
+		PmmVector3::save(writer, self.direction)?;
+		writer.write_u8(self.is_add_noize)?;
+		Ok(())
+	}
+
+	pub fn save_arrays<W:Write>(writer:&mut W, array:&[Self]) -> Result<()>
+	{
+		writer.write_u32::<LittleEndian>(array.len() as u32)?;
+		for it in array
+		{
+			it.save(writer)?;
+		}
+
+		Ok(())
+	}
 }
 
 impl PmmGravityKeyFrame
@@ -781,6 +1041,31 @@ impl PmmGravityKeyFrame
 
 		Ok(array)
 	}
+
+	pub fn save<W:Write>(&self, writer:&mut W, is_init:bool) -> Result<()>
+	{
+		if !is_init { writer.write_i32::<LittleEndian>(self.data_index)?; }
+		writer.write_i32::<LittleEndian>(self.frame_number)?;
+		writer.write_i32::<LittleEndian>(self.pre_index)?;
+		writer.write_i32::<LittleEndian>(self.next_index)?;
+		writer.write_u8(self.is_add_noize)?;
+		writer.write_u32::<LittleEndian>(self.noize_amount)?;
+		writer.write_f32::<LittleEndian>(self.acceleration)?;
+		PmmVector3::save(writer, self.direction)?;
+		writer.write_u8(self.is_selected)?;
+		Ok(())
+	}
+
+	pub fn save_arrays<W:Write>(writer:&mut W, array:&[Self], is_init:bool) -> Result<()>
+	{
+		writer.write_u32::<LittleEndian>(array.len() as u32)?;
+		for it in array
+		{
+			it.save(writer, is_init)?;
+		}
+
+		Ok(())
+	}
 }
 
 impl PmmSelfShadowKeyFrame
@@ -823,6 +1108,29 @@ impl PmmSelfShadowKeyFrame
 
 		Ok(array)
 	}
+
+	pub fn save<W:Write>(&self, writer:&mut W, is_init:bool) -> Result<()>
+	{
+		if !is_init { writer.write_i32::<LittleEndian>(self.data_index)?; }
+		writer.write_i32::<LittleEndian>(self.frame_number)?;
+		writer.write_i32::<LittleEndian>(self.pre_index)?;
+		writer.write_i32::<LittleEndian>(self.next_index)?;
+		writer.write_u8(self.mode)?;
+		writer.write_f32::<LittleEndian>(self.distance)?;
+		writer.write_u8(self.is_selected)?;
+		Ok(())
+	}
+
+	pub fn save_arrays<W:Write>(writer:&mut W, array:&[Self], is_init:bool) -> Result<()>
+	{
+		writer.write_u32::<LittleEndian>(array.len() as u32)?;
+		for it in array
+		{
+			it.save(writer, is_init)?;
+		}
+
+		Ok(())
+	}
 }
 
 impl PmmBoneCurrentData
@@ -861,6 +1169,27 @@ impl PmmBoneCurrentData
 
 		Ok(model)
 	}
+
+	pub fn save<W:Write>(&self, writer:&mut W) -> Result<()>
+	{
+		PmmVector3::save(writer, self.translation)?;
+		PmmVector4::save(writer, self.quaternion)?;
+		writer.write_u8(self.is_edit_un_commited)?;
+		writer.write_u8(self.is_physics_disabled)?;
+		writer.write_u8(self.is_row_selected)?;
+		Ok(())
+	}
+
+	pub fn save_arrays<W:Write>(writer:&mut W, array:&[Self]) -> Result<()>
+	{
+		writer.write_u32::<LittleEndian>(array.len() as u32)?;
+		for it in array
+		{
+			it.save(writer)?;
+		}
+
+		Ok(())
+	}
 }
 
 impl PmmOpCurrentData
@@ -897,6 +1226,26 @@ impl PmmOpCurrentData
 
 		Ok(model)
 	}
+
+	pub fn save<W:Write>(&self, writer:&mut W) -> Result<()>
+	{
+		writer.write_i32::<LittleEndian>(self.keyframe_begin)?;
+		writer.write_i32::<LittleEndian>(self.keyframe_end)?;
+		writer.write_i32::<LittleEndian>(self.model_index)?;
+		writer.write_i32::<LittleEndian>(self.parent_bone_index)?;
+		Ok(())
+	}
+
+	pub fn save_arrays<W:Write>(writer:&mut W, array:&[Self]) -> Result<()>
+	{
+		writer.write_u32::<LittleEndian>(array.len() as u32)?;
+		for it in array
+		{
+			it.save(writer)?;
+		}
+
+		Ok(())
+	}
 }
 
 impl PmmModel
@@ -1005,6 +1354,87 @@ impl PmmModel
 
 		Ok(model)
 	}
+
+	/// The init frames and current data are written per bone, morph, IK and op, the lists must match the name lists.
+	pub fn save<W:Write>(&self, writer:&mut W) -> Result<()>
+	{
+		if self.bone_init_frame.len() != self.bone_name.len() || self.bone_current_datas.len() != self.bone_name.len()
+		{
+			return Err(Error::LoaderError(format!("Model {} needs an init frame and current data per bone", self.name)));
+		}
+
+		if self.morph_init_frame.len() != self.morph_name.len() || self.morph_current_datas.len() != self.morph_name.len()
+		{
+			return Err(Error::LoaderError(format!("Model {} needs an init frame and current data per morph", self.name)));
+		}
+
+		if self.is_current_ik_enabled_datas.len() != self.ik_index.len() || self.op_current_data.len() != self.op_index.len()
+		{
+			return Err(Error::LoaderError(format!("Model {} needs current data per IK and op", self.name)));
+		}
+
+		writer.write_u8(self.number)?;
+		PmmName::save(writer, &self.name)?;
+		PmmName::save(writer, &self.name_en)?;
+		PmmName::save_fixed_utf8(writer, &self.path, 256)?;
+		writer.write_u8(self.keyframe_editor_toplevel_rows)?;
+		PmmName::save_arrays(writer, &self.bone_name)?;
+		PmmName::save_arrays(writer, &self.morph_name)?;
+		PmmInt32::save_arrays(writer, &self.ik_index)?;
+		PmmInt32::save_arrays(writer, &self.op_index)?;
+		writer.write_u8(self.draw_order)?;
+		writer.write_u8(self.edit_is_display)?;
+		writer.write_i32::<LittleEndian>(self.edit_selected_bone)?;
+		for panel in self.skin_panel.iter() { writer.write_i32::<LittleEndian>(*panel)?; }
+		PmmUint8::save_array_from_u8(writer, &self.is_frame_open)?;
+		writer.write_i32::<LittleEndian>(self.vscroll)?;
+		writer.write_i32::<LittleEndian>(self.last_frame)?;
+		PmmBoneFrame::save_fixed_arrays(writer, &self.bone_init_frame, true)?;
+		PmmBoneFrame::save_arrays(writer, &self.bone_key_frame, false)?;
+		PmmMorphFrame::save_fixed_arrays(writer, &self.morph_init_frame, true)?;
+		PmmMorphFrame::save_arrays(writer, &self.morph_key_frame, false)?;
+		self.op_init_frame.save(writer, true)?;
+		PmmOpFrame::save_arrays(writer, &self.op_key_frames, false)?;
+
+		for it in self.bone_current_datas.iter()
+		{
+			it.save(writer)?;
+		}
+
+		for it in self.morph_current_datas.iter()
+		{
+			writer.write_f32::<LittleEndian>(*it)?;
+		}
+
+		PmmUint8::save_fixed_arrays(writer, &self.is_current_ik_enabled_datas)?;
+
+		for it in self.op_current_data.iter()
+		{
+			it.save(writer)?;
+		}
+
+		writer.write_u8(self.is_add_blend)?;
+		writer.write_f32::<LittleEndian>(self.edge_width)?;
+		writer.write_u8(self.is_selfshadow_enabled)?;
+		writer.write_u8(self.calc_order)?;
+		Ok(())
+	}
+
+	pub fn save_arrays<W:Write>(writer:&mut W, array:&[Self]) -> Result<()>
+	{
+		if array.len() > u8::max_value() as usize
+		{
+			return Err(Error::LoaderError(format!("A project holds at most {} models, not {}", u8::max_value(), array.len())));
+		}
+
+		writer.write_u8(array.len() as u8)?;
+		for it in array
+		{
+			it.save(writer)?;
+		}
+
+		Ok(())
+	}
 }
 
 impl PmmCameraFrame
@@ -1069,6 +1499,40 @@ impl PmmCameraFrame
 
 		Ok(model)
 	}
+
+	pub fn save<W:Write>(&self, writer:&mut W, is_init:bool) -> Result<()>
+	{
+		if !is_init { writer.write_i32::<LittleEndian>(self.data_index)?; }
+		writer.write_i32::<LittleEndian>(self.frame_number)?;
+		writer.write_i32::<LittleEndian>(self.pre_index)?;
+		writer.write_i32::<LittleEndian>(self.next_index)?;
+		writer.write_f32::<LittleEndian>(self.distance)?;
+		PmmVector3::save(writer, self.eye_position)?;
+		PmmVector3::save(writer, self.rotation)?;
+		writer.write_i32::<LittleEndian>(self.looking_model_index)?;
+		writer.write_i32::<LittleEndian>(self.looking_bone_index)?;
+		writer.write_all(&self.interpolation_x)?;
+		writer.write_all(&self.interpolation_y)?;
+		writer.write_all(&self.interpolation_z)?;
+		writer.write_all(&self.interpolation_rotation)?;
+		writer.write_all(&self.interpolation_distance)?;
+		writer.write_all(&self.interpolation_angleview)?;
+		writer.write_u8(self.is_parse)?;
+		writer.write_u32::<LittleEndian>(self.angle_view)?;
+		writer.write_u8(self.is_selected)?;
+		Ok(())
+	}
+
+	pub fn save_arrays<W:Write>(writer:&mut W, array:&[Self], is_init:bool) -> Result<()>
+	{
+		writer.write_u32::<LittleEndian>(array.len() as u32)?;
+		for it in array
+		{
+			it.save(writer, is_init)?;
+		}
+
+		Ok(())
+	}
 }
 
 impl PmmCameraCurrentData
@@ -1105,6 +1569,26 @@ impl PmmCameraCurrentData
 
 		Ok(model)
 	}
+
+	pub fn save<W:Write>(&self, writer:&mut W) -> Result<()>
+	{
+		PmmVector3::save(writer, self.eye_position)?;
+		PmmVector3::save(writer, self.target_position)?;
+		PmmVector3::save(writer, self.rotation)?;
+		writer.write_u8(self.isorthro)?;
+		Ok(())
+	}
+
+	pub fn save_arrays<W:Write>(writer:&mut W, array:&[Self]) -> Result<()>
+	{
+		writer.write_u8(array.len() as u8)?;
+		for it in array
+		{
+			it.save(writer)?;
+		}
+
+		Ok(())
+	}
 }
 
 impl PmmLightFrame
@@ -1147,6 +1631,29 @@ impl PmmLightFrame
 
 		Ok(model)
 	}
+
+	pub fn save<W:Write>(&self, writer:&mut W, is_init:bool) -> Result<()>
+	{
+		if !is_init { writer.write_i32::<LittleEndian>(self.data_index)?; }
+		writer.write_i32::<LittleEndian>(self.frame_number)?;
+		writer.write_i32::<LittleEndian>(self.pre_index)?;
+		writer.write_i32::<LittleEndian>(self.next_index)?;
+		PmmVector3::save(writer, self.rgb)?;
+		PmmVector3::save(writer, self.xyz)?;
+		writer.write_u8(self.is_selected)?;
+		Ok(())
+	}
+
+	pub fn save_arrays<W:Write>(writer:&mut W, array:&[Self], is_init:bool) -> Result<()>
+	{
+		writer.write_u32::<LittleEndian>(array.len() as u32)?;
+		for it in array
+		{
+			it.save(writer, is_init)?;
+		}
+
+		Ok(())
+	}
 }
 
 impl PmmLightCurrentData
@@ -1181,6 +1688,25 @@ impl PmmLightCurrentData
 
 		Ok(model)
 	}
+
+	pub fn save<W:Write>(&self, writer:&mut W) -> Result<()>
+	{
+		PmmVector3::save(writer, self.rgb)?;
+		PmmVector3::save(writer, self.xyz)?;
+		writer.write_u8(self.is_selected)?;
+		Ok(())
+	}
+
+	pub fn save_arrays<W:Write>(writer:&mut W, array:&[Self]) -> Result<()>
+	{
+		writer.write_u8(array.len() as u8)?;
+		for it in array
+		{
+			it.save(writer)?;
+		}
+
+		Ok(())
+	}
 }
 
 impl PmmKeyFrame
@@ -1193,32 +1719,56 @@ impl PmmKeyFrame
 			frame_number:-1,
 			pre_index:-1,
 			next_index:-1,
+			data:PmmDataBody::new(),
 			is_selected:0
 		}
 	}
 
-	pub fn load(reader:&mut Cursor<&[u8]>) -> Result<Self>
+	pub fn load(reader:&mut Cursor<&[u8]>, is_init:bool) -> Result<Self>
 	{
 		let mut this = PmmKeyFrame::new();
-		this.data_index = reader.read_i32::<LittleEndian>()?;
+		this.data_index = if is_init { -1 } else { reader.read_i32::<LittleEndian>()? };
 		this.frame_number = reader.read_i32::<LittleEndian>()?;
 		this.pre_index = reader.read_i32::<LittleEndian>()?;
 		this.next_index = reader.read_i32::<LittleEndian>()?;
+		this.data = PmmDataBody::load(reader)?;
 		this.is_selected = reader.read_u8()?;
 
 		Ok(this)
 	}
 
-	pub fn load_arrays(reader:&mut Cursor<&[u8]>) -> Result<Vec<Self>>
+	pub fn load_arrays(reader:&mut Cursor<&[u8]>, is_init:bool) -> Result<Vec<Self>>
 	{
-		let mut model = Vec::with_capacity(reader.read_u8()? as usize);
+		let mut model = Vec::with_capacity(reader.read_u32::<LittleEndian>()? as usize);
 		for _ in 0..model.capacity()
 		{
-			model.push(PmmKeyFrame::load(reader)?);
+			model.push(PmmKeyFrame::load(reader, is_init)?);
 		}
 
 		Ok(model)
 	}
+
+	pub fn save<W:Write>(&self, writer:&mut W, is_init:bool) -> Result<()>
+	{
+		if !is_init { writer.write_i32::<LittleEndian>(self.data_index)?; }
+		writer.write_i32::<LittleEndian>(self.frame_number)?;
+		writer.write_i32::<LittleEndian>(self.pre_index)?;
+		writer.write_i32::<LittleEndian>(self.next_index)?;
+		self.data.save(writer)?;
+		writer.write_u8(self.is_selected)?;
+		Ok(())
+	}
+
+	pub fn save_arrays<W:Write>(writer:&mut W, array:&[Self], is_init:bool) -> Result<()>
+	{
+		writer.write_u32::<LittleEndian>(array.len() as u32)?;
+		for it in array
+		{
+			it.save(writer, is_init)?;
+		}
+
+		Ok(())
+	}
 }
 
 impl PmmDataBody
@@ -1240,9 +1790,12 @@ impl PmmDataBody
 
 	pub fn load(reader:&mut Cursor<&[u8]>) -> Result<Self>
 	{
+		// The lowest bit is the visibility, the others the transparency in percent.
+		let opacity_and_visible = reader.read_u8()?;
+
 		let mut this = PmmDataBody::new();
-		this.transparency = reader.read_u8()?;
-		this.is_visible = 0;
+		this.transparency = opacity_and_visible >> 1;
+		this.is_visible = opacity_and_visible & 1;
 		this.parent_model_index = reader.read_i32::<LittleEndian>()?;
 		this.parent_bone_index = reader.read_i32::<LittleEndian>()?;
 		this.translation = PmmVector3::load(reader)?;
@@ -1263,6 +1816,29 @@ impl PmmDataBody
 
 		Ok(model)
 	}
+
+	pub fn save<W:Write>(&self, writer:&mut W) -> Result<()>
+	{
+		writer.write_u8((self.transparency << 1) | (self.is_visible & 1))?;
+		writer.write_i32::<LittleEndian>(self.parent_model_index)?;
+		writer.write_i32::<LittleEndian>(self.parent_bone_index)?;
+		PmmVector3::save(writer, self.translation)?;
+		PmmVector3::save(writer, self.rotation)?;
+		writer.write_f32::<LittleEndian>(self.scale)?;
+		writer.write_u8(self.is_shadow_enabled)?;
+		Ok(())
+	}
+
+	pub fn save_arrays<W:Write>(writer:&mut W, array:&[Self]) -> Result<()>
+	{
+		writer.write_u8(array.len() as u8)?;
+		for it in array
+		{
+			it.save(writer)?;
+		}
+
+		Ok(())
+	}
 }
 
 impl PmmAccessoryData
@@ -1289,8 +1865,8 @@ impl PmmAccessoryData
 		this.name = PmmName::load_fixed_utf8(reader, 100)?;
 		this.path = PmmName::load_fixed_utf8(reader, 256)?;
 		this.draw_order = reader.read_u8()?;
-		this.init_frame = PmmKeyFrame::load(reader)?;
-		this.key_frames = PmmKeyFrame::load_arrays(reader)?;
+		this.init_frame = PmmKeyFrame::load(reader, true)?;
+		this.key_frames = PmmKeyFrame::load_arrays(reader, false)?;
 		this.current_data = PmmDataBody::load(reader)?;
 		this.is_add_blend = reader.read_u8()?;
 
@@ -1307,6 +1883,30 @@ impl PmmAccessoryData
 
 		Ok(model)
 	}
+
+	pub fn save<W:Write>(&self, writer:&mut W) -> Result<()>
+	{
+		writer.write_u8(self.index)?;
+		PmmName::save_fixed_utf8(writer, &self.name, 100)?;
+		PmmName::save_fixed_utf8(writer, &self.path, 256)?;
+		writer.write_u8(self.draw_order)?;
+		self.init_frame.save(writer, true)?;
+		PmmKeyFrame::save_arrays(writer, &self.key_frames, false)?;
+		self.current_data.save(writer)?;
+		writer.write_u8(self.is_add_blend)?;
+		Ok(())
+	}
+
+	pub fn save_arrays<W:Write>(writer:&mut W, array:&[Self]) -> Result<()>
+	{
+		writer.write_u8(array.len() as u8)?;
+		for it in array
+		{
+			it.save(writer)?;
+		}
+
+		Ok(())
+	}
 }
 
 impl PmmCSelectorChoiceData
@@ -1329,6 +1929,18 @@ impl PmmCSelectorChoiceData
 		Ok(this)
 	}
 
+	/// There is one choice per model without a count, they fill the rest of the file.
+	pub fn load_to_end(reader:&mut Cursor<&[u8]>) -> Result<Vec<Self>>
+	{
+		let mut array = Vec::new();
+		while reader.position() < reader.get_ref().len() as u64
+		{
+			array.push(PmmCSelectorChoiceData::load(reader)?);
+		}
+
+		Ok(array)
+	}
+
 	pub fn load_arrays(reader:&mut Cursor<&[u8]>) -> Result<Vec<Self>>
 	{
 		let mut model = Vec::with_capacity(reader.read_u8()? as usize);
@@ -1339,6 +1951,24 @@ impl PmmCSelectorChoiceData
 
 		Ok(model)
 	}
+
+	pub fn save<W:Write>(&self, writer:&mut W) -> Result<()>
+	{
+		writer.write_u8(self.mode_index)?;
+		writer.write_u32::<LittleEndian>(self.selector_choice)?;
+		Ok(())
+	}
+
+	pub fn save_arrays<W:Write>(writer:&mut W, array:&[Self]) -> Result<()>
+	{
+		writer.write_u8(array.len() as u8)?;
+		for it in array
+		{
+			it.save(writer)?;
+		}
+
+		Ok(())
+	}
 }
 
 impl PMMFile
@@ -1490,14 +2120,903 @@ impl PMMFile
 		pmm.is_physics_ground_enabled = reader.read_u8()?;
 		pmm.frame_text_box = reader.read_u32::<LittleEndian>()?;
 		pmm.selector_choice_selection_following = reader.read_u8()?;
-		pmm.selector_choice_datas = PmmCSelectorChoiceData::load_arrays(&mut reader)?;
+		pmm.selector_choice_datas = PmmCSelectorChoiceData::load_to_end(&mut reader)?;
 
 		Ok(pmm)
 	}
-}
-
-#[derive(Debug)]
-pub struct PMMLoader {}
+
+	pub fn save(&self) -> Result<Vec<u8>>
+	{
+		if self.accessory_name.len() != self.accessory_datas.len()
+		{
+			return Err(Error::LoaderError("Every accessory needs a name and data".to_string()));
+		}
+
+		if self.accessory_datas.len() > u8::max_value() as usize
+		{
+			return Err(Error::LoaderError(format!("A project holds at most {} accessories, not {}", u8::max_value(), self.accessory_datas.len())));
+		}
+
+		let mut writer = Vec::new();
+		self.header.save(&mut writer)?;
+		PmmModel::save_arrays(&mut writer, &self.model)?;
+		self.camera_init_frame.save(&mut writer, true)?;
+		PmmCameraFrame::save_arrays(&mut writer, &self.camera_key_frames, false)?;
+		self.camera_current_data.save(&mut writer)?;
+		self.light_init_frame.save(&mut writer, true)?;
+		PmmLightFrame::save_arrays(&mut writer, &self.light_key_frames, false)?;
+		self.light_current_data.save(&mut writer)?;
+		writer.write_u8(self.selected_accessory_index)?;
+		writer.write_u32::<LittleEndian>(self.accessory_vscroll)?;
+		writer.write_u8(self.accessory_datas.len() as u8)?;
+
+		for name in self.accessory_name.iter()
+		{
+			PmmName::save_fixed_utf8(&mut writer, name, 100)?;
+		}
+
+		for accessory in self.accessory_datas.iter()
+		{
+			accessory.save(&mut writer)?;
+		}
+
+		writer.write_u32::<LittleEndian>(self.current_frame_position)?;
+		writer.write_u32::<LittleEndian>(self.h_scroll_position)?;
+		writer.write_u32::<LittleEndian>(self.h_scroll_scale)?;
+		writer.write_u32::<LittleEndian>(self.bone_operation_kind)?;
+		writer.write_u8(self.looking_at)?;
+		writer.write_u8(self.is_repeat)?;
+		writer.write_u8(self.is_play_from_frame)?;
+		writer.write_u8(self.is_play_to_frame)?;
+		writer.write_u32::<LittleEndian>(self.play_start_frame)?;
+		writer.write_u32::<LittleEndian>(self.play_end_frame)?;
+		writer.write_u8(self.is_wave_enabled)?;
+		PmmName::save_fixed_utf8(&mut writer, &self.wave_path, 256)?;
+		writer.write_u32::<LittleEndian>(self.avi_offset_x)?;
+		writer.write_u32::<LittleEndian>(self.avi_offset_y)?;
+		writer.write_f32::<LittleEndian>(self.avi_scale)?;
+		PmmName::save_fixed_utf8(&mut writer, &self.avi_path, 256)?;
+		writer.write_u32::<LittleEndian>(self.is_show_avi)?;
+		writer.write_u32::<LittleEndian>(self.background_image_offset_x)?;
+		writer.write_u32::<LittleEndian>(self.background_image_offset_y)?;
+		writer.write_u32::<LittleEndian>(self.background_image_scale)?;
+		PmmName::save_fixed_utf8(&mut writer, &self.background_image_path, 255)?;
+		writer.write_u8(self.is_show_background_image)?;
+		writer.write_u8(self.is_show_infomation)?;
+		writer.write_u8(self.is_show_axis)?;
+		writer.write_u8(self.is_show_groundshadow)?;
+		writer.write_f32::<LittleEndian>(self.fps_limit)?;
+		writer.write_u32::<LittleEndian>(self.screen_capture_mode)?;
+		writer.write_u32::<LittleEndian>(self.accessory_number_render_after_model)?;
+		writer.write_f32::<LittleEndian>(self.ground_shadow_brightness)?;
+		writer.write_u8(self.is_transparent_ground_shadow)?;
+		writer.write_u8(self.physics_mode)?;
+		self.gravity_current_data.save(&mut writer)?;
+		self.gravity_init_frame.save(&mut writer, true)?;
+		PmmGravityKeyFrame::save_arrays(&mut writer, &self.gravity_key_frames, false)?;
+		writer.write_u8(self.is_show_selfshadow)?;
+		writer.write_f32::<LittleEndian>(self.selfshadow_current_data)?;
+		self.selfshadow_init_frame.save(&mut writer, true)?;
+		PmmSelfShadowKeyFrame::save_arrays(&mut writer, &self.selfshadow_keyframes, false)?;
+		writer.write_u32::<LittleEndian>(self.edge_color_r)?;
+		writer.write_u32::<LittleEndian>(self.edge_color_g)?;
+		writer.write_u32::<LittleEndian>(self.edge_color_b)?;
+		writer.write_u8(self.is_black_background)?;
+		writer.write_i32::<LittleEndian>(self.camera_current_looking_at_model)?;
+		writer.write_i32::<LittleEndian>(self.camera_current_looking_at_bone)?;
+		for it in self.unknown_array.iter() { writer.write_f32::<LittleEndian>(*it)?; }
+		writer.write_u8(self.is_view_look_at_enabled)?;
+		writer.write_u8(self.unknown)?;
+		writer.write_u8(self.is_physics_ground_enabled)?;
+		writer.write_u32::<LittleEndian>(self.frame_text_box)?;
+		writer.write_u8(self.selector_choice_selection_following)?;
+
+		for it in self.selector_choice_datas.iter()
+		{
+			it.save(&mut writer)?;
+		}
+
+		Ok(writer)
+	}
+}
+
+// Every frame list is a linked list, the init frame is followed through next_index until it ends.
+trait PmmFrame
+{
+	fn data_index(&self) -> i32;
+	fn next_index(&self) -> i32;
+	fn frame_number(&self) -> i32;
+	fn link(&mut self, data_index:i32, pre_index:i32, next_index:i32);
+}
+
+macro_rules! impl_pmm_frame
+{
+	($($t:ty),*) =>
+	{
+		$(impl PmmFrame for $t
+		{
+			fn data_index(&self) -> i32 { self.data_index }
+			fn next_index(&self) -> i32 { self.next_index }
+			fn frame_number(&self) -> i32 { self.frame_number }
+			fn link(&mut self, data_index:i32, pre_index:i32, next_index:i32)
+			{
+				self.data_index = data_index;
+				self.pre_index = pre_index;
+				self.next_index = next_index;
+			}
+		})*
+	}
+}
+
+impl_pmm_frame!(PmmBoneFrame, PmmMorphFrame, PmmOpFrame, PmmCameraFrame, PmmLightFrame, PmmKeyFrame);
+
+fn frame_map<T:PmmFrame>(frames:&[T]) -> HashMap<i32, &T>
+{
+	frames.iter().map(|frame| (frame.data_index(), frame)).collect()
+}
+
+fn frame_chain<'a, T:PmmFrame>(init:&'a T, frames:&HashMap<i32, &'a T>) -> Vec<&'a T>
+{
+	let mut chain = vec![init];
+	let mut next = init.next_index();
+
+	while next > 0 && chain.len() <= frames.len()
+	{
+		match frames.get(&next)
+		{
+			Some(frame) =>
+			{
+				chain.push(*frame);
+				next = frame.next_index();
+			},
+			None => break
+		}
+	}
+
+	chain.sort_by_key(|frame| frame.frame_number());
+	chain
+}
+
+// Key frames count from 1 after the init frame, index 0 ends the chain.
+fn link_frames<T:PmmFrame>(init:&mut T, mut frames:Vec<T>) -> Vec<T>
+{
+	link_frames_from(init, 0, &mut frames, 1);
+	frames
+}
+
+// The init frames of bones and morphs take the indices below the count of the model, so their key frames count from `first`.
+fn link_frames_from<T:PmmFrame>(init:&mut T, init_index:i32, frames:&mut [T], first:i32)
+{
+	init.link(-1, 0, if frames.len() > 0 { first } else { 0 });
+
+	let last = first + frames.len() as i32 - 1;
+	for (i, frame) in frames.iter_mut().enumerate()
+	{
+		let index = first + i as i32;
+		frame.link(index, if i == 0 { init_index } else { index - 1 }, if index < last { index + 1 } else { 0 });
+	}
+}
+
+// Every frame holding a key on any of the channels, the init frame always sits at frame 0.
+fn key_times<T>(clip:&AnimationClip<T>, channels:&[&str]) -> Vec<f32>
+{
+	let mut times:Vec<f32> = channels.iter()
+		.filter_map(|name| clip.curves.get(*name))
+		.flat_map(|curve| curve.frames.iter().map(|key| key.time.round().max(0.0)))
+		.chain(Some(0.0))
+		.collect();
+
+	times.sort_by(|a, b| a.total_cmp(b));
+	times.dedup();
+	times
+}
+
+fn sample(clip:&AnimationClip, name:&str, time:f32, default:f32) -> f32
+{
+	match clip.curves.get(name)
+	{
+		Some(curve) if curve.frames.len() > 0 => curve.evaluate(time),
+		_ => default
+	}
+}
+
+/// MikuMikuDance rolls around Z first, then pitches around X and yaws around Y.
+pub fn pmm_rotation(rotation:(f32,f32,f32)) -> Quaternion
+{
+	Quaternion::euler(&float3::new(rotation.0, rotation.1, rotation.2), RotationOrder::ZXY)
+}
+
+impl PmmModel
+{
+	/// Bone positions as "Position.X/Y/Z" curves, bones which never leave the rest pose are skipped.
+	pub fn collect_motions(&self) -> Animator
+	{
+		let frames = frame_map(&self.bone_key_frame);
+
+		let mut animator = Animator::new();
+		for (name, init) in self.bone_name.iter().zip(self.bone_init_frame.iter())
+		{
+			let chain = frame_chain(init, &frames);
+			if chain.len() == 1 && init.translation == (0.0, 0.0, 0.0) { continue; }
+
+			let mut curves:Vec<AnimationCurve> = (0..3).map(|_| AnimationCurve::new()).collect();
+
+			for (i, frame) in chain.iter().enumerate()
+			{
+				let values = [frame.translation.0, frame.translation.1, frame.translation.2];
+				let beziers = chain.get(i + 1).map(|next| [next.interpolation_x, next.interpolation_y, next.interpolation_z]);
+
+				for (channel, curve) in curves.iter_mut().enumerate()
+				{
+					curve.add_keyframe(frame.frame_number as f32, values[channel], beziers.and_then(|bezier| VMDMotion::path_interpolator(bezier[channel])));
+				}
+			}
+
+			let mut curves = curves.into_iter();
+
+			let mut clip = AnimationClip::new();
+			clip.set_name(name);
+			clip.set_curve("Position.X", curves.next().unwrap());
+			clip.set_curve("Position.Y", curves.next().unwrap());
+			clip.set_curve("Position.Z", curves.next().unwrap());
+
+			animator.add_clip(clip);
+		}
+
+		animator
+	}
+
+	/// One "Rotation" curve per bone, bones which never leave the rest pose are skipped.
+	pub fn collect_rotations(&self) -> Animator<Quaternion>
+	{
+		let frames = frame_map(&self.bone_key_frame);
+
+		let mut animator = Animator::new();
+		for (name, init) in self.bone_name.iter().zip(self.bone_init_frame.iter())
+		{
+			let chain = frame_chain(init, &frames);
+			if chain.len() == 1 && init.quaternion == (0.0, 0.0, 0.0, 1.0) { continue; }
+
+			let mut curve = AnimationCurve::new();
+
+			for (i, frame) in chain.iter().enumerate()
+			{
+				let (x, y, z, w) = frame.quaternion;
+				curve.add_keyframe(frame.frame_number as f32, Quaternion::new(x, y, z, w), chain.get(i + 1).and_then(|next| VMDMotion::path_interpolator(next.interpolation_rotation)));
+			}
+
+			let mut clip = AnimationClip::new();
+			clip.set_name(name);
+			clip.set_curve("Rotation", curve);
+
+			animator.add_clip(clip);
+		}
+
+		animator
+	}
+
+	/// One "Weight" curve per morph, morphs which are never applied are skipped.
+	pub fn collect_morphs(&self) -> Animator
+	{
+		let frames = frame_map(&self.morph_key_frame);
+
+		let mut animator = Animator::new();
+		for (name, init) in self.morph_name.iter().zip(self.morph_init_frame.iter())
+		{
+			let chain = frame_chain(init, &frames);
+			if chain.len() == 1 && init.value == 0.0 { continue; }
+
+			let mut curve = AnimationCurve::new();
+			for frame in chain
+			{
+				curve.add_keyframe(frame.frame_number as f32, frame.value, None);
+			}
+
+			let mut clip = AnimationClip::new();
+			clip.set_name(name);
+			clip.set_curve("Weight", curve);
+
+			animator.add_clip(clip);
+		}
+
+		animator
+	}
+
+	/// MikuMikuDance moves a whole model with its "全ての親" bone, while that bone has no key frames
+	/// its init frame is the placement of the model rather than a motion.
+	pub fn placement(&self) -> Option<(float3, Quaternion)>
+	{
+		let index = self.bone_name.iter().position(|name| name == PMM_ROOT_BONE)?;
+		let init = self.bone_init_frame.get(index)?;
+		if frame_chain(init, &frame_map(&self.bone_key_frame)).len() > 1 { return None; }
+
+		let (x, y, z, w) = init.quaternion;
+		Some((float3::new(init.translation.0, init.translation.1, init.translation.2), Quaternion::new(x, y, z, w)))
+	}
+
+	pub fn collect(&self) -> Motion
+	{
+		let mut motion = Motion::new();
+		motion.name = self.name.clone();
+		motion.bones = self.collect_motions();
+		motion.rotations = self.collect_rotations();
+		motion.morphs = self.collect_morphs();
+		motion
+	}
+
+	/// The record of a model loaded from a project, its motion is written back as bone and morph frames.
+	/// While the "全ての親" bone has no keys the placement of the node goes to its init frame, as `placement` reads it.
+	pub fn from_node(number:u8, node:&SceneNode, source:&PmmModelSource) -> Self
+	{
+		let motion = node.motion();
+		let motion = motion.as_ref().map(|motion| &**motion);
+
+		let mut model = PmmModel::new();
+		model.number = number;
+		model.name = node.name();
+		model.path = source.path.clone();
+		model.bone_name = source.bone_name.clone();
+		model.morph_name = source.morph_name.clone();
+		model.ik_index = source.ik_index.clone();
+		model.op_index = source.op_index.clone();
+		model.draw_order = number;
+		model.edit_is_display = 1;
+
+		let count = model.bone_name.len() as i32;
+		for (i, name) in model.bone_name.iter().enumerate()
+		{
+			let position = motion.and_then(|motion| motion.bones.clips.iter().find(|clip| &clip.name == name));
+			let rotation = motion.and_then(|motion| motion.rotations.clips.iter().find(|clip| &clip.name == name));
+
+			let mut frames = PmmBoneFrame::from_clips(position, rotation);
+			let mut init = frames.remove(0);
+
+			if name == PMM_ROOT_BONE && position.is_none() && rotation.is_none()
+			{
+				init.translation = node.translate().to_tuple();
+				init.quaternion = node.quaternion().to_tuple();
+			}
+
+			link_frames_from(&mut init, i as i32, &mut frames, count + model.bone_key_frame.len() as i32);
+
+			let mut current = PmmBoneCurrentData::new();
+			current.translation = init.translation;
+			current.quaternion = init.quaternion;
+
+			model.bone_current_datas.push(current);
+			model.bone_init_frame.push(init);
+			model.bone_key_frame.extend(frames);
+		}
+
+		let count = model.morph_name.len() as i32;
+		for (i, name) in model.morph_name.iter().enumerate()
+		{
+			let clip = motion.and_then(|motion| motion.morphs.clips.iter().find(|clip| &clip.name == name));
+			let times = clip.map(|clip| key_times(clip, &["Weight"])).unwrap_or_else(|| vec![0.0]);
+
+			let mut frames:Vec<PmmMorphFrame> = times.iter().map(|&time|
+			{
+				let mut morph = PmmMorphFrame::new();
+				morph.frame_number = time as i32;
+				morph.value = clip.map(|clip| sample(clip, "Weight", time, 0.0)).unwrap_or(0.0);
+				morph
+			}).collect();
+
+			let mut init = frames.remove(0);
+			link_frames_from(&mut init, i as i32, &mut frames, count + model.morph_key_frame.len() as i32);
+
+			model.morph_current_datas.push(init.value);
+			model.morph_init_frame.push(init);
+			model.morph_key_frame.extend(frames);
+		}
+
+		let mut op = PmmOpFrame::new();
+		op.frame_number = 0;
+		op.is_display = if node.is_visible() { 1 } else { 0 };
+		op.is_ik_enabled = vec![1; model.ik_index.len()];
+		op.op_data = vec![(-1, -1); model.op_index.len()];
+		link_frames(&mut op, Vec::new());
+
+		model.op_init_frame = op;
+		model.is_current_ik_enabled_datas = vec![1; model.ik_index.len()];
+		model.op_current_data = model.op_index.iter().map(|_| PmmOpCurrentData::new()).collect();
+		model.last_frame = model.bone_key_frame.iter().map(|frame| frame.frame_number)
+			.chain(model.morph_key_frame.iter().map(|frame| frame.frame_number))
+			.max()
+			.unwrap_or(0);
+
+		model
+	}
+}
+
+impl PmmBoneFrame
+{
+	/// One frame per key of the "Position.X/Y/Z" and "Rotation" curves of a bone, the first one is its init frame at frame 0.
+	pub fn from_clips(position:Option<&AnimationClip>, rotation:Option<&AnimationClip<Quaternion>>) -> Vec<Self>
+	{
+		let channels = ["Position.X", "Position.Y", "Position.Z"];
+
+		let mut times = position.map(|clip| key_times(clip, &channels)).unwrap_or_default();
+		times.extend(rotation.map(|clip| key_times(clip, &["Rotation"])).unwrap_or_default());
+		times.push(0.0);
+		times.sort_by(|a, b| a.total_cmp(b));
+		times.dedup();
+
+		let curve = |name:&str| position.and_then(|clip| clip.curves.get(name));
+		let rotation = rotation.and_then(|clip| clip.curves.get("Rotation")).filter(|curve| curve.frames.len() > 0);
+
+		times.iter().map(|&time|
+		{
+			let mut frame = PmmBoneFrame::new();
+			frame.frame_number = time as i32;
+			frame.interpolation_x = VMDMotion::segment_bezier(curve(channels[0]), time);
+			frame.interpolation_y = VMDMotion::segment_bezier(curve(channels[1]), time);
+			frame.interpolation_z = VMDMotion::segment_bezier(curve(channels[2]), time);
+			frame.interpolation_rotation = VMDMotion::segment_bezier(rotation, time);
+
+			if let Some(clip) = position
+			{
+				frame.translation = (sample(clip, channels[0], time, 0.0), sample(clip, channels[1], time, 0.0), sample(clip, channels[2], time, 0.0));
+			}
+
+			if let Some(curve) = rotation
+			{
+				frame.quaternion = curve.evaluate(time).to_tuple();
+			}
+
+			frame
+		}).collect()
+	}
+}
+
+impl PmmCameraFrame
+{
+	/// The point the camera orbits, MikuMikuDance stores it as the eye position.
+	pub fn target(&self) -> float3
+	{
+		float3::new(self.eye_position.0, self.eye_position.1, self.eye_position.2)
+	}
+
+	pub fn rotation(&self) -> Quaternion
+	{
+		pmm_rotation(self.rotation)
+	}
+
+	/// Where the camera is, `distance` away from the target along the rotated Z axis.
+	pub fn eye(&self) -> float3
+	{
+		self.target() + self.rotation().rotate(float3::new(0.0, 0.0, self.distance))
+	}
+
+	pub fn set_bezier(&mut self, points:[u8;4])
+	{
+		self.interpolation_x = points;
+		self.interpolation_y = points;
+		self.interpolation_z = points;
+		self.interpolation_rotation = points;
+		self.interpolation_distance = points;
+		self.interpolation_angleview = points;
+	}
+}
+
+impl PmmAccessoryData
+{
+	/// The transform of the accessory as a single "Position.X/Y/Z" and "Scale" clip with its "Rotation" clip.
+	pub fn collect(&self) -> Motion
+	{
+		let chain = frame_chain(&self.init_frame, &frame_map(&self.key_frames));
+
+		let mut curves:Vec<AnimationCurve> = (0..5).map(|_| AnimationCurve::new()).collect();
+		let mut rotation = AnimationCurve::new();
+
+		for frame in chain.iter()
+		{
+			let data = &frame.data;
+			let values = [data.translation.0, data.translation.1, data.translation.2, data.scale, data.is_visible as f32];
+
+			for (channel, curve) in curves.iter_mut().enumerate()
+			{
+				let interpolator:Option<Box<Interpolator + 'static>> = if channel == 4 { Some(Box::new(FixedInterpolator::new(0.0))) } else { None };
+				curve.add_keyframe(frame.frame_number as f32, values[channel], interpolator);
+			}
+
+			rotation.add_keyframe(frame.frame_number as f32, pmm_rotation(data.rotation), None);
+		}
+
+		let mut curves = curves.into_iter();
+
+		let mut clip = AnimationClip::new();
+		clip.set_name(&self.name);
+		clip.set_curve("Position.X", curves.next().unwrap());
+		clip.set_curve("Position.Y", curves.next().unwrap());
+		clip.set_curve("Position.Z", curves.next().unwrap());
+		clip.set_curve("Scale", curves.next().unwrap());
+		clip.set_curve("Visible", curves.next().unwrap());
+
+		let mut rotations = AnimationClip::new();
+		rotations.set_name(&self.name);
+		rotations.set_curve("Rotation", rotation);
+
+		let mut motion = Motion::new();
+		motion.name = self.name.clone();
+		motion.bones.add_clip(clip);
+		motion.rotations.add_clip(rotations);
+		motion
+	}
+
+	/// The record of an accessory loaded from a project, `collect` read back.
+	/// The node holds the current data, the frames are taken from its motion or from the node when it has none.
+	pub fn from_node(index:u8, node:&SceneNode, source:&PmmAccessorySource, parent_model_index:i32) -> Self
+	{
+		let (translate, rotation, scale) = (node.translate(), node.quaternion(), node.scale());
+
+		let mut current = PmmDataBody::new();
+		current.is_visible = if node.is_visible() { 1 } else { 0 };
+		current.parent_model_index = parent_model_index;
+		current.parent_bone_index = source.parent_bone_index;
+		current.translation = translate.to_tuple();
+		current.rotation = rotation.to_euler(RotationOrder::ZXY).to_tuple();
+		current.scale = scale.x;
+		current.is_shadow_enabled = 1;
+
+		let name = node.name();
+		let motion = node.motion();
+		let clip = motion.as_ref().and_then(|motion| motion.bones.clips.iter().find(|clip| clip.name == name));
+		let rotations = motion.as_ref().and_then(|motion| motion.rotations.clips.iter().find(|clip| clip.name == name));
+
+		let channels = ["Position.X", "Position.Y", "Position.Z", "Scale", "Visible"];
+		let defaults = [translate.x, translate.y, translate.z, scale.x, current.is_visible as f32];
+
+		let mut times = clip.map(|clip| key_times(clip, &channels)).unwrap_or_default();
+		times.extend(rotations.map(|clip| key_times(clip, &["Rotation"])).unwrap_or_default());
+		times.push(0.0);
+		times.sort_by(|a, b| a.total_cmp(b));
+		times.dedup();
+
+		let rotation_curve = rotations.and_then(|clip| clip.curves.get("Rotation")).filter(|curve| curve.frames.len() > 0);
+
+		let mut frames:Vec<PmmKeyFrame> = times.iter().map(|&time|
+		{
+			let values:Vec<f32> = channels.iter().zip(defaults.iter()).map(|(name, value)| clip.map(|clip| sample(clip, name, time, *value)).unwrap_or(*value)).collect();
+			let rotation = rotation_curve.map(|curve| curve.evaluate(time)).unwrap_or(rotation);
+
+			let mut frame = PmmKeyFrame::new();
+			frame.frame_number = time as i32;
+			frame.data.is_visible = if values[4] != 0.0 { 1 } else { 0 };
+			frame.data.parent_model_index = current.parent_model_index;
+			frame.data.parent_bone_index = current.parent_bone_index;
+			frame.data.translation = (values[0], values[1], values[2]);
+			frame.data.rotation = rotation.to_euler(RotationOrder::ZXY).to_tuple();
+			frame.data.scale = values[3];
+			frame.data.is_shadow_enabled = 1;
+			frame
+		}).collect();
+
+		let mut accessory = PmmAccessoryData::new();
+		accessory.index = index;
+		accessory.name = name;
+		accessory.path = source.path.clone();
+		accessory.draw_order = index;
+		accessory.init_frame = frames.remove(0);
+		accessory.key_frames = link_frames(&mut accessory.init_frame, frames);
+		accessory.current_data = current;
+		accessory
+	}
+}
+
+impl PMMFile
+{
+	/// A single "Camera" clip laid out like `VMDFile::collect_cameras`.
+	pub fn collect_cameras(&self) -> Animator
+	{
+		let chain = frame_chain(&self.camera_init_frame, &frame_map(&self.camera_key_frames));
+
+		let mut curves:Vec<AnimationCurve> = (0..9).map(|_| AnimationCurve::new()).collect();
+
+		for (i, camera) in chain.iter().enumerate()
+		{
+			let values = [
+				camera.distance,
+				camera.eye_position.0, camera.eye_position.1, camera.eye_position.2,
+				camera.rotation.0, camera.rotation.1, camera.rotation.2,
+				camera.angle_view as f32,
+				camera.is_parse as f32
+			];
+
+			// The bezier stored on a keyframe shapes the segment which ends at it.
+			let next = chain.get(i + 1);
+
+			for (channel, curve) in curves.iter_mut().enumerate()
+			{
+				let interpolator:Option<Box<Interpolator + 'static>> = match channel
+				{
+					0 => next.and_then(|next| VMDMotion::path_interpolator(next.interpolation_distance)),
+					1 => next.and_then(|next| VMDMotion::path_interpolator(next.interpolation_x)),
+					2 => next.and_then(|next| VMDMotion::path_interpolator(next.interpolation_y)),
+					3 => next.and_then(|next| VMDMotion::path_interpolator(next.interpolation_z)),
+					4 | 5 | 6 => next.and_then(|next| VMDMotion::path_interpolator(next.interpolation_rotation)),
+					7 => next.and_then(|next| VMDMotion::path_interpolator(next.interpolation_angleview)),
+					_ => Some(Box::new(FixedInterpolator::new(0.0))),
+				};
+
+				curve.add_keyframe(camera.frame_number as f32, values[channel], interpolator);
+			}
+		}
+
+		let mut curves = curves.into_iter();
+
+		let mut clip = AnimationClip::new();
+		clip.set_name("Camera");
+		clip.set_curve("Distance", curves.next().unwrap());
+		clip.set_curve("Target.X", curves.next().unwrap());
+		clip.set_curve("Target.Y", curves.next().unwrap());
+		clip.set_curve("Target.Z", curves.next().unwrap());
+		clip.set_curve("Rotation.X", curves.next().unwrap());
+		clip.set_curve("Rotation.Y", curves.next().unwrap());
+		clip.set_curve("Rotation.Z", curves.next().unwrap());
+		clip.set_curve("Fov", curves.next().unwrap());
+		clip.set_curve("Perspective", curves.next().unwrap());
+
+		let mut animator = Animator::new();
+		animator.add_clip(clip);
+		animator
+	}
+
+	/// A single "Light" clip laid out like `VMDFile::collect_lights`.
+	pub fn collect_lights(&self) -> Animator
+	{
+		let chain = frame_chain(&self.light_init_frame, &frame_map(&self.light_key_frames));
+
+		let mut curves:Vec<AnimationCurve> = (0..6).map(|_| AnimationCurve::new()).collect();
+
+		for light in chain
+		{
+			let values = [light.rgb.0, light.rgb.1, light.rgb.2, light.xyz.0, light.xyz.1, light.xyz.2];
+
+			for (channel, curve) in curves.iter_mut().enumerate()
+			{
+				curve.add_keyframe(light.frame_number as f32, values[channel], None);
+			}
+		}
+
+		let mut curves = curves.into_iter();
+
+		let mut clip = AnimationClip::new();
+		clip.set_name("Light");
+		clip.set_curve("Color.R", curves.next().unwrap());
+		clip.set_curve("Color.G", curves.next().unwrap());
+		clip.set_curve("Color.B", curves.next().unwrap());
+		clip.set_curve("Direction.X", curves.next().unwrap());
+		clip.set_curve("Direction.Y", curves.next().unwrap());
+		clip.set_curve("Direction.Z", curves.next().unwrap());
+
+		let mut animator = Animator::new();
+		animator.add_clip(clip);
+		animator
+	}
+
+	/// A new project holding the first camera and light of a scene, with the defaults MikuMikuDance gives a new project.
+	/// Models and accessories are written from the nodes which keep a `PmmModelSource` or `PmmAccessorySource`,
+	/// other nodes have no file a project could refer to and are left out.
+	pub fn from_scene(scene:&Scene) -> Self
+	{
+		let mut pmm = PMMFile::new();
+		pmm.header.magic = "Polygon Movie maker 0002".to_string();
+		pmm.header.view_width = 1920;
+		pmm.header.view_height = 1080;
+		pmm.header.frame_width = 250;
+		pmm.header.edit_view_angle = 30.0;
+		pmm.header.is_open_camera_panel = 1;
+		pmm.header.is_open_light_panel = 1;
+		pmm.header.is_open_accessory_panel = 1;
+		pmm.header.is_open_bone_panel = 1;
+		pmm.header.is_open_morph_panel = 1;
+		pmm.header.is_open_selfshadow_panel = 1;
+
+		pmm.h_scroll_scale = 33554432;
+		pmm.is_show_axis = 1;
+		pmm.is_show_groundshadow = 1;
+		pmm.fps_limit = 60.0;
+		pmm.accessory_number_render_after_model = 1;
+		pmm.ground_shadow_brightness = 1.0;
+		pmm.is_transparent_ground_shadow = 1;
+		pmm.physics_mode = 2;
+		pmm.gravity_current_data.acceleration = 9.8;
+		pmm.gravity_current_data.noize_amount = 10;
+		pmm.gravity_current_data.direction = (0.0, -1.0, 0.0);
+		pmm.gravity_init_frame.data_index = -1;
+		pmm.gravity_init_frame.acceleration = 9.8;
+		pmm.gravity_init_frame.noize_amount = 10;
+		pmm.gravity_init_frame.direction = (0.0, -1.0, 0.0);
+		pmm.is_show_selfshadow = 1;
+		pmm.selfshadow_current_data = 0.01125;
+		pmm.selfshadow_init_frame.data_index = -1;
+		pmm.selfshadow_init_frame.mode = 1;
+		pmm.selfshadow_init_frame.distance = 0.01125;
+		pmm.camera_current_looking_at_model = -1;
+		pmm.unknown_array = [1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0];
+		pmm.is_physics_ground_enabled = 1;
+		pmm.selector_choice_selection_following = 1;
+
+		let mut camera = PmmCameraFrame::new();
+		camera.angle_view = 30;
+		camera.looking_model_index = -1;
+		camera.set_bezier(VMD_BEZIER_LINEAR);
+
+		let mut light = PmmLightFrame::new();
+		light.rgb = (0.602, 0.602, 0.602);
+		light.xyz = (-0.5, -1.0, 0.5);
+
+		// Without a motion the camera is placed at its target, so the eye is kept as it is.
+		if let Some(node) = scene.cameras().first()
+		{
+			let (translate, rotation) = (node.translate(), node.quaternion().to_euler(RotationOrder::ZXY));
+			camera.eye_position = (translate.x, translate.y, translate.z);
+			camera.rotation = (rotation.x, rotation.y, rotation.z);
+			camera.distance = 0.0;
+		}
+
+		if let Some(data) = scene.lights().first().and_then(|node| node.user_data::<LightData>().ok())
+		{
+			light.rgb = (data.color.x, data.color.y, data.color.z);
+			light.xyz = (data.direction.x, data.direction.y, data.direction.z);
+		}
+
+		pmm.camera_init_frame = camera;
+		pmm.light_init_frame = light;
+
+		if let Some(motion) = scene.cameras().first().and_then(|node| node.motion())
+		{
+			pmm.add_cameras(&motion.cameras);
+		}
+
+		if let Some(motion) = scene.lights().first().and_then(|node| node.motion())
+		{
+			pmm.add_lights(&motion.lights);
+		}
+
+		let camera = &pmm.camera_init_frame;
+		let eye = camera.eye();
+		pmm.camera_current_data.eye_position = camera.eye_position;
+		pmm.camera_current_data.target_position = (eye.x, eye.y, eye.z);
+		pmm.camera_current_data.rotation = camera.rotation;
+		pmm.camera_current_data.isorthro = camera.is_parse;
+
+		pmm.light_current_data.rgb = pmm.light_init_frame.rgb;
+		pmm.light_current_data.xyz = pmm.light_init_frame.xyz;
+
+		let models:Vec<(&SceneNode, PmmModelSource)> = scene.models.iter()
+			.filter_map(|node| node.user_data::<PmmModelSource>().ok().map(|source| (node, source)))
+			.collect();
+
+		for (number, (node, source)) in models.iter().enumerate()
+		{
+			pmm.model.push(PmmModel::from_node(number as u8, node, source));
+		}
+
+		let accessories = scene.models.iter().filter_map(|node| node.user_data::<PmmAccessorySource>().ok().map(|source| (node, source)));
+		for (index, (node, source)) in accessories.enumerate()
+		{
+			let parent = node.parent().and_then(|parent| models.iter().position(|(model, _)| model.uuid() == parent.uuid()));
+			let accessory = PmmAccessoryData::from_node(index as u8, node, &source, parent.map(|i| i as i32).unwrap_or(-1));
+
+			pmm.accessory_name.push(accessory.name.clone());
+			pmm.accessory_datas.push(accessory);
+		}
+
+		pmm.accessory_count = pmm.accessory_datas.len() as u8;
+		pmm
+	}
+
+	/// Replaces the camera frames with the keys of a "Camera" clip laid out like `collect_cameras`.
+	pub fn add_cameras(&mut self, animator:&Animator)
+	{
+		let clip = match animator.clips.iter().find(|clip| clip.name == "Camera")
+		{
+			Some(clip) => clip,
+			None => return
+		};
+
+		let channels = ["Distance", "Target.X", "Target.Y", "Target.Z", "Rotation.X", "Rotation.Y", "Rotation.Z", "Fov", "Perspective"];
+		let times = key_times(clip, &channels);
+
+		let init = &self.camera_init_frame;
+		let defaults = [
+			init.distance,
+			init.eye_position.0, init.eye_position.1, init.eye_position.2,
+			init.rotation.0, init.rotation.1, init.rotation.2,
+			init.angle_view as f32,
+			init.is_parse as f32
+		];
+
+		let mut frames:Vec<PmmCameraFrame> = times.iter().map(|&time|
+		{
+			let values:Vec<f32> = channels.iter().zip(defaults.iter()).map(|(name, value)| sample(clip, name, time, *value)).collect();
+
+			let bezier = |name:&str| VMDMotion::segment_bezier(clip.curves.get(name), time);
+
+			let mut camera = PmmCameraFrame::new();
+			camera.frame_number = time.round() as i32;
+			camera.distance = values[0];
+			camera.eye_position = (values[1], values[2], values[3]);
+			camera.rotation = (values[4], values[5], values[6]);
+			camera.looking_model_index = -1;
+			camera.interpolation_distance = bezier("Distance");
+			camera.interpolation_x = bezier("Target.X");
+			camera.interpolation_y = bezier("Target.Y");
+			camera.interpolation_z = bezier("Target.Z");
+			camera.interpolation_rotation = bezier("Rotation.X");
+			camera.interpolation_angleview = bezier("Fov");
+			camera.angle_view = values[7].round().max(0.0) as u32;
+			camera.is_parse = if values[8] != 0.0 { 1 } else { 0 };
+			camera
+		}).collect();
+
+		if frames.len() > 0
+		{
+			self.camera_init_frame = frames.remove(0);
+			self.camera_key_frames = link_frames(&mut self.camera_init_frame, frames);
+		}
+	}
+
+	/// Replaces the light frames with the keys of a "Light" clip laid out like `collect_lights`.
+	pub fn add_lights(&mut self, animator:&Animator)
+	{
+		let clip = match animator.clips.iter().find(|clip| clip.name == "Light")
+		{
+			Some(clip) => clip,
+			None => return
+		};
+
+		let channels = ["Color.R", "Color.G", "Color.B", "Direction.X", "Direction.Y", "Direction.Z"];
+		let times = key_times(clip, &channels);
+
+		let init = &self.light_init_frame;
+		let defaults = [init.rgb.0, init.rgb.1, init.rgb.2, init.xyz.0, init.xyz.1, init.xyz.2];
+
+		let mut frames:Vec<PmmLightFrame> = times.iter().map(|&time|
+		{
+			let values:Vec<f32> = channels.iter().zip(defaults.iter()).map(|(name, value)| sample(clip, name, time, *value)).collect();
+
+			let mut light = PmmLightFrame::new();
+			light.frame_number = time.round() as i32;
+			light.rgb = (values[0], values[1], values[2]);
+			light.xyz = (values[3], values[4], values[5]);
+			light
+		}).collect();
+
+		if frames.len() > 0
+		{
+			self.light_init_frame = frames.remove(0);
+			self.light_key_frames = link_frames(&mut self.light_init_frame, frames);
+		}
+	}
+}
+
+/// Where a model of a project was loaded from, kept on its node so `PMMFile::from_scene` can write the model again.
+#[derive(Debug, Clone)]
+pub struct PmmModelSource
+{
+	pub path:String,
+	pub bone_name:Vec<String>,
+	pub morph_name:Vec<String>,
+	pub ik_index:Vec<i32>,
+	pub op_index:Vec<i32>,
+}
+
+/// Where an accessory of a project was loaded from, with the bone of the model it is bound to.
+#[derive(Debug, Clone)]
+pub struct PmmAccessorySource
+{
+	pub path:String,
+	pub parent_bone_index:i32,
+}
+
+#[derive(Debug)]
+pub struct PMMLoader
+{
+	resolver:Rc<Resolver>,
+}
 
 impl PMMLoader
 {
@@ -1505,43 +3024,138 @@ impl PMMLoader
 	{
 		Self
 		{
+			resolver:Rc::new(FileResolver::new())
 		}
 	}
-}
 
-impl Loader for PMMLoader
-{
-	fn can_read(&self, buf:&[u8]) -> bool
+	/// Models and accessories of the projects are found through the resolver.
+	pub fn with_resolver(resolver:Rc<Resolver>) -> Self
 	{
-		PmmHeader::load(&mut Cursor::new(buf)).is_ok()
+		Self
+		{
+			resolver:resolver
+		}
 	}
 
-	fn do_load(&self, buf:&[u8]) -> Result<Scene>
+	pub fn load_from_pmm(pmm:&PMMFile) -> Result<Scene>
+	{
+		PMMLoader::load_from_pmm_with(pmm, &FileResolver::new())
+	}
+
+	pub fn load_from_pmm_with(pmm:&PMMFile, resolver:&Resolver) -> Result<Scene>
 	{
-		let pmm = PMMFile::load(buf)?;
 		let mut scene = Scene::new();
 
-		let camera = PerspectiveCamera::builder()
+		let init = &pmm.camera_init_frame;
+		let mut camera = PerspectiveCamera::builder()
 			.main(true)
-			.set_fov(30.0)
-			.set_translate(float!(0.0,0.1,10.0))
+			.set_fov(init.angle_view as f32)
+			.set_translate(init.eye())
 			.build();
 
+		camera.set_quaternion(init.rotation());
+		camera.node.set_motion(Some(Rc::new(Motion { cameras:pmm.collect_cameras(), ..Motion::new() })));
 		scene.add(camera);
 
-		for model in pmm.model
+		let init = &pmm.light_init_frame;
+		let mut light = DirectionalLight::builder()
+			.set_color(Spectrum::new(init.rgb.0, init.rgb.1, init.rgb.2))
+			.set_direction(float3::new(init.xyz.0, init.xyz.1, init.xyz.2))
+			.build();
+
+		light.node.set_motion(Some(Rc::new(Motion { lights:pmm.collect_lights(), ..Motion::new() })));
+		scene.add(light);
+
+		let mut models = Vec::with_capacity(pmm.model.len());
+		for model in pmm.model.iter()
 		{
-			let mut model = ModelLoader::open(model.path)?;
-			model.set_scale(float!(0.1,0.1,0.1));
-			model.set_translate(float!(0.0,-0.8,20.0));
-			scene.add(model);
+			let mut shape = PMMLoader::open(resolver, &model.path);
+			shape.set_name(&model.name);
+			shape.set_visible(model.op_init_frame.is_display != 0);
+
+			let mut motion = model.collect();
+			if let Some((translate, rotation)) = model.placement()
+			{
+				shape.set_translate(translate);
+				shape.set_quaternion(rotation);
+
+				// The node holds the placement now, the bone would apply it a second time.
+				motion.bones.clips.retain(|clip| clip.name != PMM_ROOT_BONE);
+				motion.rotations.clips.retain(|clip| clip.name != PMM_ROOT_BONE);
+			}
+
+			shape.node.set_motion(Some(Rc::new(motion)));
+			shape.node.set_user_data(Box::new(PmmModelSource
+			{
+				path:model.path.clone(),
+				bone_name:model.bone_name.clone(),
+				morph_name:model.morph_name.clone(),
+				ik_index:model.ik_index.clone(),
+				op_index:model.op_index.clone(),
+			}));
+
+			models.push(shape.node);
+		}
+
+		let mut accessories = Vec::with_capacity(pmm.accessory_datas.len());
+		for accessory in pmm.accessory_datas.iter()
+		{
+			let data = &accessory.current_data;
+
+			let mut shape = PMMLoader::open(resolver, &accessory.path);
+			shape.set_name(&accessory.name);
+			shape.set_visible(data.is_visible != 0);
+			shape.set_translate(float3::new(data.translation.0, data.translation.1, data.translation.2));
+			shape.set_quaternion(pmm_rotation(data.rotation));
+			shape.set_scale(float3::new(data.scale, data.scale, data.scale));
+			shape.node.set_motion(Some(Rc::new(accessory.collect())));
+			shape.node.set_user_data(Box::new(PmmAccessorySource { path:accessory.path.clone(), parent_bone_index:data.parent_bone_index }));
+
+			// Accessories held by a model follow it, the bone they are bound to is kept for saving but not followed yet.
+			match models.get_mut(data.parent_model_index as usize).filter(|_| data.parent_model_index >= 0)
+			{
+				Some(model) => model.add_child(shape.node),
+				None => accessories.push(shape.node),
+			}
+		}
+
+		for node in models.into_iter().chain(accessories)
+		{
+			scene.add(node);
 		}
 
 		Ok(scene)
 	}
 
-	fn do_save(&self, _:&Scene) -> Result<Vec<u8>>
+	// Projects often refer to files on another machine, those keep their place in the scene without any shapes.
+	fn open(resolver:&Resolver, path:&str) -> ModelShape
+	{
+		match ModelLoader::open_from(resolver, path)
+		{
+			Ok(model) => model,
+			Err(e) =>
+			{
+				warn!("Loading: {} Failed: {:?}", path, e);
+				ModelShape::new(Vec::new())
+			}
+		}
+	}
+}
+
+impl Loader for PMMLoader
+{
+	fn can_read(&self, buf:&[u8]) -> bool
+	{
+		PmmHeader::load(&mut Cursor::new(buf)).is_ok()
+	}
+
+	fn do_load(&self, buf:&[u8]) -> Result<Scene>
+	{
+		PMMLoader::load_from_pmm_with(&PMMFile::load(buf)?, &*self.resolver)
+	}
+
+	fn do_save(&self, scene:&Scene) -> Result<Vec<u8>>
 	{
-  		Err(Error::LoaderError("Not Implmention yet".to_string()))
+		PMMFile::from_scene(scene).save()
 	}
 }
\ No newline at end of file
@@ -1,6 +1,6 @@
 use std::io::{Seek, BufRead};
 use image::{ImageResult, GenericImageView, DynamicImage};
-use super::super::core::Result;
+use super::super::core::{Result, Error, Resolver};
 use super::super::core::{Texture, ColorType};
 
 #[derive(Debug)]
@@ -51,4 +51,61 @@ impl TextureLoader
 	{
 		TextureLoader::load_from_image(image::load_from_memory(buffer), name)
 	}
-}
\ No newline at end of file
+
+	/// Reads a texture through a resolver, it's named after the path it was found at.
+	pub fn load_from_resolver(resolver:&Resolver, path:&str) -> Result<Texture>
+	{
+		match resolver.resolve(path)
+		{
+			Some(path) => TextureLoader::load_from_memory(&resolver.read(&path)?, Some(path)),
+			None => Err(Error::LoaderError(format!("{} not found", path))),
+		}
+	}
+
+	/// Encodes 8 bit textures as PNG, BGR data is stored as RGB and swapped back by `from_png`.
+	pub fn to_png(texture:&Texture) -> Result<Vec<u8>>
+	{
+		let (color, channels) = match texture.color_type()
+		{
+			ColorType::Gray(8) => (image::ColorType::Gray(8), 1),
+			ColorType::GrayA(8) => (image::ColorType::GrayA(8), 2),
+			ColorType::RGB(8) | ColorType::BGR(8) => (image::ColorType::RGB(8), 3),
+			ColorType::RGBA(8) | ColorType::BGRA(8) => (image::ColorType::RGBA(8), 4),
+			color => return Err(Error::LoaderError(format!("PNG doesn't support {:?} textures", color))),
+		};
+
+		let mut pixels = texture.raw_pixels().to_vec();
+		match texture.color_type()
+		{
+			ColorType::BGR(_) | ColorType::BGRA(_) => swap_red_blue(&mut pixels, channels),
+			_ => {},
+		}
+
+		let mut buffer = Vec::new();
+		image::png::PNGEncoder::new(&mut buffer).encode(&pixels, texture.width(), texture.height(), color)?;
+		Ok(buffer)
+	}
+
+	pub fn from_png(buffer:&[u8], color:ColorType, name:Option<String>) -> Result<Texture>
+	{
+		let mut texture = TextureLoader::load_from_image(image::load_from_memory_with_format(buffer, image::ImageFormat::PNG), name)?;
+
+		match color
+		{
+			ColorType::BGR(_) => swap_red_blue(&mut texture.raw_pixels, 3),
+			ColorType::BGRA(_) => swap_red_blue(&mut texture.raw_pixels, 4),
+			_ => {},
+		}
+
+		texture.color = color;
+		Ok(texture)
+	}
+}
+
+fn swap_red_blue(pixels:&mut [u8], channels:usize)
+{
+	for pixel in pixels.chunks_mut(channels)
+	{
+		pixel.swap(0, 2);
+	}
+}
@@ -8,17 +8,21 @@ use crate::math::type_size::*;
 use super::super::core::*;
 use super::super::util::uuid::OsRandNewV4;
 
+/// Bones one draw of a skinned shape reaches. WebGL 1 only promises 128 vertex uniform vectors and each bone takes four,
+/// so shapes with more bones are drawn in batches, see `Indices::split_joints`.
+pub const SKINNING_MAX_JOINTS:usize = 24;
+
 pub const SHADER_VERTEX:&'static str = r#"
 Result main_vs(Args args)
 {
-	float3 position = bindpose[args.bone.x] * args.position * float3(args.weight.x);
-	position += bindpose[args.bone.y] * args.position * float3(args.weight.y);
-	position += bindpose[args.bone.z] * args.position * float3(args.weight.z);
-	position += bindpose[args.bone.w] * args.position * float3(args.weight.w);
+	mat4 skinning = bindpose[int(args.bone.x)] * args.weight.x;
+	skinning += bindpose[int(args.bone.y)] * args.weight.y;
+	skinning += bindpose[int(args.bone.z)] * args.weight.z;
+	skinning += bindpose[int(args.bone.w)] * args.weight.w;
 
 	Result result;
-	result.position = transform(position);
-	result.normal = args.normal;
+	result.position = transform((skinning * float4(args.position, 1.0)).xyz);
+	result.normal = (skinning * float4(args.normal, 0.0)).xyz;
 	result.coord = args.coord;
 	return result;
 }
@@ -74,7 +78,8 @@ pub struct SkinningMaterial
 	pub state:RenderState,
 	pub attribs:Vec<VertexAttrib>,
 	pub uniforms:HashMap<String, Variant>,
-	pub vs:String
+	pub vs:String,
+	pub num_joint:u16
 }
 
 impl SkinningMaterial
@@ -101,28 +106,22 @@ impl SkinningMaterial
 		params.insert("smoothness_map_enable".to_string(), float1::zero().into());
 		params.insert("metalness_map_enable".to_string(), float1::zero().into());
 		params.insert("occlusion_map_enable".to_string(), float1::zero().into());
-		params.insert("bindpose".to_string(), float1::zero().into());
+		params.insert("bindpose".to_string(), Vec::<float4x4>::new().into());
 
 		let mut attribs = Vec::new();
 		let offset1 = float3::type_size() as u16;
 		let offset2 = float3::type_size() as u16;
 		let offset3 = float2::type_size() as u16;
 		let offset4 = float4::type_size() as u16;
-		let offset5 = if num_joint < 256 { u8_4::type_size() as u16 } else { u16_4::type_size() as u16 };
+		let offset5 = u8_4::type_size() as u16;
 		let stride = (offset1 + offset2 + offset3 + offset4 + offset5) as u8;
 		attribs.push(VertexAttrib::new(0, Format::RGBSFloat(8,8,8), stride, 0));
 		attribs.push(VertexAttrib::new(1, Format::RGBSFloat(8,8,8), stride, float3::type_size() as _));
 		attribs.push(VertexAttrib::new(2, Format::RGSFloat(8,8), stride, (float3::type_size() * 2) as _));
 		attribs.push(VertexAttrib::new(3, Format::RGBASFloat(8,8,8,8), stride, offset1 + offset2 + offset3));
 
-		if num_joint < 256
-		{			
-			attribs.push(VertexAttrib::new(4, Format::RGBAUInt(8,8,8,8), stride, offset1 + offset2 + offset3 + offset4));
-		}
-		else
-		{			
-			attribs.push(VertexAttrib::new(4, Format::RGBAUInt(16,16,16,16), stride, offset1 + offset2 + offset3 + offset4));
-		}
+		// Bone indices point into the palette of one batch, which is never more than SKINNING_MAX_JOINTS.
+		attribs.push(VertexAttrib::new(4, Format::RGBAUInt(8,8,8,8), stride, offset1 + offset2 + offset3 + offset4));
 
 		Self
 		{
@@ -130,7 +129,8 @@ impl SkinningMaterial
 			state:RenderState::new(),
 			uniforms:params,
 			attribs:attribs,
-			vs:format!("uniform mat4 bindpose[{}];", num_joint) + SHADER_VERTEX
+			vs:format!("uniform mat4 bindpose[{}];", (num_joint as usize).min(SKINNING_MAX_JOINTS).max(1)) + SHADER_VERTEX,
+			num_joint:num_joint
 		}
 	}
 
@@ -227,6 +227,11 @@ impl SkinningMaterial
 
 impl Material for SkinningMaterial 
 {
+	fn kind(&self) -> MaterialType
+	{
+		MaterialType::Skinning(self.num_joint)
+	}
+
 	fn input_layout(&self) -> &[VertexAttrib]
 	{
 		&self.attribs
@@ -99,6 +99,11 @@ impl SkyboxMaterial
 
 impl Material for SkyboxMaterial 
 {
+	fn kind(&self) -> MaterialType
+	{
+		MaterialType::Skybox
+	}
+
 	fn input_layout(&self) -> &[VertexAttrib]
 	{
 		&self.attribs
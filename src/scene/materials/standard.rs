@@ -204,6 +204,11 @@ impl StandardMaterial
 
 impl Material for StandardMaterial 
 {
+	fn kind(&self) -> MaterialType
+	{
+		MaterialType::Standard
+	}
+
 	fn input_layout(&self) -> &[VertexAttrib]
 	{
 		&self.attribs
@@ -7,6 +7,7 @@ pub mod shapes;
 pub mod materials;
 pub mod scene;
 pub mod loaders;
+pub mod resolvers;
 pub mod util;
 
 pub use self::core::*;
@@ -18,14 +19,22 @@ pub use self::shapes::*;
 pub use self::materials::*;
 pub use self::scene::*;
 pub use self::loaders::*;
+pub use self::resolvers::*;
 pub use self::util::*;
 
+use std::rc::Rc;
 use std::io::prelude::*;
 use std::fs::File;
 
 pub fn load_from_memory(buf:&[u8]) -> Result<Scene>
 {
-	let loaders = vec![Box::new(PMMLoader::new())];
+	load_from_resolver(buf, Rc::new(FileResolver::new()))
+}
+
+/// Loads a scene whose models and textures are found through the resolver.
+pub fn load_from_resolver(buf:&[u8], resolver:Rc<Resolver>) -> Result<Scene>
+{
+	let loaders:Vec<Box<Loader>> = vec![Box::new(PMMLoader::with_resolver(resolver)), Box::new(JsonLoader::new())];
 
 	for loader in loaders
 	{
@@ -43,11 +52,19 @@ pub fn load_from_buf<R:BufRead + Seek>(mut r:R) -> Result<Scene>
 	load_from_memory(r.fill_buf()?)
 }
 
+/// Opens a scene, the files it refers to are also looked for next to it.
 pub fn open<P: AsRef<std::path::Path>>(path:P) -> Result<Scene>
 {
 	let mut buffer = Vec::new();
-	File::open(path)?.read_to_end(&mut buffer)?;
-	load_from_memory(&buffer)
+	File::open(&path)?.read_to_end(&mut buffer)?;
+
+	let mut resolver = FileResolver::new();
+	if let Some(dir) = path.as_ref().parent()
+	{
+		resolver.add_search_path(if dir.as_os_str().is_empty() { std::path::Path::new(".") } else { dir });
+	}
+
+	load_from_resolver(&buffer, Rc::new(resolver))
 }
 
 pub fn to_string(scene:&Scene) -> Result<String>
@@ -0,0 +1,151 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use super::super::core::{Result, Resolver, normalize_path};
+
+/// Reads from the file system, paths which don't exist as written are looked up without case
+/// in every search path, dropping their leading directories until something is found.
+/// When more than one search path holds a match the path is ambiguous and nothing is resolved,
+/// a shorter tail would only be a guess between unrelated files of the same name.
+#[derive(Debug, Clone)]
+pub struct FileResolver
+{
+	search_paths:Vec<PathBuf>,
+}
+
+impl FileResolver
+{
+	pub fn new() -> Self
+	{
+		Self
+		{
+			search_paths:Vec::new()
+		}
+	}
+
+	pub fn builder() -> FileResolverBuilder
+	{
+		FileResolverBuilder::new()
+	}
+
+	pub fn search_paths(&self) -> &[PathBuf]
+	{
+		&self.search_paths
+	}
+
+	pub fn add_search_path<P: AsRef<Path>>(&mut self, path:P) -> &mut Self
+	{
+		self.search_paths.push(path.as_ref().to_path_buf());
+		self
+	}
+
+	fn find(root:&Path, components:&[&str]) -> Option<PathBuf>
+	{
+		let mut path = root.to_path_buf();
+
+		for component in components
+		{
+			let exact = path.join(component);
+			if exact.exists()
+			{
+				path = exact;
+				continue;
+			}
+
+			let name = component.to_lowercase();
+			let entry = fs::read_dir(&path).ok()?
+				.filter_map(|entry| entry.ok())
+				.find(|entry| entry.file_name().to_string_lossy().to_lowercase() == name)?;
+
+			path = entry.path();
+		}
+
+		Some(path)
+	}
+}
+
+impl Resolver for FileResolver
+{
+	fn resolve(&self, path:&str) -> Option<String>
+	{
+		let absolute = Path::new(path).is_absolute();
+		if (absolute || self.search_paths.is_empty()) && Path::new(path).is_file()
+		{
+			return Some(path.to_string());
+		}
+
+		let normalized = normalize_path(path);
+		let components:Vec<&str> = normalized.split('/').filter(|component| !component.is_empty()).collect();
+
+		if normalized.starts_with('/')
+		{
+			if let Some(path) = FileResolver::find(Path::new("/"), &components).filter(|path| path.is_file())
+			{
+				return Some(path.to_string_lossy().into_owned());
+			}
+		}
+
+		let current = [PathBuf::from(".")];
+		let roots = if self.search_paths.len() > 0 { &self.search_paths[..] } else { &current[..] };
+
+		for n in 0..components.len()
+		{
+			// Search paths may overlap, the same file found twice is still one match.
+			let mut found:Vec<(PathBuf, Option<PathBuf>)> = Vec::new();
+			for path in roots.iter().filter_map(|root| FileResolver::find(root, &components[n..])).filter(|path| path.is_file())
+			{
+				let canonical = fs::canonicalize(&path).ok();
+				if found.iter().all(|(_, other)| canonical.is_none() || *other != canonical)
+				{
+					found.push((path, canonical));
+				}
+			}
+
+			match found.len()
+			{
+				0 => continue,
+				1 => return Some(found[0].0.to_string_lossy().into_owned()),
+				_ => return None,
+			}
+		}
+
+		None
+	}
+
+	fn read(&self, path:&str) -> Result<Vec<u8>>
+	{
+		Ok(fs::read(path)?)
+	}
+}
+
+pub struct FileResolverBuilder
+{
+	search_paths:Vec<PathBuf>,
+}
+
+impl FileResolverBuilder
+{
+	#[inline]
+	pub fn new() -> Self
+	{
+		Self
+		{
+			search_paths:Vec::new()
+		}
+	}
+
+	#[inline]
+	pub fn add_search_path<P: AsRef<Path>>(mut self, path:P) -> Self
+	{
+		self.search_paths.push(path.as_ref().to_path_buf());
+		self
+	}
+
+	#[inline]
+	pub fn build(self) -> FileResolver
+	{
+		FileResolver
+		{
+			search_paths:self.search_paths
+		}
+	}
+}
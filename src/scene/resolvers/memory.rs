@@ -0,0 +1,57 @@
+use std::io;
+use std::collections::HashMap;
+use super::super::core::{Result, Resolver, PathIndex, normalize_path};
+
+/// Files kept in memory, for models and textures which never touch the disk.
+#[derive(Debug, Clone)]
+pub struct MemoryResolver
+{
+	files:HashMap<String, Vec<u8>>,
+	index:PathIndex,
+}
+
+impl MemoryResolver
+{
+	pub fn new() -> Self
+	{
+		Self
+		{
+			files:HashMap::new(),
+			index:PathIndex::new(),
+		}
+	}
+
+	pub fn insert(&mut self, path:&str, data:Vec<u8>) -> &mut Self
+	{
+		self.index.insert(path);
+		self.files.insert(normalize_path(path), data);
+		self
+	}
+
+	pub fn paths(&self) -> Vec<String>
+	{
+		self.index.paths()
+	}
+
+	pub fn len(&self) -> usize
+	{
+		self.files.len()
+	}
+}
+
+impl Resolver for MemoryResolver
+{
+	fn resolve(&self, path:&str) -> Option<String>
+	{
+		self.index.find(path)
+	}
+
+	fn read(&self, path:&str) -> Result<Vec<u8>>
+	{
+		match self.files.get(path)
+		{
+			Some(data) => Ok(data.clone()),
+			None => Err(io::Error::new(io::ErrorKind::NotFound, format!("{} not found", path)).into()),
+		}
+	}
+}
@@ -0,0 +1,7 @@
+pub mod file;
+pub mod memory;
+pub mod zip;
+
+pub use self::file::*;
+pub use self::memory::*;
+pub use self::zip::*;
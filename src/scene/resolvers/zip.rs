@@ -0,0 +1,201 @@
+use std::io;
+use std::io::{Cursor, SeekFrom};
+use std::io::prelude::*;
+use std::collections::HashMap;
+use byteorder::{LittleEndian, ReadBytesExt};
+use encoding::{Encoding, DecoderTrap};
+use encoding::all::WINDOWS_31J;
+use super::super::core::{Error, Result, Resolver, PathIndex, normalize_path};
+
+const ZIP_LOCAL_HEADER:u32 = 0x04034b50;
+const ZIP_CENTRAL_HEADER:u32 = 0x02014b50;
+const ZIP_END_OF_CENTRAL_DIRECTORY:u32 = 0x06054b50;
+
+const ZIP_FLAG_ENCRYPTED:u16 = 1 << 0;
+const ZIP_FLAG_UTF8:u16 = 1 << 11;
+
+const ZIP_METHOD_STORED:u16 = 0;
+const ZIP_METHOD_DEFLATED:u16 = 8;
+
+#[derive(Debug, Clone)]
+struct ZipEntry
+{
+	flags:u16,
+	method:u16,
+	compressed_size:u32,
+	size:u32,
+	offset:u32,
+}
+
+/// Entries of a zip archive kept in memory, each one is inflated when it's read.
+/// Names without the UTF-8 flag are decoded as Shift-JIS, the way Japanese archivers write them.
+#[derive(Debug, Clone)]
+pub struct ZipResolver
+{
+	buffer:Vec<u8>,
+	entries:HashMap<String, ZipEntry>,
+	index:PathIndex,
+}
+
+impl ZipResolver
+{
+	pub fn load_from_memory(buffer:Vec<u8>) -> Result<Self>
+	{
+		let mut entries = HashMap::new();
+		let mut index = PathIndex::new();
+
+		{
+			let mut reader = Cursor::new(&buffer[..]);
+			let (count, offset) = ZipResolver::end_of_central_directory(&buffer)?;
+
+			reader.seek(SeekFrom::Start(offset as u64))?;
+
+			for _ in 0..count
+			{
+				if reader.read_u32::<LittleEndian>()? != ZIP_CENTRAL_HEADER
+				{
+					return Err(Error::LoaderError("Invalid central directory in the zip".to_string()));
+				}
+
+				reader.seek(SeekFrom::Current(4))?;
+				let flags = reader.read_u16::<LittleEndian>()?;
+				let method = reader.read_u16::<LittleEndian>()?;
+				reader.seek(SeekFrom::Current(8))?;
+				let compressed_size = reader.read_u32::<LittleEndian>()?;
+				let size = reader.read_u32::<LittleEndian>()?;
+				let name_len = reader.read_u16::<LittleEndian>()?;
+				let extra_len = reader.read_u16::<LittleEndian>()?;
+				let comment_len = reader.read_u16::<LittleEndian>()?;
+				reader.seek(SeekFrom::Current(8))?;
+				let offset = reader.read_u32::<LittleEndian>()?;
+
+				let mut name = vec![0; name_len as usize];
+				reader.read_exact(&mut name)?;
+				reader.seek(SeekFrom::Current(extra_len as i64 + comment_len as i64))?;
+
+				let name = if flags & ZIP_FLAG_UTF8 != 0
+				{
+					String::from_utf8(name)?
+				}
+				else
+				{
+					WINDOWS_31J.decode(&name, DecoderTrap::Replace).unwrap()
+				};
+
+				if name.ends_with('/') || name.ends_with('\\')
+				{
+					continue;
+				}
+
+				if compressed_size == 0xFFFFFFFF || size == 0xFFFFFFFF || offset == 0xFFFFFFFF
+				{
+					return Err(Error::LoaderError(format!("{} needs zip64 which isn't supported", name)));
+				}
+
+				index.insert(&name);
+				entries.insert(normalize_path(&name), ZipEntry { flags, method, compressed_size, size, offset });
+			}
+		}
+
+		Ok(Self
+		{
+			buffer:buffer,
+			entries:entries,
+			index:index,
+		})
+	}
+
+	pub fn paths(&self) -> Vec<String>
+	{
+		self.index.paths()
+	}
+
+	pub fn len(&self) -> usize
+	{
+		self.entries.len()
+	}
+
+	// The record sits at the end of the archive, followed by a comment of at most 64KB.
+	fn end_of_central_directory(buffer:&[u8]) -> Result<(u16, u32)>
+	{
+		if buffer.len() < 22
+		{
+			return Err(Error::LoaderError("Invalid zip archive".to_string()));
+		}
+
+		let first = buffer.len().saturating_sub(22 + 0xFFFF);
+
+		for start in (first..buffer.len() - 21).rev()
+		{
+			let mut reader = Cursor::new(&buffer[start..]);
+			if reader.read_u32::<LittleEndian>()? != ZIP_END_OF_CENTRAL_DIRECTORY
+			{
+				continue;
+			}
+
+			reader.seek(SeekFrom::Current(6))?;
+			let count = reader.read_u16::<LittleEndian>()?;
+			reader.seek(SeekFrom::Current(4))?;
+			let offset = reader.read_u32::<LittleEndian>()?;
+
+			return Ok((count, offset));
+		}
+
+		Err(Error::LoaderError("Missing end of central directory in the zip".to_string()))
+	}
+}
+
+impl Resolver for ZipResolver
+{
+	fn resolve(&self, path:&str) -> Option<String>
+	{
+		self.index.find(path)
+	}
+
+	fn read(&self, path:&str) -> Result<Vec<u8>>
+	{
+		let entry = match self.entries.get(path)
+		{
+			Some(entry) => entry,
+			None => return Err(io::Error::new(io::ErrorKind::NotFound, format!("{} not found", path)).into()),
+		};
+
+		if entry.flags & ZIP_FLAG_ENCRYPTED != 0
+		{
+			return Err(Error::LoaderError(format!("{} is encrypted", path)));
+		}
+
+		let mut reader = Cursor::new(&self.buffer[..]);
+		reader.seek(SeekFrom::Start(entry.offset as u64))?;
+
+		if reader.read_u32::<LittleEndian>()? != ZIP_LOCAL_HEADER
+		{
+			return Err(Error::LoaderError(format!("Invalid local header for {}", path)));
+		}
+
+		reader.seek(SeekFrom::Current(22))?;
+		let name_len = reader.read_u16::<LittleEndian>()?;
+		let extra_len = reader.read_u16::<LittleEndian>()?;
+
+		let start = entry.offset as usize + 30 + name_len as usize + extra_len as usize;
+		let end = start + entry.compressed_size as usize;
+		if end > self.buffer.len()
+		{
+			return Err(Error::LoaderError(format!("{} is truncated", path)));
+		}
+
+		let data = match entry.method
+		{
+			ZIP_METHOD_STORED => self.buffer[start..end].to_vec(),
+			ZIP_METHOD_DEFLATED => inflate::inflate_bytes(&self.buffer[start..end])?,
+			method => return Err(Error::LoaderError(format!("{} uses compression method {} which isn't supported", path, method))),
+		};
+
+		if data.len() != entry.size as usize
+		{
+			return Err(Error::LoaderError(format!("{} has {} bytes instead of {}", path, data.len(), entry.size)));
+		}
+
+		Ok(data)
+	}
+}
@@ -0,0 +1,326 @@
+use std::rc::Rc;
+use std::cell::Cell;
+use std::sync::Arc;
+use std::collections::HashMap;
+use log::*;
+
+use crate::math::{float2, float3, float4};
+use crate::animation::{Animator, AnimationCurve, Evaluate};
+
+use super::{Scene, SceneNode};
+use super::super::core::{Error, Result, LightData, Variant};
+use super::super::spectrum::Spectrum;
+
+/// A property of a scene node driven by a single animation curve.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Property
+{
+	Translate(usize),
+	Rotation(usize),
+	Scale(usize),
+	Visible,
+	/// A float uniform of the material, or one component of a vector uniform, all of them when None.
+	Uniform(String, Option<usize>),
+	LightIntensity,
+	LightColor(usize),
+}
+
+impl Property
+{
+	/// Parses the part of a path after the node name, e.g. "transform.translate.x",
+	/// "material.albedo.r", "light.intensity" or "visible".
+	pub fn parse(path:&str) -> Option<Property>
+	{
+		let parts:Vec<&str> = path.split('.').collect();
+
+		match &parts[..]
+		{
+			["transform", "translate", c] => component(c).filter(|&i| i < 3).map(Property::Translate),
+			["transform", "rotation", c] => component(c).filter(|&i| i < 3).map(Property::Rotation),
+			["transform", "scale", c] => component(c).filter(|&i| i < 3).map(Property::Scale),
+			["visible"] => Some(Property::Visible),
+			["material", name] => Some(Property::Uniform(name.to_string(), None)),
+			["material", name, c] => component(c).map(|i| Property::Uniform(name.to_string(), Some(i))),
+			["light", "intensity"] => Some(Property::LightIntensity),
+			["light", "color", c] => component(c).filter(|&i| i < 3).map(Property::LightColor),
+			_ => None,
+		}
+	}
+}
+
+fn component(name:&str) -> Option<usize>
+{
+	match name
+	{
+		"x" | "r" => Some(0),
+		"y" | "g" => Some(1),
+		"z" | "b" => Some(2),
+		"w" | "a" => Some(3),
+		_ => None,
+	}
+}
+
+fn set_component3(v:float3, i:usize, value:f32) -> float3
+{
+	match i
+	{
+		0 => float3::new(value, v.y, v.z),
+		1 => float3::new(v.x, value, v.z),
+		_ => float3::new(v.x, v.y, value),
+	}
+}
+
+/// A property path resolved against a scene, applying a value needs no lookup.
+#[derive(Debug, Clone)]
+pub struct PropertyBinding
+{
+	pub path:String,
+	pub node:SceneNode,
+	pub property:Property,
+	/// Rotations keep their euler angles here, reading them back from the quaternion breaks beyond 90 degrees.
+	euler:Option<Rc<Cell<float3>>>,
+}
+
+impl PropertyBinding
+{
+	/// Resolves "node_name/property", the node is looked up by name in the scene.
+	pub fn resolve(scene:&Scene, path:&str) -> Result<Self>
+	{
+		let mut split = path.rsplitn(2, '/');
+		let property = split.next().unwrap();
+
+		let name = match split.next()
+		{
+			Some(name) => name,
+			None => return Err(Error::BindingError(format!("{} doesn't name a node", path))),
+		};
+
+		match scene.find(name)
+		{
+			Some(node) => PropertyBinding::resolve_node(&node, property).map(|binding| PropertyBinding { path:path.to_string(), ..binding }),
+			None => Err(Error::BindingError(format!("{} has no node named {}", path, name))),
+		}
+	}
+
+	/// Resolves a property of the node, checking the node has something to drive.
+	pub fn resolve_node(node:&SceneNode, path:&str) -> Result<Self>
+	{
+		let property = match Property::parse(path)
+		{
+			Some(property) => property,
+			None => return Err(Error::BindingError(format!("{} isn't a property", path))),
+		};
+
+		match property
+		{
+			Property::Uniform(ref name, component) =>
+			{
+				let mut data = node.data.borrow_mut();
+
+				// Materials are shared through Arc, only one the node holds alone can be written.
+				let material = match data.material.as_mut()
+				{
+					Some(material) => match Arc::get_mut(material)
+					{
+						Some(material) => material,
+						None => return Err(Error::BindingError(format!("{} is on a material shared with other nodes", path))),
+					},
+					None => return Err(Error::BindingError(format!("{} is on a node without material", path))),
+				};
+
+				let count = match material.uniforms().get(name)
+				{
+					Some(Variant::Float1(_)) => 1,
+					Some(Variant::Float2(_)) => 2,
+					Some(Variant::Float3(_)) => 3,
+					Some(Variant::Float4(_)) => 4,
+					Some(_) => return Err(Error::BindingError(format!("{} isn't a float uniform", path))),
+					None => return Err(Error::BindingError(format!("{} isn't a uniform of the material", path))),
+				};
+
+				if component.filter(|&i| i >= count || count == 1).is_some()
+				{
+					return Err(Error::BindingError(format!("{} has only {} components", path, count)));
+				}
+			},
+			Property::LightIntensity | Property::LightColor(_) =>
+			{
+				if node.user_data::<LightData>().is_err()
+				{
+					return Err(Error::BindingError(format!("{} is on a node which isn't a light", path)));
+				}
+			},
+			_ => {},
+		}
+
+		let euler = match property
+		{
+			Property::Rotation(_) => Some(Rc::new(Cell::new(node.rotation()))),
+			_ => None,
+		};
+
+		Ok(Self
+		{
+			path:path.to_string(),
+			node:node.clone(),
+			property:property,
+			euler:euler,
+		})
+	}
+
+	/// Lets the rotation bindings of one node build their quaternion from the angles all of them set.
+	pub fn share_euler(&mut self, other:&PropertyBinding)
+	{
+		if self.euler.is_some() && other.euler.is_some()
+		{
+			self.euler = other.euler.clone();
+		}
+	}
+
+	/// Fails when the material was shared with another node after it was bound, or the light is gone.
+	pub fn apply(&self, value:f32) -> Result<()>
+	{
+		let mut node = self.node.clone();
+
+		match self.property
+		{
+			Property::Translate(i) => { let v = set_component3(node.translate(), i, value); node.set_translate(v); },
+			Property::Rotation(i) =>
+			{
+				let euler = self.euler.as_ref().unwrap();
+				euler.set(set_component3(euler.get(), i, value));
+				node.set_rotation(euler.get());
+			},
+			Property::Scale(i) => { let v = set_component3(node.scale(), i, value); node.set_scale(v); },
+			Property::Visible => { node.set_visible(value > 0.5); },
+			Property::Uniform(ref name, component) =>
+			{
+				let mut data = node.data.borrow_mut();
+				let material = match data.material.as_mut().and_then(Arc::get_mut)
+				{
+					Some(material) => material,
+					None => return Err(Error::BindingError(format!("{} is on a material shared with other nodes", self.path))),
+				};
+
+				let uniform = match (material.uniforms().get(name), component)
+				{
+					(Some(Variant::Float1(_)), _) => Variant::Float1(value),
+					(Some(Variant::Float2(v)), Some(i)) => Variant::Float2(if i == 0 { float2::new(value, v.y) } else { float2::new(v.x, value) }),
+					(Some(Variant::Float2(_)), None) => Variant::Float2(float2::new(value, value)),
+					(Some(Variant::Float3(v)), Some(i)) => Variant::Float3(set_component3(*v, i, value)),
+					(Some(Variant::Float3(_)), None) => Variant::Float3(float3::new(value, value, value)),
+					(Some(Variant::Float4(v)), Some(i)) =>
+					{
+						let mut c = [v.x, v.y, v.z, v.w];
+						c[i] = value;
+						Variant::Float4(float4::new(c[0], c[1], c[2], c[3]))
+					},
+					(Some(Variant::Float4(_)), None) => Variant::Float4(float4::new(value, value, value, value)),
+					_ => return Err(Error::BindingError(format!("{} isn't a float uniform of the material", self.path))),
+				};
+
+				material.set_uniform(name, uniform);
+			},
+			Property::LightIntensity =>
+			{
+				match node.data.borrow_mut().user_data.downcast_mut::<LightData>()
+				{
+					Some(light) => light.intensity = value,
+					None => return Err(Error::BindingError(format!("{} is on a node which isn't a light", self.path))),
+				}
+			},
+			Property::LightColor(i) =>
+			{
+				match node.data.borrow_mut().user_data.downcast_mut::<LightData>()
+				{
+					Some(light) =>
+					{
+						let (r, g, b) = light.color.to_rgb();
+						let c = set_component3(float3::new(r, g, b), i, value);
+						light.color = Spectrum::new(c.x, c.y, c.z);
+					},
+					None => return Err(Error::BindingError(format!("{} is on a node which isn't a light", self.path))),
+				}
+			},
+		}
+
+		Ok(())
+	}
+}
+
+/// The curves of an animator bound to the scene, each kept beside the property it drives so a frame only samples and applies.
+/// Curves are named by property path, a path without node name drives the node named like its clip.
+#[derive(Debug)]
+pub struct AnimationBinding
+{
+	curves:Vec<(AnimationCurve, PropertyBinding)>,
+	unresolved:Vec<String>,
+}
+
+impl AnimationBinding
+{
+	/// Takes the curves of the animator, the ones which don't resolve are dropped.
+	pub fn new(scene:&Scene, animator:Animator) -> Self
+	{
+		let mut curves = Vec::new();
+		let mut unresolved = Vec::new();
+		let mut rotations = HashMap::new();
+
+		for clip in animator.clips.into_iter()
+		{
+			for (key, curve) in clip.curves.into_iter()
+			{
+				let path = if key.contains('/') { key } else { format!("{}/{}", clip.name, key) };
+
+				match PropertyBinding::resolve(scene, &path)
+				{
+					Ok(mut binding) =>
+					{
+						if let Property::Rotation(_) = binding.property
+						{
+							binding.share_euler(rotations.entry(*binding.node.uuid()).or_insert_with(|| binding.clone()));
+						}
+
+						curves.push((curve, binding));
+					},
+					Err(err) =>
+					{
+						warn!("{}", err);
+						unresolved.push(path);
+					}
+				}
+			}
+		}
+
+		Self
+		{
+			curves:curves,
+			unresolved:unresolved,
+		}
+	}
+
+	/// Paths which didn't resolve, their curves are skipped.
+	pub fn unresolved(&self) -> &[String]
+	{
+		&self.unresolved
+	}
+
+	pub fn bindings(&self) -> Vec<&PropertyBinding>
+	{
+		self.curves.iter().map(|(_, binding)| binding).collect()
+	}
+
+	/// Samples every curve at the time and applies its value. Every binding is applied, the first failure is returned.
+	pub fn apply(&self, time:f32) -> Result<()>
+	{
+		let mut result = Ok(());
+
+		for (curve, binding) in self.curves.iter().filter(|(curve, _)| curve.frames.len() > 0)
+		{
+			let applied = binding.apply(curve.evaluate(time));
+			result = result.and(applied);
+		}
+
+		result
+	}
+}
\ No newline at end of file
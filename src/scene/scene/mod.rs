@@ -1,7 +1,9 @@
 pub mod scene;
 pub mod scene_data;
 pub mod scene_node;
+pub mod binding;
 
 pub use self::scene::*;
 pub use self::scene_data::*;
-pub use self::scene_node::*;
\ No newline at end of file
+pub use self::scene_node::*;
+pub use self::binding::*;
\ No newline at end of file
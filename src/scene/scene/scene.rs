@@ -1,9 +1,19 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use super::{SceneNode, SceneSubData};
-use super::super::core::{Canvas, CameraData, Geometry, Material};
+use super::super::core::{Canvas, CameraData, LightData, Geometry, Material, Texture, Variant};
+use super::super::loaders::TextureLoader;
 use serde::ser::{Serialize, Serializer, SerializeStruct, SerializeSeq};
 
+/// Version of the JSON scene schema written by `Scene`'s `Serialize` impl.
+///
+/// * 0: nodes nest their children, textures are written without pixels and rotations are XYZ euler angles.
+/// * 1: every node is written once with `parent`/`children` uuids, groups are listed in `group`,
+///   textures are embedded as base64 PNG in `textures`, geometries carry skin weights in `w` and mark
+///   32 bit indices with `"format":"U32"`, rotations are quaternions `[x, y, z, w]` and cameras write
+///   their `projection_type`.
+pub const SCENE_VERSION:u32 = 1;
+
 pub struct Scene
 {
 	pub cameras:Vec<SceneNode>,
@@ -78,6 +88,12 @@ impl Scene
 		&self.shapes[..]
 	}
 
+	/// The first node reachable from the scene with the given name.
+	pub fn find(&self, name:&str) -> Option<SceneNode>
+	{
+		self.nodes().into_iter().find(|node| node.name() == name)
+	}
+
 	pub fn num_camera(&self) -> usize
 	{
 		self.cameras.len()
@@ -93,6 +109,33 @@ impl Scene
 		self.shapes.len()
 	}
 
+	/// Every node reachable from the scene, each one once.
+	fn nodes(&self) -> Vec<SceneNode>
+	{
+		fn visit(node:&SceneNode, visited:&mut HashSet<uuid::Uuid>, nodes:&mut Vec<SceneNode>)
+		{
+			if visited.insert(node.uuid)
+			{
+				nodes.push(node.clone());
+
+				for child in node.data.borrow().children.iter()
+				{
+					visit(child, visited, nodes);
+				}
+			}
+		}
+
+		let mut visited = HashSet::new();
+		let mut nodes = Vec::new();
+
+		for node in self.cameras.iter().chain(self.lights.iter()).chain(self.models.iter()).chain(self.shapes.iter())
+		{
+			visit(node, &mut visited, &mut nodes);
+		}
+
+		nodes
+	}
+
 	pub fn update(&mut self, canvas:&Canvas)
 	{
 		for camera in self.cameras.iter_mut()
@@ -110,6 +153,15 @@ impl Scene
 			shape.update(canvas);
 		}
 	}
+
+	/// Poses every camera, light and model with a motion at a frame, called once per frame before rendering.
+	pub fn update_pose(&mut self, frame:f32)
+	{
+		for node in self.cameras.iter_mut().chain(self.lights.iter_mut()).chain(self.models.iter_mut())
+		{
+			node.update_pose(frame);
+		}
+	}
 }
 
 struct ShapeSerialize
@@ -122,15 +174,25 @@ struct MaterialSerialize
 	materials:HashMap<uuid::Uuid, Arc<Material>>
 }
 
+struct TextureSerialize
+{
+	textures:HashMap<uuid::Uuid, Arc<Texture>>
+}
+
+struct TextureData<'a>
+{
+	texture:&'a Texture
+}
+
 impl ShapeSerialize
 {
-	fn new(shapes:&Vec<SceneNode>) -> Self
+	fn new(nodes:&[SceneNode]) -> Self
 	{
 		let mut geometries = HashMap::new();
 
-		for shape in shapes
+		for node in nodes
 		{
-			match shape.geometry()
+			match node.geometry()
 			{
 				Some(ref data) => 
 				{
@@ -153,13 +215,13 @@ impl ShapeSerialize
 
 impl MaterialSerialize
 {
-	fn new(shapes:&Vec<SceneNode>) -> Self
+	fn new(nodes:&[SceneNode]) -> Self
 	{
 		let mut materials = HashMap::new();
 
-		for shape in shapes
+		for node in nodes
 		{
-			match shape.material()
+			match node.material()
 			{
 				Some(ref data) => 
 				{
@@ -180,6 +242,41 @@ impl MaterialSerialize
 	}
 }
 
+impl TextureSerialize
+{
+	fn new(nodes:&[SceneNode]) -> Self
+	{
+		let mut textures = HashMap::new();
+
+		for node in nodes
+		{
+			if let Some(material) = node.material()
+			{
+				for value in material.uniforms().values()
+				{
+					if let Variant::Texture(Some(ref texture)) = value
+					{
+						textures.entry(texture.uuid).or_insert(texture.clone());
+					}
+				}
+			}
+
+			if let Ok(light) = node.user_data::<LightData>()
+			{
+				for texture in light.radiance.iter().chain(light.irradiance.iter())
+				{
+					textures.entry(texture.uuid).or_insert(texture.clone());
+				}
+			}
+		}
+
+		Self
+		{
+			textures
+		}
+	}
+}
+
 impl Serialize for ShapeSerialize
 {
 	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -198,7 +295,7 @@ impl Serialize for MaterialSerialize
 	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>
 	{
 		let mut s = serializer.serialize_seq(Some(self.materials.len()))?;
-		for material in self.materials.iter()
+		for material in self.materials.values()
 		{
 			s.serialize_element(&material)?;
 		}
@@ -206,16 +303,59 @@ impl Serialize for MaterialSerialize
 	}
 }
 
+impl Serialize for TextureSerialize
+{
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	{
+		let mut s = serializer.serialize_seq(Some(self.textures.len()))?;
+		for texture in self.textures.values()
+		{
+			s.serialize_element(&TextureData { texture:texture })?;
+		}
+		s.end()
+	}
+}
+
+impl<'a> Serialize for TextureData<'a>
+{
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	{
+		let texture = self.texture;
+
+		let mut s = serializer.serialize_struct("texture", 7)?;
+		s.serialize_field("uuid", &texture.uuid)?;
+		s.serialize_field("name", &texture.name)?;
+		s.serialize_field("sampler", &texture.sampler)?;
+		s.serialize_field("width", &texture.width)?;
+		s.serialize_field("height", &texture.height)?;
+		s.serialize_field("color", &texture.color)?;
+
+		match TextureLoader::to_png(texture)
+		{
+			Ok(png) => s.serialize_field("image", &base64::encode(&png))?,
+			Err(_) => s.serialize_field("pixels", &base64::encode(&texture.raw_pixels))?,
+		}
+
+		s.end()
+	}
+}
+
 impl Serialize for Scene
 {
 	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>
 	{
-		let mut s = serializer.serialize_struct("scene", 5)?;
-		s.serialize_field("model", &ShapeSerialize::new(&self.shapes))?;
-		s.serialize_field("materials", &MaterialSerialize::new(&self.shapes))?;
-		s.serialize_field("shape", &self.shapes)?;
-		s.serialize_field("camera", &self.cameras)?;
-		s.serialize_field("light", &self.lights)?;
+		let nodes = self.nodes();
+		let kind = |kind:SceneSubData| nodes.iter().filter(|node| node.kind() == kind).collect::<Vec<_>>();
+
+		let mut s = serializer.serialize_struct("scene", 9)?;
+		s.serialize_field("version", &SCENE_VERSION)?;
+		s.serialize_field("textures", &TextureSerialize::new(&nodes))?;
+		s.serialize_field("model", &ShapeSerialize::new(&nodes))?;
+		s.serialize_field("materials", &MaterialSerialize::new(&nodes))?;
+		s.serialize_field("shape", &kind(SceneSubData::Shape))?;
+		s.serialize_field("camera", &kind(SceneSubData::Camera))?;
+		s.serialize_field("light", &kind(SceneSubData::Light))?;
+		s.serialize_field("group", &kind(SceneSubData::Group))?;
 		s.end()
 	}
-}
\ No newline at end of file
+}
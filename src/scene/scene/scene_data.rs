@@ -1,11 +1,15 @@
+use std::rc::{Rc, Weak};
 use std::sync::Arc;
 use std::boxed::Box;
 use std::any::Any;
+use std::cell::RefCell;
 use serde::ser::{Serialize, SerializeStruct, Serializer};
-use crate::math::{float3, float4x4};
+use crate::math::{float3, float4x4, Quaternion, RotationOrder, One};
+use crate::animation::Motion;
+use crate::models::Skeleton;
 
 use super::SceneNode;
-use super::super::core::{Transform, Geometry, Material, Canvas};
+use super::super::core::{Transform, Geometry, Material, Canvas, CameraData, LightData};
 use super::super::util::uuid::OsRandNewV4;
 
 #[derive(Debug, Copy, PartialEq, Clone, Serialize, Deserialize)]
@@ -22,13 +26,21 @@ pub struct SceneData
 	pub visible:bool,
 	pub name:String,
 	pub uuid:uuid::Uuid,
-	pub transform:Transform,
 	pub kind:SceneSubData,
 	pub user_data:Box<Any + 'static>,
 	pub geometry:Option<Arc<Geometry + 'static>>,
 	pub material:Option<Arc<Material + 'static>>,
+	pub motion:Option<Rc<Motion>>,
+	pub joints:Option<Rc<Vec<float4x4>>>,
+	pub skeleton:Option<Rc<RefCell<Skeleton>>>,
 	pub children:Vec<SceneNode>,
+	pub parent:Option<Weak<RefCell<SceneData>>>,
 	pub dispatch:Option<fn(&mut Self, &Canvas)>,
+	// Changed only through the setters, which mark the world transform of the subtree dirty.
+	transform:Transform,
+	world_transform:RefCell<float4x4>,
+	world_transform_inverse:RefCell<float4x4>,
+	need_update:RefCell<bool>,
 }
 
 impl SceneData
@@ -47,9 +59,16 @@ impl SceneData
 			transform:Transform::new(),
 			user_data:Box::new(user_data),
 			children:Vec::new(),
+			parent:None,
 			geometry:None,
 			material:None,
-			dispatch:None
+			motion:None,
+			joints:None,
+			skeleton:None,
+			dispatch:None,
+			world_transform:RefCell::new(float4x4::one()),
+			world_transform_inverse:RefCell::new(float4x4::one()),
+			need_update:RefCell::new(true),
 		}
 	}
 
@@ -114,7 +133,9 @@ impl SceneData
 
 		if remove_item < self.children.len()
 		{
-			self.children.remove(remove_item);
+			let child = self.children.remove(remove_item);
+			child.data.borrow_mut().parent = None;
+			child.data.borrow().set_world_dirty();
 		}
 
 		self
@@ -136,6 +157,7 @@ impl SceneData
 	pub fn set_translate(&mut self, pos:float3) -> &mut Self
 	{
 		self.transform.set_translate(pos);
+		self.set_world_dirty();
 		self
 	}
 
@@ -149,6 +171,7 @@ impl SceneData
 	pub fn set_scale(&mut self, sz:float3) -> &mut Self
 	{
 		self.transform.set_scale(sz);
+		self.set_world_dirty();
 		self
 	}
 
@@ -162,6 +185,51 @@ impl SceneData
 	pub fn set_rotation(&mut self, rot:float3) -> &mut Self
 	{
 		self.transform.set_rotation(rot);
+		self.set_world_dirty();
+		self
+	}
+
+	#[inline(always)]
+	pub fn quaternion(&self) -> Quaternion
+	{
+		self.transform.quaternion()
+	}
+
+	#[inline(always)]
+	pub fn set_quaternion(&mut self, rot:Quaternion) -> &mut Self
+	{
+		self.transform.set_quaternion(rot);
+		self.set_world_dirty();
+		self
+	}
+
+	#[inline(always)]
+	pub fn euler(&self, order:RotationOrder) -> float3
+	{
+		self.transform.euler(order)
+	}
+
+	#[inline(always)]
+	pub fn set_euler(&mut self, euler:float3, order:RotationOrder) -> &mut Self
+	{
+		self.transform.set_euler(euler, order);
+		self.set_world_dirty();
+		self
+	}
+
+	#[inline(always)]
+	pub fn set_matrix(&mut self, m:&float4x4) -> &mut Self
+	{
+		self.transform.set_matrix(m);
+		self.set_world_dirty();
+		self
+	}
+
+	#[inline(always)]
+	pub fn look_at(&mut self, target:float3, up:float3) -> &mut Self
+	{
+		self.transform.look_at(target, up);
+		self.set_world_dirty();
 		self
 	}
 
@@ -177,6 +245,64 @@ impl SceneData
 		self.transform.transform_inverse()
 	}
 
+	/// Local transform composed with every parent up to the root.
+	#[inline]
+	pub fn world_transform(&self) -> float4x4
+	{
+		self.update_world();
+		*self.world_transform.borrow()
+	}
+
+	#[inline]
+	pub fn world_transform_inverse(&self) -> float4x4
+	{
+		self.update_world();
+		*self.world_transform_inverse.borrow()
+	}
+
+	#[inline]
+	pub fn parent(&self) -> Option<SceneNode>
+	{
+		self.parent.as_ref().and_then(|parent| parent.upgrade()).map(|data|
+		{
+			let uuid = *data.borrow().uuid();
+			SceneNode { uuid:uuid, data:data }
+		})
+	}
+
+	/// Marks the world transform of this node and all of its descendants as out of date.
+	pub fn set_world_dirty(&self)
+	{
+		self.need_update.replace(true);
+
+		for child in self.children.iter()
+		{
+			child.data.borrow().set_world_dirty();
+		}
+	}
+
+	fn update_world(&self)
+	{
+		if *self.need_update.borrow()
+		{
+			// A parent borrowed mutably, like from one of its own methods, can't be read. The local transform
+			// stands in for the world transform and stays dirty until the parent can be read again.
+			let (transform, ready) = match self.parent.as_ref().and_then(|parent| parent.upgrade())
+			{
+				Some(parent) => match parent.try_borrow()
+				{
+					Ok(parent) => (parent.world_transform() * self.transform(), true),
+					Err(_) => (self.transform(), false),
+				},
+				None => (self.transform(), true)
+			};
+
+			self.world_transform.replace(transform);
+			self.world_transform_inverse.replace(transform.transform_inverse());
+			self.need_update.replace(!ready);
+		}
+	}
+
 	#[inline(always)]
 	pub fn geometry(&self) -> Option<Arc<Geometry + 'static>>
 	{
@@ -203,6 +329,45 @@ impl SceneData
 		self
 	}
 
+	#[inline(always)]
+	pub fn motion(&self) -> Option<Rc<Motion>>
+	{
+		self.motion.clone()
+	}
+
+	#[inline(always)]
+	pub fn set_motion(&mut self, motion:Option<Rc<Motion>>) -> &mut Self
+	{
+		self.motion = motion;
+		self
+	}
+
+	#[inline(always)]
+	pub fn joints(&self) -> Option<Rc<Vec<float4x4>>>
+	{
+		self.joints.clone()
+	}
+
+	#[inline(always)]
+	pub fn set_joints(&mut self, joints:Option<Rc<Vec<float4x4>>>) -> &mut Self
+	{
+		self.joints = joints;
+		self
+	}
+
+	#[inline(always)]
+	pub fn skeleton(&self) -> Option<Rc<RefCell<Skeleton>>>
+	{
+		self.skeleton.clone()
+	}
+
+	#[inline(always)]
+	pub fn set_skeleton(&mut self, skeleton:Option<Rc<RefCell<Skeleton>>>) -> &mut Self
+	{
+		self.skeleton = skeleton;
+		self
+	}
+
 	#[inline(always)]
 	pub fn user_data(&self) -> &Box<Any + 'static>
 	{
@@ -276,9 +441,24 @@ impl Serialize for SceneData
 			_ => {}
 		}
 
+		if let Some(data) = self.user_data.downcast_ref::<CameraData>()
+		{
+			s.serialize_field("camera", data)?;
+		}
+
+		if let Some(data) = self.user_data.downcast_ref::<LightData>()
+		{
+			s.serialize_field("light", data)?;
+		}
+
+		if let Some(parent) = self.parent()
+		{
+			s.serialize_field("parent", &parent.uuid)?;
+		}
+
 		if self.children.len() > 0
 		{
-			s.serialize_field("children", &self.children)?;
+			s.serialize_field("children", &self.children.iter().map(|child| child.uuid).collect::<Vec<_>>())?;
 		}
 
 		s.end()
@@ -3,10 +3,13 @@ use std::cell::RefCell;
 use std::sync::Arc;
 use std::any::Any;
 use serde::ser::{Serialize, Serializer};
-use crate::math::{float3, float4x4};
+use crate::math::{float3, float4x4, Quaternion, RotationOrder};
+use crate::animation::{Motion, Animator, Evaluate};
+use crate::models::Skeleton;
 
 use super::{SceneData, SceneSubData};
-use super::super::core::{Geometry, Material, Canvas};
+use super::super::core::{Geometry, Material, Canvas, CameraData, LightData};
+use super::super::spectrum::Spectrum;
 
 #[derive(Clone)]
 pub struct SceneNode
@@ -65,6 +68,66 @@ impl SceneNode
 		self.data.borrow().transform_inverse()
 	}
 
+	#[inline(always)]
+	pub fn world_transform(&self) -> float4x4
+	{
+		self.data.borrow().world_transform()
+	}
+
+	#[inline(always)]
+	pub fn world_transform_inverse(&self) -> float4x4
+	{
+		self.data.borrow().world_transform_inverse()
+	}
+
+	#[inline(always)]
+	pub fn parent(&self) -> Option<SceneNode>
+	{
+		self.data.borrow().parent()
+	}
+
+	/// Whether the node is `node` itself or lies somewhere below it.
+	pub fn is_descendant_of(&self, node:&SceneNode) -> bool
+	{
+		let mut ancestor = Some(self.clone());
+		while let Some(parent) = ancestor
+		{
+			if parent.uuid == node.uuid { return true; }
+			ancestor = parent.parent();
+		}
+
+		false
+	}
+
+	/// Moves the node under another parent (or to the root) while keeping its world transform.
+	pub fn set_parent(&mut self, parent:Option<&mut SceneNode>) -> &mut Self
+	{
+		if let Some(ref parent) = parent
+		{
+			if parent.is_descendant_of(self) { return self; }
+		}
+
+		let world = self.world_transform();
+
+		if let Some(old) = self.parent()
+		{
+			old.data.borrow_mut().remove_child(self);
+		}
+
+		let local = match parent
+		{
+			Some(parent) =>
+			{
+				parent.add_child(self.clone());
+				parent.world_transform_inverse() * world
+			},
+			None => world
+		};
+
+		self.set_matrix(&local);
+		self
+	}
+
 	#[inline(always)]
 	pub fn name(&self) -> String
 	{
@@ -117,6 +180,46 @@ impl SceneNode
 		self
 	}
 
+	#[inline(always)]
+	pub fn quaternion(&self) -> Quaternion
+	{
+		self.data.borrow().quaternion()
+	}
+
+	#[inline(always)]
+	pub fn set_quaternion(&mut self, rot:Quaternion) -> &mut SceneNode
+	{
+		self.data.borrow_mut().set_quaternion(rot);
+		self
+	}
+
+	#[inline(always)]
+	pub fn euler(&self, order:RotationOrder) -> float3
+	{
+		self.data.borrow().euler(order)
+	}
+
+	#[inline(always)]
+	pub fn set_euler(&mut self, euler:float3, order:RotationOrder) -> &mut SceneNode
+	{
+		self.data.borrow_mut().set_euler(euler, order);
+		self
+	}
+
+	#[inline(always)]
+	pub fn set_matrix(&mut self, m:&float4x4) -> &mut SceneNode
+	{
+		self.data.borrow_mut().set_matrix(m);
+		self
+	}
+
+	#[inline(always)]
+	pub fn look_at(&mut self, target:float3, up:float3) -> &mut SceneNode
+	{
+		self.data.borrow_mut().look_at(target, up);
+		self
+	}
+
 	#[inline(always)]
 	pub fn geometry(&self) -> Option<Arc<Geometry + 'static>>
 	{
@@ -143,6 +246,165 @@ impl SceneNode
 		self
 	}
 
+	#[inline(always)]
+	pub fn motion(&self) -> Option<Rc<Motion>>
+	{
+		self.data.borrow().motion()
+	}
+
+	#[inline(always)]
+	pub fn set_motion(&mut self, motion:Option<Rc<Motion>>) -> &mut Self
+	{
+		self.data.borrow_mut().set_motion(motion);
+		self
+	}
+
+	/// The skinning matrices drawn with a skinned material, shared by the shapes of a model.
+	#[inline(always)]
+	pub fn joints(&self) -> Option<Rc<Vec<float4x4>>>
+	{
+		self.data.borrow().joints()
+	}
+
+	#[inline(always)]
+	pub fn set_joints(&mut self, joints:Option<Rc<Vec<float4x4>>>) -> &mut Self
+	{
+		self.data.borrow_mut().set_joints(joints);
+		self
+	}
+
+	/// The bones of a model, posed by `update_pose` and drawn by the shapes below the node.
+	#[inline(always)]
+	pub fn skeleton(&self) -> Option<Rc<RefCell<Skeleton>>>
+	{
+		self.data.borrow().skeleton()
+	}
+
+	#[inline(always)]
+	pub fn set_skeleton(&mut self, skeleton:Option<Rc<RefCell<Skeleton>>>) -> &mut Self
+	{
+		self.data.borrow_mut().set_skeleton(skeleton);
+		self
+	}
+
+	/// Shares the palette of a posed skeleton with every shape below the node.
+	pub fn set_pose(&mut self, skeleton:&Skeleton) -> &mut Self
+	{
+		let joints = Some(Rc::new(skeleton.palette()));
+
+		for child in self.data.borrow().children.iter()
+		{
+			child.clone().set_joints(joints.clone());
+		}
+
+		self
+	}
+
+	/// Poses the node with its motion at a frame, the skeleton and morphs of a model or the clips of a camera or a light.
+	/// Nodes without a motion are left as they are.
+	pub fn update_pose(&mut self, frame:f32) -> &mut Self
+	{
+		let motion = match self.motion()
+		{
+			Some(motion) => motion,
+			None => return self,
+		};
+
+		if self.kind() == SceneSubData::Camera
+		{
+			return self.pose_camera(&motion.cameras, frame);
+		}
+
+		if self.kind() == SceneSubData::Light
+		{
+			return self.pose_light(&motion.lights, frame);
+		}
+
+		if let Some(skeleton) = self.skeleton()
+		{
+			let mut skeleton = skeleton.borrow_mut();
+			skeleton.sample(&motion, frame);
+			skeleton.update();
+			self.set_pose(&skeleton);
+		}
+
+		self.pose_morphs(&motion.morphs, frame)
+	}
+
+	/// Weighs the morphs of the geometries of the node and the shapes below it by the "Weight" clips at a frame.
+	/// Morphs without a clip keep their weight.
+	pub fn pose_morphs(&mut self, morphs:&Animator, frame:f32) -> &mut Self
+	{
+		let mut geometries:Vec<_> = self.data.borrow().children.iter().filter_map(|child| child.geometry()).collect();
+		geometries.extend(self.geometry());
+		geometries.retain(|geometry| geometry.num_morph_targets() > 0);
+
+		if geometries.is_empty()
+		{
+			return self;
+		}
+
+		for clip in morphs.clips.iter()
+		{
+			if let Some(curve) = clip.curves.get("Weight").filter(|curve| curve.frames.len() > 0)
+			{
+				let weight = curve.evaluate(frame);
+				for geometry in geometries.iter()
+				{
+					geometry.set_morph_weight(&clip.name, weight);
+				}
+			}
+		}
+
+		self
+	}
+
+	/// Places a camera by the "Camera" clip at a frame the way MMD does, `Distance` along the Z axis turned by
+	/// the ZXY euler `Rotation` from `Target`, facing the target, with a vertical `Fov` in degrees.
+	pub fn pose_camera(&mut self, cameras:&Animator, frame:f32) -> &mut Self
+	{
+		if let Some(clip) = cameras.clips.iter().find(|clip| clip.name == "Camera")
+		{
+			let channel = |name:&str| clip.curves.get(name).filter(|curve| curve.frames.len() > 0).map(|curve| curve.evaluate(frame));
+			let vector = |x, y, z| float3::new(channel(x).unwrap_or(0.0), channel(y).unwrap_or(0.0), channel(z).unwrap_or(0.0));
+
+			let target = vector("Target.X", "Target.Y", "Target.Z");
+			let rotation = Quaternion::euler(&vector("Rotation.X", "Rotation.Y", "Rotation.Z"), RotationOrder::ZXY);
+			let distance = channel("Distance").unwrap_or(0.0);
+
+			// MMD keeps the distance negative, so the eye sits behind the target and the rotation alone looks at it.
+			self.set_translate(target + rotation.rotate(float3::new(0.0, 0.0, distance)));
+			self.set_quaternion(rotation);
+
+			if let Some(fov) = channel("Fov")
+			{
+				if let Some(data) = self.data.borrow_mut().user_data.downcast_mut::<CameraData>()
+				{
+					data.fov = fov;
+				}
+			}
+		}
+
+		self
+	}
+
+	/// Sets the color and direction of a light from the "Light" clip at a frame.
+	pub fn pose_light(&mut self, lights:&Animator, frame:f32) -> &mut Self
+	{
+		if let Some(clip) = lights.clips.iter().find(|clip| clip.name == "Light")
+		{
+			let channel = |name:&str| clip.curves.get(name).filter(|curve| curve.frames.len() > 0).map(|curve| curve.evaluate(frame)).unwrap_or(0.0);
+
+			if let Some(data) = self.data.borrow_mut().user_data.downcast_mut::<LightData>()
+			{
+				data.color = Spectrum::new(channel("Color.R"), channel("Color.G"), channel("Color.B"));
+				data.direction = float3::new(channel("Direction.X"), channel("Direction.Y"), channel("Direction.Z"));
+			}
+		}
+
+		self
+	}
+
 	#[inline(always)]
 	pub fn set_user_data(&mut self, user_data: Box<Any + 'static>) -> &mut Self
 	{
@@ -163,6 +425,17 @@ impl SceneNode
 	#[inline(always)]
 	pub fn add_child(&mut self, child:SceneNode)
 	{
+		// A node can't hang below itself.
+		if self.is_descendant_of(&child) { return; }
+
+		if let Some(parent) = child.parent()
+		{
+			parent.data.borrow_mut().remove_child(&child);
+		}
+
+		child.data.borrow_mut().parent = Some(Rc::downgrade(&self.data));
+		child.data.borrow().set_world_dirty();
+
 		self.data.borrow_mut().add_child(child);
 	}
 
@@ -217,4 +490,4 @@ impl Serialize for SceneNode
 	{
 		self.data.borrow().serialize(serializer)
 	}
-}
\ No newline at end of file
+}
@@ -4,6 +4,7 @@ use std::cell::RefCell;
 use std::any::Any;
 
 use crate::math::*;
+use crate::models::Skeleton;
 
 use super::super::core::*;
 use super::super::scene::{ SceneNode, SceneSubData };
@@ -13,7 +14,7 @@ use super::super::shapes::{ MeshShape };
 pub struct ModelShape
 {
 	pub node:SceneNode,
-	pub shapes:Vec<MeshShape>
+	pub shapes:Vec<MeshShape>,
 }
 
 impl ModelShape
@@ -22,19 +23,16 @@ impl ModelShape
 	{
 		let mut node = SceneNode::new(SceneSubData::Group);
 
+		// The shapes are the children, a second node holding the same material would keep it from being animated.
 		for shape in shapes.iter()
 		{
-			let mut shape_node = SceneNode::new(SceneSubData::Shape);
-			shape_node.set_geometry(Some(shape.geometry()));
-			shape_node.set_material(Some(shape.material()));
-
-			node.add_child(shape_node);
-		}	
+			node.add_child(shape.node.clone());
+		}
 
 		Self
 		{
 			node:node,
-			shapes:shapes
+			shapes:shapes,
 		}
 	}
 
@@ -43,50 +41,40 @@ impl ModelShape
 	{
 		ModelShapeBuilder::new()
 	}
-}
 
-impl Object for ModelShape
-{
-	#[inline(always)]
-	fn set_translate(&mut self, pos:float3) -> &mut SceneNode
+	/// Skins the shapes with the bones of the skeleton, they start in the rest pose.
+	/// The skeleton is kept on the node, so the model is still posed once only the node is added to a scene.
+	pub fn with_skeleton(mut self, mut skeleton:Skeleton) -> Self
 	{
-		self.as_mut().set_translate(pos);
-
-		for child in self.node.data.borrow_mut().children.iter_mut()
-		{
-			child.set_translate(pos);
-		}
-
-		self.as_mut()
+		skeleton.update();
+		self.set_pose(&skeleton);
+		self.node.set_skeleton(Some(Rc::new(RefCell::new(skeleton))));
+		self
 	}
 
-	#[inline(always)]
-	fn set_scale(&mut self, sz:float3) -> &mut SceneNode
+	#[inline]
+	pub fn skeleton(&self) -> Option<Rc<RefCell<Skeleton>>>
 	{
-		self.as_mut().set_scale(sz);
-
-		for child in self.node.data.borrow_mut().children.iter_mut()
-		{
-			child.set_scale(sz);
-		}
-
-		self.as_mut()
+		self.node.skeleton()
 	}
-	
-	#[inline(always)]
-	fn set_rotation(&mut self, rot:float3) -> &mut SceneNode
-	{
-		self.as_mut().set_rotation(rot);
 
-		for child in self.node.data.borrow_mut().children.iter_mut()
-		{
-			child.set_rotation(rot);
-		}
+	/// Shares the palette of a posed skeleton with every shape of the model.
+	pub fn set_pose(&mut self, skeleton:&Skeleton)
+	{
+		self.node.set_pose(skeleton);
+	}
 
-		self.as_mut()
+	/// Poses the skeleton and morphs with the motion of the model at a frame, models without a motion are left as they are.
+	pub fn update_pose(&mut self, frame:f32)
+	{
+		self.node.update_pose(frame);
 	}
 }
 
+impl Object for ModelShape
+{
+}
+
 impl Resource for ModelShape
 {
 	#[inline]
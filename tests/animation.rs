@@ -0,0 +1,88 @@
+extern crate octoon;
+
+mod common;
+
+use std::f32::consts::PI;
+use octoon::math::*;
+use octoon::math::vec::Slerp;
+use octoon::animation::{Animation, AnimationCurve, Evaluate};
+use common::*;
+
+#[test]
+fn quaternion_slerp_is_unit_and_constant_speed()
+{
+	let a = Quaternion::rotation_y(0.0);
+	let b = Quaternion::rotation_y(PI * 0.9);
+
+	for i in 0..=10
+	{
+		let t = i as f32 / 10.0;
+		let q = a.animation(&b, t);
+
+		assert!(approx(q.length(), 1.0));
+		assert!(approx(q.angle(), PI * 0.9 * t), "{} : {}", t, q.angle());
+	}
+}
+
+#[test]
+fn quaternion_slerp_takes_the_shortest_path()
+{
+	let a = Quaternion::rotation_z(0.1);
+	let b = -Quaternion::rotation_z(0.3);
+
+	let q = a.slerp(b, 0.5);
+	let expect = Quaternion::rotation_z(0.2);
+
+	assert!(approx(q.dot(expect).abs(), 1.0));
+
+	let q = a.nlerp(b, 0.5);
+	assert!(approx(q.length(), 1.0));
+	assert!(approx(q.dot(expect).abs(), 1.0));
+}
+
+#[test]
+fn quaternion_curve()
+{
+	let mut curve = AnimationCurve::new();
+	curve.add_keyframe(0.0, Quaternion::rotation_x(0.0), None);
+	curve.add_keyframe(10.0, Quaternion::rotation_x(PI * 0.5), None);
+
+	let q = curve.evaluate(5.0);
+	assert!(approx(q.dot(Quaternion::rotation_x(PI * 0.25)), 1.0));
+}
+
+#[test]
+fn vector_animation_is_lerp()
+{
+	let a = float3::new(0.0, 2.0, 4.0);
+	let b = float3::new(2.0, 4.0, 8.0);
+	assert_eq!(a.animation(&b, 0.5), float3::new(1.0, 3.0, 6.0));
+
+	let a = float4::new(0.0, 2.0, 4.0, 1.0);
+	let b = float4::new(2.0, 4.0, 8.0, 1.0);
+	assert_eq!(a.animation(&b, 0.25), float4::new(0.5, 2.5, 5.0, 1.0));
+}
+
+#[test]
+fn dual_quaternion_sclerp_follows_the_screw()
+{
+	let a = DualQuaternion::from_transform(Quaternion::rotation_y(0.0), float3::new(0.0, 0.0, 0.0));
+	let b = DualQuaternion::from_transform(Quaternion::rotation_y(PI * 0.5), float3::new(0.0, 4.0, 0.0));
+
+	assert!(approx(b.translate().y, 4.0));
+
+	// A rotation about Y combined with a translation along Y is a pure screw, halfway is half of both.
+	let q = a.animation(&b, 0.5);
+	assert!(approx(q.rotation().dot(Quaternion::rotation_y(PI * 0.25)), 1.0));
+	assert!(approx(q.translate().x, 0.0));
+	assert!(approx(q.translate().y, 2.0));
+	assert!(approx(q.translate().z, 0.0));
+
+	let q = a.sclerp(b, 1.0);
+	assert!(approx(q.translate().y, 4.0));
+
+	let c = DualQuaternion::from_transform(Quaternion::rotation_y(0.0), float3::new(2.0, 0.0, 0.0));
+	let q = a.sclerp(c, 0.25);
+	assert!(approx(q.translate().x, 0.5));
+	assert!(approx(q.rotation().w, 1.0));
+}
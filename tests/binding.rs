@@ -0,0 +1,242 @@
+extern crate octoon;
+
+mod common;
+
+use std::sync::Arc;
+use octoon::math::*;
+use octoon::animation::{Animator, AnimationClip, AnimationCurve};
+use octoon::scene::*;
+use common::*;
+
+fn build_scene() -> Scene
+{
+	let geometry:Arc<Geometry> = CubeGeometry::new(1.0, 1.0, 1.0, 1, 1, 1).into();
+
+	let mut cube = MeshShape::new(geometry.clone(), StandardMaterial::new().into());
+	cube.node.set_name("Cube");
+
+	let shared:Arc<Material> = StandardMaterial::new().into();
+	let mut left = MeshShape::new(geometry.clone(), shared.clone());
+	left.node.set_name("Left");
+	let mut right = MeshShape::new(geometry.clone(), shared.clone());
+	right.node.set_name("Right");
+
+	let mut sun = DirectionalLight::builder().set_intensity(1.0).build();
+	sun.node.set_name("Sun");
+
+	let mut scene = Scene::new();
+	scene.add(cube);
+	scene.add(left);
+	scene.add(right);
+	scene.add(sun);
+	scene
+}
+
+fn uniform(node:&SceneNode, name:&str) -> Variant
+{
+	node.material().unwrap().uniforms()[name].clone()
+}
+
+#[test]
+fn property_paths_are_parsed()
+{
+	assert_eq!(Property::parse("transform.translate.x"), Some(Property::Translate(0)));
+	assert_eq!(Property::parse("transform.rotation.z"), Some(Property::Rotation(2)));
+	assert_eq!(Property::parse("transform.scale.y"), Some(Property::Scale(1)));
+	assert_eq!(Property::parse("visible"), Some(Property::Visible));
+	assert_eq!(Property::parse("material.albedo"), Some(Property::Uniform("albedo".to_string(), None)));
+	assert_eq!(Property::parse("material.albedo.g"), Some(Property::Uniform("albedo".to_string(), Some(1))));
+	assert_eq!(Property::parse("light.intensity"), Some(Property::LightIntensity));
+	assert_eq!(Property::parse("light.color.b"), Some(Property::LightColor(2)));
+
+	assert_eq!(Property::parse("transform.translate.w"), None);
+	assert_eq!(Property::parse("transform.position.x"), None);
+	assert_eq!(Property::parse("Position.X"), None);
+}
+
+#[test]
+fn bindings_resolve_against_the_scene()
+{
+	let scene = build_scene();
+
+	let binding = PropertyBinding::resolve(&scene, "Cube/transform.translate.y").unwrap();
+	assert_eq!(binding.node.name(), "Cube");
+	assert_eq!(binding.property, Property::Translate(1));
+
+	assert!(PropertyBinding::resolve(&scene, "Sun/light.intensity").is_ok());
+	assert!(PropertyBinding::resolve(&scene, "Cube/material.smoothness").is_ok());
+
+	assert!(PropertyBinding::resolve(&scene, "transform.translate.y").is_err());
+	assert!(PropertyBinding::resolve(&scene, "Missing/visible").is_err());
+	assert!(PropertyBinding::resolve(&scene, "Cube/light.intensity").is_err());
+	assert!(PropertyBinding::resolve(&scene, "Cube/material.missing").is_err());
+	assert!(PropertyBinding::resolve(&scene, "Cube/material.albedo_map").is_err());
+	assert!(PropertyBinding::resolve(&scene, "Cube/material.smoothness.y").is_err());
+	assert!(PropertyBinding::resolve(&scene, "Left/material.albedo").is_err());
+	assert!(PropertyBinding::resolve(&scene, "Left/transform.scale.x").is_ok());
+}
+
+#[test]
+fn animators_drive_bound_properties()
+{
+	let scene = build_scene();
+
+	let mut cube = AnimationClip::new();
+	cube.set_name("Cube");
+	cube.set_curve("transform.translate.x", curve(&[(0.0, 0.0), (10.0, 10.0)]));
+	cube.set_curve("material.albedo.g", curve(&[(0.0, 1.0), (10.0, 0.0)]));
+	cube.set_curve("material.smoothness", curve(&[(0.0, 0.0), (10.0, 1.0)]));
+	cube.set_curve("Sun/light.intensity", curve(&[(0.0, 1.0), (10.0, 3.0)]));
+	cube.set_curve("Sun/light.color.r", curve(&[(0.0, 0.0), (10.0, 0.5)]));
+	cube.set_curve("Missing/visible", curve(&[(0.0, 0.0)]));
+	cube.set_curve("Left/material.albedo.r", curve(&[(0.0, 0.0)]));
+
+	let mut visible = AnimationClip::new();
+	visible.set_name("Right");
+	visible.set_curve("visible", curve(&[(0.0, 1.0), (5.0, 0.0)]));
+
+	let mut animator = Animator::new();
+	animator.add_clip(cube);
+	animator.add_clip(visible);
+
+	let binding = AnimationBinding::new(&scene, animator);
+	assert_eq!(binding.bindings().len(), 6);
+
+	let mut unresolved = binding.unresolved().to_vec();
+	unresolved.sort();
+	assert_eq!(unresolved, vec!["Left/material.albedo.r".to_string(), "Missing/visible".to_string()]);
+
+	binding.apply(5.0).unwrap();
+
+	let cube = scene.find("Cube").unwrap();
+	assert_eq!(cube.translate(), float3::new(5.0, 0.0, 0.0));
+	assert_eq!(uniform(&cube, "albedo"), Variant::Float3(float3::new(1.0, 0.5, 1.0)));
+	assert_eq!(uniform(&cube, "smoothness"), Variant::Float1(0.5));
+
+	let sun = scene.find("Sun").unwrap().user_data::<LightData>().unwrap();
+	assert_eq!(sun.intensity, 2.0);
+	assert_eq!(sun.color.to_rgb().0, 0.25);
+
+	assert!(!scene.find("Right").unwrap().is_visible());
+	assert!(scene.find("Left").unwrap().is_visible());
+
+	binding.apply(0.0).unwrap();
+	assert_eq!(scene.find("Cube").unwrap().translate(), float3::new(0.0, 0.0, 0.0));
+	assert!(scene.find("Right").unwrap().is_visible());
+}
+
+#[test]
+fn rotations_keep_their_angles_beyond_90_degrees()
+{
+	let scene = build_scene();
+
+	let mut clip = AnimationClip::new();
+	clip.set_name("Cube");
+	clip.set_curve("transform.rotation.x", curve(&[(0.0, 0.0), (10.0, 2.5)]));
+	clip.set_curve("transform.rotation.y", curve(&[(0.0, 0.3)]));
+	clip.set_curve("transform.rotation.z", curve(&[(0.0, 0.1)]));
+
+	let mut animator = Animator::new();
+	animator.add_clip(clip);
+
+	let binding = AnimationBinding::new(&scene, animator);
+
+	let expect = Quaternion::euler(&float3::new(2.5, 0.3, 0.1), RotationOrder::XYZ);
+
+	for _ in 0..3
+	{
+		binding.apply(10.0).unwrap();
+
+		let rotation = scene.find("Cube").unwrap().quaternion();
+		assert!((rotation.dot(expect).abs() - 1.0).abs() < 1e-5, "{:?}", rotation);
+	}
+}
+
+#[test]
+fn bindings_keep_the_curves_they_resolved()
+{
+	let scene = build_scene();
+
+	let mut clip = AnimationClip::new();
+	clip.set_name("Cube");
+	clip.set_curve("transform.translate.x", curve(&[(0.0, 1.0)]));
+	clip.set_curve("transform.scale.y", curve(&[(0.0, 2.0)]));
+	clip.set_curve("visible", AnimationCurve::new());
+
+	for i in 0..16
+	{
+		clip.set_curve(&format!("Missing{}/visible", i), curve(&[(0.0, 0.0)]));
+	}
+
+	let mut animator = Animator::new();
+	animator.add_clip(clip);
+
+	let binding = AnimationBinding::new(&scene, animator);
+	assert_eq!(binding.bindings().len(), 3);
+	assert_eq!(binding.unresolved().len(), 16);
+
+	binding.apply(0.0).unwrap();
+
+	// Empty curves are skipped instead of hiding the node.
+	let cube = scene.find("Cube").unwrap();
+	assert_eq!(cube.translate(), float3::new(1.0, 0.0, 0.0));
+	assert_eq!(cube.scale(), float3::new(1.0, 2.0, 1.0));
+	assert!(cube.is_visible());
+}
+
+#[test]
+fn materials_shared_after_binding_are_reported()
+{
+	let scene = build_scene();
+
+	let mut clip = AnimationClip::new();
+	clip.set_name("Cube");
+	clip.set_curve("material.smoothness", curve(&[(0.0, 0.5)]));
+	clip.set_curve("transform.translate.x", curve(&[(0.0, 1.0)]));
+
+	let mut animator = Animator::new();
+	animator.add_clip(clip);
+
+	let binding = AnimationBinding::new(&scene, animator);
+	assert!(binding.unresolved().is_empty());
+
+	let shared = scene.find("Cube").unwrap().material().unwrap();
+	let err = binding.apply(0.0).unwrap_err();
+	assert!(err.to_string().contains("shared"), "{}", err);
+	assert_eq!(scene.find("Cube").unwrap().translate(), float3::new(1.0, 0.0, 0.0));
+
+	drop(shared);
+	binding.apply(0.0).unwrap();
+	assert_eq!(uniform(&scene.find("Cube").unwrap(), "smoothness"), Variant::Float1(0.5));
+}
+#[test]
+fn materials_of_models_are_bound()
+{
+	let geometry:Arc<Geometry> = CubeGeometry::new(1.0, 1.0, 1.0, 1, 1, 1).into();
+
+	let mut body = MeshShape::new(geometry.clone(), StandardMaterial::new().into());
+	body.node.set_name("Body");
+	let mut hair = MeshShape::new(geometry.clone(), StandardMaterial::new().into());
+	hair.node.set_name("Hair");
+
+	let mut model = ModelShape::new(vec![body, hair]);
+	model.node.set_name("Miku");
+
+	let mut scene = Scene::new();
+	scene.add(&model);
+
+	let mut clip = AnimationClip::new();
+	clip.set_name("Body");
+	clip.set_curve("material.smoothness", curve(&[(0.0, 0.0), (10.0, 1.0)]));
+	clip.set_curve("Hair/material.albedo.b", curve(&[(0.0, 0.25)]));
+
+	let mut animator = Animator::new();
+	animator.add_clip(clip);
+
+	let binding = AnimationBinding::new(&scene, animator);
+	assert!(binding.unresolved().is_empty(), "{:?}", binding.unresolved());
+
+	binding.apply(5.0).unwrap();
+	assert_eq!(uniform(&model.shapes[0].node, "smoothness"), Variant::Float1(0.5));
+	assert_eq!(uniform(&scene.find("Hair").unwrap(), "albedo"), Variant::Float3(float3::new(1.0, 1.0, 0.25)));
+}
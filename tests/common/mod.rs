@@ -0,0 +1,43 @@
+#![allow(dead_code)]
+
+use octoon::math::float3;
+use octoon::animation::AnimationCurve;
+
+/// Values compared with a tolerance, scalars by their difference and vectors by their distance.
+pub trait Distance
+{
+	fn distance(self, other:Self) -> f32;
+}
+
+impl Distance for f32
+{
+	fn distance(self, other:Self) -> f32
+	{
+		(self - other).abs()
+	}
+}
+
+impl Distance for float3
+{
+	fn distance(self, other:Self) -> f32
+	{
+		(self - other).length()
+	}
+}
+
+pub fn approx<T:Distance>(a:T, b:T) -> bool
+{
+	a.distance(b) < 1e-4
+}
+
+/// A curve through the keys, given as (time, value) and interpolated linearly.
+pub fn curve<T:Copy>(keys:&[(f32, T)]) -> AnimationCurve<T>
+{
+	let mut curve = AnimationCurve::new();
+	for &(time, value) in keys
+	{
+		curve.add_keyframe(time, value, None);
+	}
+
+	curve
+}
@@ -0,0 +1,270 @@
+extern crate octoon;
+
+use std::f32;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use octoon::math::*;
+use octoon::animation::*;
+
+const VMD_PATH:&str = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/vmd/M.vmd");
+
+// A key on every frame, like a motion capture.
+fn dense<F:Fn(f32) -> f32>(frames:usize, f:F) -> AnimationCurve
+{
+	let mut curve = AnimationCurve::new();
+	for frame in 0..frames
+	{
+		curve.add_keyframe(frame as f32, f(frame as f32), None);
+	}
+	curve
+}
+
+fn angle(a:&Quaternion, b:&Quaternion) -> f32
+{
+	let b = if a.dot(*b) < 0.0 { -*b } else { *b };
+	((*a - b).length() * 0.5).min(1.0).asin() * 4.0
+}
+
+fn max_error(a:&AnimationCurve, b:&AnimationCurve, frames:usize) -> f32
+{
+	(0..frames * 2).map(|i| (a.evaluate(i as f32 * 0.5) - b.evaluate(i as f32 * 0.5)).abs()).fold(0.0, f32::max)
+}
+
+#[test]
+fn lines_keep_their_ends()
+{
+	let original = dense(100, |time| time * 0.5 - 3.0);
+	let mut curve = dense(100, |time| time * 0.5 - 3.0);
+	curve.simplify(1e-4, false);
+
+	assert_eq!(curve.len(), 2);
+	assert_eq!(curve.frames[1].time, 99.0);
+	assert!(max_error(&original, &curve, 100) < 1e-4);
+
+	// Too short to lose anything.
+	let mut curve = dense(2, |time| time);
+	curve.simplify(1.0, true);
+	assert_eq!(curve.len(), 2);
+}
+
+#[test]
+fn beziers_follow_smooth_motion_with_fewer_keys()
+{
+	let ease = |time:f32| { let t = time / 60.0; 10.0 * t * t * (3.0 - 2.0 * t) };
+	let original = dense(61, ease);
+
+	let mut linear = dense(61, ease);
+	linear.simplify(0.01, false);
+
+	let mut bezier = dense(61, ease);
+	bezier.simplify(0.01, true);
+
+	assert!(bezier.len() < linear.len(), "{} {}", bezier.len(), linear.len());
+	assert_eq!(bezier.len(), 2);
+	assert!(max_error(&original, &linear, 61) < 0.011);
+	assert!(max_error(&original, &bezier, 61) < 0.011);
+}
+
+#[test]
+fn jumps_are_kept()
+{
+	let mut curve = AnimationCurve::new();
+	for frame in 0..20
+	{
+		curve.add_keyframe(frame as f32, if frame < 10 { 0.0 } else { 1.0 }, None);
+	}
+	curve.add_keyframe(10.0, 5.0, None);
+
+	let original = curve.frames.iter().map(|key| (key.time, key.value)).collect::<Vec<_>>();
+	curve.simplify(0.1, true);
+
+	assert!(curve.len() < original.len());
+	for (time, _) in original
+	{
+		let expect = if time < 10.0 { 0.0 } else if time == 10.0 { 5.0 } else { 1.0 };
+		assert_eq!(curve.evaluate(time), expect);
+	}
+}
+
+#[test]
+fn random_curves_stay_within_tolerance()
+{
+	let mut rng = StdRng::seed_from_u64(25);
+
+	for _ in 0..100
+	{
+		let frames = rng.gen_range(3, 80);
+		let mut value = 0.0;
+		let values:Vec<f32> = (0..frames).map(|_| { value += rng.gen_range(-1.0, 1.0); value }).collect();
+
+		let original = dense(frames, |time| values[time as usize]);
+		let mut curve = dense(frames, |time| values[time as usize]);
+
+		let tolerance = rng.gen_range(0.01, 0.5);
+		curve.simplify(tolerance, rng.gen());
+
+		assert!(curve.len() >= 2 && curve.len() <= frames);
+		assert_eq!(curve.frames[0].time, 0.0);
+		assert_eq!(curve.frames[curve.len() - 1].time, (frames - 1) as f32);
+		assert!(max_error(&original, &curve, frames) <= tolerance + 1e-4);
+	}
+}
+
+#[test]
+fn rotations_simplify_by_angle()
+{
+	let mut curve = AnimationCurve::new();
+	for frame in 0..=30
+	{
+		curve.add_keyframe(frame as f32, Quaternion::rotation_y(frame as f32 / 30.0), None);
+	}
+
+	curve.simplify_by(1e-3, angle);
+	assert_eq!(curve.len(), 2);
+	assert!(curve.evaluate(15.0).dot(Quaternion::rotation_y(0.5)) > 0.9999);
+}
+
+#[test]
+fn quaternions_quantize_to_48_bits()
+{
+	let mut rng = StdRng::seed_from_u64(2500);
+
+	let identity = Quaternion::new(0.0, 0.0, 0.0, 1.0);
+	assert_eq!(QuantizedQuat::new(identity).to_quat(), identity);
+	assert_eq!(QuantizedQuat::new(identity), QuantizedQuat::new(-identity));
+
+	for _ in 0..1000
+	{
+		let q = Quaternion::new(rng.gen_range(-1.0, 1.0), rng.gen_range(-1.0, 1.0), rng.gen_range(-1.0, 1.0), rng.gen_range(-1.0, 1.0)).normalize();
+		let r:Quaternion = QuantizedQuat::from(q).into();
+
+		assert!((r.length() - 1.0).abs() < 1e-4);
+		assert!(angle(&q, &r) < 2e-4, "{:?} {:?}", q, r);
+	}
+}
+
+#[test]
+fn baked_motions_play_like_their_curves()
+{
+	let motion = VMDLoader::open(VMD_PATH).unwrap();
+	let baked = BakedMotion::new(&motion, 1.0);
+
+	assert!(baked.len() > 1);
+	assert!(baked.names.len() > 0);
+
+	let mut translates = vec![float3::zero(); baked.names.len()];
+	let mut rotations = vec![Quaternion::default(); baked.names.len()];
+
+	for frame in (0..baked.len()).step_by(7)
+	{
+		let time = baked.start + frame as f32;
+		baked.sample(time, &mut translates, &mut rotations);
+
+		for clip in motion.rotations.clips.iter()
+		{
+			let bone = baked.find(&clip.name).unwrap();
+			let expect = clip.curves["Rotation"].evaluate(time).normalize();
+			assert!(angle(&expect, &rotations[bone]) < 2e-4);
+		}
+
+		for clip in motion.bones.clips.iter()
+		{
+			let bone = baked.find(&clip.name).unwrap();
+			let expect = float3::new(clip.curves["Position.X"].evaluate(time), clip.curves["Position.Y"].evaluate(time), clip.curves["Position.Z"].evaluate(time));
+			assert!((expect - translates[bone]).length() < 1e-4);
+		}
+	}
+
+	// Between frames the samples blend, outside the motion they clamp.
+	let bone = baked.find(&motion.bones.clips[0].name).unwrap();
+	let mut a = vec![float3::zero(); baked.names.len()];
+	let mut b = a.clone();
+	baked.sample(baked.start + 3.0, &mut a, &mut rotations);
+	baked.sample(baked.start + 4.0, &mut b, &mut rotations);
+	baked.sample(baked.start + 3.5, &mut translates, &mut rotations);
+	assert!((translates[bone] - (a[bone] + b[bone]) * 0.5).length() < 1e-4);
+
+	baked.sample(f32::NAN, &mut a, &mut rotations);
+	baked.sample(-100.0, &mut b, &mut rotations);
+	assert_eq!(a, b);
+
+	let empty = BakedMotion::new(&Motion::new(), 1.0);
+	assert_eq!(empty.len(), 0);
+	empty.sample(10.0, &mut a, &mut rotations);
+	assert!(a.iter().all(|v| *v == float3::zero()));
+}
+
+#[test]
+fn baked_motions_play_their_morphs()
+{
+	let mut bone = AnimationClip::new();
+	bone.set_name("Center");
+	bone.set_curve("Position.X", dense(11, |frame| frame));
+
+	let mut smile = AnimationClip::new();
+	smile.set_name("Smile");
+	smile.set_curve("Weight", dense(21, |frame| frame / 20.0));
+
+	let mut blink = AnimationClip::new();
+	blink.set_name("Blink");
+
+	let mut motion = Motion::new();
+	motion.bones.add_clip(bone);
+	motion.morphs.add_clips(vec![smile, blink]);
+
+	let baked = BakedMotion::new(&motion, 1.0);
+	assert_eq!(baked.len(), 21);
+	assert_eq!(baked.morph_names, vec!["Smile".to_string(), "Blink".to_string()]);
+
+	let smile = baked.find_morph("Smile").unwrap();
+	let mut weights = vec![1.0; baked.morph_names.len()];
+
+	for &(time, expect) in [(5.0, 0.25), (5.5, 0.275), (-1.0, 0.0), (30.0, 1.0)].iter()
+	{
+		baked.sample_morphs(time, &mut weights);
+		assert!((weights[smile] - expect).abs() < 1e-5, "{} : {}", time, weights[smile]);
+		assert_eq!(weights[baked.find_morph("Blink").unwrap()], 0.0);
+	}
+
+	let mut translates = vec![float3::zero(); 1];
+	let mut rotations = vec![Quaternion::default(); 1];
+	baked.sample(15.0, &mut translates, &mut rotations);
+	assert_eq!(translates[0].x, 10.0);
+
+	let empty = BakedMotion::new(&Motion::new(), 1.0);
+	empty.sample_morphs(3.0, &mut weights);
+	assert!(weights.iter().all(|&weight| weight == 0.0));
+}
+
+#[test]
+fn vmd_motions_simplify_within_tolerance()
+{
+	let original = VMDLoader::open(VMD_PATH).unwrap();
+	let mut motion = VMDLoader::open(VMD_PATH).unwrap();
+	motion.simplify(1e-3, 1e-3);
+
+	let keys = |motion:&Motion| motion.bones.clips.iter().chain(motion.morphs.clips.iter()).flat_map(|clip| clip.curves.values()).map(|curve| curve.len()).sum::<usize>()
+		+ motion.rotations.clips.iter().flat_map(|clip| clip.curves.values()).map(|curve| curve.len()).sum::<usize>();
+	assert!(keys(&motion) <= keys(&original));
+
+	for clip in original.bones.clips.iter()
+	{
+		let other = motion.bones.clips.iter().find(|other| other.name == clip.name).unwrap();
+		for (name, curve) in clip.curves.iter()
+		{
+			for key in curve.frames.iter()
+			{
+				assert!((other.curves[name].evaluate(key.time) - curve.evaluate(key.time)).abs() <= 1e-3 + 1e-5);
+			}
+		}
+	}
+
+	for clip in original.rotations.clips.iter()
+	{
+		let other = motion.rotations.clips.iter().find(|other| other.name == clip.name).unwrap();
+		for key in clip.curves["Rotation"].frames.iter()
+		{
+			assert!(angle(&other.curves["Rotation"].evaluate(key.time), &clip.curves["Rotation"].evaluate(key.time)) <= 1e-3 + 1e-5);
+		}
+	}
+}
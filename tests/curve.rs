@@ -0,0 +1,238 @@
+extern crate octoon;
+extern crate proptest;
+
+mod common;
+
+use std::f32;
+use proptest::prelude::*;
+use octoon::math::*;
+use octoon::animation::*;
+use common::*;
+
+// Few distinct times so keys often share one.
+fn keys() -> impl Strategy<Value = Vec<(f32, f32)>>
+{
+	prop::collection::vec((0u8..20, -10.0f32..10.0).prop_map(|(time, value)| (time as f32, value)), 1..16)
+}
+
+#[test]
+fn empty_curves_give_the_identity_and_single_keys_hold()
+{
+	let curve:AnimationCurve = AnimationCurve::new();
+	assert!(curve.is_empty());
+	assert_eq!(curve.evaluate(1.0), 0.0);
+	assert_eq!(curve.evaluate(f32::NAN), 0.0);
+
+	let curve:AnimationCurve<Quaternion> = AnimationCurve::new();
+	assert_eq!(curve.evaluate(1.0), Quaternion::new(0.0, 0.0, 0.0, 1.0));
+	assert_eq!(curve.evaluate(1.0), Quaternion::identity());
+
+	let mut curve = AnimationCurve::new();
+	curve.add_keyframe(5.0, 3.0, None);
+
+	for &time in &[f32::NEG_INFINITY, -1.0, 5.0, 10.0, f32::INFINITY, f32::NAN]
+	{
+		assert_eq!(curve.evaluate(time), 3.0);
+	}
+}
+
+#[test]
+fn times_outside_the_keys_are_clamped()
+{
+	let mut curve = AnimationCurve::new();
+	curve.add_keyframe(10.0, 2.0, None);
+	curve.add_keyframe(0.0, 1.0, None);
+
+	assert_eq!(curve.evaluate(-5.0), 1.0);
+	assert_eq!(curve.evaluate(f32::NEG_INFINITY), 1.0);
+	assert_eq!(curve.evaluate(5.0), 1.5);
+	assert_eq!(curve.evaluate(15.0), 2.0);
+	assert_eq!(curve.evaluate(f32::INFINITY), 2.0);
+	assert_eq!(curve.evaluate(f32::NAN), 1.0);
+}
+
+#[test]
+fn keys_without_time_are_ignored()
+{
+	let mut curve = AnimationCurve::new();
+	curve.add_keyframe(f32::NAN, 9.0, None);
+	curve.add_keyframe(0.0, 1.0, None);
+	curve.add_keyframe(10.0, 2.0, None);
+
+	assert_eq!(curve.frames[0].time, 0.0);
+	assert!(curve.frames[2].time.is_nan());
+	assert_eq!(curve.evaluate(20.0), 2.0);
+
+	let curve = AnimationCurve::new_with(vec![Keyframe::new(f32::NAN, 9.0, None), Keyframe::new(4.0, 1.0, None), Keyframe::new(2.0, 3.0, None)], None);
+	assert_eq!(curve.frames.iter().map(|key| key.value).collect::<Vec<_>>(), vec![3.0, 1.0, 9.0]);
+	assert_eq!(curve.evaluate(3.0), 2.0);
+}
+
+proptest!
+{
+	#[test]
+	fn curves_are_sorted_and_bounded_by_their_keys(keys in keys(), times in prop::collection::vec(-5.0f32..25.0, 20))
+	{
+		let curve = curve(&keys);
+
+		prop_assert!(curve.frames.windows(2).all(|pair| pair[0].time <= pair[1].time));
+
+		let first = curve.frames[0].value;
+		let last = curve.frames[curve.len() - 1].value;
+		let min = keys.iter().map(|key| key.1).fold(f32::INFINITY, f32::min);
+		let max = keys.iter().map(|key| key.1).fold(f32::NEG_INFINITY, f32::max);
+
+		prop_assert_eq!(curve.evaluate(-1.0), first);
+		prop_assert_eq!(curve.evaluate(f32::NAN), first);
+		prop_assert_eq!(curve.evaluate(100.0), last);
+
+		// A time shared by several keys gives the one added last.
+		for &(time, _) in keys.iter()
+		{
+			let value = keys.iter().rev().find(|key| key.0 == time).unwrap().1;
+			prop_assert_eq!(curve.evaluate(time), value);
+		}
+
+		for time in times
+		{
+			let value = curve.evaluate(time);
+			prop_assert!(value.is_finite());
+			prop_assert!(value >= min - 1e-4 && value <= max + 1e-4, "{} at {} is outside {}..{}", value, time, min, max);
+		}
+	}
+
+	#[test]
+	fn fixed_interpolation_holds_each_key(keys in keys(), times in prop::collection::vec(-5.0f32..25.0, 20))
+	{
+		let mut curve = AnimationCurve::new();
+		for &(time, value) in keys.iter()
+		{
+			curve.add_keyframe(time, value, Some(Box::new(FixedInterpolator::new(0.0))));
+		}
+
+		for time in times
+		{
+			let value = curve.evaluate(time);
+			prop_assert!(keys.iter().any(|key| key.1 == value), "{} at {} isn't a key", value, time);
+		}
+	}
+
+	#[test]
+	fn catmull_rom_curves_hit_their_keys(keys in keys(), times in prop::collection::vec(-5.0f32..25.0, 20))
+	{
+		let mut curve = curve(&keys);
+		curve.set_catmull_rom();
+
+		for &(time, _) in keys.iter()
+		{
+			let value = keys.iter().rev().find(|key| key.0 == time).unwrap().1;
+			prop_assert!(approx(curve.evaluate(time), value), "{} at {} isn't {}", curve.evaluate(time), time, value);
+		}
+
+		for time in times
+		{
+			prop_assert!(curve.evaluate(time).is_finite());
+		}
+	}
+}
+
+#[test]
+fn fixed_zero_holds_until_the_next_key()
+{
+	let mut curve = AnimationCurve::new();
+	curve.add_keyframe(0.0, 1.0, Some(Box::new(FixedInterpolator::new(0.0))));
+	curve.add_keyframe(10.0, 2.0, None);
+
+	assert_eq!(curve.evaluate(0.0), 1.0);
+	assert_eq!(curve.evaluate(9.99), 1.0);
+	assert_eq!(curve.evaluate(10.0), 2.0);
+}
+
+#[test]
+fn hermite_tangents()
+{
+	let linear = HermiteInterpolator::new(1.0, 1.0);
+	let ease = HermiteInterpolator::new(0.0, 0.0);
+
+	for i in 0..=10
+	{
+		let t = i as f32 / 10.0;
+		assert!(approx(linear.interpolator(t), t));
+	}
+
+	assert_eq!(ease.interpolator(0.0), 0.0);
+	assert_eq!(ease.interpolator(0.5), 0.5);
+	assert_eq!(ease.interpolator(1.0), 1.0);
+	assert!(ease.interpolator(0.1) < 0.1);
+	assert!(ease.interpolator(0.9) > 0.9);
+
+	// Slopes of a segment from 0 to 4 over 2 seconds, matching the line is linear.
+	let hermite = HermiteInterpolator::from_slopes((0.0, 0.0), (2.0, 4.0), 2.0, 2.0);
+	assert!(approx(hermite.out_tangent, 1.0) && approx(hermite.in_tangent, 1.0));
+
+	let mut curve = AnimationCurve::new();
+	curve.add_keyframe(0.0, 0.0, Some(Box::new(HermiteInterpolator::from_slopes((0.0, 0.0), (2.0, 4.0), 8.0, 0.0))));
+	curve.add_keyframe(2.0, 4.0, None);
+
+	// A steep start leaves at its slope and overshoots the end before settling.
+	let h = 1e-3;
+	assert!((curve.evaluate(h) / h - 8.0).abs() < 0.1);
+	assert!(curve.evaluate(1.5) > 4.0);
+	assert_eq!(curve.evaluate(2.0), 4.0);
+}
+
+#[test]
+fn catmull_rom_passes_through_keys_smoothly()
+{
+	let keys = [(0.0, 0.0), (1.0, 2.0), (3.0, 1.0), (4.0, 5.0), (6.0, 4.0)];
+
+	let mut curve = AnimationCurve::new();
+	for &(time, value) in keys.iter()
+	{
+		curve.add_keyframe(time, value, None);
+	}
+	curve.set_catmull_rom();
+
+	for &(time, value) in keys.iter()
+	{
+		assert!(approx(curve.evaluate(time), value));
+	}
+
+	// The slope is the same on both sides of an inner key, and matches its neighbours.
+	let h = 1e-3;
+	for i in 1..keys.len() - 1
+	{
+		let time = keys[i].0;
+		let left = (curve.evaluate(time) - curve.evaluate(time - h)) / h;
+		let right = (curve.evaluate(time + h) - curve.evaluate(time)) / h;
+		let expect = (keys[i + 1].1 - keys[i - 1].1) / (keys[i + 1].0 - keys[i - 1].0);
+
+		assert!((left - right).abs() < 0.05, "{} : {} {}", time, left, right);
+		assert!((left - expect).abs() < 0.05, "{} : {} {}", time, left, expect);
+	}
+
+	// Keys on a line stay on it.
+	let mut curve = AnimationCurve::new();
+	for &time in [0.0, 1.0, 3.0, 4.0].iter()
+	{
+		curve.add_keyframe(time, time * 2.0, None);
+	}
+	curve.set_catmull_rom();
+
+	for i in 0..=40
+	{
+		let time = i as f32 / 10.0;
+		assert!(approx(curve.evaluate(time), time * 2.0));
+	}
+
+	// A plateau between a rise and a fall bulges over its keys like the neighbours lead it to.
+	let mut curve = AnimationCurve::new();
+	for &(time, value) in [(0.0, 0.0), (1.0, 1.0), (2.0, 1.0), (3.0, 0.0)].iter()
+	{
+		curve.add_keyframe(time, value, None);
+	}
+	curve.set_catmull_rom();
+
+	assert!(approx(curve.evaluate(1.5), 1.125), "{}", curve.evaluate(1.5));
+	assert!(approx(curve.evaluate(1.0), 1.0) && approx(curve.evaluate(2.0), 1.0));
+}
\ No newline at end of file
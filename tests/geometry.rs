@@ -0,0 +1,229 @@
+extern crate octoon;
+
+use std::sync::Arc;
+use octoon::math::*;
+use octoon::scene::*;
+use octoon::models::{self, MorphTarget, VertexMorph, UVMorph, MaterialMorph, VertexWeight};
+
+// A strip of quads wide enough to need more than 16 bit indices.
+fn strip(quads:u32) -> MeshGeometry
+{
+	let mut vertices = Vec::new();
+	let mut indices:Vec<u32> = Vec::new();
+
+	for i in 0..=quads
+	{
+		vertices.push(float3::new(i as f32, 0.0, 0.0));
+		vertices.push(float3::new(i as f32, 1.0, 0.0));
+	}
+
+	for i in 0..quads
+	{
+		let n = i * 2;
+		indices.extend(&[n, n + 2, n + 1, n + 1, n + 2, n + 3]);
+	}
+
+	let count = vertices.len();
+	MeshGeometry::new(vertices, vec![float3::new(0.0, 0.0, 1.0); count], vec![float2::new(0.0, 0.0); count], Vec::new(), indices)
+}
+
+#[test]
+fn indices_pick_the_narrowest_format()
+{
+	assert_eq!(Indices::new(vec![0, 1, 65535]).format(), IndexFormat::U16);
+	assert_eq!(Indices::new(vec![0, 1, 65536]).format(), IndexFormat::U32);
+	assert_eq!(Indices::from(vec![0u16, 1, 2]).stride(), 2);
+	assert_eq!(Indices::from(vec![0u32, 1, 70000]).stride(), 4);
+
+	let mut indices = Indices::new(vec![0, 1, 2]);
+	indices.push(3);
+	assert_eq!(indices.format(), IndexFormat::U16);
+	indices.push(70000);
+	assert_eq!(indices.format(), IndexFormat::U32);
+	assert_eq!(indices.to_vec(), vec![0, 1, 2, 3, 70000]);
+	assert_eq!(indices.as_bytes().len(), 5 * 4);
+	assert_eq!(indices.get(4), Some(70000));
+	assert_eq!(indices.get(5), None);
+}
+
+#[test]
+fn primitives_stay_16_bit()
+{
+	let sphere = SphereGeometry::new(1.0, 32, 24, 0.0, std::f32::consts::PI * 2.0, 0.0, std::f32::consts::PI);
+	assert_eq!(sphere.indices().format(), IndexFormat::U16);
+	assert_eq!(CubeGeometry::new(1.0, 1.0, 1.0, 1, 1, 1).indices().format(), IndexFormat::U16);
+	assert!(sphere.indices().to_vec().iter().all(|&i| (i as usize) < sphere.num_vertices()));
+}
+
+#[test]
+fn large_meshes_split_into_16_bit_pieces()
+{
+	let geometry = strip(40000);
+	assert_eq!(geometry.indices().format(), IndexFormat::U32);
+
+	let all = geometry.indices().to_vec();
+	let pieces = geometry.indices().split(65536);
+	assert!(pieces.len() > 1);
+
+	let mut triangles = Vec::new();
+	for (vertices, indices) in pieces.iter()
+	{
+		assert!(vertices.len() <= 65536);
+		assert_eq!(indices.len() % 3, 0);
+		triangles.extend(indices.iter().map(|&i| vertices[i as usize]));
+	}
+
+	assert_eq!(triangles, all);
+}
+
+#[test]
+fn large_meshes_keep_32_bit_indices_in_json()
+{
+	let geometry:Arc<Geometry> = strip(40000).into();
+
+	let mut scene = Scene::new();
+	scene.add(MeshShape::new(geometry.clone(), StandardMaterial::new().into()));
+
+	let json = to_string(&scene).unwrap();
+	assert!(json.contains("\"format\":\"U32\""));
+
+	let loaded = load_from_memory(json.as_bytes()).unwrap();
+	let other = loaded.shapes()[0].geometry().unwrap();
+	assert_eq!(other.indices().format(), IndexFormat::U32);
+	assert_eq!(other.indices(), geometry.indices());
+}
+
+fn material_morph(operation:u8, value:f32) -> MaterialMorph
+{
+	MaterialMorph
+	{
+		material:None,
+		operation:operation,
+		diffuse:(value, value, value, value),
+		specular:(value, value, value),
+		shininess:value,
+		ambient:(value, value, value),
+		edge_color:(value, value, value, value),
+		edge_size:value,
+		texture:(value, value, value, value),
+		sphere:(value, value, value, value),
+		toon:(value, value, value, value),
+	}
+}
+
+#[test]
+fn morph_weights_blend_vertices_and_materials()
+{
+	let mut smile = MorphTarget::new();
+	smile.name = "smile".to_string();
+	smile.positions = vec![VertexMorph { index:1, offset:(0.0, 2.0, 0.0) }, VertexMorph { index:2, offset:(1.0, 0.0, 0.0) }];
+	smile.texcoords = vec![UVMorph { index:1, offset:(0.5, 0.5, 0.0, 0.0) }];
+	smile.materials = vec![material_morph(1, 0.4)];
+
+	let mut blink = MorphTarget::new();
+	blink.name = "blink".to_string();
+	blink.positions = vec![VertexMorph { index:1, offset:(0.0, 0.0, -4.0) }, VertexMorph { index:9, offset:(1.0, 1.0, 1.0) }];
+	blink.materials = vec![material_morph(0, 0.0)];
+
+	let geometry = MeshGeometry::builder()
+		.set_vertices(vec![float3::new(0.0, 0.0, 0.0), float3::new(1.0, 0.0, 0.0), float3::new(0.0, 1.0, 0.0)])
+		.set_normals(vec![float3::new(0.0, 0.0, 1.0); 3])
+		.set_texcoords(vec![float2::new(0.0, 0.0); 3])
+		.set_morphs(vec![smile, blink])
+		.set_indices(vec![0u32, 1, 2])
+		.build();
+
+	let mut material = models::Material::new();
+	material.diffuse = [0.5, 0.5, 0.5];
+
+	// Nothing moves at zero weight.
+	assert_eq!(geometry.morph_vertices(), geometry.vertices().to_vec());
+	assert_eq!(geometry.morph_material(&material).diffuse, [0.5, 0.5, 0.5]);
+
+	assert!(geometry.set_morph_weight("smile", 0.5));
+	assert!(geometry.set_morph_weight("blink", 0.25));
+	assert!(!geometry.set_morph_weight("angry", 1.0));
+	assert_eq!(geometry.morph_weight("blink"), Some(0.25));
+
+	// Offsets add up scaled by their weights, the one past the last vertex is skipped.
+	let vertices = geometry.morph_vertices();
+	assert_eq!(vertices[0], float3::new(0.0, 0.0, 0.0));
+	assert_eq!(vertices[1], float3::new(1.0, 1.0, -1.0));
+	assert_eq!(vertices[2], float3::new(0.5, 1.0, 0.0));
+	assert_eq!(geometry.morph_texcoords()[1], float2::new(0.25, 0.25));
+
+	// A quarter of a multiply by zero keeps 75%, then half of +0.4 is added.
+	let morphed = geometry.morph_material(&material);
+	assert!((morphed.diffuse[0] - (0.5 * 0.75 + 0.2)).abs() < 1e-6);
+	assert!((morphed.dissolve - (1.0 * 0.75 + 0.2)).abs() < 1e-6);
+}
+
+
+// Every vertex of the strip follows its own column bone and, half way, the next one.
+fn strip_weights(quads:u32) -> Vec<VertexWeight>
+{
+	(0..(quads + 1) * 2).map(|i|
+	{
+		let column = (i / 2) as u16;
+		VertexWeight { bone:[column, column + 1, 0, 0], weight:[0.5, 0.5, 0.0, 0.0] }
+	}).collect()
+}
+
+#[test]
+fn skinned_meshes_split_into_batches_under_the_joint_limit()
+{
+	let geometry = strip(100);
+	let weights = strip_weights(100);
+	let all = geometry.indices().to_vec();
+
+	let batches = geometry.indices().split_joints(&weights, 24, 65536);
+	assert!(batches.len() > 1);
+
+	let mut triangles = Vec::new();
+	for batch in batches.iter()
+	{
+		assert!(batch.joints.len() <= 24);
+		assert_eq!(batch.indices.len() % 3, 0);
+
+		for &index in batch.indices.iter()
+		{
+			let vertex = batch.vertices[index as usize];
+			let local = batch.local_bones(&weights[vertex as usize]);
+
+			for i in 0..2
+			{
+				assert_eq!(batch.joints[local[i] as usize], weights[vertex as usize].bone[i]);
+			}
+
+			triangles.push(vertex);
+		}
+	}
+
+	assert_eq!(triangles, all);
+}
+
+#[test]
+fn skinned_batches_stay_under_the_vertex_limit()
+{
+	let geometry = strip(40000);
+	let weights = vec![VertexWeight { bone:[1, 0, 0, 0], weight:[1.0, 0.0, 0.0, 0.0] }; geometry.num_vertices()];
+
+	let batches = geometry.indices().split_joints(&weights, 24, 65536);
+	assert!(batches.len() > 1);
+
+	for batch in batches.iter()
+	{
+		assert!(batch.vertices.len() <= 65536);
+		assert_eq!(batch.joints, vec![1]);
+		assert_eq!(batch.local_bones(&weights[0]), [0, 0, 0, 0]);
+	}
+
+	assert_eq!(batches.iter().map(|batch| batch.indices.len()).sum::<usize>(), geometry.indices().len());
+}
+
+#[test]
+fn skinning_shaders_declare_one_batch_of_joints()
+{
+	assert!(SkinningMaterial::new(300).vs().starts_with(&format!("uniform mat4 bindpose[{}];", SKINNING_MAX_JOINTS)));
+	assert!(SkinningMaterial::new(8).vs().starts_with("uniform mat4 bindpose[8];"));
+}
\ No newline at end of file
@@ -0,0 +1,236 @@
+extern crate octoon;
+
+mod common;
+
+use std::rc::Rc;
+use octoon::math::*;
+use octoon::animation::*;
+use common::*;
+
+fn clip<T>(name:&str, keys:&[(f32, T)]) -> AnimationClip<T> where T:Copy
+{
+	let mut clip = AnimationClip::new();
+	clip.set_name(name);
+	clip.set_curve("Position.X", curve(keys));
+	clip
+}
+
+fn animator(clips:Vec<AnimationClip>) -> Rc<Animator>
+{
+	let mut animator = Animator::new();
+	animator.add_clips(clips);
+	Rc::new(animator)
+}
+
+fn value(pose:&Pose, clip:&str) -> f32
+{
+	pose[clip]["Position.X"]
+}
+
+#[test]
+fn wrap_modes_map_times_into_the_animation()
+{
+	assert_eq!(WrapMode::Clamp.wrap(-2.0, 10.0), 0.0);
+	assert_eq!(WrapMode::Clamp.wrap(12.0, 10.0), 10.0);
+	assert_eq!(WrapMode::Loop.wrap(12.0, 10.0), 2.0);
+	assert_eq!(WrapMode::Loop.wrap(-2.0, 10.0), 8.0);
+	assert_eq!(WrapMode::PingPong.wrap(12.0, 10.0), 8.0);
+	assert_eq!(WrapMode::PingPong.wrap(-2.0, 10.0), 2.0);
+	assert_eq!(WrapMode::PingPong.wrap(22.0, 10.0), 2.0);
+	assert_eq!(WrapMode::Loop.wrap(5.0, 0.0), 0.0);
+}
+
+#[test]
+fn states_play_every_clip_of_the_animator_and_wrap_after_the_last_key()
+{
+	// The animator runs from the first key of any clip to the last one, 0 to 10 here.
+	let walk = animator(vec![clip("センター", &[(0.0, 0.0), (10.0, 10.0)]), clip("左足", &[(2.0, 0.0), (6.0, 8.0)])]);
+	assert_eq!(walk.range(), Some((0.0, 10.0)));
+
+	let mut player = AnimationPlayer::new();
+	let mut state = AnimationState::new(walk.clone());
+	state.speed = 2.0;
+	let layer = player.add_layer(AnimationLayer::new());
+	player.layer_mut(layer).unwrap().play(state);
+
+	for (post_wrap, center, leg) in [(WrapMode::Clamp, 10.0, 8.0), (WrapMode::Loop, 4.0, 4.0), (WrapMode::PingPong, 6.0, 8.0)].iter()
+	{
+		let state = player.layer_mut(layer).unwrap().state_mut().unwrap();
+		state.time = 0.0;
+		state.post_wrap = *post_wrap;
+
+		for _ in 0..7
+		{
+			player.advance(1.0);
+		}
+
+		let pose = player.evaluate();
+		assert!(approx(value(&pose, "センター"), *center), "{:?} : {:?}", post_wrap, pose);
+		assert!(approx(value(&pose, "左足"), *leg), "{:?} : {:?}", post_wrap, pose);
+	}
+}
+
+#[test]
+fn states_wrap_before_the_animation_when_played_backwards()
+{
+	let walk = animator(vec![clip("センター", &[(0.0, 0.0), (10.0, 10.0)]), clip("左足", &[(5.0, 0.0), (10.0, 5.0)])]);
+
+	let mut state = AnimationState::looping(walk);
+	state.speed = -1.0;
+
+	let mut player = AnimationPlayer::new();
+	player.add_layer(AnimationLayer::new());
+	player.layer_mut(0).unwrap().play(state);
+
+	player.advance(3.0);
+	assert!(approx(value(&player.evaluate(), "センター"), 7.0));
+	assert!(approx(value(&player.evaluate(), "左足"), 2.0));
+
+	player.layer_mut(0).unwrap().state_mut().unwrap().pre_wrap = WrapMode::Clamp;
+	assert!(approx(value(&player.evaluate(), "センター"), 0.0));
+	assert!(approx(value(&player.evaluate(), "左足"), 0.0));
+}
+
+#[test]
+fn crossfades_blend_every_clip_of_the_two_animators()
+{
+	let stand = animator(vec![clip("センター", &[(0.0, 0.0), (100.0, 0.0)]), clip("右腕", &[(0.0, 4.0)])]);
+	let run = animator(vec![clip("センター", &[(0.0, 10.0), (100.0, 10.0)]), clip("左足", &[(0.0, -2.0), (100.0, 98.0)])]);
+
+	let mut player = AnimationPlayer::new();
+	player.add_layer(AnimationLayer::new());
+	player.layer_mut(0).unwrap().play(AnimationState::new(stand));
+	player.layer_mut(0).unwrap().crossfade(AnimationState::new(run.clone()), 4.0);
+
+	for (step, &center) in [0.0, 2.5, 5.0, 7.5].iter().enumerate()
+	{
+		let pose = player.evaluate();
+		assert!(approx(value(&pose, "センター"), center), "{} : {:?}", center, pose);
+
+		// Clips only one of the animators has fade from and to the identity while there is no layer below.
+		let fade = step as f32 / 4.0;
+		assert!(approx(value(&pose, "右腕"), 4.0 * (1.0 - fade)), "{:?}", pose);
+		assert!(approx(value(&pose, "左足"), (step as f32 - 2.0) * fade), "{:?}", pose);
+
+		player.advance(1.0);
+	}
+
+	let pose = player.evaluate();
+	assert_eq!(player.layer(0).unwrap().fade_weight(), 1.0);
+	assert!(Rc::ptr_eq(&player.layer(0).unwrap().state().unwrap().animation, &run));
+	assert!(approx(value(&pose, "センター"), 10.0));
+	assert!(approx(value(&pose, "左足"), 2.0));
+	assert!(!pose.contains_key("右腕"));
+}
+
+#[test]
+fn layers_blend_over_the_layers_below_by_their_weight()
+{
+	let stand = animator(vec![clip("センター", &[(0.0, 0.0)]), clip("右腕", &[(0.0, 4.0)])]);
+	let wave = animator(vec![clip("右腕", &[(0.0, 8.0)]), clip("左腕", &[(0.0, 1.0)])]);
+
+	let mut player = AnimationPlayer::new();
+	player.add_layer(AnimationLayer::new());
+	player.layer_mut(0).unwrap().play(AnimationState::new(stand));
+
+	let mut top = AnimationLayer::new();
+	top.weight = 0.25;
+	top.play(AnimationState::new(wave));
+	player.add_layer(top);
+
+	let pose = player.evaluate();
+	assert!(approx(value(&pose, "センター"), 0.0));
+	assert!(approx(value(&pose, "右腕"), 5.0));
+
+	// Clips no layer below reaches blend from the identity by the weight.
+	assert!(approx(value(&pose, "左腕"), 0.25));
+
+	player.layer_mut(1).unwrap().weight = 0.0;
+	let pose = player.evaluate();
+	assert!(approx(value(&pose, "右腕"), 4.0));
+	assert!(!pose.contains_key("左腕"));
+}
+
+#[test]
+fn additive_layers_move_the_layers_below()
+{
+	let dance = animator(vec![clip("センター", &[(0.0, 5.0), (10.0, 5.0)]), clip("首", &[(0.0, 1.0), (10.0, 1.0)])]);
+	let breathe = animator(vec![clip("センター", &[(0.0, 0.0), (1.0, 1.0), (2.0, 0.0)]), clip("上半身", &[(0.0, 3.0), (1.0, 5.0), (2.0, 3.0)])]);
+
+	let mut player = AnimationPlayer::new();
+	player.add_layer(AnimationLayer::new());
+	player.layer_mut(0).unwrap().play(AnimationState::new(dance));
+
+	let mut layer = AnimationLayer::additive();
+	layer.weight = 0.5;
+	layer.play(AnimationState::looping(breathe));
+	player.add_layer(layer);
+
+	for &(center, chest) in [(5.0, 3.0), (5.5, 4.0), (5.0, 3.0), (5.5, 4.0), (5.0, 3.0)].iter()
+	{
+		let pose = player.evaluate();
+		assert!(approx(value(&pose, "センター"), center), "{:?}", pose);
+		assert!(approx(value(&pose, "首"), 1.0));
+
+		// Clips no layer below reaches move from the first key of the additive animation.
+		assert!(approx(value(&pose, "上半身"), chest), "{:?}", pose);
+
+		player.advance(1.0);
+	}
+}
+
+#[test]
+fn additive_rotations_turn_the_rotation_below()
+{
+	let mut dance:Animator<Quaternion> = Animator::new();
+	dance.add_clip(clip("首", &[(0.0, Quaternion::rotation_y(0.5))]));
+	dance.add_clip(clip("頭", &[(0.0, Quaternion::rotation_x(0.2))]));
+	let dance = Rc::new(dance);
+
+	let mut breathe:Animator<Quaternion> = Animator::new();
+	breathe.add_clip(clip("首", &[(0.0, Quaternion::rotation_y(0.0)), (1.0, Quaternion::rotation_y(0.4))]));
+	let breathe = Rc::new(breathe);
+
+	let mut player = AnimationPlayer::new();
+	player.add_layer(AnimationLayer::new());
+	player.layer_mut(0).unwrap().play(AnimationState::new(dance));
+
+	let mut layer = AnimationLayer::additive();
+	layer.weight = 0.5;
+	layer.play(AnimationState::new(breathe));
+	player.add_layer(layer);
+
+	player.advance(1.0);
+	let pose = player.evaluate();
+	assert!(approx(pose["首"]["Position.X"].dot(Quaternion::rotation_y(0.7)).abs(), 1.0), "{:?}", pose);
+	assert!(approx(pose["頭"]["Position.X"].dot(Quaternion::rotation_x(0.2)).abs(), 1.0), "{:?}", pose);
+}
+
+#[test]
+fn motions_crossfade_their_bones_rotations_and_morphs()
+{
+	let motion = |x:f32, angle:f32, weight:f32|
+	{
+		let mut motion = Motion::new();
+		motion.bones.add_clip(clip("センター", &[(0.0, x), (30.0, x)]));
+		motion.rotations.add_clip(clip("首", &[(0.0, Quaternion::rotation_y(angle))]));
+
+		let mut morph = AnimationClip::new();
+		morph.set_name("あ");
+		morph.set_curve("Weight", curve(&[(0.0, weight)]));
+		motion.morphs.add_clip(morph);
+		Rc::new(motion)
+	};
+
+	let mut player = AnimationPlayer::new();
+	player.add_layer(AnimationLayer::new());
+	player.layer_mut(0).unwrap().play(AnimationState::new(motion(0.0, 0.0, 0.0)));
+	player.layer_mut(0).unwrap().crossfade(AnimationState::new(motion(4.0, 1.0, 1.0)), 2.0);
+	player.advance(1.0);
+
+	let pose:MotionPose = player.evaluate();
+	assert!(approx(value(&pose.bones, "センター"), 2.0));
+	assert!(approx(pose.rotations["首"]["Position.X"].dot(Quaternion::rotation_y(0.5)).abs(), 1.0));
+	assert!(approx(pose.morphs["あ"]["Weight"], 0.5));
+	assert!(pose.cameras.is_empty());
+}
@@ -0,0 +1,194 @@
+extern crate octoon;
+
+use byteorder::{LittleEndian, WriteBytesExt};
+use encoding::{Encoding, EncoderTrap};
+use encoding::all::WINDOWS_31J;
+use octoon::models::{Loader, PMDFile, PMDLoader, SphereMode, Toon};
+
+fn name(buf:&mut Vec<u8>, name:&str, length:usize)
+{
+	let mut bytes = WINDOWS_31J.encode(name, EncoderTrap::Strict).unwrap();
+	bytes.resize(length, 0);
+	buf.extend(bytes);
+}
+
+fn floats(buf:&mut Vec<u8>, values:&[f32])
+{
+	for &v in values
+	{
+		buf.write_f32::<LittleEndian>(v).unwrap();
+	}
+}
+
+// A triangle with two bones, one morph and a toon table, small enough to write by hand.
+fn triangle(indices:&[u16], face_count:u32, texture:&str, toon:u8) -> Vec<u8>
+{
+	let mut buf = b"Pmd".to_vec();
+	floats(&mut buf, &[1.0]);
+	name(&mut buf, "triangle", 20);
+	name(&mut buf, "", 256);
+
+	buf.write_u32::<LittleEndian>(3).unwrap();
+	for (i, position) in [(0.0, 0.0, 0.0), (1.0, 0.0, 0.0), (0.0, 1.0, 0.0)].iter().enumerate()
+	{
+		floats(&mut buf, &[position.0, position.1, position.2, 0.0, 0.0, -1.0, position.0, position.1]);
+		buf.write_u16::<LittleEndian>(0).unwrap();
+		buf.write_u16::<LittleEndian>(1).unwrap();
+		buf.write_u8([100, 50, 0][i]).unwrap();
+		buf.write_u8(0).unwrap();
+	}
+
+	buf.write_u32::<LittleEndian>(indices.len() as u32).unwrap();
+	for &index in indices
+	{
+		buf.write_u16::<LittleEndian>(index).unwrap();
+	}
+
+	buf.write_u32::<LittleEndian>(1).unwrap();
+	floats(&mut buf, &[1.0, 1.0, 1.0, 1.0, 5.0, 0.0, 0.0, 0.0, 0.5, 0.5, 0.5]);
+	buf.write_u8(toon).unwrap();
+	buf.write_u8(1).unwrap();
+	buf.write_u32::<LittleEndian>(face_count).unwrap();
+	name(&mut buf, texture, 20);
+
+	buf.write_u16::<LittleEndian>(2).unwrap();
+	for (bone, parent) in [("センター", 0xFFFF), ("下半身", 0)].iter()
+	{
+		name(&mut buf, bone, 20);
+		buf.write_u16::<LittleEndian>(*parent).unwrap();
+		buf.write_u16::<LittleEndian>(0xFFFF).unwrap();
+		buf.write_u8(0).unwrap();
+		buf.write_u16::<LittleEndian>(0xFFFF).unwrap();
+		floats(&mut buf, &[0.0, 1.0, 0.0]);
+	}
+
+	buf.write_u16::<LittleEndian>(0).unwrap();
+
+	// The base morph lists the vertices the others move by their index into it.
+	buf.write_u16::<LittleEndian>(2).unwrap();
+	name(&mut buf, "base", 20);
+	buf.write_u32::<LittleEndian>(1).unwrap();
+	buf.write_u8(0).unwrap();
+	buf.write_u32::<LittleEndian>(2).unwrap();
+	floats(&mut buf, &[0.0, 1.0, 0.0]);
+
+	name(&mut buf, "smile", 20);
+	buf.write_u32::<LittleEndian>(1).unwrap();
+	buf.write_u8(3).unwrap();
+	buf.write_u32::<LittleEndian>(0).unwrap();
+	floats(&mut buf, &[0.5, 0.0, 0.0]);
+
+	buf.write_u8(0).unwrap();
+	buf.write_u8(0).unwrap();
+	buf.write_u32::<LittleEndian>(0).unwrap();
+
+	// No english names, then the toon table with the second toon replaced.
+	buf.write_u8(0).unwrap();
+	for i in 0..10
+	{
+		name(&mut buf, &if i == 1 { "custom.bmp".to_string() } else { format!("toon{:02}.bmp", i + 1) }, 100);
+	}
+
+	buf.write_u32::<LittleEndian>(0).unwrap();
+	buf.write_u32::<LittleEndian>(0).unwrap();
+	buf
+}
+
+#[test]
+fn pmd_loads_a_triangle()
+{
+	let buf = triangle(&[0, 1, 2], 3, "face.bmp", 0xFF);
+	assert!(PMDLoader::new().can_read(&buf));
+
+	let model = PMDLoader::new().do_load(&buf).unwrap();
+	assert_eq!(model.name, "triangle");
+	assert_eq!(model.meshes.len(), 1);
+	assert_eq!(model.textures, vec!["face.bmp".to_string()]);
+
+	let mesh = &model.meshes[0];
+	assert_eq!(mesh.indices, vec![0, 1, 2]);
+	assert_eq!(mesh.positions.len(), 9);
+	assert_eq!(mesh.weights.len(), 3);
+
+	// Vertices are laid out in the order the indices first use them.
+	assert_eq!(&mesh.positions[3..6], &[1.0, 0.0, 0.0]);
+	assert_eq!(mesh.weights[1].bone, [0, 1, 0, 0]);
+	assert_eq!(mesh.weights[1].weight, [0.5, 0.5, 0.0, 0.0]);
+
+	assert_eq!(model.bones.len(), 2);
+	assert_eq!(model.bones[0].name, "センター");
+	assert_eq!(model.bones[0].parent, None);
+	assert_eq!(model.bones[1].parent, Some(0));
+
+	assert_eq!(model.morphs.len(), 1);
+	assert_eq!(mesh.morphs.len(), 1);
+	assert_eq!(mesh.morphs[0].name, "smile");
+	assert_eq!(mesh.morphs[0].positions[0].index, 2);
+	assert_eq!(mesh.morphs[0].positions[0].offset, (0.5, 0.0, 0.0));
+}
+
+#[test]
+fn pmd_sphere_maps_and_toons()
+{
+	let load = |texture:&str, toon:u8| PMDLoader::new().do_load(&triangle(&[0, 1, 2], 3, texture, toon)).unwrap();
+
+	let model = load("face.bmp*face.spa", 0);
+	assert_eq!(model.textures, vec!["face.bmp".to_string(), "face.spa".to_string()]);
+	assert_eq!(model.materials[0].diffuse_texture, Some(0));
+	assert_eq!(model.materials[0].sphere_texture, Some(1));
+	assert_eq!(model.materials[0].sphere_mode, SphereMode::Additive);
+	assert_eq!(model.materials[0].toon, Toon::Shared(0));
+
+	// The toon table replaces the second shared toon with a file of the model.
+	let model = load("face.sph", 1);
+	assert_eq!(model.materials[0].diffuse_texture, None);
+	assert_eq!(model.materials[0].sphere_texture, Some(0));
+	assert_eq!(model.materials[0].sphere_mode, SphereMode::Multiply);
+	assert_eq!(model.materials[0].toon, Toon::Texture(1));
+	assert_eq!(model.textures[1], "custom.bmp");
+
+	let model = load("face.bmp", 0xFF);
+	assert_eq!(model.materials[0].sphere_texture, None);
+	assert_eq!(model.materials[0].sphere_mode, SphereMode::None);
+	assert_eq!(model.materials[0].toon, Toon::None);
+	assert_eq!(load("", 9).materials[0].toon, Toon::Shared(9));
+	assert_eq!(load("", 10).materials[0].toon, Toon::None);
+}
+
+#[test]
+fn pmd_rejects_indices_out_of_range()
+{
+	let buf = triangle(&[0, 1, 3], 3, "", 0xFF);
+	assert!(PMDFile::load(&buf).is_ok());
+	assert!(PMDLoader::new().do_load(&buf).is_err());
+
+	// More faces than indices.
+	let buf = triangle(&[0, 1, 2], 6, "", 0xFF);
+	assert!(PMDLoader::new().do_load(&buf).is_err());
+}
+
+#[test]
+fn pmd_rejects_counts_larger_than_the_file()
+{
+	let mut buf = triangle(&[0, 1, 2], 3, "", 0xFF);
+	buf.truncate(283);
+	buf.write_u32::<LittleEndian>(0xFFFF_FFFF).unwrap();
+	assert!(PMDFile::load(&buf).is_err());
+
+	// Indices right after the vertices.
+	let mut buf = triangle(&[0, 1, 2], 3, "", 0xFF);
+	buf.truncate(283 + 4 + 3 * 38);
+	buf.write_u32::<LittleEndian>(0x7FFF_FFFF).unwrap();
+	assert!(PMDFile::load(&buf).is_err());
+}
+
+#[test]
+fn pmd_truncated_files_do_not_panic()
+{
+	let buf = triangle(&[0, 1, 2], 3, "face.bmp*face.sph", 1);
+
+	for length in 0..buf.len()
+	{
+		let _ = PMDLoader::new().do_load(&buf[..length]);
+	}
+}
@@ -0,0 +1,526 @@
+extern crate octoon;
+
+mod common;
+
+use std::fs;
+use octoon::math::*;
+use octoon::animation::Evaluate;
+use octoon::scene::*;
+use common::*;
+
+const PMM_PATH:&str = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/pmm/1.pmm");
+const PMM_PATH2:&str = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/pmm/2.pmm");
+
+fn bone_frame(data_index:i32, frame_number:i32, next_index:i32) -> PmmBoneFrame
+{
+	let mut frame = PmmBoneFrame::new();
+	frame.data_index = data_index;
+	frame.frame_number = frame_number;
+	frame.next_index = next_index;
+	frame.interpolation_x = [20, 20, 107, 107];
+	frame.interpolation_y = [20, 20, 107, 107];
+	frame.interpolation_z = [20, 20, 107, 107];
+	frame.interpolation_rotation = [20, 20, 107, 107];
+	frame
+}
+
+#[test]
+fn projects_build_camera_light_and_models()
+{
+	for (path, num_model) in [(PMM_PATH, 2), (PMM_PATH2, 1)].iter()
+	{
+		let pmm = PMMFile::load(&fs::read(path).unwrap()).unwrap();
+		let scene = load_from_memory(&fs::read(path).unwrap()).unwrap();
+
+		assert_eq!(scene.num_camera(), 1);
+		assert_eq!(scene.num_light(), 1);
+		assert_eq!(scene.models.len(), *num_model);
+
+		// The models live on another machine, they keep their name and place without any shapes.
+		for (node, model) in scene.models.iter().zip(pmm.model.iter())
+		{
+			assert_eq!(node.name(), model.name);
+			assert!(node.is_visible());
+			assert_eq!(node.translate(), float3::zero());
+			assert_eq!(node.scale(), float3::one());
+			assert_eq!(node.motion().unwrap().name, model.name);
+		}
+	}
+}
+
+#[test]
+fn camera_orbits_its_target()
+{
+	let pmm = PMMFile::load(&fs::read(PMM_PATH).unwrap()).unwrap();
+	let scene = PMMLoader::load_from_pmm(&pmm).unwrap();
+
+	let camera = &scene.cameras()[0];
+	let current = pmm.camera_current_data.target_position;
+	assert!(approx(camera.translate(), float3::new(current.0, current.1, current.2)));
+	assert!(approx(camera.translate(), float3::new(0.0, 10.0, -45.0)));
+
+	let motion = camera.motion().unwrap();
+	let clip = &motion.cameras.clips[0];
+	assert_eq!(clip.name, "Camera");
+	assert_eq!(clip.curves["Distance"].evaluate(0.0), -45.0);
+	assert_eq!(clip.curves["Target.Y"].evaluate(0.0), 10.0);
+	assert_eq!(clip.curves["Fov"].evaluate(0.0), 30.0);
+
+	// A quarter turn around Y moves the camera to the side of its target and keeps it looking there.
+	let mut frame = PmmCameraFrame::new();
+	frame.distance = -10.0;
+	frame.eye_position = (1.0, 2.0, 3.0);
+	frame.rotation = (0.0, std::f32::consts::PI * 0.5, 0.0);
+
+	assert!(approx(frame.eye(), float3::new(-9.0, 2.0, 3.0)));
+	assert!(approx(frame.rotation().rotate(float3::new(0.0, 0.0, 1.0)), float3::new(1.0, 0.0, 0.0)));
+}
+
+#[test]
+fn light_keeps_its_color_and_direction()
+{
+	let scene = load_from_memory(&fs::read(PMM_PATH).unwrap()).unwrap();
+
+	let light = &scene.lights()[0];
+	let data = light.user_data::<LightData>().unwrap();
+	assert_eq!(data.kind, LightType::Directional);
+	assert_eq!(data.color, Spectrum::new(0.602, 0.602, 0.602));
+	assert_eq!(data.direction, float3::new(-0.5, -1.0, 0.5));
+
+	let motion = light.motion().unwrap();
+	assert_eq!(motion.lights.clips[0].name, "Light");
+	assert_eq!(motion.lights.clips[0].curves["Direction.Y"].evaluate(0.0), -1.0);
+}
+
+#[test]
+fn key_frames_are_followed_from_their_init_frame()
+{
+	let mut pmm = PMMFile::load(&fs::read(PMM_PATH).unwrap()).unwrap();
+
+	{
+		let model = &mut pmm.model[1];
+		let bone = model.bone_name.len() as i32;
+
+		// Listed out of order, the chain is init -> 30 -> 60.
+		let mut last = bone_frame(bone + 1, 60, 0);
+		last.translation = (0.0, 4.0, 0.0);
+		last.quaternion = (0.0, 1.0, 0.0, 0.0);
+
+		let mut middle = bone_frame(bone, 30, bone + 1);
+		middle.translation = (0.0, 2.0, 0.0);
+
+		model.bone_key_frame = vec![last, middle];
+		model.bone_init_frame[3].next_index = bone;
+
+		let mut morph = PmmMorphFrame::new();
+		morph.data_index = model.morph_name.len() as i32;
+		morph.frame_number = 10;
+		morph.next_index = 0;
+		morph.value = 1.0;
+
+		model.morph_key_frame = vec![morph];
+		model.morph_init_frame[5].next_index = model.morph_name.len() as i32;
+	}
+
+	let mut camera = PmmCameraFrame::new();
+	camera.data_index = 1;
+	camera.frame_number = 60;
+	camera.distance = -15.0;
+	camera.angle_view = 45;
+	camera.interpolation_distance = [20, 20, 107, 107];
+	pmm.camera_init_frame.next_index = 1;
+	pmm.camera_key_frames = vec![camera];
+
+	let scene = PMMLoader::load_from_pmm(&pmm).unwrap();
+
+	let motion = scene.models[1].motion().unwrap();
+	let name = &pmm.model[1].bone_name[3];
+
+	assert_eq!(motion.bones.len(), 1);
+	assert_eq!(&motion.bones.clips[0].name, name);
+
+	let position = &motion.bones.clips[0].curves["Position.Y"];
+	assert_eq!(position.frames.iter().map(|key| key.time).collect::<Vec<_>>(), vec![0.0, 30.0, 60.0]);
+	assert_eq!(position.evaluate(15.0), 1.0);
+	assert_eq!(position.evaluate(45.0), 3.0);
+
+	assert_eq!(motion.rotations.len(), 1);
+	assert_eq!(motion.rotations.clips[0].curves["Rotation"].frames.len(), 3);
+
+	assert_eq!(motion.morphs.len(), 1);
+	assert_eq!(motion.morphs.clips[0].name, pmm.model[1].morph_name[5]);
+	assert_eq!(motion.morphs.clips[0].curves["Weight"].evaluate(5.0), 0.5);
+
+	let motion = scene.cameras()[0].motion().unwrap();
+	assert_eq!(motion.cameras.clips[0].curves["Distance"].evaluate(30.0), -30.0);
+	assert_eq!(motion.cameras.clips[0].curves["Fov"].evaluate(60.0), 45.0);
+}
+
+#[test]
+fn accessories_are_placed_and_follow_their_model()
+{
+	let mut pmm = PMMFile::load(&fs::read(PMM_PATH).unwrap()).unwrap();
+
+	let mut floor = PmmAccessoryData::new();
+	floor.name = "floor".to_string();
+	floor.path = "missing/floor.x".to_string();
+	floor.current_data.is_visible = 1;
+	floor.current_data.parent_model_index = -1;
+	floor.current_data.translation = (1.0, 2.0, 3.0);
+	floor.current_data.scale = 2.0;
+	floor.init_frame.data = PmmDataBody::new();
+	floor.init_frame.data.translation = (1.0, 2.0, 3.0);
+
+	let mut hat = PmmAccessoryData::new();
+	hat.name = "hat".to_string();
+	hat.path = "missing/hat.x".to_string();
+	hat.current_data.parent_model_index = 1;
+	hat.current_data.scale = 1.0;
+
+	pmm.accessory_datas = vec![floor, hat];
+
+	let scene = PMMLoader::load_from_pmm(&pmm).unwrap();
+	assert_eq!(scene.models.len(), 4);
+
+	let floor = scene.models.iter().find(|node| node.name() == "floor").unwrap();
+	assert!(floor.is_visible());
+	assert!(floor.parent().is_none());
+	assert_eq!(floor.translate(), float3::new(1.0, 2.0, 3.0));
+	assert_eq!(floor.scale(), float3::new(2.0, 2.0, 2.0));
+	assert_eq!(floor.motion().unwrap().bones.clips[0].curves["Position.Z"].evaluate(0.0), 3.0);
+
+	let hat = scene.models.iter().find(|node| node.name() == "hat").unwrap();
+	assert!(!hat.is_visible());
+	assert_eq!(hat.parent().unwrap().name(), pmm.model[1].name);
+}
+
+#[test]
+fn models_keep_their_placement()
+{
+	let mut pmm = PMMFile::load(&fs::read(PMM_PATH).unwrap()).unwrap();
+	let root = pmm.model[1].bone_name.iter().position(|name| name == PMM_ROOT_BONE).unwrap();
+
+	let rotation = Quaternion::euler(&float3::new(0.0, 0.5, 0.0), RotationOrder::ZXY);
+	pmm.model[1].bone_init_frame[root].translation = (5.0, 0.0, -2.0);
+	pmm.model[1].bone_init_frame[root].quaternion = rotation.to_tuple();
+
+	let scene = PMMLoader::load_from_pmm(&pmm).unwrap();
+	let model = scene.models.iter().find(|node| node.name() == pmm.model[1].name).unwrap();
+	assert_eq!(model.translate(), float3::new(5.0, 0.0, -2.0));
+	assert!(model.quaternion().dot(rotation).abs() > 0.9999);
+
+	// The bone doesn't move the model a second time.
+	let motion = model.motion().unwrap();
+	assert!(motion.bones.clips.iter().all(|clip| clip.name != PMM_ROOT_BONE));
+	assert!(motion.rotations.clips.iter().all(|clip| clip.name != PMM_ROOT_BONE));
+
+	// A keyed root bone is a motion, the node stays where it is.
+	let mut init = bone_frame(root as i32, 0, 1000);
+	init.translation = (5.0, 0.0, -2.0);
+	let mut key = bone_frame(1000, 30, -1);
+	key.pre_index = root as i32;
+	pmm.model[1].bone_init_frame[root] = init;
+	pmm.model[1].bone_key_frame.push(key);
+	assert!(pmm.model[1].placement().is_none());
+}
+
+// MikuMikuDance leaves whatever was in its buffer after the NUL of a fixed length name, which is saved as zeros.
+// The longest of those names is a path of 256 bytes.
+fn is_name_padding(original:&[u8], saved:&[u8], i:usize) -> bool
+{
+	saved[i] == 0 && (i.saturating_sub(256)..i).rev().take_while(|&j| saved[j] == 0).any(|j| original[j] == 0)
+}
+
+#[test]
+fn saving_a_project_round_trips()
+{
+	for path in [PMM_PATH, PMM_PATH2].iter()
+	{
+		let buf = fs::read(path).unwrap();
+		let pmm = PMMFile::load(&buf).unwrap();
+		let saved = pmm.save().unwrap();
+
+		assert_eq!(saved.len(), buf.len());
+
+		let differing:Vec<usize> = (0..buf.len()).filter(|&i| buf[i] != saved[i]).collect();
+		assert!(differing.iter().all(|&i| is_name_padding(&buf, &saved, i)), "{:?}", differing.iter().find(|&&i| !is_name_padding(&buf, &saved, i)));
+
+		let reloaded = PMMFile::load(&saved).unwrap();
+		assert_eq!(format!("{:?}", reloaded), format!("{:?}", pmm));
+		assert_eq!(reloaded.save().unwrap(), saved);
+
+		assert_eq!(reloaded.header.magic, pmm.header.magic);
+		assert_eq!(reloaded.model.len(), pmm.model.len());
+
+		for (a, b) in reloaded.model.iter().zip(pmm.model.iter())
+		{
+			assert_eq!(a.name, b.name);
+			assert_eq!(a.path, b.path);
+			assert_eq!(a.bone_name, b.bone_name);
+			assert_eq!(a.morph_name, b.morph_name);
+			assert_eq!(a.bone_key_frame.len(), b.bone_key_frame.len());
+			assert_eq!(a.morph_key_frame.len(), b.morph_key_frame.len());
+		}
+
+		assert_eq!(reloaded.camera_key_frames.len(), pmm.camera_key_frames.len());
+		assert_eq!(reloaded.camera_current_data.target_position, pmm.camera_current_data.target_position);
+		assert_eq!(reloaded.light_init_frame.rgb, pmm.light_init_frame.rgb);
+		assert_eq!(reloaded.accessory_name, pmm.accessory_name);
+		assert_eq!(reloaded.gravity_current_data.acceleration, pmm.gravity_current_data.acceleration);
+		assert_eq!(reloaded.selfshadow_current_data, pmm.selfshadow_current_data);
+		assert_eq!(reloaded.selector_choice_datas.len(), pmm.selector_choice_datas.len());
+	}
+}
+
+#[test]
+fn edited_projects_keep_their_frames()
+{
+	let mut pmm = PMMFile::load(&fs::read(PMM_PATH).unwrap()).unwrap();
+
+	let mut floor = PmmAccessoryData::new();
+	floor.name = "floor".to_string();
+	floor.path = "missing/floor.x".to_string();
+	floor.current_data.scale = 2.0;
+
+	let mut camera = PmmCameraFrame::new();
+	camera.data_index = 1;
+	camera.frame_number = 60;
+	camera.distance = -15.0;
+	camera.angle_view = 45;
+	pmm.camera_init_frame.next_index = 1;
+	pmm.camera_key_frames = vec![camera];
+
+	pmm.accessory_name = vec![floor.name.clone()];
+	pmm.accessory_datas = vec![floor];
+
+	let reloaded = PMMFile::load(&pmm.save().unwrap()).unwrap();
+	assert_eq!(reloaded.camera_key_frames[0].frame_number, 60);
+	assert_eq!(reloaded.camera_key_frames[0].angle_view, 45);
+	assert_eq!(reloaded.accessory_name, vec!["floor".to_string()]);
+	assert_eq!(reloaded.accessory_datas[0].path, "missing/floor.x");
+	assert_eq!(reloaded.accessory_datas[0].current_data.scale, 2.0);
+
+	// Every accessory needs its name written before the data.
+	pmm.accessory_name.clear();
+	assert!(pmm.save().is_err());
+}
+
+#[test]
+fn scenes_save_their_camera_and_light()
+{
+	let mut pmm = PMMFile::load(&fs::read(PMM_PATH).unwrap()).unwrap();
+
+	let mut camera = PmmCameraFrame::new();
+	camera.data_index = 1;
+	camera.frame_number = 60;
+	camera.distance = -15.0;
+	camera.eye_position = (0.0, 10.0, 0.0);
+	camera.angle_view = 45;
+	pmm.camera_init_frame.next_index = 1;
+	pmm.camera_key_frames = vec![camera];
+
+	let scene = PMMLoader::load_from_pmm(&pmm).unwrap();
+	let saved = PMMFile::load(&PMMLoader::new().do_save(&scene).unwrap()).unwrap();
+
+	assert_eq!(saved.model.len(), pmm.model.len());
+	assert_eq!(saved.camera_init_frame.distance, -45.0);
+	assert_eq!(saved.camera_key_frames.len(), 1);
+	assert_eq!(saved.camera_init_frame.next_index, saved.camera_key_frames[0].data_index);
+	assert_eq!(saved.camera_key_frames[0].frame_number, 60);
+	assert_eq!(saved.camera_key_frames[0].distance, -15.0);
+	assert_eq!(saved.camera_key_frames[0].angle_view, 45);
+	assert_eq!(saved.camera_key_frames[0].interpolation_distance, [20, 20, 107, 107]);
+	assert!(approx(float3::new(saved.camera_current_data.target_position.0, saved.camera_current_data.target_position.1, saved.camera_current_data.target_position.2), float3::new(0.0, 10.0, -45.0)));
+
+	assert_eq!(saved.light_init_frame.rgb, (0.602, 0.602, 0.602));
+	assert_eq!(saved.light_init_frame.xyz, (-0.5, -1.0, 0.5));
+
+	// Loading the saved project again gives back the same camera path.
+	let scene = PMMLoader::load_from_pmm(&saved).unwrap();
+	let motion = scene.cameras()[0].motion().unwrap();
+	assert_eq!(motion.cameras.clips[0].curves["Distance"].evaluate(30.0), -30.0);
+	assert!(approx(scene.cameras()[0].translate(), float3::new(0.0, 10.0, -45.0)));
+}
+
+#[test]
+fn scenes_save_the_curves_of_their_camera()
+{
+	let mut pmm = PMMFile::load(&fs::read(PMM_PATH).unwrap()).unwrap();
+
+	let mut camera = PmmCameraFrame::new();
+	camera.data_index = 1;
+	camera.frame_number = 60;
+	camera.distance = -15.0;
+	camera.angle_view = 45;
+	camera.interpolation_distance = [64, 0, 64, 127];
+	camera.interpolation_x = [10, 30, 90, 120];
+	camera.interpolation_rotation = [127, 0, 0, 127];
+	pmm.camera_init_frame.next_index = 1;
+	pmm.camera_key_frames = vec![camera];
+
+	let scene = PMMLoader::load_from_pmm(&pmm).unwrap();
+	let saved = PMMFile::load(&PMMLoader::new().do_save(&scene).unwrap()).unwrap();
+
+	let camera = &saved.camera_key_frames[0];
+	assert_eq!(camera.interpolation_distance, [64, 0, 64, 127]);
+	assert_eq!(camera.interpolation_x, [10, 30, 90, 120]);
+	assert_eq!(camera.interpolation_rotation, [127, 0, 0, 127]);
+	assert_eq!(camera.interpolation_y, [20, 20, 107, 107]);
+	assert_eq!(saved.camera_init_frame.interpolation_distance, [20, 20, 107, 107]);
+}
+
+#[test]
+fn scenes_save_their_models_and_accessories()
+{
+	let mut pmm = PMMFile::load(&fs::read(PMM_PATH).unwrap()).unwrap();
+	let root = pmm.model[1].bone_name.iter().position(|name| name == PMM_ROOT_BONE).unwrap();
+
+	{
+		let model = &mut pmm.model[1];
+		let bone = model.bone_name.len() as i32;
+
+		let mut key = bone_frame(bone, 30, 0);
+		key.pre_index = 3;
+		key.translation = (0.0, 2.0, 0.0);
+		key.interpolation_y = [64, 0, 64, 127];
+
+		model.bone_key_frame = vec![key];
+		model.bone_init_frame[3].next_index = bone;
+		model.bone_init_frame[root].translation = (5.0, 0.0, -2.0);
+
+		let mut morph = PmmMorphFrame::new();
+		morph.data_index = model.morph_name.len() as i32;
+		morph.frame_number = 10;
+		morph.pre_index = 5;
+		morph.next_index = 0;
+		morph.value = 1.0;
+
+		model.morph_key_frame = vec![morph];
+		model.morph_init_frame[5].next_index = model.morph_name.len() as i32;
+	}
+
+	let mut floor = PmmAccessoryData::new();
+	floor.name = "floor".to_string();
+	floor.path = "missing/floor.x".to_string();
+	floor.current_data.is_visible = 1;
+	floor.current_data.parent_model_index = -1;
+	floor.current_data.translation = (1.0, 2.0, 3.0);
+	floor.current_data.scale = 2.0;
+	floor.init_frame.frame_number = 0;
+	floor.init_frame.data.is_visible = 1;
+	floor.init_frame.data.translation = (1.0, 2.0, 3.0);
+	floor.init_frame.data.scale = 2.0;
+
+	let mut hat = PmmAccessoryData::new();
+	hat.name = "hat".to_string();
+	hat.path = "missing/hat.x".to_string();
+	hat.current_data.parent_model_index = 1;
+	hat.current_data.parent_bone_index = 7;
+	hat.current_data.scale = 1.0;
+	hat.init_frame.frame_number = 0;
+	hat.init_frame.data.scale = 1.0;
+
+	pmm.accessory_name = vec![floor.name.clone(), hat.name.clone()];
+	pmm.accessory_datas = vec![floor, hat];
+
+	let scene = PMMLoader::load_from_pmm(&pmm).unwrap();
+	let saved = PMMFile::load(&PMMLoader::new().do_save(&scene).unwrap()).unwrap();
+
+	assert_eq!(saved.model.len(), pmm.model.len());
+	for (a, b) in saved.model.iter().zip(pmm.model.iter())
+	{
+		assert_eq!(a.name, b.name);
+		assert_eq!(a.path, b.path);
+		assert_eq!(a.bone_name, b.bone_name);
+		assert_eq!(a.morph_name, b.morph_name);
+		assert_eq!(a.ik_index, b.ik_index);
+		assert_eq!(a.op_index, b.op_index);
+	}
+
+	// The saved frames load back into the same motion and placement.
+	let model = &saved.model[1];
+	assert_eq!(model.bone_key_frame.len(), 1);
+	assert_eq!(model.bone_key_frame[0].interpolation_y, [64, 0, 64, 127]);
+	assert_eq!(model.bone_init_frame[root].translation, (5.0, 0.0, -2.0));
+	assert_eq!(model.morph_key_frame.len(), 1);
+
+	let reloaded = PMMLoader::load_from_pmm(&saved).unwrap();
+	let node = reloaded.models.iter().find(|node| node.name() == model.name).unwrap();
+	assert_eq!(node.translate(), float3::new(5.0, 0.0, -2.0));
+
+	let motion = node.motion().unwrap();
+	let position = &motion.bones.clips[0].curves["Position.Y"];
+	assert_eq!(position.frames.iter().map(|key| key.time).collect::<Vec<_>>(), vec![0.0, 30.0]);
+	assert_eq!(position.evaluate(30.0), 2.0);
+	assert_eq!(motion.morphs.clips[0].curves["Weight"].evaluate(5.0), 0.5);
+
+	assert_eq!(saved.accessory_name.len(), 2);
+	let floor = saved.accessory_datas.iter().find(|accessory| accessory.name == "floor").unwrap();
+	assert_eq!(floor.path, "missing/floor.x");
+	assert_eq!(floor.current_data.parent_model_index, -1);
+	assert_eq!(floor.current_data.translation, (1.0, 2.0, 3.0));
+	assert_eq!(floor.init_frame.data.scale, 2.0);
+
+	let hat = saved.accessory_datas.iter().find(|accessory| accessory.name == "hat").unwrap();
+	assert_eq!(hat.path, "missing/hat.x");
+	assert_eq!(hat.current_data.is_visible, 0);
+	assert_eq!(hat.current_data.parent_model_index, 1);
+	assert_eq!(hat.current_data.parent_bone_index, 7);
+}
+
+#[test]
+fn projects_hold_at_most_255_accessories()
+{
+	let mut pmm = PMMFile::load(&fs::read(PMM_PATH).unwrap()).unwrap();
+
+	pmm.accessory_name = (0..256).map(|i| i.to_string()).collect();
+	pmm.accessory_datas = (0..256).map(|_| PmmAccessoryData::new()).collect();
+	assert!(pmm.save().is_err());
+
+	pmm.accessory_name.pop();
+	pmm.accessory_datas.pop();
+	assert_eq!(PMMFile::load(&pmm.save().unwrap()).unwrap().accessory_datas.len(), 255);
+}
+
+#[test]
+fn posing_a_scene_moves_its_camera_and_light()
+{
+	// The sample projects never move their camera or light, so both get a key at frame 60.
+	let mut pmm = PMMFile::load(&fs::read(PMM_PATH).unwrap()).unwrap();
+
+	let mut camera = PmmCameraFrame::new();
+	camera.data_index = 1;
+	camera.frame_number = 60;
+	camera.distance = -20.0;
+	camera.eye_position = (0.0, 10.0, 0.0);
+	camera.rotation = (0.0, std::f32::consts::PI * 0.5, 0.0);
+	camera.angle_view = 45;
+	pmm.camera_init_frame.next_index = 1;
+	pmm.camera_key_frames = vec![camera];
+
+	let mut light = PmmLightFrame::new();
+	light.data_index = 1;
+	light.frame_number = 60;
+	light.rgb = (1.0, 0.5, 0.25);
+	light.xyz = (0.0, -1.0, 0.0);
+	pmm.light_init_frame.next_index = 1;
+	pmm.light_key_frames = vec![light];
+
+	let mut scene = PMMLoader::load_from_pmm(&pmm).unwrap();
+
+	scene.update_pose(0.0);
+	let camera = &scene.cameras()[0];
+	assert!(approx(camera.translate(), float3::new(0.0, 10.0, -45.0)), "{:?}", camera.translate());
+	assert_eq!(camera.user_data::<CameraData>().unwrap().fov, 30.0);
+	assert_eq!(scene.lights()[0].user_data::<LightData>().unwrap().direction, float3::new(-0.5, -1.0, 0.5));
+
+	scene.update_pose(60.0);
+	let camera = &scene.cameras()[0];
+	assert!(approx(camera.translate(), float3::new(-20.0, 10.0, 0.0)), "{:?}", camera.translate());
+	assert!(approx(camera.quaternion().rotate(float3::new(0.0, 0.0, 1.0)), float3::new(1.0, 0.0, 0.0)));
+	assert_eq!(camera.user_data::<CameraData>().unwrap().fov, 45.0);
+
+	let data = scene.lights()[0].user_data::<LightData>().unwrap();
+	assert_eq!(data.color, Spectrum::new(1.0, 0.5, 0.25));
+	assert_eq!(data.direction, float3::new(0.0, -1.0, 0.0));
+}
@@ -0,0 +1,346 @@
+extern crate octoon;
+extern crate byteorder;
+
+use std::fs;
+use std::io::Cursor;
+use byteorder::{ByteOrder, LittleEndian};
+use octoon::models::{self, Loader, PMXFile, PMXLoader, PMX_ENCODE_UTF8, SphereMode, Toon, BoneTail};
+use octoon::models::{PmxMorph, PmxMorphData, PmxMorphVertex, PmxMorphUV, PmxMorphGroup, PMX_MORPH_VERTEX, PMX_MORPH_UV, PMX_MORPH_GROUP, PMX_QDEF};
+use octoon::models::{PmxSoftbody, PmxSoftbodyAnchor, PmxBoneWeight, PmxHeader, PMX_BDEF2};
+use octoon::models::{PMX_SPHERE_NONE, PMX_SPHERE_MULTIPLY, PMX_SPHERE_ADDITIVE, PMX_SPHERE_SUB_TEXTURE};
+
+const PMX_PATH:&str = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/pmx/安特.pmx");
+
+#[test]
+fn pmx_file_round_trip()
+{
+	let buf = fs::read(PMX_PATH).unwrap();
+	let pmx = PMXFile::load(&buf).unwrap();
+	let saved = pmx.save().unwrap();
+
+	assert_eq!(buf.len(), saved.len());
+	assert!(buf == saved);
+}
+
+// The sample model has no morphs, this adds vertex, uv and group morphs touching vertices of every material.
+fn with_morphs() -> Vec<u8>
+{
+	let mut pmx = PMXFile::load(&fs::read(PMX_PATH).unwrap()).unwrap();
+	let vertices:Vec<u32> = (0..pmx.vertices.len() as u32).step_by(97).collect();
+
+	let mut morph = PmxMorph::new();
+	morph.name = "move".to_string();
+	morph.kind = PMX_MORPH_VERTEX;
+	morph.data = PmxMorphData::Vertex(vertices.iter().map(|&index| PmxMorphVertex { index, offset:(index as f32, 1.0, -1.0) }).collect());
+	pmx.morphs.push(morph);
+
+	let mut morph = PmxMorph::new();
+	morph.name = "scroll".to_string();
+	morph.kind = PMX_MORPH_UV;
+	morph.data = PmxMorphData::UV(vertices.iter().map(|&index| PmxMorphUV { index:index + 1, offset:(0.5, index as f32, 0.0, 0.0) }).collect());
+	pmx.morphs.push(morph);
+
+	let mut morph = PmxMorph::new();
+	morph.name = "both".to_string();
+	morph.kind = PMX_MORPH_GROUP;
+	morph.data = PmxMorphData::Group(vec![PmxMorphGroup { index:0, ratio:0.5 }, PmxMorphGroup { index:1, ratio:1.0 }]);
+	pmx.morphs.push(morph);
+
+	pmx.update_header();
+	pmx.save().unwrap()
+}
+
+#[test]
+fn pmx_model_round_trip()
+{
+	let model = PMXLoader::new().do_load(&with_morphs()).unwrap();
+	let buf = PMXLoader::new().do_save(&model).unwrap();
+	let loaded = PMXLoader::new().do_load(&buf).unwrap();
+
+	assert_eq!(model.meshes.len(), loaded.meshes.len());
+	assert_eq!(model.materials.len(), loaded.materials.len());
+	assert_eq!(model.textures, loaded.textures);
+	assert_eq!(model.bones.len(), loaded.bones.len());
+	assert_eq!(model.iks.len(), loaded.iks.len());
+	assert_eq!(model.rigidbodies.len(), loaded.rigidbodies.len());
+	assert_eq!(model.joints.len(), loaded.joints.len());
+
+	for (a, b) in model.meshes.iter().zip(loaded.meshes.iter())
+	{
+		assert_eq!(a.positions, b.positions);
+		assert_eq!(a.normals, b.normals);
+		assert_eq!(a.texcoords, b.texcoords);
+		assert_eq!(a.indices, b.indices);
+
+		// Morphs still move the same vertices of each mesh.
+		assert_eq!(a.morphs.len(), b.morphs.len());
+		for (a, b) in a.morphs.iter().zip(b.morphs.iter())
+		{
+			assert_eq!(a.name, b.name);
+			assert_eq!(a.positions.iter().map(|v| (v.index, v.offset)).collect::<Vec<_>>(), b.positions.iter().map(|v| (v.index, v.offset)).collect::<Vec<_>>());
+			assert_eq!(a.texcoords.iter().map(|v| (v.index, v.offset)).collect::<Vec<_>>(), b.texcoords.iter().map(|v| (v.index, v.offset)).collect::<Vec<_>>());
+			assert_eq!(a.materials.len(), b.materials.len());
+		}
+	}
+
+	assert_eq!(model.morphs.len(), loaded.morphs.len());
+	assert!(model.meshes.iter().filter(|mesh| mesh.morphs.len() == 3).count() > 1);
+
+	for (a, b) in model.bones.iter().zip(loaded.bones.iter())
+	{
+		assert_eq!(a.name, b.name);
+		assert_eq!(a.parent, b.parent);
+		assert_eq!(a.position, b.position);
+		assert_eq!(a.tail, b.tail);
+	}
+
+	assert!(model.bones.iter().any(|bone| match bone.tail { BoneTail::Bone(_) => true, _ => false }));
+}
+
+#[test]
+fn pmx_model_utf8()
+{
+	let model = models::open(PMX_PATH).unwrap();
+	let pmx = PMXFile::from_model(&model, PMX_ENCODE_UTF8).unwrap();
+	let loaded = PMXFile::load(&pmx.save().unwrap()).unwrap();
+
+	assert_eq!(loaded.hdr.encode, PMX_ENCODE_UTF8);
+	assert_eq!(loaded.hdr.sizeof_indices, 2);
+
+	for (a, b) in model.bones.iter().zip(loaded.bones.iter())
+	{
+		assert_eq!(a.name, b.name);
+	}
+}
+
+#[test]
+fn pmx_sphere_modes_and_toons()
+{
+	let mut pmx = PMXFile::load(&fs::read(PMX_PATH).unwrap()).unwrap();
+	assert!(pmx.materials.len() >= 5);
+
+	let modes = [PMX_SPHERE_NONE, PMX_SPHERE_MULTIPLY, PMX_SPHERE_ADDITIVE, PMX_SPHERE_SUB_TEXTURE, PMX_SPHERE_ADDITIVE];
+	let toons = [(1, 3), (0, 0), (0, -1), (1, 9), (0, 1)];
+
+	for (i, material) in pmx.materials.iter_mut().take(5).enumerate()
+	{
+		material.sphere_texture_index = if i < 4 { 0 } else { -1 };
+		material.sphere_mode = modes[i];
+		material.toon_index = toons[i].0;
+		material.toon_texture = toons[i].1;
+	}
+
+	let model = PMXLoader::new().do_load(&pmx.save().unwrap()).unwrap();
+	let spheres:Vec<_> = model.materials.iter().take(5).map(|material| (material.sphere_texture, material.sphere_mode)).collect();
+	let toons:Vec<_> = model.materials.iter().take(5).map(|material| material.toon).collect();
+
+	// A sphere mode without a sphere texture draws nothing.
+	assert_eq!(spheres, vec![(Some(0), SphereMode::None), (Some(0), SphereMode::Multiply), (Some(0), SphereMode::Additive), (Some(0), SphereMode::SubTexture), (None, SphereMode::None)]);
+	assert_eq!(toons, vec![Toon::Shared(3), Toon::Texture(0), Toon::None, Toon::Shared(9), Toon::Texture(1)]);
+
+	// And they are written back the same way.
+	let loaded = PMXLoader::new().do_load(&PMXLoader::new().do_save(&model).unwrap()).unwrap();
+	for (a, b) in model.materials.iter().zip(loaded.materials.iter())
+	{
+		assert_eq!((a.sphere_texture, a.sphere_mode, a.toon), (b.sphere_texture, b.sphere_mode, b.toon));
+	}
+}
+
+#[test]
+fn pmx_qdef_reads_four_bones()
+{
+	let mut pmx = PMXFile::load(&fs::read(PMX_PATH).unwrap()).unwrap();
+	for vertex in pmx.vertices.iter_mut().take(2)
+	{
+		let w = &mut vertex.weight;
+		w.kind = PMX_QDEF;
+		w.bone1 = 0; w.bone2 = 1; w.bone3 = 2; w.bone4 = 3;
+		w.weight1 = 0.1; w.weight2 = 0.2; w.weight3 = 0.3; w.weight4 = 0.4;
+	}
+
+	pmx.update_header();
+	let buf = pmx.save().unwrap();
+
+	// Reading only two bones and one weight would shift everything after the first vertex.
+	let loaded = PMXFile::load(&buf).unwrap();
+	assert_eq!(loaded.vertices.len(), pmx.vertices.len());
+	assert_eq!(loaded.vertices[2].position, pmx.vertices[2].position);
+	assert!(loaded.save().unwrap() == buf);
+
+	let w = &loaded.vertices[1].weight;
+	assert_eq!(w.kind, PMX_QDEF);
+	assert_eq!((w.bone1, w.bone2, w.bone3, w.bone4), (0, 1, 2, 3));
+	assert_eq!((w.weight1, w.weight2, w.weight3, w.weight4), (0.1, 0.2, 0.3, 0.4));
+
+	let model = PMXLoader::new().do_load(&buf).unwrap();
+	assert!(model.meshes.iter().flat_map(|mesh| mesh.weights.iter()).any(|weight| weight.bone == [0, 1, 2, 3] && weight.weight == [0.1, 0.2, 0.3, 0.4]));
+}
+
+#[test]
+fn pmx_soft_bodies_round_trip()
+{
+	let mut pmx = PMXFile::load(&fs::read(PMX_PATH).unwrap()).unwrap();
+	let start = pmx.materials[0].face_count as usize;
+	let drawn:Vec<u32> = (start..start + 3).map(|i| pmx.indices.get(i)).collect();
+
+	let mut softbody = PmxSoftbody::new();
+	softbody.name = "skirt".to_string();
+	softbody.material = 1;
+	softbody.total_mass = 2.0;
+	softbody.config[3] = 0.5;
+	softbody.iteration = [1, 2, 3, 4];
+	softbody.anchors = drawn.iter().map(|&vertex| PmxSoftbodyAnchor { rigidbody:0, vertex, near_mode:1 }).collect();
+	softbody.pin_vertices = drawn.clone();
+	pmx.softbodies.push(softbody);
+	pmx.update_header();
+
+	let model = PMXLoader::new().do_load(&pmx.save().unwrap()).unwrap();
+	assert_eq!(model.softbodies.len(), 1);
+
+	// Vertices point into the mesh of the material, where they are drawn first.
+	let softbody = &model.softbodies[0];
+	assert_eq!(softbody.name, "skirt");
+	assert_eq!(softbody.material, Some(1));
+	assert_eq!((softbody.total_mass, softbody.config[3], softbody.iteration), (2.0, 0.5, [1, 2, 3, 4]));
+	assert_eq!(softbody.pin_vertices, model.meshes[1].indices[0..3].to_vec());
+	assert_eq!(softbody.anchors.iter().map(|x| (x.rigidbody, x.vertex, x.near_mode)).collect::<Vec<_>>(), softbody.pin_vertices.iter().map(|&x| (0, x, true)).collect::<Vec<_>>());
+
+	let saved = PMXFile::from_model(&model, PMX_ENCODE_UTF8).unwrap();
+	let loaded = PMXLoader::new().do_load(&saved.save().unwrap()).unwrap();
+	assert_eq!(saved.hdr.version, pmx.hdr.version);
+	assert_eq!(loaded.softbodies[0].pin_vertices, softbody.pin_vertices);
+	assert_eq!(loaded.softbodies[0].anchors.len(), 3);
+
+	let position = |vertices:&[octoon::models::PmxVertex], i:u32| vertices[i as usize].position;
+	for (a, b) in drawn.iter().zip(saved.softbodies[0].pin_vertices.iter())
+	{
+		assert_eq!(position(&pmx.vertices, *a), position(&saved.vertices, *b));
+	}
+}
+
+#[test]
+fn pmx_morphs_sharing_a_name_keep_their_offsets()
+{
+	let mut pmx = PMXFile::load(&with_morphs()).unwrap();
+
+	let mut morph = PmxMorph::new();
+	morph.name = "move".to_string();
+	morph.kind = PMX_MORPH_VERTEX;
+	morph.data = PmxMorphData::Vertex(vec![PmxMorphVertex { index:1, offset:(0.0, 0.0, 7.0) }]);
+	pmx.morphs.push(morph);
+	pmx.update_header();
+
+	let model = PMXLoader::new().do_load(&pmx.save().unwrap()).unwrap();
+	let saved = PMXFile::from_model(&model, PMX_ENCODE_UTF8).unwrap();
+
+	let offsets = |morph:&PmxMorph| match morph.data
+	{
+		PmxMorphData::Vertex(ref v) => v.iter().map(|x| x.offset).collect::<Vec<_>>(),
+		_ => Vec::new(),
+	};
+
+	assert_eq!(saved.morphs[0].name, saved.morphs[3].name);
+	assert_eq!(offsets(&saved.morphs[3]), vec![(0.0, 0.0, 7.0)]);
+	assert_eq!(offsets(&saved.morphs[0]).len(), offsets(&pmx.morphs[0]).len());
+}
+
+#[test]
+fn pmx_bone_flags_and_iks_round_trip()
+{
+	let mut model = models::open(PMX_PATH).unwrap();
+	assert!(model.iks.len() > 0);
+
+	model.bones[1].visible = false;
+	model.bones[2].movable = !model.bones[2].movable;
+	model.bones[3].operable = false;
+
+	let loaded = PMXLoader::new().do_load(&PMXLoader::new().do_save(&model).unwrap()).unwrap();
+
+	for (a, b) in model.bones.iter().zip(loaded.bones.iter())
+	{
+		assert_eq!((a.rotatable, a.movable, a.visible, a.operable), (b.rotatable, b.movable, b.visible, b.operable));
+		assert_eq!(a.parent, b.parent);
+		assert_eq!(a.grant.as_ref().map(|x| (x.parent, x.rotation, x.translation)), b.grant.as_ref().map(|x| (x.parent, x.rotation, x.translation)));
+	}
+
+	assert_eq!(model.iks.len(), loaded.iks.len());
+	for (a, b) in model.iks.iter().zip(loaded.iks.iter())
+	{
+		assert_eq!((a.bone, a.target_bone, a.loop_count, a.limited_radian), (b.bone, b.target_bone, b.loop_count, b.limited_radian));
+		assert_eq!(a.links.iter().map(|x| (x.bone, x.rotate_limited)).collect::<Vec<_>>(), b.links.iter().map(|x| (x.bone, x.rotate_limited)).collect::<Vec<_>>());
+	}
+}
+
+#[test]
+fn pmx_bone_indices_must_fit_their_size()
+{
+	let mut weight = PmxBoneWeight::new();
+	weight.kind = PMX_BDEF2;
+	weight.bone1 = 40000;
+	weight.bone2 = 0xFFFF;
+	weight.weight1 = 1.0;
+
+	let mut hdr = PmxHeader::new();
+	hdr.sizeof_bone = 2;
+	assert!(weight.save(&mut Vec::new(), &hdr).is_err());
+
+	// Four byte indices reach past 32767 and the missing bone is still written as -1.
+	hdr.sizeof_bone = 4;
+	let mut buf = Vec::new();
+	weight.save(&mut buf, &hdr).unwrap();
+	assert_eq!(LittleEndian::read_i32(&buf[5..]), -1);
+
+	let loaded = PmxBoneWeight::load(&mut Cursor::new(&buf[..]), &hdr).unwrap();
+	assert_eq!((loaded.bone1, loaded.bone2), (40000, 0xFFFF));
+}
+
+// The vertex count follows the 17 bytes of the header and the four names of the description.
+fn vertex_count_offset(buf:&[u8]) -> usize
+{
+	let mut offset = 17;
+	for _ in 0..4
+	{
+		offset += 4 + LittleEndian::read_u32(&buf[offset..]) as usize;
+	}
+
+	offset
+}
+
+#[test]
+fn pmx_rejects_counts_larger_than_the_file()
+{
+	let buf = fs::read(PMX_PATH).unwrap();
+	let offset = vertex_count_offset(&buf);
+
+	let mut vertices = buf.clone();
+	LittleEndian::write_u32(&mut vertices[offset..], 0xFFFF_FFFF);
+	assert!(PMXFile::load(&vertices).is_err());
+
+	let mut name = buf.clone();
+	LittleEndian::write_u32(&mut name[17..], 0x7FFF_FFFF);
+	assert!(PMXFile::load(&name).is_err());
+}
+
+#[test]
+fn pmx_rejects_faces_and_indices_out_of_range()
+{
+	let mut pmx = PMXFile::load(&fs::read(PMX_PATH).unwrap()).unwrap();
+	pmx.materials[0].face_count += pmx.indices.len() as u32;
+	assert!(PMXLoader::new().do_load(&pmx.save().unwrap()).is_err());
+
+	let mut pmx = PMXFile::load(&fs::read(PMX_PATH).unwrap()).unwrap();
+	pmx.vertices.pop();
+	assert!(PMXLoader::new().do_load(&pmx.save().unwrap()).is_err());
+}
+
+#[test]
+fn pmx_truncated_files_do_not_panic()
+{
+	let buf = with_morphs();
+	let lengths = (0..buf.len()).step_by(buf.len() / 509 + 1).chain(buf.len() - 256..buf.len());
+
+	for length in lengths
+	{
+		assert!(PMXLoader::new().do_load(&buf[..length]).is_err());
+	}
+}
@@ -0,0 +1,278 @@
+extern crate octoon;
+extern crate encoding;
+
+use std::fs;
+use std::rc::Rc;
+use std::path::PathBuf;
+use encoding::{Encoding, EncoderTrap};
+use encoding::all::WINDOWS_31J;
+use octoon::models;
+use octoon::scene::*;
+
+const PMM_PATH:&str = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/pmm/1.pmm");
+const PMX_PATH:&str = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/pmx/安特.pmx");
+const VMD_PATH:&str = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/vmd/M.vmd");
+
+fn temp_dir(name:&str) -> PathBuf
+{
+	let dir = std::env::temp_dir().join(format!("octoon_{}_{}", name, std::process::id()));
+	let _ = fs::remove_dir_all(&dir);
+	fs::create_dir_all(&dir).unwrap();
+	dir
+}
+
+fn red_png() -> Vec<u8>
+{
+	TextureLoader::to_png(&Texture::new(ColorType::RGBA(8), 1, 1, vec![255, 0, 0, 255])).unwrap()
+}
+
+// A triangle whose material samples the given texture.
+fn textured_model(texture:&str) -> models::Model
+{
+	let mut mesh = models::Mesh::new();
+	mesh.material_id = Some(0);
+	mesh.positions = vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0];
+	mesh.normals = vec![0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0];
+	mesh.texcoords = vec![0.0, 0.0, 1.0, 0.0, 0.0, 1.0];
+	mesh.indices = vec![0, 1, 2];
+
+	let mut material = models::Material::new();
+	material.diffuse_texture = Some(0);
+
+	let mut model = models::Model::new();
+	model.meshes.push(mesh);
+	model.materials.push(material);
+	model.textures.push(texture.to_string());
+	model
+}
+
+fn albedo_map(shape:&ModelShape) -> Option<std::sync::Arc<Texture>>
+{
+	match shape.shapes[0].node.material().unwrap().uniforms()["albedo_map"]
+	{
+		Variant::Texture(ref texture) => texture.clone(),
+		_ => None
+	}
+}
+
+// Stored and deflated entries, names are written as they are given.
+fn zip(entries:&[(&[u8], u16, u16, &[u8], usize)]) -> Vec<u8>
+{
+	let mut buffer = Vec::new();
+	let mut central = Vec::new();
+
+	let u16le = |v:u16| v.to_le_bytes().to_vec();
+	let u32le = |v:u32| v.to_le_bytes().to_vec();
+
+	for (name, flags, method, data, size) in entries.iter()
+	{
+		let offset = buffer.len() as u32;
+		let header = [u16le(20), u16le(*flags), u16le(*method), u32le(0), u32le(0), u32le(data.len() as u32), u32le(*size as u32), u16le(name.len() as u16), u16le(0)].concat();
+
+		buffer.extend(u32le(0x04034b50));
+		buffer.extend(&header);
+		buffer.extend(name.iter());
+		buffer.extend(data.iter());
+
+		central.extend(u32le(0x02014b50));
+		central.extend(u16le(20));
+		central.extend(&header);
+		central.extend([u16le(0), u16le(0), u16le(0), u32le(0), u32le(offset)].concat());
+		central.extend(name.iter());
+	}
+
+	let offset = buffer.len() as u32;
+	let count = entries.len() as u16;
+	buffer.extend(&central);
+	buffer.extend([u32le(0x06054b50), u16le(0), u16le(0), u16le(count), u16le(count), u32le(central.len() as u32), u32le(offset), u16le(0)].concat());
+	buffer
+}
+
+#[test]
+fn paths_are_normalized()
+{
+	assert_eq!(normalize_path("E:\\resources\\Model\\a.pmx"), "/resources/Model/a.pmx");
+	assert_eq!(normalize_path("tex\\..\\toon\\.\\a.bmp"), "toon/a.bmp");
+	assert_eq!(normalize_path("../a/./b/../c.png"), "../a/c.png");
+	assert_eq!(normalize_path("/../a.png"), "/a.png");
+
+	assert_eq!(parent_path("model/miku/miku.pmx"), "model/miku");
+	assert_eq!(parent_path("miku.pmx"), "");
+	assert_eq!(join_path("model/miku", "tex\\hair.png"), "model/miku/tex\\hair.png");
+	assert_eq!(join_path("model/miku", "C:\\tex\\hair.png"), "C:\\tex\\hair.png");
+}
+
+#[test]
+fn files_are_found_without_case_and_drive()
+{
+	let root = temp_dir("files");
+	let dir = root.join("Model").join("TDA Miku");
+	fs::create_dir_all(&dir).unwrap();
+	fs::write(dir.join("Miku.pmx"), b"pmx").unwrap();
+
+	let resolver = FileResolver::builder().add_search_path(&root).build();
+
+	let found = resolver.resolve("E:\\resources\\model\\tda miku\\MIKU.PMX").unwrap();
+	assert_eq!(PathBuf::from(&found), dir.join("Miku.pmx"));
+	assert_eq!(resolver.open("E:\\resources\\model\\tda miku\\MIKU.PMX").unwrap(), b"pmx");
+
+	// Absolute paths which exist are taken as they are.
+	let absolute = dir.join("Miku.pmx");
+	assert_eq!(resolver.resolve(absolute.to_str().unwrap()).unwrap(), absolute.to_str().unwrap());
+
+	assert!(resolver.resolve("E:\\resources\\Model\\missing.pmx").is_none());
+	assert!(resolver.open("missing.pmx").is_err());
+
+	fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn ambiguous_files_are_not_guessed()
+{
+	let root = temp_dir("ambiguous");
+	let (miku, luka) = (root.join("Miku"), root.join("Luka"));
+	fs::create_dir_all(miku.join("tex")).unwrap();
+	fs::create_dir_all(luka.join("tex")).unwrap();
+	fs::write(miku.join("tex").join("face.png"), b"miku").unwrap();
+	fs::write(luka.join("tex").join("face.png"), b"luka").unwrap();
+
+	// Both search paths hold "tex/face.png", neither of them is the one the project meant.
+	let resolver = FileResolver::builder().add_search_path(&miku).add_search_path(&luka).build();
+	assert!(resolver.resolve("C:\\MMD\\Rin\\tex\\face.png").is_none());
+	assert!(resolver.resolve("face.png").is_none());
+
+	// A longer tail which only one of them has is still found.
+	fs::create_dir_all(miku.join("Miku").join("tex")).unwrap();
+	fs::write(miku.join("Miku").join("tex").join("face.png"), b"miku").unwrap();
+	assert_eq!(resolver.open("C:\\MMD\\Miku\\tex\\face.png").unwrap(), b"miku");
+
+	// The same file reached through overlapping search paths is one match.
+	let resolver = FileResolver::builder().add_search_path(&luka).add_search_path(root.join("Luka").join(".")).build();
+	assert_eq!(resolver.open("C:\\MMD\\Rin\\tex\\face.png").unwrap(), b"luka");
+
+	fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn memory_models_find_their_textures()
+{
+	let mut resolver = MemoryResolver::new();
+	resolver.insert("Miku/Tex/Hair.png", red_png());
+	assert_eq!(resolver.paths(), vec!["Miku/Tex/Hair.png".to_string()]);
+
+	let shape = ModelLoader::load_from_resolver(textured_model("tex\\hair.PNG"), &resolver, "miku").unwrap();
+	let texture = albedo_map(&shape).unwrap();
+	assert_eq!(texture.raw_pixels(), &[255, 0, 0, 255]);
+	assert_eq!(texture.name(), "Miku/Tex/Hair.png");
+
+	let shape = ModelLoader::load_from_resolver(textured_model("tex\\missing.png"), &resolver, "miku").unwrap();
+	assert!(albedo_map(&shape).is_none());
+
+	// Models read through the resolver look for textures next to themselves.
+	resolver.insert("Miku/Miku.pmx", fs::read(PMX_PATH).unwrap());
+	let shape = ModelLoader::open_from(&resolver, "D:\\MMD\\miku\\miku.pmx").unwrap();
+	assert!(shape.shapes.len() > 0);
+	assert!(ModelLoader::open_from(&resolver, "D:\\MMD\\luka\\luka.pmx").is_err());
+}
+
+#[test]
+fn zip_entries_are_inflated_and_decoded()
+{
+	let deflated:&[u8] = &[203, 72, 205, 201, 201, 87, 200, 64, 39, 1];
+	let shift_jis:&[u8] = &[131, 101, 131, 78, 131, 88, 131, 96, 131, 131, 47, 148, 175, 46, 112, 110, 103];
+	let png = red_png();
+
+	let buffer = zip(&[
+		(b"readme.txt", 0, 8, deflated, 23),
+		(shift_jis, 0, 0, &png, png.len()),
+		("モデル/説明.txt".as_bytes(), 1 << 11, 0, b"utf8", 4),
+		(b"broken.txt", 0, 8, deflated, 24),
+	]);
+
+	let resolver = ZipResolver::load_from_memory(buffer).unwrap();
+	assert_eq!(resolver.len(), 4);
+	assert_eq!(resolver.open("README.TXT").unwrap(), b"hello hello hello hello");
+	assert_eq!(resolver.open("C:\\モデル\\説明.txt").unwrap(), b"utf8");
+	assert!(resolver.open("broken.txt").is_err());
+
+	let texture = TextureLoader::load_from_resolver(&resolver, "テクスチャ\\髪.png").unwrap();
+	assert_eq!(texture.raw_pixels(), &[255, 0, 0, 255]);
+
+	assert!(ZipResolver::load_from_memory(b"not a zip".to_vec()).is_err());
+}
+
+#[test]
+fn projects_find_models_next_to_them()
+{
+	let root = temp_dir("projects");
+	let dir = root.join("model").join("tda school uniform miku");
+	fs::create_dir_all(&dir).unwrap();
+	fs::copy(PMX_PATH, dir.join("TDA School Uniform Miku.PMX")).unwrap();
+	fs::copy(PMM_PATH, root.join("1.pmm")).unwrap();
+
+	// The project was saved on another machine as "E:\resources\Model\TDA School uniform Miku\Tda School uniform Miku.pmx".
+	// The stage can't be found, it stays without any shapes.
+	let scene = open(root.join("1.pmm")).unwrap();
+	assert_eq!(scene.models.len(), 2);
+	assert!(scene.shapes().len() > 0);
+	assert!(scene.shapes().iter().all(|shape| shape.parent().unwrap().name() == "Tda School uniform Miku"));
+
+	let mut resolver = MemoryResolver::new();
+	resolver.insert("Model/Tda School uniform Miku/Tda School uniform Miku.pmx", fs::read(PMX_PATH).unwrap());
+
+	let scene = load_from_resolver(&fs::read(PMM_PATH).unwrap(), Rc::new(resolver)).unwrap();
+	assert!(scene.shapes().len() > 0);
+
+	fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn projects_load_their_x_accessories()
+{
+	let mut pmm = PMMFile::load(&fs::read(PMM_PATH).unwrap()).unwrap();
+
+	let mut floor = PmmAccessoryData::new();
+	floor.name = "floor".to_string();
+	floor.path = "C:\\MikuMikuDance\\UserFile\\Accessory\\floor.x".to_string();
+	floor.current_data.parent_model_index = -1;
+	pmm.accessory_datas = vec![floor];
+
+	let mut resolver = MemoryResolver::new();
+	resolver.insert("UserFile/Accessory/floor.x", b"xof 0302txt 0032\nMesh { 3; 0;0;0;, 0;1;0;, 1;0;0;; 1; 3;0,1,2;; }".to_vec());
+
+	let scene = PMMLoader::load_from_pmm_with(&pmm, &resolver).unwrap();
+	assert_eq!(scene.shapes().iter().filter(|shape| shape.parent().unwrap().name() == "floor").count(), 1);
+}
+
+#[test]
+fn models_load_from_archives()
+{
+	let mut model = models::open(PMX_PATH).unwrap();
+	model.textures.push("tex\\髪.png".to_string());
+	model.materials[0].diffuse_texture = Some(0);
+
+	let pmx = models::PMXLoader::new().do_save(&model).unwrap();
+	let vmd = fs::read(VMD_PATH).unwrap();
+	let png = red_png();
+
+	let name = |name:&str| WINDOWS_31J.encode(name, EncoderTrap::Strict).unwrap();
+	let (spare, model_name, texture_name, motion_name) = (name("安特/古い/安特.pmx"), name("安特/安特.pmx"), name("安特/Tex/髪.png"), name("モーション.vmd"));
+
+	let buffer = zip(&[
+		(b"readme.txt", 0, 0, b"readme", 6),
+		(&spare, 0, 0, b"", 0),
+		(&model_name, 0, 0, &pmx, pmx.len()),
+		(&texture_name, 0, 0, &png, png.len()),
+		(&motion_name, 0, 0, &vmd, vmd.len()),
+	]);
+
+	let shape = ModelLoader::load_from_archive(buffer).unwrap();
+	assert_eq!(shape.shapes.len(), model.meshes.len());
+	assert_eq!(albedo_map(&shape).unwrap().raw_pixels(), &[255, 0, 0, 255]);
+
+	let motion = shape.node.motion().unwrap();
+	assert!(motion.bones.len() > 0);
+
+	let buffer = zip(&[(b"readme.txt", 0, 0, b"readme", 6)]);
+	assert!(ModelLoader::load_from_archive(buffer).is_err());
+}
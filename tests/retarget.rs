@@ -0,0 +1,288 @@
+extern crate octoon;
+
+mod common;
+
+use std::fs;
+use octoon::math::*;
+use octoon::models::{self, Bone, BoneMap, Retargeter, Skeleton};
+use octoon::animation::{AnimationClip, Animator, Motion, VMDFile};
+use common::*;
+
+const PMX_PATH:&str = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/pmx/安特.pmx");
+const VMD_PATH:&str = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/vmd/M.vmd");
+
+fn bone(name:&str, parent:Option<u16>, position:(f32, f32, f32)) -> Bone
+{
+	let mut bone = Bone::new();
+	bone.name = name.to_string();
+	bone.parent = parent;
+	bone.position = position;
+	bone
+}
+
+// A body with one arm and one leg, the arm resting at `elbow` and the leg `leg` long.
+fn body(names:&[&str; 7], elbow:(f32, f32, f32), leg:f32) -> Vec<Bone>
+{
+	vec![
+		bone(names[0], None, (0.0, leg, 0.0)),
+		bone(names[1], Some(0), (1.0, 10.0, 0.0)),
+		bone(names[2], Some(1), elbow),
+		bone(names[3], Some(2), (elbow.0 * 2.0 - 1.0, elbow.1 * 2.0 - 10.0, 0.0)),
+		bone(names[4], Some(0), (1.0, leg, 0.0)),
+		bone(names[5], Some(4), (1.0, leg * 0.5, 0.0)),
+		bone(names[6], Some(5), (1.0, 0.0, 0.0)),
+	]
+}
+
+const JAPANESE:[&str; 7] = ["センター", "左腕", "左ひじ", "左手首", "左足", "左ひざ", "左足首"];
+const ENGLISH:[&str; 7] = ["center", "arm_L", "elbow_L", "wrist_L", "leg_L", "knee_L", "ankle_L"];
+
+fn motion(rotation:Quaternion, translate:float3) -> Motion
+{
+	let mut motion = Motion::new();
+
+	let mut arm = AnimationClip::new();
+	arm.set_name("左腕");
+	arm.set_curve("Rotation", curve(&[(0.0, rotation)]));
+
+	let mut elbow = AnimationClip::new();
+	elbow.set_name("左ひじ");
+	elbow.set_curve("Rotation", curve(&[(0.0, Quaternion::rotation_z(0.5))]));
+
+	let mut missing = AnimationClip::new();
+	missing.set_name("右腕");
+	missing.set_curve("Rotation", curve(&[(0.0, rotation)]));
+
+	motion.rotations.add_clip(arm);
+	motion.rotations.add_clip(elbow);
+	motion.rotations.add_clip(missing);
+
+	let mut center = AnimationClip::new();
+	center.set_name("センター");
+	center.set_curve("Position.X", curve(&[(0.0, translate.x), (10.0, 0.0)]));
+	center.set_curve("Position.Y", curve(&[(0.0, translate.y), (10.0, 0.0)]));
+	center.set_curve("Position.Z", curve(&[(0.0, translate.z), (10.0, 0.0)]));
+	motion.bones.add_clip(center);
+
+	motion
+}
+
+fn skeleton(bones:Vec<Bone>) -> Skeleton
+{
+	let mut model = models::Model::new();
+	for bone in bones
+	{
+		model.add_bone(bone);
+	}
+	Skeleton::new(&model)
+}
+
+fn posed(bones:Vec<Bone>, motion:&Motion) -> Skeleton
+{
+	let mut skeleton = skeleton(bones);
+	skeleton.sample(motion, 0.0);
+	skeleton.update();
+	skeleton
+}
+
+fn world(skeleton:&Skeleton, bone:usize) -> float3
+{
+	let m = skeleton.world(bone);
+	float3::new(m.d1, m.d2, m.d3)
+}
+
+#[test]
+fn bone_names_resolve_through_aliases()
+{
+	let bones:Vec<Bone> = ["center", "leg IK_L", "arm_R", "Left Knee", "upper body2", "右つま先IK"].iter().map(|name| bone(name, None, (0.0, 0.0, 0.0))).collect();
+	let mut map = BoneMap::mmd();
+
+	assert_eq!(map.find(&bones, "センター"), Some(0));
+	assert_eq!(map.find(&bones, "左足ＩＫ"), Some(1));
+	assert_eq!(map.find(&bones, "左足IK"), Some(1));
+	assert_eq!(map.find(&bones, "右腕"), Some(2));
+	assert_eq!(map.find(&bones, "左ひざ"), Some(3));
+	assert_eq!(map.find(&bones, "上半身２"), Some(4));
+	assert_eq!(map.find(&bones, "toe IK_R"), Some(5));
+	assert_eq!(map.find(&bones, "CENTER"), Some(0));
+	assert_eq!(map.find(&bones, "左腕"), None);
+	assert_eq!(map.find(&bones, "右ひざ"), None);
+
+	map.insert("グルーブ", "center").insert("右腕", "missing");
+	assert_eq!(map.find(&bones, "グルーブ"), Some(0));
+	assert_eq!(map.find(&bones, "右腕"), None);
+
+	assert_eq!(BoneMap::new().find(&bones, "センター"), None);
+}
+
+#[test]
+fn motions_move_to_renamed_bones()
+{
+	let target = body(&ENGLISH, (2.0, 9.0, 0.0), 10.0);
+	let retargeter = Retargeter::new(BoneMap::mmd(), &target);
+	assert_eq!(retargeter.scale(), 1.0);
+
+	let motion = retargeter.retarget_motion(motion(Quaternion::rotation_z(0.3), float3::new(1.0, 2.0, 3.0)));
+
+	let mut names:Vec<&str> = motion.rotations.clips.iter().map(|clip| clip.name.as_str()).collect();
+	names.sort();
+	assert_eq!(names, vec!["arm_L", "elbow_L"]);
+	assert_eq!(motion.bones.clips[0].name, "center");
+
+	// Alike models see the same pose.
+	let source = posed(body(&JAPANESE, (2.0, 9.0, 0.0), 10.0), &self::motion(Quaternion::rotation_z(0.3), float3::new(1.0, 2.0, 3.0)));
+	let target = posed(target, &motion);
+	for bone in 0..source.len()
+	{
+		assert!((world(&source, bone) - world(&target, bone)).length() < 1e-4);
+	}
+}
+
+#[test]
+fn arms_follow_their_source_from_another_rest_pose()
+{
+	// The motion is made for an A-pose and plays on a T-pose.
+	let source = body(&JAPANESE, (2.0, 9.0, 0.0), 10.0);
+	let target = body(&ENGLISH, (1.0 + 2.0f32.sqrt(), 10.0, 0.0), 10.0);
+
+	let retargeter = Retargeter::new(BoneMap::mmd(), &target).with_source(&source);
+	assert!(approx(retargeter.rest_rotation(1).angle(), std::f32::consts::PI * 0.25));
+	assert!(approx(retargeter.rest_rotation(2).angle(), std::f32::consts::PI * 0.25));
+	assert_eq!(retargeter.rest_rotation(0), Quaternion::new(0.0, 0.0, 0.0, 1.0));
+	assert_eq!(retargeter.rest_rotation(4), Quaternion::new(0.0, 0.0, 0.0, 1.0));
+
+	for &angle in &[0.0, 0.3, -1.2]
+	{
+		let source = posed(source.clone(), &motion(Quaternion::rotation_z(angle), float3::zero()));
+		let target = posed(target.clone(), &retargeter.retarget_motion(motion(Quaternion::rotation_z(angle), float3::zero())));
+
+		// Arm and forearm point the same way, whatever their rest pose.
+		for &(from, to) in &[(1, 2), (2, 3)]
+		{
+			let a = (world(&source, to) - world(&source, from)).normalize();
+			let b = (world(&target, to) - world(&target, from)).normalize();
+			assert!(a.dot(b) > 0.9999, "{} {} : {:?} {:?}", angle, from, a, b);
+		}
+	}
+}
+
+#[test]
+fn root_translation_scales_with_leg_length()
+{
+	let source = body(&JAPANESE, (2.0, 9.0, 0.0), 10.0);
+	let target = body(&ENGLISH, (2.0, 9.0, 0.0), 5.0);
+
+	let retargeter = Retargeter::new(BoneMap::mmd(), &target).with_source(&source);
+	assert_eq!(retargeter.scale(), 0.5);
+
+	let motion = retargeter.retarget_motion(motion(Quaternion::rotation_z(0.0), float3::new(2.0, -4.0, 6.0)));
+	let center = &motion.bones.clips[0];
+
+	assert_eq!(center.curves["Position.X"].frames[0].value, 1.0);
+	assert_eq!(center.curves["Position.Y"].frames[0].value, -2.0);
+	assert_eq!(center.curves["Position.Z"].frames[0].value, 3.0);
+	assert_eq!(center.curves["Position.X"].frames[1].value, 0.0);
+}
+
+#[test]
+fn vmd_motions_retarget_onto_the_model()
+{
+	let model = models::open(PMX_PATH).unwrap();
+	let vmd = VMDFile::load(&fs::read(VMD_PATH).unwrap()).unwrap();
+
+	let retargeter = Retargeter::new(BoneMap::mmd(), &model.bones).with_source(&model.bones);
+	assert_eq!(retargeter.scale(), 1.0);
+
+	let motions:Animator = retargeter.retarget(vmd.collect_positions());
+	let rotations = retargeter.retarget_rotations(vmd.collect_rotations());
+	assert!(motions.clips.len() > 0);
+
+	let skeleton = Skeleton::new(&model);
+	let original = vmd.collect_positions();
+
+	for clip in motions.clips.iter()
+	{
+		assert!(skeleton.find(&clip.name).is_some());
+
+		// A model moved onto itself keeps its motion.
+		let other = original.clips.iter().find(|other| other.name == clip.name).unwrap();
+		for (name, curve) in clip.curves.iter()
+		{
+			let keys = other.curves[name].frames.iter().map(|key| key.value);
+			assert!(curve.frames.iter().map(|key| key.value).zip(keys).all(|(a, b)| approx(a, b)));
+		}
+	}
+
+	let original = vmd.collect_rotations();
+	for clip in rotations.clips.iter()
+	{
+		let other = original.clips.iter().find(|other| other.name == clip.name).unwrap();
+		let keys = other.curves["Rotation"].frames.iter().map(|key| key.value);
+		assert!(clip.curves["Rotation"].frames.iter().map(|key| key.value).zip(keys).all(|(a, b)| approx(a.dot(b).abs(), 1.0)));
+	}
+}
+
+#[test]
+fn vmd_motions_retarget_onto_a_renamed_model_in_another_pose()
+{
+	let model = models::open(PMX_PATH).unwrap();
+	let vmd = VMDFile::load(&fs::read(VMD_PATH).unwrap()).unwrap();
+
+	// The left arm of the target is named in English and rests raised, like a T-pose for an A-pose.
+	let mut target = model.bones.clone();
+	let arm = target.iter().position(|bone| bone.name == "左腕").unwrap();
+	let raise = Quaternion::rotation_z(0.5);
+	let pivot = float3::new(target[arm].position.0, target[arm].position.1, target[arm].position.2);
+
+	for i in 0..target.len()
+	{
+		let mut parent = target[i].parent;
+		let mut depth = 0;
+		while let Some(index) = parent.filter(|&index| index as usize != arm && depth < target.len())
+		{
+			parent = target[index as usize].parent;
+			depth += 1;
+		}
+
+		if parent.is_some() && depth < target.len()
+		{
+			let p = pivot + raise.rotate(float3::new(target[i].position.0, target[i].position.1, target[i].position.2) - pivot);
+			target[i].position = (p.x, p.y, p.z);
+		}
+	}
+
+	for &(from, to) in &[("左腕", "arm_L"), ("左ひじ", "elbow_L"), ("左手首", "wrist_L")]
+	{
+		target.iter_mut().find(|bone| bone.name == from).unwrap().name = to.to_string();
+	}
+
+	let retargeter = Retargeter::new(BoneMap::mmd(), &target).with_source(&model.bones);
+	assert!(approx(retargeter.rest_rotation(arm).dot(raise.inverse()).abs(), 1.0), "{:?}", retargeter.rest_rotation(arm));
+
+	// The rotation components of `collect_motions` are corrected like the slerped rotations.
+	let motions = retargeter.retarget(vmd.collect_motions());
+	let rotations = retargeter.retarget_rotations(vmd.collect_rotations());
+	let original = vmd.collect_rotations();
+	let mut corrected = 0;
+
+	for clip in rotations.clips.iter()
+	{
+		let components = motions.clips.iter().find(|other| other.name == clip.name).unwrap();
+		let source = original.clips.iter().find(|other| retargeter.find(&other.name).map(|bone| &target[bone].name) == Some(&clip.name)).unwrap();
+
+		for (i, key) in clip.curves["Rotation"].frames.iter().enumerate()
+		{
+			let value = |name:&str| components.curves[name].frames[i].value;
+			let q = Quaternion::new(value("Rotation.X"), value("Rotation.Y"), value("Rotation.Z"), value("Rotation.W"));
+			assert!(approx(q.dot(key.value).abs(), 1.0), "{} {} : {:?} {:?}", clip.name, key.time, q, key.value);
+
+			if !approx(source.curves["Rotation"].frames[i].value.dot(key.value).abs(), 1.0)
+			{
+				corrected += 1;
+			}
+		}
+	}
+
+	assert!(rotations.clips.iter().any(|clip| clip.name == "arm_L"));
+	assert!(corrected > 0);
+}
@@ -0,0 +1,169 @@
+extern crate octoon;
+
+mod common;
+
+use std::f32::consts::PI;
+use octoon::math::*;
+use octoon::scene::{SceneNode, SceneSubData};
+use common::*;
+
+fn point(m:&float4x4, p:float3) -> float3
+{
+	float3::new(
+		m.a1 * p.x + m.b1 * p.y + m.c1 * p.z + m.d1,
+		m.a2 * p.x + m.b2 * p.y + m.c2 * p.z + m.d2,
+		m.a3 * p.x + m.b3 * p.y + m.c3 * p.z + m.d3)
+}
+
+#[test]
+fn child_world_transform_composes_its_parents()
+{
+	let mut root = SceneNode::new(SceneSubData::Group);
+	let mut arm = SceneNode::new(SceneSubData::Group);
+	let mut hand = SceneNode::new(SceneSubData::Shape);
+
+	root.set_translate(float3::new(10.0, 0.0, 0.0));
+	arm.set_translate(float3::new(0.0, 2.0, 0.0));
+	hand.set_translate(float3::new(1.0, 0.0, 0.0));
+
+	arm.add_child(hand.clone());
+	root.add_child(arm.clone());
+
+	assert_eq!(hand.parent().unwrap().uuid(), arm.uuid());
+	assert!(approx(point(&hand.world_transform(), float3::zero()), float3::new(11.0, 2.0, 0.0)));
+
+	// Changing an ancestor invalidates the cached world transform of every descendant.
+	root.set_rotation(float3::new(0.0, 0.0, PI * 0.5));
+	assert!(approx(point(&hand.world_transform(), float3::zero()), float3::new(8.0, 1.0, 0.0)));
+
+	let inverse = hand.world_transform_inverse();
+	assert!(approx(point(&inverse, float3::new(8.0, 1.0, 0.0)), float3::zero()));
+
+	// The local transform is untouched.
+	assert_eq!(hand.translate(), float3::new(1.0, 0.0, 0.0));
+}
+
+#[test]
+fn removed_child_is_back_in_world_space()
+{
+	let mut root = SceneNode::new(SceneSubData::Group);
+	let mut child = SceneNode::new(SceneSubData::Shape);
+
+	root.set_translate(float3::new(0.0, 5.0, 0.0));
+	child.set_translate(float3::new(1.0, 0.0, 0.0));
+	root.add_child(child.clone());
+
+	assert!(approx(point(&child.world_transform(), float3::zero()), float3::new(1.0, 5.0, 0.0)));
+
+	root.remove_child(&child);
+	assert!(child.parent().is_none());
+	assert!(approx(point(&child.world_transform(), float3::zero()), float3::new(1.0, 0.0, 0.0)));
+}
+
+#[test]
+fn reparent_keeps_the_world_pose()
+{
+	let mut a = SceneNode::new(SceneSubData::Group);
+	let mut b = SceneNode::new(SceneSubData::Group);
+	let mut child = SceneNode::new(SceneSubData::Shape);
+
+	a.set_translate(float3::new(3.0, 0.0, 0.0));
+	a.set_rotation(float3::new(0.0, PI * 0.25, 0.0));
+	b.set_translate(float3::new(0.0, 0.0, -4.0));
+	b.set_rotation(float3::new(0.3, 0.0, 1.2));
+	b.set_scale(float3::new(2.0, 2.0, 2.0));
+
+	child.set_translate(float3::new(1.0, 2.0, 3.0));
+	child.set_rotation(float3::new(0.1, 0.2, 0.3));
+	a.add_child(child.clone());
+
+	let world = child.world_transform();
+	let probe = float3::new(0.5, -1.0, 2.0);
+
+	child.set_parent(Some(&mut b));
+	assert_eq!(child.parent().unwrap().uuid(), b.uuid());
+	assert_eq!(a.data.borrow().num_children(), 0);
+	assert_eq!(b.data.borrow().num_children(), 1);
+	assert!(approx(point(&child.world_transform(), probe), point(&world, probe)));
+
+	child.set_parent(None);
+	assert!(child.parent().is_none());
+	assert!(approx(point(&child.world_transform(), probe), point(&world, probe)));
+}
+
+#[test]
+fn reparent_under_a_mirrored_parent_uses_the_shared_decompose()
+{
+	let mut mirror = SceneNode::new(SceneSubData::Group);
+	let mut child = SceneNode::new(SceneSubData::Shape);
+
+	mirror.set_translate(float3::new(0.0, 1.0, 0.0));
+	mirror.set_rotation(float3::new(0.0, 0.0, 0.7));
+	// Non-uniform scale would shear the child, which a translate, rotation and scale can't hold.
+	mirror.set_scale(float3::new(-1.5, 1.5, 1.5));
+
+	child.set_translate(float3::new(1.0, 2.0, 3.0));
+	child.set_rotation(float3::new(0.4, -0.2, 0.1));
+
+	let world = child.world_transform();
+	let probe = float3::new(0.5, -1.0, 2.0);
+
+	child.set_parent(Some(&mut mirror));
+	assert!(approx(point(&child.world_transform(), probe), point(&world, probe)));
+
+	// The local pose is what float4x4::decompose makes of the relative matrix, mirror included.
+	let (translate, rotation, scale) = (mirror.world_transform_inverse() * world).decompose();
+	assert!(approx(child.translate(), translate));
+	assert!(approx(child.scale(), scale));
+	assert!(child.quaternion().dot(rotation).abs() > 0.9999);
+	assert!(child.scale().x < 0.0);
+}
+
+#[test]
+fn reparent_under_a_descendant_is_ignored()
+{
+	let mut root = SceneNode::new(SceneSubData::Group);
+	let mut child = SceneNode::new(SceneSubData::Group);
+	root.add_child(child.clone());
+
+	root.set_parent(Some(&mut child));
+	assert!(root.parent().is_none());
+	assert_eq!(child.parent().unwrap().uuid(), root.uuid());
+}
+
+#[test]
+fn adding_an_ancestor_as_a_child_is_ignored()
+{
+	let mut root = SceneNode::new(SceneSubData::Group);
+	let mut child = SceneNode::new(SceneSubData::Group);
+	let mut leaf = SceneNode::new(SceneSubData::Shape);
+	root.add_child(child.clone());
+	child.add_child(leaf.clone());
+
+	leaf.add_child(root.clone());
+	child.add_child(child.clone());
+	assert!(root.parent().is_none());
+	assert_eq!(child.parent().unwrap().uuid(), root.uuid());
+	assert_eq!(leaf.data.borrow().num_children(), 0);
+	assert_eq!(child.data.borrow().num_children(), 1);
+	assert!(leaf.is_descendant_of(&root));
+	assert!(!root.is_descendant_of(&leaf));
+}
+
+#[test]
+fn children_of_a_borrowed_parent_fall_back_to_their_local_transform()
+{
+	let mut root = SceneNode::new(SceneSubData::Group);
+	let mut child = SceneNode::new(SceneSubData::Shape);
+
+	root.set_translate(float3::new(0.0, 5.0, 0.0));
+	child.set_translate(float3::new(1.0, 0.0, 0.0));
+	root.add_child(child.clone());
+
+	{
+		let _parent = root.data.borrow_mut();
+		assert!(approx(point(&child.world_transform(), float3::zero()), float3::new(1.0, 0.0, 0.0)));
+	}
+
+	assert!(approx(point(&child.world_transform(), float3::zero()), float3::new(1.0, 5.0, 0.0)));
+}
@@ -0,0 +1,434 @@
+extern crate octoon;
+
+use std::sync::Arc;
+use octoon::math::*;
+use octoon::scene::*;
+use octoon::models::VertexWeight;
+
+struct Viewport;
+
+impl Canvas for Viewport
+{
+	fn width(&self) -> u32 { 800 }
+	fn height(&self) -> u32 { 600 }
+	fn render(&mut self, _scene:&Scene) {}
+}
+
+fn build_scene() -> Scene
+{
+	let mut scene = Scene::new();
+
+	let camera = PerspectiveCamera::builder()
+		.main(true)
+		.set_translate(float3::new(0.0, 1.0, -10.0))
+		.build();
+	scene.add(camera);
+
+	let light = DirectionalLight::builder()
+		.set_color(Spectrum::new(1.0, 0.5, 0.25))
+		.set_intensity(3.0)
+		.set_direction(float3::new(0.0, -1.0, 1.0))
+		.build();
+	scene.add(light);
+
+	let geometry:Arc<Geometry> = CubeGeometry::new(1.0, 2.0, 3.0, 1, 1, 1).into();
+
+	let mut standard = StandardMaterial::new();
+	standard.set_albedo(float3::new(0.2, 0.4, 0.6));
+	standard.set_smoothness(0.75);
+	standard.state.cull_mode = CullMode::Front;
+	let standard:Arc<Material> = standard.into();
+
+	let mut skinning = SkinningMaterial::new(300);
+	skinning.set_metalness(0.5);
+	let skinning:Arc<Material> = skinning.into();
+
+	let mut first = MeshShape::new(geometry.clone(), standard.clone());
+	first.set_name("first");
+	first.set_translate(float3::new(1.0, 2.0, 3.0));
+	first.set_scale(float3::new(2.0, 2.0, 2.0));
+	first.set_rotation(float3::new(0.1, 0.2, 0.3));
+	scene.add(first);
+
+	let mut second = MeshShape::new(geometry.clone(), standard.clone());
+	second.set_name("second");
+	second.set_visible(false);
+	scene.add(second);
+
+	let mut third = MeshShape::new(geometry.clone(), skinning.clone());
+	third.set_name("third");
+	scene.add(third);
+
+	scene
+}
+
+fn find<'a>(nodes:&'a [SceneNode], name:&str) -> &'a SceneNode
+{
+	nodes.iter().find(|node| node.name() == name).unwrap()
+}
+
+#[test]
+fn saved_scene_is_readable()
+{
+	let buf = dumps(&build_scene()).unwrap();
+	assert!(JsonLoader::new().can_read(&buf));
+	assert!(!JsonLoader::new().can_read(b"Polygon Movie maker 0002"));
+	assert!(!JsonLoader::new().can_read(b"{ broken"));
+}
+
+#[test]
+fn scene_round_trips_through_json()
+{
+	let scene = build_scene();
+	let loaded = load_from_memory(&dumps(&scene).unwrap()).unwrap();
+
+	assert_eq!(loaded.num_camera(), 1);
+	assert_eq!(loaded.num_light(), 1);
+	assert_eq!(loaded.num_shape(), 3);
+
+	for node in scene.shapes().iter().chain(scene.cameras()).chain(scene.lights())
+	{
+		let other = loaded.shapes().iter().chain(loaded.cameras()).chain(loaded.lights()).find(|other| other.uuid() == node.uuid()).unwrap();
+		assert_eq!(other.name(), node.name());
+		assert_eq!(other.kind(), node.kind());
+		assert_eq!(other.is_visible(), node.is_visible());
+		assert_eq!(other.transform(), node.transform());
+	}
+
+	assert_eq!(to_string(&loaded).unwrap().len(), to_string(&scene).unwrap().len());
+}
+
+#[test]
+fn shared_geometries_and_materials_are_relinked()
+{
+	let scene = build_scene();
+	let loaded = load_from_memory(&dumps(&scene).unwrap()).unwrap();
+
+	let first = find(loaded.shapes(), "first");
+	let second = find(loaded.shapes(), "second");
+	let third = find(loaded.shapes(), "third");
+
+	assert!(Arc::ptr_eq(&first.geometry().unwrap(), &second.geometry().unwrap()));
+	assert!(Arc::ptr_eq(&first.geometry().unwrap(), &third.geometry().unwrap()));
+	assert!(Arc::ptr_eq(&first.material().unwrap(), &second.material().unwrap()));
+	assert!(!Arc::ptr_eq(&first.material().unwrap(), &third.material().unwrap()));
+
+	let original = find(scene.shapes(), "first").geometry().unwrap();
+	let geometry = first.geometry().unwrap();
+	assert_eq!(geometry.uuid(), original.uuid());
+	assert_eq!(geometry.vertices(), original.vertices());
+	assert_eq!(geometry.normals(), original.normals());
+	assert_eq!(geometry.texcoords(), original.texcoords());
+	assert_eq!(geometry.indices(), original.indices());
+}
+
+#[test]
+fn materials_keep_their_type_parameters_and_state()
+{
+	let scene = build_scene();
+	let loaded = load_from_memory(&dumps(&scene).unwrap()).unwrap();
+
+	let standard = find(loaded.shapes(), "first").material().unwrap();
+	assert_eq!(standard.kind(), MaterialType::Standard);
+	assert_eq!(standard.uniforms(), find(scene.shapes(), "first").material().unwrap().uniforms());
+	assert_eq!(standard.uniforms()["albedo"], Variant::Float3(float3::new(0.2, 0.4, 0.6)));
+	assert_eq!(standard.cull_mode(), CullMode::Front);
+	assert_eq!(standard.vs(), find(scene.shapes(), "first").material().unwrap().vs());
+
+	let skinning = find(loaded.shapes(), "third").material().unwrap();
+	assert_eq!(skinning.kind(), MaterialType::Skinning(300));
+	assert_eq!(skinning.uniforms()["metalness"], Variant::Float1(0.5));
+	assert_eq!(skinning.input_layout(), find(scene.shapes(), "third").material().unwrap().input_layout());
+}
+
+#[test]
+fn cameras_and_lights_keep_their_data()
+{
+	let mut scene = build_scene();
+	scene.update(&Viewport);
+
+	let loaded = load_from_memory(&dumps(&scene).unwrap()).unwrap();
+
+	let camera = &loaded.cameras()[0];
+	let data = camera.user_data::<CameraData>().unwrap();
+	let original = scene.cameras()[0].user_data::<CameraData>().unwrap();
+	assert_eq!(data.kind, CameraType::Main);
+	assert_eq!(data.projection, original.projection);
+	assert!(camera.material().is_some());
+
+	let light = loaded.lights()[0].user_data::<LightData>().unwrap();
+	assert_eq!(light.kind, LightType::Directional);
+	assert_eq!(DirectionalLight::from_node(loaded.lights()[0].clone()).unwrap().direction(), float3::new(0.0, -1.0, 1.0));
+	assert_eq!(light.color, Spectrum::new(1.0, 0.5, 0.25));
+	assert_eq!(light.intensity, 3.0);
+	assert_eq!(light.direction, float3::new(0.0, -1.0, 1.0));
+}
+
+#[test]
+fn cameras_and_lights_keep_their_type()
+{
+	let mut scene = Scene::new();
+	scene.add(OrthographicCamera::new());
+	scene.add(PerspectiveCamera::new());
+
+	let mut point = PointLight::new();
+	point.set_intensity(2.0);
+	scene.add(point);
+
+	let mut spot = SpotLight::new();
+	spot.set_direction(float3::new(1.0, 0.0, 0.0));
+	scene.add(spot);
+
+	// Neither camera rendered yet, both still hold an identity projection.
+	let loaded = load_from_memory(&dumps(&scene).unwrap()).unwrap();
+	let projections:Vec<_> = loaded.cameras().iter().map(|camera| camera.user_data::<CameraData>().unwrap().projection_type).collect();
+	assert_eq!(projections, vec![Projection::Orthographic, Projection::Perspective]);
+
+	let lights = loaded.lights();
+	assert!(DirectionalLight::from_node(lights[0].clone()).is_none());
+	assert_eq!(PointLight::from_node(lights[0].clone()).unwrap().intensity(), 2.0);
+	assert_eq!(SpotLight::from_node(lights[1].clone()).unwrap().direction(), float3::new(1.0, 0.0, 0.0));
+}
+
+#[test]
+fn saved_scene_carries_its_version()
+{
+	let json = to_string(&build_scene()).unwrap();
+	assert!(json.contains(&format!("\"version\":{}", SCENE_VERSION)));
+
+	let newer = json.replace(&format!("\"version\":{}", SCENE_VERSION), &format!("\"version\":{}", SCENE_VERSION + 1));
+	assert!(JsonLoader::new().do_load(newer.as_bytes()).is_err());
+}
+
+#[test]
+fn textures_are_embedded_and_relinked()
+{
+	let mut scene = Scene::new();
+
+	let mut rgba = Texture::new(ColorType::RGBA(8), 2, 1, vec![255, 0, 0, 255, 0, 128, 255, 64]);
+	rgba.set_name("rgba");
+	let rgba = Arc::new(rgba);
+
+	let bgr = Arc::new(Texture::new(ColorType::BGR(8), 1, 2, vec![10, 20, 30, 40, 50, 60]));
+
+	let mut skybox = SkyboxMaterial::new();
+	skybox.set_texture(Some(rgba.clone()));
+	let skybox:Arc<Material> = skybox.into();
+
+	let mut standard = StandardMaterial::new();
+	standard.set_albedo_map(Some(bgr.clone()));
+	let standard:Arc<Material> = standard.into();
+
+	let geometry:Arc<Geometry> = CubeGeometry::new(1.0, 1.0, 1.0, 1, 1, 1).into();
+	scene.add(MeshShape::new(geometry.clone(), skybox.clone()));
+	scene.add(MeshShape::new(geometry.clone(), skybox.clone()));
+	scene.add(MeshShape::new(geometry.clone(), standard.clone()));
+
+	let loaded = load_from_memory(&dumps(&scene).unwrap()).unwrap();
+
+	let texture = |node:&SceneNode, name:&str| match node.material().unwrap().uniforms()[name]
+	{
+		Variant::Texture(Some(ref texture)) => texture.clone(),
+		_ => panic!("{} is not bound", name),
+	};
+
+	let first = texture(&loaded.shapes()[0], "texture");
+	assert!(Arc::ptr_eq(&first, &texture(&loaded.shapes()[1], "texture")));
+	assert_eq!(first.uuid, rgba.uuid);
+	assert_eq!(first.name(), "rgba");
+	assert_eq!(first.color_type(), ColorType::RGBA(8));
+	assert_eq!((first.width(), first.height()), (2, 1));
+	assert_eq!(first.raw_pixels(), rgba.raw_pixels());
+
+	let albedo = texture(&loaded.shapes()[2], "albedo_map");
+	assert_eq!(albedo.uuid, bgr.uuid);
+	assert_eq!(albedo.color_type(), ColorType::BGR(8));
+	assert_eq!((albedo.width(), albedo.height()), (1, 2));
+	assert_eq!(albedo.raw_pixels(), bgr.raw_pixels());
+}
+
+#[test]
+fn skin_weights_round_trip()
+{
+	let weight = |bone:[u16;4], weight:[f32;4]| VertexWeight { bone:bone, weight:weight };
+
+	let geometry:Arc<Geometry> = MeshGeometry::new(
+		vec![float3::new(0.0, 0.0, 0.0), float3::new(1.0, 0.0, 0.0), float3::new(0.0, 1.0, 0.0)],
+		vec![float3::new(0.0, 0.0, 1.0); 3],
+		vec![float2::new(0.0, 0.0); 3],
+		vec![weight([0, 1, 0, 0], [0.75, 0.25, 0.0, 0.0]), weight([2, 0, 0, 0], [1.0, 0.0, 0.0, 0.0]), weight([3, 4, 5, 6], [0.4, 0.3, 0.2, 0.1])],
+		vec![0u16, 1, 2]).into();
+
+	let mut scene = Scene::new();
+	scene.add(MeshShape::new(geometry.clone(), SkinningMaterial::new(7).into()));
+
+	let loaded = load_from_memory(&dumps(&scene).unwrap()).unwrap();
+	let other = loaded.shapes()[0].geometry().unwrap();
+
+	assert_eq!(other.num_weights(), 3);
+	for (a, b) in other.weights().iter().zip(geometry.weights())
+	{
+		assert_eq!(a.bone, b.bone);
+		assert_eq!(a.weight, b.weight);
+	}
+}
+
+#[test]
+fn geometries_with_missing_attributes_or_stray_indices_are_rejected()
+{
+	let dump = |normals:usize, indices:Vec<u16>|
+	{
+		let geometry:Arc<Geometry> = MeshGeometry::new(
+			vec![float3::new(0.0, 0.0, 0.0), float3::new(1.0, 0.0, 0.0), float3::new(0.0, 1.0, 0.0)],
+			vec![float3::new(0.0, 0.0, 1.0); normals],
+			vec![float2::new(0.0, 0.0); 3],
+			Vec::new(),
+			indices).into();
+
+		let mut scene = Scene::new();
+		scene.add(MeshShape::new(geometry, StandardMaterial::new().into()));
+		dumps(&scene).unwrap()
+	};
+
+	assert!(load_from_memory(&dump(3, vec![0, 1, 2])).is_ok());
+	assert!(load_from_memory(&dump(2, vec![0, 1, 2])).is_err());
+	assert!(load_from_memory(&dump(3, vec![0, 1, 3])).is_err());
+
+	// Built-in geometries have one of each attribute per vertex.
+	let mut scene = Scene::new();
+	scene.add(MeshShape::new(PlaneGeometry::new(1.0, 1.0, 2, 2).into(), StandardMaterial::new().into()));
+	assert!(load_from_memory(&dumps(&scene).unwrap()).is_ok());
+}
+
+#[test]
+fn hierarchy_and_world_transforms_survive()
+{
+	let mut root = SceneNode::new(SceneSubData::Group);
+	root.set_name("root");
+	root.set_translate(float3::new(10.0, 0.0, 0.0));
+	root.set_rotation(float3::new(0.0, 0.0, 1.0));
+
+	let mut arm = SceneNode::new(SceneSubData::Group);
+	arm.set_name("arm");
+	arm.set_translate(float3::new(0.0, 2.0, 0.0));
+
+	let geometry:Arc<Geometry> = CubeGeometry::new(1.0, 1.0, 1.0, 1, 1, 1).into();
+	let mut hand = MeshShape::new(geometry.clone(), StandardMaterial::new().into());
+	hand.set_name("hand");
+	hand.set_translate(float3::new(1.0, 0.0, 0.0));
+
+	let mut finger = MeshShape::new(geometry.clone(), StandardMaterial::new().into());
+	finger.set_name("finger");
+	finger.set_scale(float3::new(0.5, 0.5, 0.5));
+
+	let mut hand_node:SceneNode = hand.as_ref().clone();
+	hand_node.add_child(finger.as_ref().clone());
+	arm.add_child(hand_node);
+	root.add_child(arm.clone());
+
+	let mut scene = Scene::new();
+	scene.add(root.clone());
+
+	// The finger is only reachable through its parent shape.
+	let loaded = load_from_memory(&dumps(&scene).unwrap()).unwrap();
+	assert_eq!(scene.num_shape(), 1);
+	assert_eq!(loaded.num_shape(), 2);
+
+	let other_hand = find(loaded.shapes(), "hand");
+	let other_finger = find(loaded.shapes(), "finger");
+	let other_arm = other_hand.parent().unwrap();
+
+	assert_eq!(other_finger.parent().unwrap().uuid(), other_hand.uuid());
+	assert_eq!(other_arm.name(), "arm");
+	assert_eq!(other_arm.kind(), SceneSubData::Group);
+	assert_eq!(other_arm.parent().unwrap().name(), "root");
+	assert!(other_arm.parent().unwrap().parent().is_none());
+
+	assert_eq!(other_hand.world_transform(), hand.as_ref().world_transform());
+	assert_eq!(other_finger.world_transform(), finger.as_ref().world_transform());
+}
+
+#[test]
+fn version_zero_nested_children_still_load()
+{
+	let json = r#"{
+		"model":[], "materials":[], "camera":[], "light":[],
+		"shape":[{
+			"type":"Group",
+			"uuid":"6f1c2a52-4c8e-4b7f-9a53-1d2f0c4e8a01",
+			"name":"root",
+			"visible":true,
+			"transform":{"translate":[0.0,1.0,0.0],"rotation":[0.0,0.0,0.0],"scale":[1.0,1.0,1.0]},
+			"model":null, "material":null, "camera":null, "light":null,
+			"children":[{
+				"type":"Shape",
+				"uuid":"6f1c2a52-4c8e-4b7f-9a53-1d2f0c4e8a02",
+				"name":"child",
+				"visible":false,
+				"transform":{"translate":[2.0,0.0,0.0],"rotation":[0.0,0.0,0.0],"scale":[1.0,1.0,1.0]},
+				"model":null, "material":null, "camera":null, "light":null,
+				"children":[]
+			}]
+		}]
+	}"#;
+
+	let scene = JsonLoader::new().do_load(json.as_bytes()).unwrap();
+	assert_eq!(scene.num_shape(), 1);
+
+	let child = &scene.shapes()[0];
+	assert_eq!(child.name(), "child");
+	assert!(!child.is_visible());
+	assert_eq!(child.parent().unwrap().name(), "root");
+
+	let world = child.world_transform();
+	assert_eq!(float3::new(world.d1, world.d2, world.d3), float3::new(2.0, 1.0, 0.0));
+
+	// Saving upgrades the scene to the current version.
+	let json = to_string(&scene).unwrap();
+	assert!(json.contains(&format!("\"version\":{}", SCENE_VERSION)));
+	assert_eq!(load_from_memory(json.as_bytes()).unwrap().shapes()[0].parent().unwrap().name(), "root");
+}
+
+#[test]
+fn euler_rotations_from_version_zero_still_load()
+{
+	let json = r#"{
+		"shape":[{
+			"type":"Shape",
+			"uuid":"6f1c2a52-4c8e-4b7f-9a53-1d2f0c4e8a03",
+			"name":"turned",
+			"visible":true,
+			"transform":{"translate":[0.0,0.0,0.0],"rotation":[0.1,0.2,0.3],"scale":[1.0,1.0,1.0]},
+			"model":null, "material":null, "camera":null, "light":null, "parent":null
+		}]
+	}"#;
+
+	let scene = JsonLoader::new().do_load(json.as_bytes()).unwrap();
+
+	let mut expect = SceneNode::new(SceneSubData::Shape);
+	expect.set_rotation(float3::new(0.1, 0.2, 0.3));
+	assert!(scene.shapes()[0].quaternion().dot(expect.quaternion()).abs() > 0.9999);
+}
+
+#[test]
+fn texture_bindings_keep_their_pixels()
+{
+	let mut scene = Scene::new();
+
+	let texture = Arc::new(Texture::new(ColorType::RGBA(8), 1, 1, vec![255, 0, 0, 255]));
+	let mut skybox = SkyboxMaterial::new();
+	skybox.set_texture(Some(texture));
+
+	let geometry:Arc<Geometry> = CubeGeometry::new(1.0, 1.0, 1.0, 1, 1, 1).into();
+	scene.add(MeshShape::new(geometry, skybox.into()));
+
+	let loaded = load_from_memory(&dumps(&scene).unwrap()).unwrap();
+	let material = loaded.shapes()[0].material().unwrap();
+
+	assert_eq!(material.kind(), MaterialType::Skybox);
+	match material.uniforms()["texture"]
+	{
+		Variant::Texture(Some(ref texture)) => assert_eq!(texture.raw_pixels(), &[255, 0, 0, 255]),
+		_ => panic!("texture is not bound"),
+	}
+}
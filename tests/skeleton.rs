@@ -0,0 +1,153 @@
+extern crate octoon;
+
+use std::f32::consts::PI;
+use octoon::math::*;
+use octoon::models::{self, Model, Bone, BoneGrant, BoneLink, Solver, Skeleton};
+use octoon::animation::VMDLoader;
+
+const PMX_PATH:&str = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/pmx/安特.pmx");
+const VMD_PATH:&str = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/vmd/M.vmd");
+
+fn bone(name:&str, parent:Option<u16>, position:(f32,f32,f32)) -> Bone
+{
+	let mut bone = Bone::new();
+	bone.name = name.to_string();
+	bone.parent = parent;
+	bone.position = position;
+	bone
+}
+
+fn leg() -> Model
+{
+	let mut model = Model::new();
+	model.add_bone(bone("センター", None, (0.0, 10.0, 0.0)));
+	model.add_bone(bone("左足", Some(0), (1.0, 10.0, 0.0)));
+	model.add_bone(bone("左ひざ", Some(1), (1.0, 5.0, -0.2)));
+	model.add_bone(bone("左足首", Some(2), (1.0, 0.5, 0.0)));
+	model.add_bone(bone("左足ＩＫ", Some(0), (1.0, 0.5, 0.0)));
+
+	let mut knee = BoneLink::new();
+	knee.bone = 2;
+	knee.rotate_limited = true;
+	knee.minimum_radian = (-PI, 0.0, 0.0);
+	knee.maximum_radian = (-0.5f32.to_radians(), 0.0, 0.0);
+
+	let mut hip = BoneLink::new();
+	hip.bone = 1;
+
+	let mut ik = Solver::new();
+	ik.bone = 4;
+	ik.target_bone = 3;
+	ik.loop_count = 40;
+	ik.limited_radian = 2.0;
+	ik.links = vec![knee, hip];
+
+	model.add_ik(ik);
+	model
+}
+
+fn distance(a:float3, b:float3) -> f32
+{
+	(a - b).length()
+}
+
+#[test]
+fn rest_pose_palette_is_identity()
+{
+	let mut skeleton = Skeleton::new(&leg());
+	skeleton.update();
+
+	assert!(distance(skeleton.position(3), float3::new(1.0, 0.5, 0.0)) < 1e-5);
+
+	for m in skeleton.palette()
+	{
+		assert!((m.a1 - 1.0).abs() < 1e-5 && (m.b2 - 1.0).abs() < 1e-5 && (m.c3 - 1.0).abs() < 1e-5);
+		assert!(m.d1.abs() < 1e-5 && m.d2.abs() < 1e-5 && m.d3.abs() < 1e-5);
+	}
+}
+
+#[test]
+fn forward_kinematics_follows_the_parent()
+{
+	let mut model = leg();
+	model.iks.clear();
+
+	let mut skeleton = Skeleton::new(&model);
+	skeleton.set_translate(0, float3::new(0.0, 1.0, 0.0));
+	skeleton.set_rotation(1, Quaternion::rotation_z(PI * 0.5));
+	skeleton.update();
+
+	// The hip turns the leg around Z, the knee is 5 units below the hip in the rest pose.
+	let knee = skeleton.position(2);
+	assert!(distance(knee, float3::new(6.0, 11.0, -0.2)) < 1e-4, "{:?}", knee);
+
+	let palette = skeleton.palette();
+	let m = palette[2];
+	let rest = float3::new(1.0, 5.0, -0.2);
+	let moved = float3::new(
+		m.a1 * rest.x + m.b1 * rest.y + m.c1 * rest.z + m.d1,
+		m.a2 * rest.x + m.b2 * rest.y + m.c2 * rest.z + m.d2,
+		m.a3 * rest.x + m.b3 * rest.y + m.c3 * rest.z + m.d3);
+
+	assert!(distance(moved, knee) < 1e-4, "{:?}", moved);
+}
+
+#[test]
+fn leg_ik_reaches_its_target()
+{
+	let mut skeleton = Skeleton::new(&leg());
+
+	let goal = float3::new(1.0, 3.0, 1.5);
+	skeleton.set_translate(4, goal - float3::new(1.0, 0.5, 0.0));
+	skeleton.update();
+
+	assert!(distance(skeleton.position(4), goal) < 1e-5);
+	assert!(distance(skeleton.position(3), goal) < 1e-2, "{:?}", skeleton.position(3));
+
+	// The knee only bends around X within its limits.
+	let knee = skeleton.locals[2].rotation;
+	assert!(knee.y.abs() < 1e-3 && knee.z.abs() < 1e-3, "{:?}", knee);
+	assert!(knee.x < 0.0);
+}
+
+#[test]
+fn grant_rotation_follows_its_parent_by_ratio()
+{
+	let mut model = leg();
+	let mut grant = BoneGrant::new();
+	grant.parent = 1;
+	grant.ratio = 0.5;
+	grant.rotation = true;
+
+	let mut twist = bone("左足捩", Some(0), (1.0, 8.0, 0.0));
+	twist.grant = Some(grant);
+	model.add_bone(twist);
+
+	let mut skeleton = Skeleton::new(&model);
+	skeleton.set_rotation(1, Quaternion::rotation_y(1.0));
+	skeleton.update();
+
+	let q = skeleton.worlds[5].rotation;
+	assert!((q.dot(Quaternion::rotation_y(0.5)).abs() - 1.0).abs() < 1e-5, "{:?}", q);
+}
+
+#[test]
+fn skeleton_plays_vmd_motion()
+{
+	let model = models::open(PMX_PATH).unwrap();
+	let motion = VMDLoader::open(VMD_PATH).unwrap();
+
+	let mut skeleton = Skeleton::new(&model);
+	assert_eq!(skeleton.len(), model.bones.len());
+
+	for frame in [0.0, 30.5, 120.0].iter()
+	{
+		skeleton.sample(&motion, *frame);
+		skeleton.update();
+
+		for m in skeleton.palette()
+		{
+			assert!(m.to_array().iter().all(|x| x.is_finite()));
+		}
+	}
+}
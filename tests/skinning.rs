@@ -0,0 +1,184 @@
+extern crate octoon;
+
+use std::rc::Rc;
+use octoon::math::*;
+use octoon::models::{self, Bone, Skeleton, MorphTarget, VertexMorph};
+use octoon::animation::{VMDLoader, Motion, AnimationClip, AnimationCurve};
+use octoon::scene::*;
+
+const PMX_PATH:&str = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/pmx/安特.pmx");
+const PMM_PATH:&str = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/pmm/1.pmm");
+const VMD_PATH:&str = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/vmd/M.vmd");
+
+// Leg IK settles within a millimetre of the rest pose.
+fn is_identity(m:&float4x4) -> bool
+{
+	m.to_array().iter().zip(float4x4::one().to_array().iter()).all(|(a, b)| (a - b).abs() < 1e-2)
+}
+
+fn triangle() -> models::Model
+{
+	let mut mesh = models::Mesh::new();
+	mesh.material_id = Some(0);
+	mesh.positions = vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0];
+	mesh.normals = vec![0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0];
+	mesh.texcoords = vec![0.0, 0.0, 1.0, 0.0, 0.0, 1.0];
+	mesh.indices = vec![0, 1, 2];
+
+	let mut model = models::Model::new();
+	model.meshes.push(mesh);
+	model.materials.push(models::Material::new());
+	model
+}
+
+#[test]
+fn inverse_bind_matrices_move_bones_to_the_origin()
+{
+	let mut model = triangle();
+	let mut bone = Bone::new();
+	bone.position = (1.0, 2.0, 3.0);
+	model.add_bone(bone);
+
+	let mut skeleton = Skeleton::new(&model);
+	let m = skeleton.inverse_bind_matrices()[0];
+	assert_eq!((m.d1, m.d2, m.d3), (-1.0, -2.0, -3.0));
+
+	skeleton.set_rotation(0, Quaternion::rotation_z(1.0));
+	skeleton.update();
+	assert_eq!(skeleton.palette()[0], skeleton.world(0) * m);
+}
+
+#[test]
+fn models_without_bones_stay_standard()
+{
+	let shape = ModelLoader::load_from_resolver(triangle(), &MemoryResolver::new(), "").unwrap();
+	assert_eq!(shape.shapes[0].material().kind(), MaterialType::Standard);
+	assert!(shape.skeleton().is_none());
+	assert!(shape.shapes[0].node.joints().is_none());
+}
+
+#[test]
+fn models_with_bones_are_skinned_in_the_rest_pose()
+{
+	let model = models::open(PMX_PATH).unwrap();
+	let shape = ModelLoader::open(PMX_PATH).unwrap();
+
+	assert_eq!(shape.skeleton().unwrap().borrow().len(), model.bones.len());
+
+	let mut scene = Scene::new();
+	scene.add(shape);
+
+	for node in scene.shapes()
+	{
+		assert_eq!(node.material().unwrap().kind(), MaterialType::Skinning(model.bones.len() as u16));
+		assert!(node.geometry().unwrap().num_weights() > 0);
+
+		let joints = node.joints().unwrap();
+		assert_eq!(joints.len(), model.bones.len());
+		assert!(joints.iter().all(is_identity));
+	}
+
+	let joints = scene.shapes()[0].joints().unwrap();
+	assert!(scene.shapes().iter().all(|node| Rc::ptr_eq(&node.joints().unwrap(), &joints)));
+}
+
+#[test]
+fn poses_follow_the_motion()
+{
+	let mut shape = ModelLoader::open(PMX_PATH).unwrap();
+
+	// Nothing moves without a motion.
+	shape.update_pose(30.0);
+	assert!(shape.shapes[0].node.joints().unwrap().iter().all(is_identity));
+
+	shape.node.set_motion(Some(Rc::new(VMDLoader::open(VMD_PATH).unwrap())));
+	shape.update_pose(30.0);
+
+	let mut skeleton = shape.skeleton().unwrap().borrow().clone();
+	skeleton.sample(&shape.node.motion().unwrap(), 30.0);
+	skeleton.update();
+
+	let joints = shape.shapes[0].node.joints().unwrap();
+	assert!(!joints.iter().all(is_identity));
+	assert_eq!(*joints, skeleton.palette());
+}
+
+#[test]
+fn scenes_pose_the_models_they_hold()
+{
+	let mut shape = ModelLoader::open(PMX_PATH).unwrap();
+	shape.node.set_motion(Some(Rc::new(VMDLoader::open(VMD_PATH).unwrap())));
+
+	let node = shape.node.clone();
+	let mut skeleton = shape.skeleton().unwrap().borrow().clone();
+
+	// Only the node is kept by the scene, the skeleton goes along with it.
+	let mut scene = Scene::new();
+	scene.add(shape.node);
+	assert!(scene.models[0].skeleton().is_some());
+
+	scene.update_pose(30.0);
+
+	skeleton.sample(&node.motion().unwrap(), 30.0);
+	skeleton.update();
+
+	assert!(scene.shapes().iter().all(|shape| *shape.joints().unwrap() == skeleton.palette()));
+	assert!(!scene.shapes()[0].joints().unwrap().iter().all(is_identity));
+}
+
+#[test]
+fn projects_keep_the_skeletons_of_their_models()
+{
+	let mut resolver = MemoryResolver::new();
+	resolver.insert("Model/ant.pmx", std::fs::read(PMX_PATH).unwrap());
+
+	let mut pmm = PMMFile::load(&std::fs::read(PMM_PATH).unwrap()).unwrap();
+	pmm.model.truncate(1);
+	pmm.model[0].path = "C:\\MMD\\Model\\ant.pmx".to_string();
+
+	let mut scene = PMMLoader::load_from_pmm_with(&pmm, &resolver).unwrap();
+	let model = scene.models.iter().find(|node| node.name() == pmm.model[0].name).unwrap().clone();
+	assert!(model.skeleton().is_some());
+
+	scene.update_pose(0.0);
+	assert!(scene.shapes().iter().all(|shape| shape.joints().is_some()));
+}
+
+#[test]
+fn poses_weigh_the_morphs_of_the_shapes()
+{
+	let mut model = triangle();
+	let mut smile = MorphTarget::new();
+	smile.name = "smile".to_string();
+	smile.positions = vec![VertexMorph { index:2, offset:(0.0, 1.0, 0.0) }];
+	model.meshes[0].morphs.push(smile);
+
+	let mut curve = AnimationCurve::new();
+	curve.add_keyframe(0.0, 0.0, None);
+	curve.add_keyframe(10.0, 1.0, None);
+
+	let mut clip = AnimationClip::new();
+	clip.set_name("smile");
+	clip.set_curve("Weight", curve);
+
+	let mut motion = Motion::new();
+	motion.morphs.add_clip(clip);
+
+	let mut shape = ModelLoader::load_from_resolver(model, &MemoryResolver::new(), "").unwrap();
+	shape.node.set_motion(Some(Rc::new(motion)));
+	shape.node.update_pose(5.0);
+
+	let geometry = shape.shapes[0].geometry();
+	assert_eq!(geometry.morph_weight("smile"), Some(0.5));
+	assert_eq!(geometry.morph_vertices()[2], float3::new(0.0, 1.5, 0.0));
+}
+
+#[test]
+fn palettes_upload_every_joint_of_a_batch()
+{
+	let palette:Vec<f32> = (0..SKINNING_MAX_JOINTS).flat_map(|i| float4x4::translate(i as f32, 0.0, 0.0).to_array().to_vec()).collect();
+
+	assert_eq!(octoon::renderer::matrix_count(&palette, 4), SKINNING_MAX_JOINTS as i32);
+	assert_eq!(octoon::renderer::matrix_count(&float4x4::one().to_array()[..], 4), 1);
+	assert_eq!(octoon::renderer::matrix_count(&[0.0; 18], 3), 2);
+}
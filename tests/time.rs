@@ -0,0 +1,151 @@
+extern crate octoon;
+
+mod common;
+
+use std::rc::Rc;
+use std::cell::RefCell;
+use octoon::animation::*;
+use common::*;
+
+const VMD_PATH:&str = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/vmd/M.vmd");
+
+#[test]
+fn frames_and_seconds()
+{
+	assert_eq!(seconds_to_frames(2.0), 60.0);
+	assert_eq!(frames_to_seconds(45.0), 1.5);
+
+	assert_eq!(Frame::mmd(30).seconds(), 1.0);
+	assert_eq!(Frame::from_seconds(1.5, MMD_FRAME_RATE), Frame::mmd(45));
+	assert_eq!(Frame::from_seconds(0.02, 60).number, 1);
+
+	// Frames compare by the time they show whatever their rate.
+	assert_eq!(Frame::mmd(15), Frame::new(30, 60));
+	assert!(Frame::mmd(15) < Frame::new(31, 60));
+	assert!(Frame::new(-1, 60) < Frame::mmd(0));
+
+	assert_eq!(Frame::mmd(15).to_rate(60).number, 30);
+	assert_eq!(Frame::new(31, 60).to_rate(30).number, 16);
+	assert_eq!(Frame::new(-31, 60).to_rate(30).number, -16);
+
+	assert_eq!(Frame::mmd(10).since(Frame::mmd(4)), 0.2);
+	assert_eq!(Frame::new(60, 60).since(Frame::mmd(0)), 1.0);
+	assert_eq!(10.0f32.since(4.0), 6.0);
+	assert_eq!(Frame::new(30, 60).fraction(Frame::mmd(0), Frame::mmd(30)), 0.5);
+}
+
+#[test]
+fn curves_evaluate_on_frames()
+{
+	let mut curve = AnimationCurve::new();
+	curve.add_keyframe(Frame::mmd(30), 3.0, None);
+	curve.add_keyframe(Frame::mmd(0), 0.0, None);
+
+	assert_eq!(curve.evaluate(Frame::mmd(10)), 1.0);
+	assert_eq!(curve.evaluate(Frame::new(20, 60)), 1.0);
+	assert_eq!(curve.evaluate(Frame::mmd(-5)), 0.0);
+	assert_eq!(curve.evaluate(Frame::mmd(90)), 3.0);
+}
+
+#[test]
+fn long_timelines_keep_precision()
+{
+	let start = 1.0e7;
+
+	let mut curve = AnimationCurve::new();
+	curve.add_keyframe(start, 0.0f32, None);
+	curve.add_keyframe(start + 1.0, 1.0, None);
+	assert_eq!(curve.evaluate(start + 0.25f64), 0.25);
+
+	// An f32 timeline can't tell those times apart.
+	assert_eq!(start as f32 + 0.25, start as f32);
+
+	let mut curve = AnimationCurve::new();
+	curve.add_keyframe(Frame::mmd(1 << 40), 0.0, None);
+	curve.add_keyframe(Frame::mmd((1 << 40) + 4), 1.0, None);
+	assert_eq!(curve.evaluate(Frame::mmd((1 << 40) + 1)), 0.25);
+}
+
+#[test]
+fn curves_resample_to_another_rate()
+{
+	let mut curve = AnimationCurve::new();
+	curve.add_keyframe(Frame::mmd(0), 0.0, None);
+	curve.add_keyframe(Frame::mmd(30), 30.0, Some(Box::new(FixedInterpolator::new(0.0))));
+	curve.add_keyframe(Frame::mmd(60), 0.0, None);
+
+	let resampled = curve.resample(60);
+	assert_eq!(resampled.len(), 121);
+	assert!(resampled.frames.iter().all(|key| key.time.rate == 60));
+
+	for number in 0..=120
+	{
+		let frame = Frame::new(number, 60);
+		assert_eq!(resampled.evaluate(frame), curve.evaluate(frame));
+	}
+
+	let halved = resampled.resample(30);
+	assert_eq!(halved.len(), 61);
+	assert_eq!(halved.evaluate(Frame::mmd(15)), 15.0);
+	assert_eq!(halved.evaluate(Frame::mmd(45)), 30.0);
+
+	assert!(AnimationCurve::<f32, Frame>::new().resample(60).is_empty());
+}
+
+#[test]
+fn vmd_curves_play_frame_exact()
+{
+	let reference = open(VMD_PATH).unwrap().bones;
+	let animator = open(VMD_PATH).unwrap().bones;
+	let mut checked = 0;
+
+	for clip in animator.clips.into_iter()
+	{
+		let curves = &reference.clips.iter().find(|other| other.name == clip.name).unwrap().curves;
+
+		for (name, curve) in clip.curves.into_iter()
+		{
+			let reference = &curves[&name];
+			let curve = curve.map_time(|time| Frame::mmd(time as i64));
+
+			let (first, last) = match (reference.frames.first(), reference.frames.last())
+			{
+				(Some(first), Some(last)) => (first.time as i64, last.time as i64),
+				_ => continue,
+			};
+
+			for number in first - 1..=last + 1
+			{
+				let expect = reference.evaluate(number as f32);
+				let value = curve.evaluate(Frame::mmd(number));
+				assert!((value - expect).abs() <= 1e-4 * expect.abs().max(1.0), "{} {} : {} {}", name, number, value, expect);
+				checked += 1;
+			}
+		}
+	}
+
+	assert!(checked > 0);
+}
+
+#[test]
+fn clips_evaluate_on_any_timeline()
+{
+	let mut curve:AnimationCurve<f32, f64> = AnimationCurve::new();
+	curve.add_keyframe(0.0, 0.0, None);
+	curve.add_keyframe(2.0, 1.0, None);
+
+	let seen = Rc::new(RefCell::new(Vec::new()));
+	let events = seen.clone();
+
+	let mut clip = AnimationClip::new();
+	clip.set_curve("x", curve);
+	clip.add_event(move |name, value| events.borrow_mut().push((name.to_string(), *value)));
+
+	let mut animator = Animator::new();
+	animator.add_clip(clip);
+	animator.evaluate(0.5f64);
+
+	assert_eq!(seen.borrow().len(), 1);
+	assert_eq!(seen.borrow()[0].0, "x");
+	assert!(approx(seen.borrow()[0].1, 0.25));
+}
@@ -0,0 +1,144 @@
+extern crate octoon;
+
+mod common;
+
+use std::f32::consts::PI;
+use octoon::math::*;
+use octoon::scene::Transform;
+use common::*;
+
+const ORDERS:[RotationOrder; 6] = [
+	RotationOrder::XYZ, RotationOrder::XZY, RotationOrder::YXZ,
+	RotationOrder::YZX, RotationOrder::ZXY, RotationOrder::ZYX,
+];
+
+fn same_rotation(a:Quaternion, b:Quaternion) -> bool
+{
+	a.dot(b).abs() > 1.0 - 1e-5
+}
+
+#[test]
+fn euler_orders_compose_axis_rotations()
+{
+	let e = float3::new(0.3, -0.7, 1.1);
+	let (x, y, z) = (Quaternion::rotation_x(e.x), Quaternion::rotation_y(e.y), Quaternion::rotation_z(e.z));
+
+	assert!(same_rotation(Quaternion::euler(&e, RotationOrder::XYZ), Quaternion::euler_xyz(&e)));
+	assert!(same_rotation(Quaternion::euler(&e, RotationOrder::XYZ), z.concat(y).concat(x)));
+	assert!(same_rotation(Quaternion::euler(&e, RotationOrder::ZXY), y.concat(x).concat(z)));
+	assert!(same_rotation(Quaternion::euler(&e, RotationOrder::YXZ), z.concat(x).concat(y)));
+}
+
+#[test]
+fn euler_round_trips_in_every_order()
+{
+	let angles = [
+		float3::new(0.3, -0.7, 1.1),
+		float3::new(-2.5, 1.2, 0.05),
+		float3::new(1.0, 0.0, -3.0),
+	];
+
+	for order in ORDERS.iter()
+	{
+		for e in angles.iter()
+		{
+			let q = Quaternion::euler(e, *order);
+			let back = q.to_euler(*order);
+			assert!(same_rotation(Quaternion::euler(&back, *order), q), "{:?} {:?} -> {:?}", order, e, back);
+		}
+
+		let e = float3::new(0.3, -0.7, 1.1);
+		assert!(approx(Quaternion::euler(&e, *order).to_euler(*order), e), "{:?}", order);
+	}
+}
+
+#[test]
+fn euler_survives_gimbal_lock()
+{
+	for order in ORDERS.iter()
+	{
+		let mut e = float3::new(0.4, 0.4, 0.4);
+		match *order
+		{
+			RotationOrder::XYZ | RotationOrder::ZYX => e.y = PI * 0.5,
+			RotationOrder::XZY | RotationOrder::YZX => e.z = -PI * 0.5,
+			RotationOrder::YXZ | RotationOrder::ZXY => e.x = PI * 0.5,
+		}
+
+		let q = Quaternion::euler(&e, *order);
+		let back = q.to_euler(*order);
+		assert!(same_rotation(Quaternion::euler(&back, *order), q), "{:?} {:?} -> {:?}", order, e, back);
+	}
+}
+
+#[test]
+fn matrix_decomposes_into_translate_rotation_and_scale()
+{
+	let translate = float3::new(1.0, -2.0, 3.5);
+	let rotation = Quaternion::euler(&float3::new(0.2, 1.3, -0.4), RotationOrder::ZXY);
+
+	for scale in [float3::new(2.0, 0.5, 1.5), float3::new(-1.0, 3.0, 0.25)].iter()
+	{
+		let m = float4x4::translate(translate.x, translate.y, translate.z) * float4x4::from(rotation) * float4x4::scale(scale.x, scale.y, scale.z);
+		let (t, r, s) = m.decompose();
+
+		assert!(approx(t, translate));
+		assert!(same_rotation(r, rotation));
+		assert!(approx(s, *scale));
+	}
+}
+
+#[test]
+fn transform_stores_a_quaternion()
+{
+	let mut transform = Transform::new();
+	let q = Quaternion::rotation(&float3::new(0.0, 1.0, 0.0), PI * 0.5);
+
+	transform.set_quaternion(q);
+	assert_eq!(transform.quaternion(), q);
+	assert!(approx(transform.rotation(), float3::new(0.0, PI * 0.5, 0.0)));
+	assert!(approx(transform.transform().forward(), float3::new(1.0, 0.0, 0.0)));
+
+	let e = float3::new(0.1, 0.2, 0.3);
+	transform.set_euler(e, RotationOrder::YXZ);
+	assert!(approx(transform.euler(RotationOrder::YXZ), e));
+
+	transform.set_rotation(e);
+	assert!(approx(transform.rotation(), e));
+	assert!(same_rotation(transform.quaternion(), Quaternion::euler_xyz(&e)));
+}
+
+#[test]
+fn transform_set_matrix_rebuilds_the_same_matrix()
+{
+	let mut source = Transform::new();
+	source.set_translate(float3::new(4.0, 5.0, 6.0));
+	source.set_euler(float3::new(-0.6, 0.9, 2.0), RotationOrder::ZYX);
+	source.set_scale(float3::new(1.0, 2.0, 3.0));
+
+	let mut transform = Transform::new();
+	transform.set_matrix(&source.transform());
+
+	let (a, b) = (source.transform().to_array(), transform.transform().to_array());
+	for i in 0..16
+	{
+		assert!((a[i] - b[i]).abs() < 1e-4, "{:?} != {:?}", a, b);
+	}
+}
+
+#[test]
+fn look_at_turns_forward_to_the_target()
+{
+	let mut transform = Transform::new();
+	transform.set_translate(float3::new(1.0, 2.0, 3.0));
+	transform.look_at(float3::new(4.0, 6.0, 3.0), float3::new(0.0, 1.0, 0.0));
+
+	let m = transform.transform();
+	assert!(approx(m.forward(), float3::new(0.6, 0.8, 0.0)));
+	assert!(m.right().dot(float3::new(0.0, 1.0, 0.0)).abs() < 1e-5);
+	assert!(m.up().y > 0.0);
+
+	// Looking straight up falls back to another reference axis.
+	transform.look_at(float3::new(1.0, 10.0, 3.0), float3::new(0.0, 1.0, 0.0));
+	assert!(approx(transform.transform().forward(), float3::new(0.0, 1.0, 0.0)));
+}
@@ -0,0 +1,414 @@
+extern crate octoon;
+
+use std::fs;
+use std::rc::Rc;
+use octoon::math::*;
+use octoon::math::vec::Slerp;
+use octoon::animation::{Loader, Evaluate, Animator, AnimationClip, AnimationCurve, VMDFile, VMDLoader, VMDCamera, VMDLight, VMDSelfShadow, VMD_MAGIC};
+use octoon::scene::{PerspectiveCamera, CameraData};
+
+const VMD_PATH:&str = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/vmd/M.vmd");
+
+#[test]
+fn vmd_file_round_trip()
+{
+	let buf = fs::read(VMD_PATH).unwrap();
+	let vmd = VMDFile::load(&buf).unwrap();
+	let saved = vmd.save().unwrap();
+	let loaded = VMDFile::load(&saved).unwrap();
+
+	assert_eq!(buf.len(), saved.len());
+	assert_eq!(vmd.header.name, loaded.header.name);
+	assert_eq!(vmd.motions.len(), loaded.motions.len());
+	assert_eq!(vmd.morphs.len(), loaded.morphs.len());
+	assert_eq!(vmd.cameras.len(), loaded.cameras.len());
+	assert_eq!(vmd.lights.len(), loaded.lights.len());
+	assert_eq!(vmd.self_shadows.len(), loaded.self_shadows.len());
+	assert_eq!(vmd.properties.len(), loaded.properties.len());
+
+	for (a, b) in vmd.motions.iter().zip(loaded.motions.iter())
+	{
+		assert_eq!(a.name, b.name);
+		assert_eq!(a.frame, b.frame);
+		assert_eq!(a.position, b.position);
+		assert_eq!(a.rotate, b.rotate);
+		assert_eq!(a.bezier_x, b.bezier_x);
+		assert_eq!(a.bezier_r, b.bezier_r);
+	}
+
+	for (a, b) in vmd.morphs.iter().zip(loaded.morphs.iter())
+	{
+		assert_eq!(a.name, b.name);
+		assert_eq!(a.frame, b.frame);
+		assert_eq!(a.weight, b.weight);
+	}
+}
+
+#[test]
+fn vmd_animator_round_trip()
+{
+	let buf = fs::read(VMD_PATH).unwrap();
+	let vmd = VMDFile::load(&buf).unwrap();
+	let animator = vmd.collect_motions();
+	let saved = VMDFile::load(&VMDFile::from_animator(&animator).save().unwrap()).unwrap();
+
+	assert_eq!(vmd.motions.len(), saved.motions.len());
+	assert!(vmd.motions.iter().any(|motion| motion.rotate != (0.0, 0.0, 0.0, 1.0)));
+
+	for motion in vmd.motions.iter()
+	{
+		let other = saved.motions.iter().find(|m| m.name == motion.name && m.frame == motion.frame).unwrap();
+		assert_eq!(motion.position, other.position);
+		assert_eq!(motion.rotate, other.rotate);
+	}
+}
+
+#[test]
+fn vmd_motion_round_trip()
+{
+	let buf = fs::read(VMD_PATH).unwrap();
+	let vmd = VMDFile::load(&buf).unwrap();
+	let motion = VMDLoader::new().do_load(&buf).unwrap();
+	let saved = VMDFile::load(&VMDLoader::new().do_save(&motion).unwrap()).unwrap();
+
+	assert_eq!(vmd.motions.len(), saved.motions.len());
+	assert_eq!(vmd.morphs.len(), saved.morphs.len());
+
+	for motion in vmd.motions.iter()
+	{
+		let other = saved.motions.iter().find(|m| m.name == motion.name && m.frame == motion.frame).unwrap();
+		assert_eq!(motion.position, other.position);
+		assert_eq!(motion.rotate, other.rotate);
+	}
+}
+
+#[test]
+fn vmd_name_is_truncated_on_character_boundary()
+{
+	let mut vmd = VMDFile::load(&fs::read(VMD_PATH).unwrap()).unwrap();
+	vmd.header.name = "あいうえおかきくけこさ".to_string();
+
+	let loaded = VMDFile::load(&vmd.save().unwrap()).unwrap();
+	assert_eq!(loaded.header.name, "あいうえおかきくけこ");
+}
+
+fn bezier(x1:f32, y1:f32, x2:f32, y2:f32, x:f32) -> f32
+{
+	let curve = |p1:f32, p2:f32, t:f32| 3.0 * (1.0 - t) * (1.0 - t) * t * p1 + 3.0 * (1.0 - t) * t * t * p2 + t * t * t;
+
+	let (mut lo, mut hi) = (0.0f32, 1.0f32);
+	for _ in 0..64
+	{
+		let t = (lo + hi) * 0.5;
+		if curve(x1, x2, t) < x { lo = t; } else { hi = t; }
+	}
+
+	curve(y1, y2, (lo + hi) * 0.5)
+}
+
+#[test]
+fn vmd_bezier_is_applied_per_keyframe()
+{
+	let buf = fs::read(VMD_PATH).unwrap();
+	let vmd = VMDFile::load(&buf).unwrap();
+	let animator = vmd.collect_motions();
+
+	let channels = [("Position.X", 0), ("Position.Y", 1), ("Position.Z", 2), ("Rotation.X", 3), ("Rotation.W", 3)];
+
+	let mut checked = 0;
+
+	for clip in animator.clips.iter()
+	{
+		let mut motions:Vec<_> = vmd.motions.iter().filter(|m| m.name == clip.name).collect();
+		motions.sort_by_key(|m| m.frame);
+
+		for (name, channel) in channels.iter()
+		{
+			let curve = &clip.curves[*name];
+			assert_eq!(curve.frames.len(), motions.len());
+
+			for (i, pair) in motions.windows(2).enumerate()
+			{
+				let (a, b) = (&curve.frames[i], &curve.frames[i + 1]);
+				let points = pair[1].bezier(*channel);
+				let linear = points[0] == points[1] && points[2] == points[3];
+
+				assert_eq!(a.time, pair[0].frame as f32);
+				assert_eq!(a.interpolator.is_none(), linear);
+
+				if linear || a.value == b.value || pair[1].frame - pair[0].frame < 2 { continue; }
+
+				for x in [0.25f32, 0.5, 0.75].iter()
+				{
+					let y = bezier(points[0] as f32 / 127.0, points[1] as f32 / 127.0, points[2] as f32 / 127.0, points[3] as f32 / 127.0, *x);
+					let expect = a.value + (b.value - a.value) * y;
+					let actual = curve.evaluate(a.time + (b.time - a.time) * x);
+
+					assert!((actual - expect).abs() <= 1e-3 * (1.0 + (b.value - a.value).abs()), "{} {} {} : {} != {}", clip.name, name, a.time, actual, expect);
+				}
+
+				checked += 1;
+			}
+		}
+	}
+
+	assert!(checked > 0);
+}
+
+#[test]
+fn vmd_bezier_round_trip()
+{
+	let vmd = VMDFile::load(&fs::read(VMD_PATH).unwrap()).unwrap();
+	let saved = VMDFile::from_motion(&vmd.collect());
+
+	let linear = |p:[u8;4]| p[0] == p[1] && p[2] == p[3];
+	let mut eased = 0;
+
+	for motion in vmd.motions.iter()
+	{
+		let other = saved.motions.iter().find(|m| m.name == motion.name && m.frame == motion.frame).unwrap();
+		let first = vmd.motions.iter().all(|m| m.name != motion.name || m.frame >= motion.frame);
+
+		for channel in 0..4
+		{
+			let (a, b) = (motion.bezier(channel), other.bezier(channel));
+
+			// The first key of a bone starts no segment, so its easing is never used.
+			if first || linear(a)
+			{
+				assert!(linear(b), "{} {} {} : {:?}", motion.name, motion.frame, channel, b);
+			}
+			else
+			{
+				assert_eq!(a, b, "{} {} {}", motion.name, motion.frame, channel);
+				eased += 1;
+			}
+		}
+	}
+
+	assert!(eased > 0);
+}
+
+#[test]
+fn vmd_keys_without_a_frame_are_skipped()
+{
+	let mut curve = AnimationCurve::new();
+	curve.add_keyframe(0.0, 1.0, None);
+	curve.add_keyframe(std::f32::NAN, 2.0, None);
+	curve.add_keyframe(10.0, 3.0, None);
+
+	let mut clip = AnimationClip::new();
+	clip.set_name("センター");
+	clip.set_curve("Position.X", curve);
+
+	let mut animator = Animator::new();
+	animator.add_clip(clip);
+
+	let vmd = VMDFile::from_animator(&animator);
+	assert_eq!(vmd.motions.iter().map(|m| m.frame).collect::<Vec<_>>(), vec![0, 10]);
+}
+
+#[test]
+fn vmd_keys_are_written_once_per_frame()
+{
+	let mut position = AnimationCurve::new();
+	position.add_keyframe(-5.0, 1.0, None);
+	position.add_keyframe(2.0, 2.0, None);
+	position.add_keyframe(2.3, 3.0, None);
+	position.add_keyframe(std::f32::INFINITY, 4.0, None);
+
+	let mut weight = AnimationCurve::new();
+	weight.add_keyframe(-1.0, 0.0, None);
+	weight.add_keyframe(4.0, 0.5, None);
+	weight.add_keyframe(3.8, 1.0, None);
+
+	let mut bone = AnimationClip::new();
+	bone.set_name("センター");
+	bone.set_curve("Position.X", position);
+
+	let mut morph = AnimationClip::new();
+	morph.set_name("あ");
+	morph.set_curve("Weight", weight);
+
+	let mut animator = Animator::new();
+	animator.add_clip(bone);
+	animator.add_clip(morph);
+
+	let vmd = VMDFile::from_animator(&animator);
+	assert_eq!(vmd.motions.iter().map(|m| (m.frame, m.position.0)).collect::<Vec<_>>(), vec![(2, 2.0)]);
+	assert_eq!(vmd.morphs.iter().map(|m| (m.frame, m.weight)).collect::<Vec<_>>(), vec![(4, 1.0)]);
+}
+
+#[test]
+fn vmd_bezier_golden_values()
+{
+	let motion = VMDLoader::new().do_load(&fs::read(VMD_PATH).unwrap()).unwrap();
+	let clip = motion.bones.clips.iter().find(|clip| clip.name == "左足ＩＫ").unwrap();
+	let curve = &clip.curves["Position.X"];
+
+	for (time, value) in [(100.0, -6.997048), (112.0, -6.997048), (115.0, -6.940967), (120.0, -6.495274), (125.0, -5.729167), (132.0, -5.347048)].iter()
+	{
+		assert!((curve.evaluate(*time) - value).abs() < 1e-3, "{} : {}", time, curve.evaluate(*time));
+	}
+}
+
+fn camera(frame:u32, length:f32, x:f32, fov:u32) -> VMDCamera
+{
+	let mut camera = VMDCamera::new();
+	camera.frame = frame;
+	camera.length = length;
+	camera.position = (x, 10.0, 0.0);
+	camera.rotation = (0.0, x * 0.1, 0.0);
+	camera.view_angle = fov;
+	camera.perspective = 0;
+
+	for i in 0..6
+	{
+		camera.bezier[i * 4..i * 4 + 4].copy_from_slice(&[20, 107, 20, 107]);
+	}
+
+	camera
+}
+
+#[test]
+fn vmd_camera_light_and_self_shadow_tracks()
+{
+	let mut vmd = VMDFile::load(&fs::read(VMD_PATH).unwrap()).unwrap();
+
+	let mut second = camera(30, -20.0, 10.0, 45);
+	second.bezier[0..4].copy_from_slice(&[64, 64, 0, 127]); // X eases in : x1 = 64, x2 = 64, y1 = 0, y2 = 127
+	second.perspective = 1;
+	vmd.cameras = vec![second, camera(0, -40.0, 0.0, 30)];
+
+	let mut light = VMDLight::new();
+	light.frame = 0;
+	light.rgb = (0.6, 0.6, 0.6);
+	light.position = (-0.5, -1.0, 0.5);
+	vmd.lights.push(light);
+
+	let mut shadow = VMDSelfShadow::new();
+	shadow.frame = 0;
+	shadow.mode = 1;
+	shadow.distance = 0.0875;
+	vmd.self_shadows.push(shadow);
+
+	let motion = VMDLoader::new().do_load(&vmd.save().unwrap()).unwrap();
+
+	assert_eq!(motion.bones.len(), vmd.collect_motions().len());
+	assert_eq!(motion.morphs.len(), vmd.collect_morphs().len());
+	assert_eq!(motion.cameras.len(), 1);
+	assert_eq!(motion.lights.len(), 1);
+	assert_eq!(motion.self_shadows.len(), 1);
+	assert_eq!(motion.len(), motion.bones.len() + motion.rotations.len() + motion.morphs.len() + 3);
+
+	let camera = &motion.cameras.clips[0];
+	assert_eq!(camera.name, "Camera");
+
+	let expect = 10.0 * bezier(64.0 / 127.0, 0.0, 64.0 / 127.0, 1.0, 0.5);
+	assert!((camera.curves["Target.X"].evaluate(15.0) - expect).abs() < 1e-3);
+	assert!(camera.curves["Target.X"].evaluate(15.0) < 5.0);
+	assert!((camera.curves["Target.Y"].evaluate(15.0) - 10.0).abs() < 1e-5);
+	assert!((camera.curves["Distance"].evaluate(15.0) + 30.0).abs() < 1e-3);
+	assert!((camera.curves["Rotation.Y"].evaluate(30.0) - 1.0).abs() < 1e-5);
+	assert!((camera.curves["Fov"].evaluate(15.0) - 37.5).abs() < 1e-3);
+	assert_eq!(camera.curves["Perspective"].evaluate(29.0), 0.0);
+	assert_eq!(camera.curves["Perspective"].evaluate(30.0), 1.0);
+
+	let light = &motion.lights.clips[0];
+	assert_eq!(light.curves["Color.R"].evaluate(10.0), 0.6);
+	assert_eq!(light.curves["Direction.Y"].evaluate(10.0), -1.0);
+
+	let shadow = &motion.self_shadows.clips[0];
+	assert_eq!(shadow.curves["Mode"].evaluate(0.0), 1.0);
+	assert_eq!(shadow.curves["Distance"].evaluate(0.0), 0.0875);
+}
+
+#[test]
+fn vmd_camera_motion_poses_a_perspective_camera()
+{
+	let mut vmd = VMDFile::new();
+	vmd.header.magic = VMD_MAGIC.to_string();
+	vmd.header.name = "カメラ・照明".to_string();
+	vmd.cameras = vec![camera(0, -40.0, 0.0, 30), camera(30, -20.0, 10.0, 45)];
+
+	let motion = VMDLoader::new().do_load(&vmd.save().unwrap()).unwrap();
+	assert_eq!(motion.bones.len(), 0);
+
+	let mut camera = PerspectiveCamera::new();
+	camera.node.set_motion(Some(Rc::new(motion)));
+
+	camera.node.update_pose(0.0);
+	assert!((camera.node.translate() - float3::new(0.0, 10.0, -40.0)).length() < 1e-4, "{:?}", camera.node.translate());
+	assert_eq!(camera.node.user_data::<CameraData>().unwrap().fov, 30.0);
+
+	camera.node.update_pose(30.0);
+	let target = float3::new(10.0, 10.0, 0.0);
+	let forward = camera.node.quaternion().rotate(float3::new(0.0, 0.0, 1.0));
+	assert!(((camera.node.translate() - target).length() - 20.0).abs() < 1e-4);
+	assert!((camera.node.translate() + forward * 20.0 - target).length() < 1e-4, "{:?}", camera.node.translate());
+	assert!((forward - float3::new(1.0f32.sin(), 0.0, 1.0f32.cos())).length() < 1e-4, "{:?}", forward);
+	assert_eq!(camera.node.user_data::<CameraData>().unwrap().fov, 45.0);
+}
+
+#[test]
+fn vmd_rejects_counts_larger_than_the_file()
+{
+	let mut vmd = VMDFile::new();
+	vmd.header.magic = VMD_MAGIC.to_string();
+	let buf = vmd.save().unwrap();
+
+	// The motion count follows the 50 bytes of the header, the property count ends the file.
+	for &offset in [50, buf.len() - 4].iter()
+	{
+		let mut buf = buf.clone();
+		buf[offset..offset + 4].copy_from_slice(&0xFFFF_FFFFu32.to_le_bytes());
+		assert!(VMDFile::load(&buf).is_err(), "{}", offset);
+	}
+
+	assert!(VMDFile::load(&buf).is_ok());
+}
+
+#[test]
+fn vmd_rotation_is_one_quaternion_curve_per_bone()
+{
+	// The loader slerps rotations by default, the rotation components of `collect_motions` are kept for animators alone.
+	let buf = fs::read(VMD_PATH).unwrap();
+	let vmd = VMDFile::load(&buf).unwrap();
+	let rotations = VMDLoader::new().do_load(&buf).unwrap().rotations;
+
+	assert_eq!(rotations.len(), vmd.collect_motions().len());
+
+	let mut checked = 0;
+
+	for clip in rotations.clips.iter()
+	{
+		assert_eq!(clip.len(), 1);
+
+		let curve = &clip.curves["Rotation"];
+		let mut motions:Vec<_> = vmd.motions.iter().filter(|m| m.name == clip.name).collect();
+		motions.sort_by_key(|m| m.frame);
+
+		for pair in motions.windows(2)
+		{
+			let (a, b) = (pair[0], pair[1]);
+			if b.frame - a.frame < 2 { continue; }
+
+			let q0 = Quaternion::new(a.rotate.0, a.rotate.1, a.rotate.2, a.rotate.3);
+			let q1 = Quaternion::new(b.rotate.0, b.rotate.1, b.rotate.2, b.rotate.3);
+
+			let p = b.bezier(3);
+			let x = 0.5;
+			let y = if p[0] == p[1] && p[2] == p[3] { x } else { bezier(p[0] as f32 / 127.0, p[1] as f32 / 127.0, p[2] as f32 / 127.0, p[3] as f32 / 127.0, x) };
+
+			let q = curve.evaluate(a.frame as f32 + (b.frame - a.frame) as f32 * x);
+			let expect = q0.slerp(q1, y);
+
+			assert!((q.length() - 1.0).abs() < 1e-3, "{} {} : {:?}", clip.name, a.frame, q);
+			assert!(q.dot(expect).abs() > 0.9999, "{} {} : {:?} != {:?}", clip.name, a.frame, q, expect);
+
+			checked += 1;
+		}
+	}
+
+	assert!(checked > 0);
+}
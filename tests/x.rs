@@ -0,0 +1,145 @@
+extern crate octoon;
+
+use octoon::models::{self, Loader, XLoader, XFile, SphereMode};
+use octoon::scene::ModelLoader;
+
+const QUAD:&str = r#"xof 0302txt 0064
+template Vector {
+ <3D82AB5E-62DA-11cf-AB39-0020AF71E433>
+ FLOAT x;
+ FLOAT y;
+ FLOAT z;
+}
+
+Material Red {
+ 1.000000;0.000000;0.000000;0.500000;;
+ 5.000000;
+ 0.100000;0.100000;0.100000;;
+ 0.400000;0.000000;0.000000;;
+}
+
+Frame Stage {
+ FrameTransformMatrix {
+  1.000000,0.000000,0.000000,0.000000,
+  0.000000,1.000000,0.000000,0.000000,
+  0.000000,0.000000,1.000000,0.000000,
+  10.000000,0.000000,0.000000,1.000000;;
+ }
+
+ // A quad and a triangle, the quad has a flat normal and the triangle none of its own.
+ Mesh Floor {
+  5;
+  0.000000;0.000000;0.000000;,
+  0.000000;0.000000;1.000000;,
+  1.000000;0.000000;1.000000;,
+  1.000000;0.000000;0.000000;,
+  0.000000;1.000000;0.000000;;
+  2;
+  4;0,1,2,3;,
+  3;0,3,4;;
+
+  MeshNormals {
+   2;
+   0.000000;1.000000;0.000000;,
+   0.000000;0.000000;-1.000000;;
+   2;
+   4;0,0,0,0;,
+   3;1,1,1;;
+  }
+
+  MeshTextureCoords {
+   5;
+   0.000000;1.000000;,
+   0.000000;0.000000;,
+   1.000000;0.000000;,
+   1.000000;1.000000;,
+   0.500000;0.500000;;
+  }
+
+  MeshMaterialList {
+   2;
+   2;
+   0,
+   1;;
+   Material {
+    1.000000;1.000000;1.000000;1.000000;;
+    10.000000;
+    0.000000;0.000000;0.000000;;
+    0.500000;0.500000;0.500000;;
+    TextureFilename {
+     "stage.png*light.spa";
+    }
+   }
+   { Red }
+  }
+ }
+}
+"#;
+
+#[test]
+fn text_x_meshes_are_split_by_material()
+{
+	let model = models::load_from_memory(QUAD.as_bytes()).unwrap();
+
+	assert_eq!(model.meshes.len(), 2);
+	assert_eq!(model.materials.len(), 2);
+	assert_eq!(model.textures, vec!["stage.png".to_string(), "light.spa".to_string()]);
+
+	// The quad is a fan of two triangles, its corners moved by the frame.
+	let quad = &model.meshes[0];
+	assert_eq!(quad.indices, vec![0, 1, 2, 0, 2, 3]);
+	assert_eq!(&quad.positions[6..9], &[11.0, 0.0, 1.0]);
+	assert_eq!(&quad.normals[0..3], &[0.0, 1.0, 0.0]);
+	assert_eq!(&quad.texcoords[6..8], &[1.0, 1.0]);
+
+	// The triangle shares positions with the quad but not their normal, so they are repeated.
+	let triangle = &model.meshes[1];
+	assert_eq!(triangle.indices, vec![0, 1, 2]);
+	assert_eq!(triangle.positions, vec![10.0, 0.0, 0.0, 11.0, 0.0, 0.0, 10.0, 1.0, 0.0]);
+	assert_eq!(&triangle.normals[0..3], &[0.0, 0.0, -1.0]);
+
+	let white = &model.materials[quad.material_id.unwrap()];
+	assert_eq!(white.diffuse, [1.0, 1.0, 1.0]);
+	assert_eq!(white.ambient, [0.5, 0.5, 0.5]);
+	assert_eq!(white.shininess, 10.0);
+	assert_eq!(white.diffuse_texture, Some(0));
+	assert_eq!(white.sphere_texture, Some(1));
+	assert_eq!(white.sphere_mode, SphereMode::Additive);
+
+	let red = &model.materials[triangle.material_id.unwrap()];
+	assert_eq!(red.diffuse, [1.0, 0.0, 0.0]);
+	assert_eq!(red.dissolve, 0.5);
+	assert!(red.double_sided);
+	assert_eq!(red.diffuse_texture, None);
+}
+
+#[test]
+fn text_x_meshes_without_normals_get_smooth_ones()
+{
+	let x = "xof 0303txt 0032\nMesh { 3; 0;0;0;, 0;1;0;, 1;0;0;; 1; 3;0,1,2;; }";
+	let model = XLoader::new().do_load(x.as_bytes()).unwrap();
+
+	assert_eq!(model.meshes.len(), 1);
+	assert_eq!(model.materials.len(), 1);
+	assert_eq!(model.meshes[0].texcoords, vec![0.0; 6]);
+	assert_eq!(model.meshes[0].normals, vec![0.0, 0.0, -1.0, 0.0, 0.0, -1.0, 0.0, 0.0, -1.0]);
+
+	assert!(ModelLoader::load_from_memory(x.as_bytes()).is_ok());
+}
+
+#[test]
+fn broken_x_files_are_errors()
+{
+	assert!(!XLoader::new().can_read(b"xof 0302bin 0032"));
+	assert!(XFile::load(b"xof 0302bin 0032").is_err());
+
+	for x in [
+		"xof 0302txt 0032\nMesh { 3; 0;0;0;, 0;1;0;, 1;0;0;; 1; 3;0,1,5;; }",
+		"xof 0302txt 0032\nMesh { 4000000000; 0;0;0;; }",
+		"xof 0302txt 0032\nMesh { 3; 0;0;0;, 0;1;0;, 1;0;0;; 1; 3;0,1,2;; MeshMaterialList { 1; 1; 0;; { Missing } } }",
+		"xof 0302txt 0032\nMesh { 3; 0;0;0;, 0;1;0;",
+	].iter()
+	{
+		assert!(XLoader::new().do_load(x.as_bytes()).is_err(), "{}", x);
+	}
+}